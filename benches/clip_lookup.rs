@@ -0,0 +1,144 @@
+//! Clip lookup on a synthetic feature-length track: 10,000 clips of 1-5 s
+//! with occasional gaps.
+//!
+//! Compares the indexed `Track` queries with the linear scans they replaced.
+//! Run with `cargo bench --bench clip_lookup`.
+
+use std::hint::black_box;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use aves::core::time::{Time, constants::NANOS_PER_SECOND};
+use aves::timeline::{Clip, Track, TrackType};
+
+const CLIPS: u64 = 10_000;
+const QUERIES: usize = 100_000;
+
+/// Clips of 1-5 s laid end to end, with a gap after every seventh.
+fn synthetic_clips() -> Vec<Clip> {
+    let mut start: Time = 0;
+    (1..=CLIPS)
+        .map(|id| {
+            let length = (1 + (id * 7919 % 5) as Time) * NANOS_PER_SECOND;
+            let clip = Clip::new(id, PathBuf::from("source.mp4"), 0, length, start, 0);
+            start += length + if id % 7 == 0 { NANOS_PER_SECOND / 2 } else { 0 };
+            clip
+        })
+        .collect()
+}
+
+/// Deterministic pseudo-random query times over `[0, end)`.
+fn query_times(end: Time) -> Vec<Time> {
+    let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+    (0..QUERIES)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % end as u64) as Time
+        })
+        .collect()
+}
+
+/// Time `f` over `runs` runs and return the fastest.
+fn fastest(runs: usize, mut f: impl FnMut()) -> Duration {
+    (0..runs)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+fn report(name: &str, operations: usize, scan: Duration, indexed: Duration) {
+    let per_op = |d: Duration| d.as_nanos() as f64 / operations as f64;
+    println!(
+        "{:<28} scan {:>12.1} ns/op   indexed {:>9.1} ns/op   {:>8.1}x",
+        name,
+        per_op(scan),
+        per_op(indexed),
+        scan.as_secs_f64() / indexed.as_secs_f64().max(f64::EPSILON),
+    );
+}
+
+fn main() {
+    let clips = synthetic_clips();
+    let mut track = Track::new(1, TrackType::Video);
+
+    // Building the track, clips added out of order: previously every add
+    // checked every clip for overlaps and re-sorted the whole track
+    let order: Vec<&Clip> = clips.iter().step_by(2).chain(clips.iter().skip(1).step_by(2)).collect();
+    let scan = fastest(3, || {
+        let mut scanned: Vec<Clip> = Vec::new();
+        for clip in &order {
+            assert!(!scanned.iter().any(|c| clip.overlaps_with(c)));
+            scanned.push((*clip).clone());
+            scanned.sort_by_key(|c| c.timeline_start);
+        }
+        black_box(scanned);
+    });
+    let indexed = fastest(3, || {
+        track = Track::new(1, TrackType::Video);
+        for clip in &order {
+            track.add_clip((*clip).clone()).unwrap();
+        }
+    });
+    report("add_clip (10k clips)", order.len(), scan, indexed);
+
+    let end = track.duration();
+    let times = query_times(end);
+
+    let scan = fastest(5, || {
+        for &time in &times {
+            black_box(track.clips.iter().find(|c| c.contains(time)));
+        }
+    });
+    let indexed = fastest(5, || {
+        for &time in &times {
+            black_box(track.clip_at(time));
+        }
+    });
+    report("clip_at", times.len(), scan, indexed);
+
+    // Ten-second windows, as export and the timeline view ask for
+    let window = 10 * NANOS_PER_SECOND;
+    let scan = fastest(5, || {
+        for &time in &times {
+            black_box(track.clips.iter()
+                .filter(|c| c.timeline_start <= time + window && c.timeline_end >= time)
+                .count());
+        }
+    });
+    let indexed = fastest(5, || {
+        for &time in &times {
+            black_box(track.clips_in_range(time, time + window).len());
+        }
+    });
+    report("clips_in_range (10 s)", times.len(), scan, indexed);
+
+    // Removing and re-adding clips across the track (a move in place)
+    let moved: Vec<Clip> = times.iter()
+        .take(1_000)
+        .filter_map(|&time| track.clip_at(time).cloned())
+        .collect();
+    let scan = fastest(3, || {
+        let mut scanned = track.clips.clone();
+        for clip in &moved {
+            let index = scanned.iter().position(|c| c.id == clip.id).unwrap();
+            scanned.remove(index);
+            assert!(!scanned.iter().any(|c| clip.overlaps_with(c)));
+            scanned.push(clip.clone());
+            scanned.sort_by_key(|c| c.timeline_start);
+        }
+        black_box(scanned);
+    });
+    let indexed = fastest(3, || {
+        for clip in &moved {
+            track.remove_clip(clip.id).unwrap();
+            track.add_clip(clip.clone()).unwrap();
+        }
+    });
+    report("remove + add_clip", moved.len(), scan, indexed);
+}
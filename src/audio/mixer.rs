@@ -4,6 +4,8 @@
 use crate::timeline::Timeline;
 use crate::core::time::Time;
 use crate::audio::buffer::AudioBuffer;
+use crate::audio::retime::{retime, ClipStretchers};
+use crate::timeline::{ClipSource, SequenceId, Speed};
 use crate::decode::decoder::Decoder;
 
 /// Error type for audio mixing operations
@@ -11,6 +13,7 @@ use crate::decode::decoder::Decoder;
 pub enum MixerError {
     Decode(crate::decode::decoder::DecodeError),
     NoClip,
+    SequenceCycle(SequenceId),
 }
 
 impl std::fmt::Display for MixerError {
@@ -18,6 +21,7 @@ impl std::fmt::Display for MixerError {
         match self {
             MixerError::Decode(e) => write!(f, "Decode error: {}", e),
             MixerError::NoClip => write!(f, "No audio clip at position"),
+            MixerError::SequenceCycle(id) => write!(f, "Sequence {} contains itself", id),
         }
     }
 }
@@ -30,11 +34,28 @@ impl From<crate::decode::decoder::DecodeError> for MixerError {
     }
 }
 
+/// Gain of `channel` (of `channels` interleaved) at `pan` (-1.0 left to 1.0 right).
+///
+/// A balance control: centre leaves both channels at full level and panning
+/// turns the opposite channel down. Only stereo is panned; other layouts
+/// are returned unchanged.
+pub fn pan_gain(pan: f32, channel: usize, channels: usize) -> f32 {
+    if channels != 2 {
+        return 1.0;
+    }
+    let pan = pan.clamp(-1.0, 1.0);
+    match channel {
+        0 => (1.0 - pan).min(1.0),
+        _ => (1.0 + pan).min(1.0),
+    }
+}
+
 /// Audio mixer that combines audio from timeline tracks
 pub struct AudioMixer {
     pub timeline: Timeline,
     pub sample_rate: u32,
     pub channels: u32,
+    stretchers: ClipStretchers, // Pitch-preserving clips' stretch state, carried between windows
 }
 
 impl AudioMixer {
@@ -44,11 +65,20 @@ impl AudioMixer {
             timeline,
             sample_rate,
             channels,
+            stretchers: ClipStretchers::default(),
         }
     }
 
     /// Get audio samples for a specific time range
     /// Returns interleaved PCM f32 samples (per SPEC.md)
+    /// 
+    /// The audible tracks (see `Timeline::audible_tracks`: unmuted, and only
+    /// the soloed ones while any is soloed) are summed, each scaled by its track volume
+    /// and its clips' keyframed volume and pan at the window start.
+    /// Clips at non-normal speed are resampled (or time-stretched, if they
+    /// preserve pitch) to fit the window; time-remapped clips fit the source
+    /// span their curve covers in the window. Compound clips are mixed from
+    /// their nested sequences, and both clips of a crossfade are mixed over it.
     pub fn get_samples(
         &mut self,
         start_time: Time,
@@ -65,50 +95,137 @@ impl AudioMixer {
             start_time,
         );
 
-        // Get the audio clip at the start time
-        if let Some(clip) = self.timeline.audio_track.clip_at(start_time) {
-            // Get decoder for this clip's source
-            let _decoder = decoders
-                .entry(clip.source_path.clone())
-                .or_insert_with(|| {
-                    Decoder::new(&clip.source_path)
-                        .expect("Failed to create decoder")
-                });
-
-            // Convert timeline position to source position
-            if let Some(_source_time) = clip.timeline_to_source(start_time) {
-                // TODO: Decode audio samples from source
-                // This would involve:
-                // 1. Seeking decoder to source_time
-                // 2. Decoding audio packets (returns AudioFrame with interleaved PCM f32)
-                // 3. Resampling if needed
-                // 4. Mixing with volume/mute settings
-                
-                // Placeholder: generate silence
-                let silence = vec![0.0f32; num_samples * self.channels as usize];
-                buffer.append(&silence);
-            }
-        } else {
-            // No clip at this position - generate silence
-            let silence = vec![0.0f32; num_samples * self.channels as usize];
-            buffer.append(&silence);
+        // Compound clips are mixed by recursion, which must end
+        if let Some(sequence) = self.timeline.find_sequence_cycle() {
+            return Err(MixerError::SequenceCycle(sequence));
         }
 
-        // Apply track volume and mute
-        if self.timeline.audio_track.muted {
-            buffer.clear();
-            // Re-fill with silence
-            let silence = vec![0.0f32; num_samples * self.channels as usize];
-            buffer.append(&silence);
-        } else {
-            for sample in buffer.as_mut_slice() {
-                *sample *= self.timeline.audio_track.volume;
-            }
-        }
+        // Mix every audio track into one buffer
+        let mut stretchers = std::mem::take(&mut self.stretchers);
+        let mix = self.mix_timeline(
+            &self.timeline,
+            None,
+            start_time,
+            duration_nanos,
+            num_samples,
+            decoders,
+            &mut stretchers,
+        );
+        stretchers.finish_window();
+        self.stretchers = stretchers;
+        buffer.append(&mix);
 
         Ok(buffer)
     }
 
+    /// Mix every audible track of `timeline` (the sequence `sequence`, or the
+    /// top-level timeline) for the window starting at `start_time` into
+    /// `num_samples` interleaved frames.
+    ///
+    /// Compound clips mix their sequence over the source window they play,
+    /// recursively, and fit it to the window like decoded audio.
+    #[allow(clippy::too_many_arguments)]
+    fn mix_timeline(
+        &self,
+        timeline: &Timeline,
+        sequence: Option<SequenceId>,
+        start_time: Time,
+        duration_nanos: Time,
+        num_samples: usize,
+        decoders: &mut std::collections::HashMap<std::path::PathBuf, Decoder>,
+        stretchers: &mut ClipStretchers,
+    ) -> Vec<f32> {
+        let mut mix = vec![0.0f32; num_samples * self.channels as usize];
+
+        for track in timeline.audible_tracks() {
+            // The clip at the start time on this track, or both clips of a
+            // crossfade there (each faded by its curve at the window start)
+            let clips: Vec<_> = match track.transition_at(start_time) {
+                Some(window) => vec![
+                    (window.from, window.gain(window.from.id, start_time)),
+                    (window.to, window.gain(window.to.id, start_time)),
+                ],
+                None => track.clip_at(start_time).map(|clip| (clip, 1.0)).into_iter().collect(),
+            };
+
+            for (clip, gain) in clips {
+                // Convert timeline position to source position (in the clip's
+                // handles over a transition)
+                let source_time = clip.handle_source_at(start_time);
+
+                // Source audio covered by this window at the clip's speed, or
+                // along its time-remap curve (which may run backwards)
+                let (source_nanos, speed) = match &clip.time_remap {
+                    Some(_) => {
+                        let window_end = (start_time + duration_nanos).min(clip.timeline_end).max(start_time);
+                        let source_end = clip.handle_source_at(window_end);
+                        let direction = if source_end < source_time { Speed::REVERSE } else { Speed::NORMAL };
+                        ((source_end - source_time).abs(), direction)
+                    }
+                    None => (clip.speed.to_source(duration_nanos).abs(), clip.speed),
+                };
+                let source_samples = (crate::core::time::to_seconds(source_nanos)
+                    * self.sample_rate as f64) as usize;
+
+                let decoded = match &clip.source {
+                    ClipSource::Media(path) => {
+                        // Get decoder for this clip's source
+                        let _decoder = decoders
+                            .entry(path.clone())
+                            .or_insert_with(|| {
+                                Decoder::new(path)
+                                    .expect("Failed to create decoder")
+                            });
+
+                        // TODO: Decode audio samples from source
+                        // This would involve:
+                        // 1. Seeking decoder to source_time (the window's earliest source time)
+                        // 2. Decoding audio packets (returns AudioFrame with interleaved PCM f32)
+                        // 3. Resampling if needed
+
+                        // Placeholder: decoded samples are silence
+                        vec![0.0f32; source_samples * self.channels as usize]
+                    }
+                    ClipSource::Sequence(id) => {
+                        let Some(nested) = self.timeline.sequence(*id) else {
+                            continue;
+                        };
+                        // The nested mix is in source order, like decoded audio
+                        let source_start = if speed.is_reverse() { source_time - source_nanos } else { source_time };
+                        self.mix_timeline(
+                            &nested.timeline,
+                            Some(*id),
+                            source_start,
+                            source_nanos,
+                            source_samples,
+                            decoders,
+                            stretchers,
+                        )
+                    }
+                };
+
+                // Fit the source audio to the window: speed, direction and pitch
+                let channels = self.channels as usize;
+                let stretcher = if clip.preserve_pitch {
+                    Some(stretchers.get((sequence, clip.id), channels, start_time, start_time + duration_nanos))
+                } else {
+                    None
+                };
+                let samples = retime(&decoded, channels, speed, num_samples, stretcher);
+
+                // Apply track volume, clip volume and pan, and crossfade gain
+                // while summing into the mix
+                let offset = start_time - clip.timeline_start;
+                let gain = track.volume * clip.animation.volume_at(offset) * gain;
+                let pan = clip.animation.pan_at(offset);
+                for (i, (mixed, sample)) in mix.iter_mut().zip(&samples).enumerate() {
+                    *mixed += sample * gain * pan_gain(pan, i % channels, channels);
+                }
+            }
+        }
+        mix
+    }
+
     /// Update the timeline reference
     pub fn update_timeline(&mut self, timeline: Timeline) {
         self.timeline = timeline;
@@ -1,6 +1,7 @@
 pub mod player;
 pub mod mixer;
 pub mod buffer;
+pub mod retime;
 
 pub use player::AudioPlayer;
 pub use mixer::AudioMixer;
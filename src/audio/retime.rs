@@ -0,0 +1,288 @@
+//! Audio for clips playing at non-normal speed.
+//! Per SPEC.md: samples are interleaved PCM f32.
+//!
+//! Source samples are decoded in source order and then fitted to the number
+//! of output frames the clip occupies on the timeline: reversed for negative
+//! speeds, and either resampled (pitch follows speed, like tape) or
+//! time-stretched with overlap-add (pitch preserved).
+//!
+//! Playback and export fit audio one short window at a time, so the stretch
+//! keeps its state per clip in a [`TimeStretcher`]: overlap-add windows run
+//! across window boundaries as over one continuous stream.
+
+use std::collections::HashMap;
+use crate::core::time::Time;
+use crate::timeline::{ClipId, SequenceId, Speed};
+
+/// Analysis/synthesis window for pitch-preserving stretch, in frames
+const STRETCH_WINDOW: usize = 1024;
+
+/// Fit decoded source audio to `frames` output frames for a clip at `speed`.
+///
+/// `samples` are interleaved with `channels` channels, in source order.
+/// With a `stretcher` (clips preserving pitch) the audio is time-stretched
+/// as the continuation of what the stretcher was fed before; otherwise it
+/// is resampled. Freeze frames are silent.
+pub fn retime(
+    samples: &[f32],
+    channels: usize,
+    speed: Speed,
+    frames: usize,
+    stretcher: Option<&mut TimeStretcher>,
+) -> Vec<f32> {
+    if speed.is_freeze() || samples.is_empty() || channels == 0 {
+        return vec![0.0; frames * channels];
+    }
+
+    let reversed;
+    let input = if speed.is_reverse() {
+        reversed = reverse_frames(samples, channels);
+        &reversed
+    } else {
+        samples
+    };
+
+    match stretcher {
+        Some(stretcher) if stretcher.channels == channels => stretcher.process(input, frames),
+        _ => resample(input, channels, frames),
+    }
+}
+
+/// Reverse the order of frames, keeping channel order within each frame.
+fn reverse_frames(samples: &[f32], channels: usize) -> Vec<f32> {
+    samples
+        .chunks_exact(channels)
+        .rev()
+        .flatten()
+        .copied()
+        .collect()
+}
+
+/// Linear-interpolation resample to `frames` frames (pitch changes with length).
+fn resample(input: &[f32], channels: usize, frames: usize) -> Vec<f32> {
+    let in_frames = input.len() / channels;
+    if in_frames == frames {
+        return input[..frames * channels].to_vec();
+    }
+
+    let step = in_frames as f64 / frames as f64;
+    let mut output = Vec::with_capacity(frames * channels);
+    for i in 0..frames {
+        let position = i as f64 * step;
+        let index = (position as usize).min(in_frames - 1);
+        let next = (index + 1).min(in_frames - 1);
+        let fraction = (position - index as f64) as f32;
+        for c in 0..channels {
+            let a = input[index * channels + c];
+            let b = input[next * channels + c];
+            output.push(a + (b - a) * fraction);
+        }
+    }
+    output
+}
+
+/// Streaming overlap-add time stretch (pitch unchanged).
+///
+/// Output is synthesized with Hann windows at a fixed hop of half a window,
+/// each reading the input at the position its output maps to, so each call
+/// to `process` may use its own length ratio. Windows that straddle the end
+/// of a call are finished by the next call, so consecutive calls join
+/// without a seam. When slowing down, a window reads the input that ends
+/// where its own end maps to, so the part output in the current call never
+/// reads past the input received (at the cost of up to half a window of
+/// delay against the mapped position).
+#[derive(Debug, Clone)]
+pub struct TimeStretcher {
+    channels: usize,
+    window: Vec<f32>,
+    history: Vec<f32>,        // Input frames still read by open windows, interleaved
+    history_start: i64,       // Stream index of the first frame in `history`
+    input_frames: i64,        // Input frames received so far
+    output_frames: i64,       // Output frames produced so far
+    next_window: i64,         // Output position of the next window to open
+    open: Vec<(i64, f64)>,    // Open windows: output start and input start
+}
+
+impl TimeStretcher {
+    /// A stretcher for interleaved audio with `channels` channels.
+    pub fn new(channels: usize) -> Self {
+        let window = (0..STRETCH_WINDOW)
+            .map(|k| {
+                let phase = std::f32::consts::PI * (k as f32 + 0.5) / STRETCH_WINDOW as f32;
+                phase.sin().powi(2)
+            })
+            .collect();
+        Self {
+            channels,
+            window,
+            history: Vec::new(),
+            history_start: 0,
+            input_frames: 0,
+            output_frames: 0,
+            // Start half a window early so every output frame is covered by two windows
+            next_window: -(STRETCH_WINDOW as i64 / 2),
+            open: Vec::new(),
+        }
+    }
+
+    /// Stretch the next `input` frames of the stream to `frames` output frames.
+    ///
+    /// A window that would read past the input received so far (after the
+    /// ratio rises between calls) reads its last frame there instead.
+    pub fn process(&mut self, input: &[f32], frames: usize) -> Vec<f32> {
+        let channels = self.channels;
+        let in_frames = input.len() / channels;
+        self.history.extend_from_slice(&input[..in_frames * channels]);
+        let (in_start, out_start) = (self.input_frames, self.output_frames);
+        self.input_frames += in_frames as i64;
+        let out_end = out_start + frames as i64;
+        if frames == 0 {
+            return Vec::new();
+        }
+
+        // Open the windows starting in this call, reading input at the same ratio
+        let ratio = in_frames as f64 / frames as f64;
+        let lag = STRETCH_WINDOW as f64 * (1.0 - ratio).max(0.0);
+        while self.next_window < out_end {
+            let input_at = in_start as f64 + (self.next_window - out_start) as f64 * ratio - lag;
+            self.open.push((self.next_window, input_at));
+            self.next_window += STRETCH_WINDOW as i64 / 2;
+        }
+
+        let mut output = vec![0.0f32; frames * channels];
+        let mut weights = vec![0.0f32; frames];
+        let last = self.input_frames - 1;
+        for &(window_start, input_at) in &self.open {
+            let from = window_start.max(out_start);
+            let to = (window_start + STRETCH_WINDOW as i64).min(out_end);
+            for o in from..to {
+                let k = (o - window_start) as usize;
+                let i = (input_at.floor() as i64 + k as i64).clamp(0, last.max(0));
+                let Some(frame) = self.history.get(((i - self.history_start) as usize) * channels..)
+                else {
+                    continue;
+                };
+                let w = self.window[k];
+                let out = (o - out_start) as usize * channels;
+                for c in 0..channels {
+                    output[out + c] += frame[c] * w;
+                }
+                weights[(o - out_start) as usize] += w;
+            }
+        }
+        for (frame, &weight) in output.chunks_exact_mut(channels).zip(&weights) {
+            if weight > 1e-3 {
+                for sample in frame {
+                    *sample /= weight;
+                }
+            }
+        }
+
+        // Close finished windows and drop the input no open window reads
+        self.open.retain(|&(window_start, _)| window_start + STRETCH_WINDOW as i64 > out_end);
+        self.output_frames = out_end;
+        let first_needed = self.open
+            .iter()
+            .map(|&(_, input_at)| input_at.floor() as i64)
+            .min()
+            .unwrap_or(self.input_frames)
+            .min(last)
+            .max(self.history_start);
+        let drop = (first_needed - self.history_start) as usize * channels;
+        self.history.drain(..drop.min(self.history.len()));
+        self.history_start = first_needed;
+        output
+    }
+}
+
+/// Identifies a clip for `ClipStretchers`: the sequence holding it (`None`
+/// for the top-level timeline) and its ID.
+pub type StretchKey = (Option<SequenceId>, ClipId);
+
+/// The `TimeStretcher`s of the pitch-preserving clips being played.
+///
+/// A clip's stretcher carries over to its next window when that window
+/// starts where the previous one ended; after a seek, or a gap in which
+/// the clip was not heard, it starts afresh.
+#[derive(Debug, Default)]
+pub struct ClipStretchers {
+    previous: HashMap<StretchKey, (TimeStretcher, Time)>, // Stretchers used in the last window, and where it ended
+    current: HashMap<StretchKey, (TimeStretcher, Time)>,  // Stretchers used in this window so far
+}
+
+impl ClipStretchers {
+    /// The stretcher for a clip heard over `[start, end)` in the time of the
+    /// timeline holding it.
+    pub fn get(&mut self, key: StretchKey, channels: usize, start: Time, end: Time) -> &mut TimeStretcher {
+        let stretcher = self.previous
+            .remove(&key)
+            .filter(|(stretcher, next)| *next == start && stretcher.channels == channels)
+            .map(|(stretcher, _)| stretcher)
+            .unwrap_or_else(|| TimeStretcher::new(channels));
+        &mut self.current.entry(key).insert_entry((stretcher, end)).into_mut().0
+    }
+
+    /// End the window: stretchers of clips not heard in it are dropped.
+    pub fn finish_window(&mut self) {
+        self.previous = std::mem::take(&mut self.current);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resample_speed() {
+        // Stereo ramp 0..4 in both channels
+        let input: Vec<f32> = (0..4).flat_map(|i| [i as f32, i as f32]).collect();
+        assert_eq!(retime(&input, 2, Speed::NORMAL, 4, None), input);
+
+        // Half speed: twice as many frames, interpolated
+        let slow = retime(&input, 2, Speed::percent(50), 8, None);
+        assert_eq!(slow.len(), 16);
+        assert_eq!(&slow[..6], &[0.0, 0.0, 0.5, 0.5, 1.0, 1.0]);
+
+        let reverse = retime(&input, 2, Speed::REVERSE, 4, None);
+        assert_eq!(reverse, vec![3.0, 3.0, 2.0, 2.0, 1.0, 1.0, 0.0, 0.0]);
+
+        assert_eq!(retime(&input, 2, Speed::FREEZE, 3, None), vec![0.0; 6]);
+    }
+
+    #[test]
+    fn test_time_stretch_preserves_level() {
+        let input = vec![0.5f32; 4800];
+        let mut stretcher = TimeStretcher::new(1);
+        let stretched = retime(&input, 1, Speed::percent(50), 9600, Some(&mut stretcher));
+        assert_eq!(stretched.len(), 9600);
+        assert!(stretched.iter().all(|s| (s - 0.5).abs() < 1e-3));
+    }
+
+    #[test]
+    fn test_time_stretch_is_continuous_across_calls() {
+        // A ramp stretched in one go and in callback-sized pieces
+        let input: Vec<f32> = (0..4800).map(|i| i as f32 / 4800.0).collect();
+        let whole = TimeStretcher::new(1).process(&input, 9600);
+        let mut stretcher = TimeStretcher::new(1);
+        let pieces: Vec<f32> = input
+            .chunks(240)
+            .flat_map(|chunk| retime(chunk, 1, Speed::percent(50), 480, Some(&mut stretcher)))
+            .collect();
+        assert_eq!(pieces.len(), whole.len());
+        assert!(pieces.iter().zip(&whole).all(|(a, b)| (a - b).abs() < 1e-4));
+        assert!(pieces.windows(2).all(|w| (w[1] - w[0]).abs() < 0.01));
+    }
+
+    #[test]
+    fn test_clip_stretchers_restart_after_a_seek() {
+        let mut stretchers = ClipStretchers::default();
+        stretchers.get((None, 1), 1, 0, 10).process(&[0.5; 64], 128);
+        stretchers.finish_window();
+        assert_eq!(stretchers.get((None, 1), 1, 10, 20).output_frames, 128);
+        stretchers.finish_window();
+        assert_eq!(stretchers.get((None, 1), 1, 50, 60).output_frames, 0);
+        stretchers.finish_window();
+        stretchers.finish_window();
+        assert!(stretchers.previous.is_empty());
+    }
+}
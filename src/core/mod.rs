@@ -4,6 +4,8 @@
 //! All time values are in nanoseconds (i64) as specified in SPEC.md.
 
 pub mod time;
+pub mod timecode;
 
 // Re-export time type and common functions
-pub use time::{Time, Timestamp, ZERO};
+pub use time::{Time, Timestamp, ZERO, FrameRate, TimeRange};
+pub use timecode::{Timecode, TimecodeFormat, TimecodeError};
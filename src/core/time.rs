@@ -1,6 +1,8 @@
 //! Time representation using nanoseconds for frame-accurate video editing.
 //! Per SPEC.md: Time unit is nanoseconds (i64), with audio as master clock.
 
+use serde::{Deserialize, Serialize};
+
 /// Time in nanoseconds since timeline start
 /// This is the core time representation throughout the application
 pub type Time = i64;
@@ -51,15 +53,264 @@ pub fn to_micros(nanos: Time) -> i64 {
 }
 
 /// Convert time to frame index given a frame rate
+///
+/// The `f64` rate is snapped to the nearest exact [`FrameRate`] first, so
+/// 29.97 is treated as 30000/1001 rather than as a lossy float. Rates no
+/// `FrameRate` can hold (zero, negative, or out of range) use float math.
 #[inline]
 pub fn to_frame_index(nanos: Time, fps: f64) -> usize {
-    (to_seconds(nanos) * fps).floor() as usize
+    match FrameRate::from_f64(fps) {
+        Some(rate) => rate.time_to_frame(nanos).max(0) as usize,
+        None => (to_seconds(nanos) * fps).floor() as usize,
+    }
 }
 
 /// Convert frame index to time given a frame rate
+///
+/// Inverse of [`to_frame_index`]: `to_frame_index(from_frame_index(n, fps), fps) == n`.
 #[inline]
 pub fn from_frame_index(frame_index: usize, fps: f64) -> Time {
-    from_seconds(frame_index as f64 / fps)
+    match FrameRate::from_f64(fps) {
+        Some(rate) => rate.frame_to_time(frame_index as i64),
+        None => from_seconds(frame_index as f64 / fps),
+    }
+}
+
+/// Convert time to audio sample index (per channel) at a sample rate
+#[inline]
+pub fn to_sample_index(nanos: Time, sample_rate: u32) -> i64 {
+    time_to_units(nanos, sample_rate as i128, 1)
+}
+
+/// Convert audio sample index (per channel) to time at a sample rate
+///
+/// Inverse of [`to_sample_index`] for every sample index.
+#[inline]
+pub fn from_sample_index(sample_index: i64, sample_rate: u32) -> Time {
+    units_to_time(sample_index, sample_rate as i128, 1)
+}
+
+/// Time of unit `index` for a rate of `num / den` units per second.
+///
+/// Rounded up to the next whole nanosecond, which makes [`time_to_units`]
+/// an exact inverse (the true start never lies after the returned value).
+fn units_to_time(index: i64, num: i128, den: i128) -> Time {
+    let scaled = index as i128 * den * constants::NANOS_PER_SECOND as i128;
+    div_ceil(scaled, num) as Time
+}
+
+/// Index of the unit containing `nanos` for a rate of `num / den` units per second.
+fn time_to_units(nanos: Time, num: i128, den: i128) -> i64 {
+    let scaled = nanos as i128 * num;
+    div_floor(scaled, den * constants::NANOS_PER_SECOND as i128) as i64
+}
+
+#[inline]
+fn div_floor(a: i128, b: i128) -> i128 {
+    let q = a / b;
+    if (a % b != 0) && ((a < 0) != (b < 0)) { q - 1 } else { q }
+}
+
+#[inline]
+fn div_ceil(a: i128, b: i128) -> i128 {
+    let q = a / b;
+    if (a % b != 0) && ((a < 0) == (b < 0)) { q + 1 } else { q }
+}
+
+/// Exact rational frame rate (`num / den` frames per second).
+///
+/// NTSC rates are not representable as `f64` without drift, so all frame
+/// math goes through integer arithmetic on the rational: frame N always
+/// lands on the same nanosecond in export, playback and the UI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FrameRate {
+    pub num: u32,
+    pub den: u32,
+}
+
+impl FrameRate {
+    pub const FPS_23_976: FrameRate = FrameRate { num: 24000, den: 1001 };
+    pub const FPS_24: FrameRate = FrameRate { num: 24, den: 1 };
+    pub const FPS_25: FrameRate = FrameRate { num: 25, den: 1 };
+    pub const FPS_29_97: FrameRate = FrameRate { num: 30000, den: 1001 };
+    pub const FPS_30: FrameRate = FrameRate { num: 30, den: 1 };
+    pub const FPS_50: FrameRate = FrameRate { num: 50, den: 1 };
+    pub const FPS_59_94: FrameRate = FrameRate { num: 60000, den: 1001 };
+    pub const FPS_60: FrameRate = FrameRate { num: 60, den: 1 };
+
+    /// Create a frame rate of `num / den` frames per second.
+    ///
+    /// # Panics
+    /// Panics if `num` or `den` is zero.
+    pub fn new(num: u32, den: u32) -> Self {
+        assert!(num > 0 && den > 0, "FrameRate num and den must be > 0");
+        Self { num, den }
+    }
+
+    /// Convert a floating point rate to the closest exact rational.
+    ///
+    /// Values within 0.01 of an NTSC rate (`n * 1000 / 1001`) snap to it,
+    /// other values are kept to millihertz precision. Returns `None` if `fps`
+    /// is not finite, or rounds to zero or to more than `u32::MAX` at that
+    /// precision.
+    pub fn from_f64(fps: f64) -> Option<Self> {
+        let in_range = |value: f64| value >= 1.0 && value <= u32::MAX as f64;
+        if !fps.is_finite() {
+            return None;
+        }
+        let nominal = fps.round();
+        if (fps - nominal).abs() < 1e-9 {
+            return in_range(nominal).then(|| Self::new(nominal as u32, 1));
+        }
+        let ntsc_nominal = (fps * 1001.0 / 1000.0).round();
+        if in_range(ntsc_nominal * 1000.0) && (fps - ntsc_nominal * 1000.0 / 1001.0).abs() < 0.01 {
+            return Some(Self::new(ntsc_nominal as u32 * 1000, 1001));
+        }
+        let num = (fps * 1000.0).round();
+        if !in_range(num) {
+            return None;
+        }
+        let divisor = gcd(num as u32, 1000);
+        Some(Self::new(num as u32 / divisor, 1000 / divisor))
+    }
+
+    /// Frames per second as a float (for display only).
+    pub fn as_f64(&self) -> f64 {
+        self.num as f64 / self.den as f64
+    }
+
+    /// Integer frames per second used for frame counting in timecode (30 for 29.97).
+    pub fn nominal(&self) -> u32 {
+        (self.num + self.den / 2) / self.den
+    }
+
+    /// Whether this is a 1000/1001 NTSC rate.
+    pub fn is_ntsc(&self) -> bool {
+        self.den == 1001
+    }
+
+    /// Start time of frame `frame` (nanoseconds).
+    pub fn frame_to_time(&self, frame: i64) -> Time {
+        units_to_time(frame, self.num as i128, self.den as i128)
+    }
+
+    /// Index of the frame displayed at `nanos`.
+    pub fn time_to_frame(&self, nanos: Time) -> i64 {
+        time_to_units(nanos, self.num as i128, self.den as i128)
+    }
+
+    /// Snap a time down to the start of the frame it falls in.
+    pub fn snap(&self, nanos: Time) -> Time {
+        self.frame_to_time(self.time_to_frame(nanos))
+    }
+
+    /// Number of frames whose start time lies in `[0, duration)`.
+    pub fn frames_in(&self, duration: Time) -> i64 {
+        if duration <= 0 {
+            0
+        } else {
+            self.time_to_frame(duration - 1) + 1
+        }
+    }
+
+    /// Duration of frame `frame` in nanoseconds (varies by 1ns between frames for NTSC rates).
+    pub fn frame_duration(&self, frame: i64) -> Time {
+        self.frame_to_time(frame + 1) - self.frame_to_time(frame)
+    }
+}
+
+impl Default for FrameRate {
+    fn default() -> Self {
+        Self::FPS_30
+    }
+}
+
+impl std::fmt::Display for FrameRate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.den == 1 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{:.3}", self.as_f64())
+        }
+    }
+}
+
+pub(crate) fn gcd(mut a: u32, mut b: u32) -> u32 {
+    while b != 0 {
+        let t = a % b;
+        a = b;
+        b = t;
+    }
+    a.max(1)
+}
+
+/// Half-open time range `[start, end)` in nanoseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct TimeRange {
+    pub start: Time,
+    pub end: Time,
+}
+
+impl TimeRange {
+    /// Create a range from start and end times.
+    ///
+    /// # Panics
+    /// Panics if `end < start`.
+    pub fn new(start: Time, end: Time) -> Self {
+        assert!(end >= start, "TimeRange end must be >= start");
+        Self { start, end }
+    }
+
+    /// Create a range from a start time and a duration.
+    pub fn from_duration(start: Time, duration: Time) -> Self {
+        Self::new(start, start + duration)
+    }
+
+    /// Length of the range in nanoseconds.
+    pub fn duration(&self) -> Time {
+        self.end - self.start
+    }
+
+    /// Check if the range has zero length.
+    pub fn is_empty(&self) -> bool {
+        self.end == self.start
+    }
+
+    /// Check if a time lies within `[start, end)`.
+    pub fn contains(&self, t: Time) -> bool {
+        t >= self.start && t < self.end
+    }
+
+    /// Check if two ranges share any time (touching ranges do not overlap).
+    pub fn overlaps(&self, other: &TimeRange) -> bool {
+        self.start < other.end && other.start < self.end
+    }
+
+    /// Overlapping part of two ranges, or `None` if they do not overlap.
+    pub fn intersection(&self, other: &TimeRange) -> Option<TimeRange> {
+        let start = self.start.max(other.start);
+        let end = self.end.min(other.end);
+        if start < end {
+            Some(TimeRange { start, end })
+        } else {
+            None
+        }
+    }
+
+    /// Range moved by `offset` nanoseconds.
+    pub fn shifted(&self, offset: Time) -> TimeRange {
+        TimeRange { start: self.start + offset, end: self.end + offset }
+    }
+
+    /// Frame indices `[first, last)` covered by this range at `rate`.
+    pub fn to_frames(&self, rate: FrameRate) -> (i64, i64) {
+        (rate.frames_in(self.start), rate.frames_in(self.end))
+    }
+
+    /// Range spanning frames `[first, last)` at `rate`.
+    pub fn from_frames(first: i64, last: i64, rate: FrameRate) -> Self {
+        Self::new(rate.frame_to_time(first), rate.frame_to_time(last))
+    }
 }
 
 /// Time zero constant
@@ -164,12 +415,12 @@ mod tests {
         assert_eq!(one_nano, 1);
         
         // Test frame-accurate timing at 30fps
-        let frame_time_30fps = from_seconds(1.0 / 30.0);
+        let frame_time_30fps = from_frame_index(1, 30.0);
         let frame_index = to_frame_index(frame_time_30fps, 30.0);
         assert_eq!(frame_index, 1);
         
         // Test frame-accurate timing at 60fps
-        let frame_time_60fps = from_seconds(1.0 / 60.0);
+        let frame_time_60fps = from_frame_index(1, 60.0);
         let frame_index_60 = to_frame_index(frame_time_60fps, 60.0);
         assert_eq!(frame_index_60, 1);
     }
@@ -209,4 +460,90 @@ mod tests {
         let frame_idx = to_frame_index(half_frame, 30.0);
         assert_eq!(frame_idx, 0); // Should floor to 0
     }
+
+    #[test]
+    fn test_frame_rate_from_f64() {
+        assert_eq!(FrameRate::from_f64(29.97), Some(FrameRate::FPS_29_97));
+        assert_eq!(FrameRate::from_f64(23.976), Some(FrameRate::FPS_23_976));
+        assert_eq!(FrameRate::from_f64(59.94), Some(FrameRate::FPS_59_94));
+        assert_eq!(FrameRate::from_f64(25.0), Some(FrameRate::FPS_25));
+        assert_eq!(FrameRate::from_f64(12.5), Some(FrameRate::new(25, 2)));
+        assert_eq!(FrameRate::from_f64(0.005), Some(FrameRate::new(1, 200)));
+
+        // Rates that round to zero or overflow the numerator are rejected
+        for fps in [0.0, -25.0, 0.0001, 5_000_000.5, 1e12, f64::NAN, f64::INFINITY] {
+            assert_eq!(FrameRate::from_f64(fps), None, "{fps}");
+        }
+        assert_eq!(to_frame_index(from_seconds(2.0), 0.0), 0);
+    }
+
+    #[test]
+    fn test_frame_rate_round_trip() {
+        for rate in [
+            FrameRate::FPS_23_976,
+            FrameRate::FPS_24,
+            FrameRate::FPS_29_97,
+            FrameRate::FPS_59_94,
+            FrameRate::FPS_60,
+        ] {
+            for frame in (0..200_000).step_by(997).chain([-5, -1, 1]) {
+                let t = rate.frame_to_time(frame);
+                assert_eq!(rate.time_to_frame(t), frame, "{} frame {}", rate, frame);
+                assert_eq!(rate.time_to_frame(t - 1), frame - 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_frame_rate_ntsc_no_drift() {
+        // One hour of 29.97 holds 107892.1 frames (3600 * 30000 / 1001), so 107893 frames start in it
+        let rate = FrameRate::FPS_29_97;
+        assert_eq!(rate.frames_in(from_seconds(3600.0)), 107893);
+        // Frame 30000 starts at exactly 1001 seconds
+        assert_eq!(rate.frame_to_time(30000), from_seconds(1001.0));
+        assert_eq!(rate.nominal(), 30);
+        assert!(rate.is_ntsc());
+    }
+
+    #[test]
+    fn test_frames_in() {
+        let rate = FrameRate::FPS_30;
+        assert_eq!(rate.frames_in(0), 0);
+        assert_eq!(rate.frames_in(1), 1);
+        assert_eq!(rate.frames_in(from_seconds(1.0)), 30);
+        assert_eq!(rate.frames_in(from_seconds(1.0) + 1), 31);
+    }
+
+    #[test]
+    fn test_sample_index_round_trip() {
+        for rate in [44_100, 48_000, 96_000] {
+            for sample in (0..10_000_000).step_by(7919) {
+                let t = from_sample_index(sample, rate);
+                assert_eq!(to_sample_index(t, rate), sample);
+            }
+        }
+        assert_eq!(to_sample_index(from_seconds(1.0), 48_000), 48_000);
+    }
+
+    #[test]
+    fn test_time_range() {
+        let a = TimeRange::new(from_seconds(1.0), from_seconds(3.0));
+        let b = TimeRange::from_duration(from_seconds(2.0), from_seconds(2.0));
+        let c = TimeRange::new(from_seconds(3.0), from_seconds(4.0));
+
+        assert_eq!(a.duration(), from_seconds(2.0));
+        assert!(a.contains(from_seconds(1.0)));
+        assert!(!a.contains(from_seconds(3.0)));
+        assert!(a.overlaps(&b));
+        assert!(!a.overlaps(&c)); // Touching ranges don't overlap
+        assert_eq!(
+            a.intersection(&b),
+            Some(TimeRange::new(from_seconds(2.0), from_seconds(3.0)))
+        );
+        assert_eq!(a.intersection(&c), None);
+        assert_eq!(a.shifted(from_seconds(1.0)).start, from_seconds(2.0));
+
+        let frames = TimeRange::from_frames(10, 20, FrameRate::FPS_29_97);
+        assert_eq!(frames.to_frames(FrameRate::FPS_29_97), (10, 20));
+    }
 }
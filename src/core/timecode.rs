@@ -0,0 +1,336 @@
+//! SMPTE timecode formatting and parsing.
+//! Supports non-drop-frame (`HH:MM:SS:FF`) for any frame rate and
+//! drop-frame (`HH:MM:SS;FF`) for 29.97 and 59.94.
+//!
+//! Timecode is a display/interchange format only: internally all positions
+//! stay nanoseconds (`Time`) per SPEC.md, converted through exact frame indices.
+
+use std::fmt;
+use crate::core::time::{Time, FrameRate};
+
+/// Error type for timecode operations
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimecodeError {
+    /// String is not of the form `HH:MM:SS:FF` / `HH:MM:SS;FF`
+    InvalidFormat(String),
+    /// A field exceeds its range (e.g. minutes >= 60, frames >= fps)
+    FieldOutOfRange { field: &'static str, value: u32 },
+    /// Drop-frame was requested for a rate that has no drop-frame form
+    DropFrameUnsupported(FrameRate),
+    /// Frame number is skipped in drop-frame counting (e.g. 00:01:00;00)
+    DroppedFrameNumber(String),
+}
+
+impl fmt::Display for TimecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimecodeError::InvalidFormat(s) => write!(f, "Invalid timecode: {:?}", s),
+            TimecodeError::FieldOutOfRange { field, value } => {
+                write!(f, "Timecode {} out of range: {}", field, value)
+            }
+            TimecodeError::DropFrameUnsupported(rate) => {
+                write!(f, "Drop-frame timecode is not defined for {} fps", rate)
+            }
+            TimecodeError::DroppedFrameNumber(s) => {
+                write!(f, "Timecode {} does not exist in drop-frame counting", s)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TimecodeError {}
+
+/// Hours per day, after which timecode wraps to 00:00:00:00.
+const HOURS_PER_DAY: i64 = 24;
+
+/// A SMPTE timecode value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Timecode {
+    pub hours: u32,
+    pub minutes: u32,
+    pub seconds: u32,
+    pub frames: u32,
+    /// Drop-frame counting (displayed with `;` before the frame field)
+    pub drop_frame: bool,
+}
+
+/// Number of frame numbers skipped per minute in drop-frame counting.
+fn dropped_per_minute(rate: FrameRate, drop_frame: bool) -> Result<i64, TimecodeError> {
+    if !drop_frame {
+        return Ok(0);
+    }
+    // Drop-frame exists only for 1000/1001 rates whose nominal rate is a multiple of 30
+    let nominal = rate.nominal();
+    if !rate.is_ntsc() || !nominal.is_multiple_of(30) {
+        return Err(TimecodeError::DropFrameUnsupported(rate));
+    }
+    Ok(nominal as i64 / 15)
+}
+
+impl Timecode {
+    /// Create a timecode from its fields (not validated against a rate).
+    pub fn new(hours: u32, minutes: u32, seconds: u32, frames: u32, drop_frame: bool) -> Self {
+        Self { hours, minutes, seconds, frames, drop_frame }
+    }
+
+    /// Build a timecode from a frame count since 00:00:00:00.
+    ///
+    /// Negative counts and counts past 24 hours wrap around the day,
+    /// as SMPTE timecode does.
+    pub fn from_frame(frame: i64, rate: FrameRate, drop_frame: bool) -> Result<Self, TimecodeError> {
+        let nominal = rate.nominal() as i64;
+        let drop = dropped_per_minute(rate, drop_frame)?;
+
+        let frames_per_day = nominal * 3600 * HOURS_PER_DAY - drop * 54 * HOURS_PER_DAY;
+        let mut frame = frame.rem_euclid(frames_per_day);
+
+        if drop > 0 {
+            // Re-insert the skipped frame numbers so the count can be split naively
+            let frames_per_10_min = nominal * 600 - drop * 9;
+            let frames_per_min = nominal * 60 - drop;
+            let tens = frame / frames_per_10_min;
+            let rem = frame % frames_per_10_min;
+            frame += drop * 9 * tens;
+            if rem > drop {
+                frame += drop * ((rem - drop) / frames_per_min);
+            }
+        }
+
+        Ok(Self {
+            hours: (frame / (nominal * 3600)) as u32,
+            minutes: (frame / (nominal * 60) % 60) as u32,
+            seconds: (frame / nominal % 60) as u32,
+            frames: (frame % nominal) as u32,
+            drop_frame,
+        })
+    }
+
+    /// Frame count since 00:00:00:00 represented by this timecode.
+    pub fn to_frame(&self, rate: FrameRate) -> Result<i64, TimecodeError> {
+        self.validate(rate)?;
+        let nominal = rate.nominal() as i64;
+        let drop = dropped_per_minute(rate, self.drop_frame)?;
+        let total_minutes = self.hours as i64 * 60 + self.minutes as i64;
+        let nominal_frames = (total_minutes * 60 + self.seconds as i64) * nominal + self.frames as i64;
+        Ok(nominal_frames - drop * (total_minutes - total_minutes / 10))
+    }
+
+    /// Check every field against `rate`, including skipped drop-frame numbers.
+    pub fn validate(&self, rate: FrameRate) -> Result<(), TimecodeError> {
+        let drop = dropped_per_minute(rate, self.drop_frame)?;
+        if self.hours as i64 >= HOURS_PER_DAY {
+            return Err(TimecodeError::FieldOutOfRange { field: "hours", value: self.hours });
+        }
+        if self.minutes >= 60 {
+            return Err(TimecodeError::FieldOutOfRange { field: "minutes", value: self.minutes });
+        }
+        if self.seconds >= 60 {
+            return Err(TimecodeError::FieldOutOfRange { field: "seconds", value: self.seconds });
+        }
+        if self.frames >= rate.nominal() {
+            return Err(TimecodeError::FieldOutOfRange { field: "frames", value: self.frames });
+        }
+        if drop > 0 && self.seconds == 0 && !self.minutes.is_multiple_of(10) && (self.frames as i64) < drop {
+            return Err(TimecodeError::DroppedFrameNumber(self.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Parse `HH:MM:SS:FF` (non-drop) or `HH:MM:SS;FF` / `HH:MM:SS.FF` (drop-frame).
+    pub fn parse(s: &str, rate: FrameRate) -> Result<Self, TimecodeError> {
+        let trimmed = s.trim();
+        let invalid = || TimecodeError::InvalidFormat(s.to_string());
+
+        let split = trimmed.rfind([':', ';', '.']).ok_or_else(invalid)?;
+        let drop_frame = trimmed[split..].starts_with([';', '.']);
+
+        let mut fields = trimmed[..split].split(':').chain(std::iter::once(&trimmed[split + 1..]));
+        let mut next = || -> Result<u32, TimecodeError> {
+            let field = fields.next().ok_or_else(invalid)?;
+            if field.is_empty() || !field.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(invalid());
+            }
+            field.parse().map_err(|_| invalid())
+        };
+        let tc = Self::new(next()?, next()?, next()?, next()?, drop_frame);
+        if fields.next().is_some() {
+            return Err(invalid());
+        }
+
+        tc.validate(rate)?;
+        Ok(tc)
+    }
+}
+
+impl fmt::Display for Timecode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let separator = if self.drop_frame { ';' } else { ':' };
+        write!(
+            f,
+            "{:02}:{:02}:{:02}{}{:02}",
+            self.hours, self.minutes, self.seconds, separator, self.frames
+        )
+    }
+}
+
+/// Timecode display settings: frame rate, counting mode and start offset.
+///
+/// Converts timeline positions (nanoseconds, 0 = timeline start) to timecode
+/// strings and back. `start` is the timecode shown at timeline position 0,
+/// e.g. 01:00:00:00 for a broadcast program start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimecodeFormat {
+    pub rate: FrameRate,
+    pub drop_frame: bool,
+    pub start: Timecode,
+}
+
+impl TimecodeFormat {
+    /// Create a format starting at 00:00:00:00.
+    ///
+    /// Drop-frame is silently disabled for rates that do not support it.
+    pub fn new(rate: FrameRate, drop_frame: bool) -> Self {
+        let drop_frame = drop_frame && dropped_per_minute(rate, true).is_ok();
+        Self {
+            rate,
+            drop_frame,
+            start: Timecode::new(0, 0, 0, 0, drop_frame),
+        }
+    }
+
+    /// Set the timecode shown at timeline position 0.
+    pub fn with_start(mut self, mut start: Timecode) -> Result<Self, TimecodeError> {
+        start.drop_frame = self.drop_frame;
+        start.validate(self.rate)?;
+        self.start = start;
+        Ok(self)
+    }
+
+    /// Frame count of the start timecode.
+    fn start_frame(&self) -> i64 {
+        self.start.to_frame(self.rate).unwrap_or(0)
+    }
+
+    /// Timecode at a timeline position (the frame containing `position`).
+    pub fn timecode_at(&self, position: Time) -> Timecode {
+        let frame = self.rate.time_to_frame(position) + self.start_frame();
+        Timecode::from_frame(frame, self.rate, self.drop_frame)
+            .expect("drop-frame support checked in TimecodeFormat::new")
+    }
+
+    /// Format a timeline position as a timecode string.
+    pub fn format(&self, position: Time) -> String {
+        self.timecode_at(position).to_string()
+    }
+
+    /// Parse a timecode string into a timeline position (start of that frame).
+    ///
+    /// Either separator style is accepted; the frame count is interpreted
+    /// using this format's drop-frame setting.
+    pub fn parse(&self, s: &str) -> Result<Time, TimecodeError> {
+        let mut tc = Timecode::parse(s, self.rate)?;
+        tc.drop_frame = self.drop_frame;
+        let frame = tc.to_frame(self.rate)? - self.start_frame();
+        Ok(self.rate.frame_to_time(frame))
+    }
+}
+
+impl Default for TimecodeFormat {
+    fn default() -> Self {
+        Self::new(FrameRate::default(), false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::time;
+
+    #[test]
+    fn test_non_drop_format() {
+        let format = TimecodeFormat::new(FrameRate::FPS_25, false);
+        assert_eq!(format.format(0), "00:00:00:00");
+        assert_eq!(format.format(time::from_seconds(3661.0) + FrameRate::FPS_25.frame_to_time(12)), "01:01:01:12");
+    }
+
+    #[test]
+    fn test_drop_frame_boundaries() {
+        let rate = FrameRate::FPS_29_97;
+        // Frame 1799 is the last frame of minute 0; 1800 skips ;00 and ;01
+        assert_eq!(Timecode::from_frame(1799, rate, true).unwrap().to_string(), "00:00:59;29");
+        assert_eq!(Timecode::from_frame(1800, rate, true).unwrap().to_string(), "00:01:00;02");
+        // Every tenth minute keeps its frames
+        assert_eq!(Timecode::from_frame(17982, rate, true).unwrap().to_string(), "00:10:00;00");
+        // One hour of 29.97 DF is 107892 frames
+        assert_eq!(Timecode::from_frame(107892, rate, true).unwrap().to_string(), "01:00:00;00");
+    }
+
+    #[test]
+    fn test_drop_frame_59_94() {
+        let rate = FrameRate::FPS_59_94;
+        assert_eq!(Timecode::from_frame(3600, rate, true).unwrap().to_string(), "00:01:00;04");
+        assert_eq!(Timecode::from_frame(215784, rate, true).unwrap().to_string(), "01:00:00;00");
+    }
+
+    #[test]
+    fn test_drop_frame_round_trip() {
+        for rate in [FrameRate::FPS_29_97, FrameRate::FPS_59_94] {
+            for frame in (0..300_000).step_by(37) {
+                let tc = Timecode::from_frame(frame, rate, true).unwrap();
+                assert_eq!(tc.to_frame(rate).unwrap(), frame, "{}", tc);
+            }
+        }
+    }
+
+    #[test]
+    fn test_drop_frame_unsupported() {
+        assert_eq!(
+            Timecode::from_frame(0, FrameRate::FPS_25, true),
+            Err(TimecodeError::DropFrameUnsupported(FrameRate::FPS_25))
+        );
+        assert!(!TimecodeFormat::new(FrameRate::FPS_24, true).drop_frame);
+    }
+
+    #[test]
+    fn test_parse() {
+        let rate = FrameRate::FPS_29_97;
+        let tc = Timecode::parse("01:02:03;04", rate).unwrap();
+        assert_eq!(tc, Timecode::new(1, 2, 3, 4, true));
+        assert!(!Timecode::parse("01:02:03:04", rate).unwrap().drop_frame);
+
+        assert!(matches!(Timecode::parse("01:02:03", rate), Err(TimecodeError::InvalidFormat(_))));
+        assert!(matches!(Timecode::parse("aa:02:03:04", rate), Err(TimecodeError::InvalidFormat(_))));
+        assert!(matches!(
+            Timecode::parse("00:00:00:30", rate),
+            Err(TimecodeError::FieldOutOfRange { field: "frames", .. })
+        ));
+        assert!(matches!(
+            Timecode::parse("00:01:00;01", rate),
+            Err(TimecodeError::DroppedFrameNumber(_))
+        ));
+    }
+
+    #[test]
+    fn test_start_offset() {
+        let format = TimecodeFormat::new(FrameRate::FPS_29_97, true)
+            .with_start(Timecode::new(1, 0, 0, 0, true))
+            .unwrap();
+        assert_eq!(format.format(0), "01:00:00;00");
+        assert_eq!(format.parse("01:00:00;00").unwrap(), 0);
+
+        let position = FrameRate::FPS_29_97.frame_to_time(1800);
+        assert_eq!(format.format(position), "01:01:00;02");
+        assert_eq!(format.parse("01:01:00;02").unwrap(), position);
+
+        // Positions before the start offset count backwards from it
+        assert_eq!(format.format(FrameRate::FPS_29_97.frame_to_time(-1)), "00:59:59;29");
+    }
+
+    #[test]
+    fn test_wraps_at_24_hours() {
+        let rate = FrameRate::FPS_24;
+        let day = 24 * 3600 * 24;
+        assert_eq!(Timecode::from_frame(day, rate, false).unwrap().to_string(), "00:00:00:00");
+        assert_eq!(Timecode::from_frame(-1, rate, false).unwrap().to_string(), "23:59:59:23");
+    }
+}
@@ -1,6 +1,6 @@
 //! Stream metadata information extracted from media files.
 
-use crate::core::time::Time;
+use crate::core::time::{Time, FrameRate};
 
 /// Information about a video or audio stream
 #[derive(Debug, Clone)]
@@ -16,7 +16,7 @@ pub struct VideoStreamInfo {
     pub stream_info: StreamInfo,
     pub width: u32,
     pub height: u32,
-    pub fps: f64,
+    pub fps: FrameRate,
     pub pixel_format: String,
 }
 
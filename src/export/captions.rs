@@ -0,0 +1,380 @@
+//! Caption tracks in export: muxed as soft subtitle streams, or burnt into
+//! the frames.
+//!
+//! Burn-in lays cue text out with egui's font rendering (the same fonts the
+//! UI uses) and rasterizes the glyph meshes on the CPU, since export has no
+//! GPU surface (see `export::composite`).
+
+use egui::epaint::text::{FontDefinitions, Fonts, LayoutJob, TextFormat};
+use egui::{Align, Color32, FontId, Galley, Stroke};
+use std::sync::Arc;
+
+use crate::decode::decoder::VideoFrame;
+use crate::export::composite;
+use crate::export::encoder::{Encoder, Subtitle, SubtitleStream};
+use crate::timeline::{Cue, CueAlign, CueId, Timeline, Track};
+
+/// Largest font atlas side, in texels.
+const MAX_TEXTURE_SIDE: usize = 8192;
+/// Title-safe margin for default placement, as a fraction of the frame.
+const SAFE_MARGIN: f32 = 0.05;
+/// Text size, as a fraction of the frame height.
+const TEXT_SIZE: f32 = 0.055;
+/// Colour of the box drawn behind each line.
+const BOX_COLOR: [u8; 4] = [0, 0, 0, 160];
+
+/// How caption tracks are exported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CaptionMode {
+    /// Caption tracks are left out
+    Off,
+    /// Cues are drawn into the video frames
+    BurnIn,
+    /// Each enabled caption track becomes a `mov_text` subtitle stream
+    #[default]
+    Soft,
+}
+
+/// Add a subtitle stream to `encoder` for each enabled caption track of
+/// `timeline` that has cues, in track order.
+pub fn add_subtitle_streams(encoder: &mut Encoder, timeline: &Timeline) {
+    for track in timeline.caption_tracks().filter(|t| t.enabled && !t.cues.is_empty()) {
+        encoder.add_subtitle_stream(subtitle_stream(timeline, track));
+    }
+}
+
+/// Subtitle stream for a caption track, titled with its display name.
+///
+/// `mov_text` keeps bold, italic, underline and colour; it has no per-cue
+/// placement, so players show every cue at their default position.
+pub fn subtitle_stream(timeline: &Timeline, track: &Track) -> SubtitleStream {
+    SubtitleStream {
+        title: timeline.track_name(track.id).unwrap_or_default(),
+        subtitles: track.cues.iter()
+            .map(|cue| Subtitle { range: cue.range(), text: ass_text(cue) })
+            .collect(),
+    }
+}
+
+/// ASS dialogue text for a cue: style overrides, then the lines joined by `\N`.
+fn ass_text(cue: &Cue) -> String {
+    let mut text = String::new();
+    if cue.style.bold {
+        text.push_str("{\\b1}");
+    }
+    if cue.style.italic {
+        text.push_str("{\\i1}");
+    }
+    if cue.style.underline {
+        text.push_str("{\\u1}");
+    }
+    if let Some([r, g, b]) = cue.style.color {
+        text.push_str(&format!("{{\\c&H{:02X}{:02X}{:02X}&}}", b, g, r));
+    }
+    text.push_str(&cue.text.replace("\r\n", "\n").replace('\n', "\\N"));
+    text
+}
+
+/// Draws cues onto export frames.
+///
+/// The rendered text is kept until the set of cues shown changes, so a cue
+/// is laid out and rasterized once, not once per frame.
+pub struct CaptionRenderer {
+    fonts: Fonts,
+    width: u32,
+    height: u32,
+    cache: Option<(Vec<CueId>, VideoFrame)>, // Cues shown and their layer
+}
+
+impl CaptionRenderer {
+    /// Create a renderer for frames of the given size.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            fonts: Fonts::new(1.0, MAX_TEXTURE_SIDE, FontDefinitions::default()),
+            width,
+            height,
+            cache: None,
+        }
+    }
+
+    /// Draw `cues` (in start order) over `frame`.
+    ///
+    /// Cues with a default vertical position are stacked upwards from the
+    /// bottom margin, the first cue lowest.
+    pub fn draw(&mut self, frame: &mut VideoFrame, cues: &[&Cue]) {
+        if cues.is_empty() {
+            return;
+        }
+        let ids: Vec<CueId> = cues.iter().map(|cue| cue.id).collect();
+        if !matches!(&self.cache, Some((cached, _)) if *cached == ids) {
+            let layer = self.render(cues);
+            self.cache = Some((ids, layer));
+        }
+        if let Some((_, layer)) = &self.cache {
+            composite::blend_over(frame, layer, 1.0);
+        }
+    }
+
+    /// Render `cues` onto a transparent layer at frame size.
+    fn render(&self, cues: &[&Cue]) -> VideoFrame {
+        // Lets the font atlas be rebuilt if it has filled up
+        self.fonts.begin_frame(1.0, MAX_TEXTURE_SIDE);
+
+        let (width, height) = (self.width as f32, self.height as f32);
+        let size = (height * TEXT_SIZE).max(8.0);
+        let galleys: Vec<Arc<Galley>> = cues.iter().map(|cue| self.layout(cue, size)).collect();
+        let atlas = self.fonts.image();
+
+        let mut layer = composite::transparent_frame(self.width, self.height);
+        let mut bottom = height * (1.0 - SAFE_MARGIN);
+        for (cue, galley) in cues.iter().zip(&galleys) {
+            let x = match (cue.position.x, cue.style.align) {
+                (Some(x), _) => x * width,
+                (None, CueAlign::Start) => SAFE_MARGIN * width,
+                (None, CueAlign::Center) => 0.5 * width,
+                (None, CueAlign::End) => (1.0 - SAFE_MARGIN) * width,
+            };
+            let y = match cue.position.y {
+                Some(y) => y * height,
+                None => {
+                    bottom -= galley.rect.height();
+                    let top = bottom;
+                    bottom -= size * 0.25;
+                    top
+                }
+            };
+            let offset = egui::vec2(x, y);
+
+            let pad = size * 0.25;
+            for row in galley.rows.iter().filter(|row| !row.visuals.mesh_bounds.is_negative()) {
+                let bounds = row.visuals.mesh_bounds;
+                fill_rect(
+                    &mut layer,
+                    [bounds.min.x + offset.x - pad, row.rect.min.y + offset.y],
+                    [bounds.max.x + offset.x + pad, row.rect.max.y + offset.y],
+                    BOX_COLOR,
+                );
+            }
+            // Faux bold: the default fonts have no bold face, so the glyphs
+            // are drawn a second time, shifted right
+            let strokes: &[f32] = if cue.style.bold { &[0.0, (size / 24.0).max(1.0)] } else { &[0.0] };
+            for row in &galley.rows {
+                for shift in strokes {
+                    draw_mesh(&mut layer, &row.visuals.mesh, offset + egui::vec2(*shift, 0.0), &atlas);
+                }
+            }
+        }
+        layer
+    }
+
+    /// Lay a cue's text out at `size`, anchored horizontally at x = 0 by its
+    /// alignment and wrapped inside the title-safe area.
+    fn layout(&self, cue: &Cue, size: f32) -> Arc<Galley> {
+        let color = cue.style.color
+            .map_or(Color32::WHITE, |[r, g, b]| Color32::from_rgb(r, g, b));
+        let mut job = LayoutJob::default();
+        job.wrap.max_width = self.width as f32 * (1.0 - 2.0 * SAFE_MARGIN);
+        job.halign = match cue.style.align {
+            CueAlign::Start => Align::LEFT,
+            CueAlign::Center => Align::Center,
+            CueAlign::End => Align::RIGHT,
+        };
+        let underline = if cue.style.underline {
+            Stroke::new((size / 16.0).max(1.0), color)
+        } else {
+            Stroke::NONE
+        };
+        job.append(&cue.text.replace("\r\n", "\n"), 0.0, TextFormat {
+            font_id: FontId::proportional(size),
+            color,
+            italics: cue.style.italic,
+            underline,
+            ..Default::default()
+        });
+        self.fonts.layout_job(job)
+    }
+}
+
+/// Fill the pixels whose centres lie in `[min, max)` with `rgba`, blended over `layer`.
+fn fill_rect(layer: &mut VideoFrame, min: [f32; 2], max: [f32; 2], rgba: [u8; 4]) {
+    let x0 = min[0].round().clamp(0.0, layer.width as f32) as u32;
+    let x1 = max[0].round().clamp(0.0, layer.width as f32) as u32;
+    let y0 = min[1].round().clamp(0.0, layer.height as f32) as u32;
+    let y1 = max[1].round().clamp(0.0, layer.height as f32) as u32;
+    for y in y0..y1 {
+        for x in x0..x1 {
+            let i = ((y * layer.width + x) * 4) as usize;
+            layer_over(&mut layer.data[i..i + 4], rgba);
+        }
+    }
+}
+
+/// Rasterize a text mesh onto `layer`, moved by `offset`.
+///
+/// Each pixel centre inside a triangle samples the font atlas coverage at
+/// its interpolated texel (nearest-neighbour) and blends the vertex colour
+/// over the layer at that coverage.
+fn draw_mesh(layer: &mut VideoFrame, mesh: &egui::Mesh, offset: egui::Vec2, atlas: &egui::FontImage) {
+    let [atlas_width, atlas_height] = atlas.size;
+    for triangle in mesh.indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| &mesh.vertices[triangle[i] as usize]);
+        let [pa, pb, pc] = [a, b, c].map(|v| v.pos + offset);
+        let area = (pb - pa).x * (pc - pa).y - (pb - pa).y * (pc - pa).x;
+        if area.abs() < f32::EPSILON {
+            continue;
+        }
+        let x0 = pa.x.min(pb.x).min(pc.x).floor().max(0.0) as u32;
+        let x1 = (pa.x.max(pb.x).max(pc.x).ceil().max(0.0) as u32).min(layer.width);
+        let y0 = pa.y.min(pb.y).min(pc.y).floor().max(0.0) as u32;
+        let y1 = (pa.y.max(pb.y).max(pc.y).ceil().max(0.0) as u32).min(layer.height);
+        let color = a.color;
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let p = egui::pos2(x as f32 + 0.5, y as f32 + 0.5);
+                // Barycentric weights of b and c; outside if any is negative
+                let wb = ((p - pa).x * (pc - pa).y - (p - pa).y * (pc - pa).x) / area;
+                let wc = ((pb - pa).x * (p - pa).y - (pb - pa).y * (p - pa).x) / area;
+                let wa = 1.0 - wb - wc;
+                if wa < 0.0 || wb < 0.0 || wc < 0.0 {
+                    continue;
+                }
+                let u = wa * a.uv.x + wb * b.uv.x + wc * c.uv.x;
+                let v = wa * a.uv.y + wb * b.uv.y + wc * c.uv.y;
+                let tx = (u.max(0.0) as usize).min(atlas_width - 1);
+                let ty = (v.max(0.0) as usize).min(atlas_height - 1);
+                let coverage = atlas.pixels[ty * atlas_width + tx];
+                let alpha = (coverage * color.a() as f32).round() as u8;
+                if alpha == 0 {
+                    continue;
+                }
+                let i = ((y * layer.width + x) * 4) as usize;
+                layer_over(&mut layer.data[i..i + 4], [color.r(), color.g(), color.b(), alpha]);
+            }
+        }
+    }
+}
+
+/// Blend a straight-alpha RGBA8 pixel over a pixel of a (possibly
+/// transparent) layer, keeping the layer straight-alpha.
+#[inline]
+fn layer_over(dst: &mut [u8], src: [u8; 4]) {
+    let src_alpha = src[3] as f32 / 255.0;
+    let dst_alpha = dst[3] as f32 / 255.0 * (1.0 - src_alpha);
+    let alpha = src_alpha + dst_alpha;
+    if alpha <= 0.0 {
+        return;
+    }
+    for c in 0..3 {
+        dst[c] = ((src[c] as f32 * src_alpha + dst[c] as f32 * dst_alpha) / alpha).round() as u8;
+    }
+    dst[3] = (alpha * 255.0).round() as u8;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::time::constants::NANOS_PER_SECOND;
+    use crate::core::time::FrameRate;
+    use crate::timeline::{CuePosition, CueStyle, TrackType};
+
+    #[test]
+    fn test_subtitle_stream_text() {
+        let mut timeline = Timeline::new();
+        let track_id = timeline.add_track(TrackType::Caption);
+        let styled = CueStyle { italic: true, color: Some([255, 128, 0]), ..Default::default() };
+        timeline.add_cue(track_id, Cue::new(1, 0, NANOS_PER_SECOND, "Hello\nthere")).unwrap();
+        timeline.add_cue(track_id, Cue::new(2, NANOS_PER_SECOND, 2 * NANOS_PER_SECOND, "Bye")
+            .with_style(styled)).unwrap();
+
+        let stream = subtitle_stream(&timeline, timeline.track(track_id).unwrap());
+        assert_eq!(stream.title, "C1");
+        assert_eq!(stream.subtitles.len(), 2);
+        assert_eq!(stream.subtitles[0].text, "Hello\\Nthere");
+        assert_eq!(stream.subtitles[0].range.end, NANOS_PER_SECOND);
+        assert_eq!(stream.subtitles[1].text, "{\\i1}{\\c&H0080FF&}Bye");
+    }
+
+    #[test]
+    fn test_soft_subtitle_streams() {
+        let mut timeline = Timeline::new();
+        let c1 = timeline.add_track(TrackType::Caption);
+        let c2 = timeline.add_track(TrackType::Caption);
+        let c3 = timeline.add_track(TrackType::Caption);
+        timeline.add_cue(c1, Cue::new(1, 0, NANOS_PER_SECOND, "One")).unwrap();
+        timeline.add_cue(c2, Cue::new(2, 0, NANOS_PER_SECOND, "Two")).unwrap();
+        timeline.track_mut(c2).unwrap().set_enabled(false);
+        timeline.track_mut(c3).unwrap().name = "Empty".to_string();
+
+        let mut encoder = Encoder::new(std::path::Path::new("out.mp4"), 64, 36, FrameRate::FPS_25, 1, 1, 48_000, 2).unwrap();
+        add_subtitle_streams(&mut encoder, &timeline);
+        assert_eq!(encoder.subtitle_streams(), [subtitle_stream(&timeline, timeline.track(c1).unwrap())]);
+    }
+
+    /// Bounding box `(min_x, min_y, max_x, max_y)` of pixels brighter than
+    /// black in `frame`, or `None` if there are none.
+    fn lit_bounds(frame: &VideoFrame) -> Option<(u32, u32, u32, u32)> {
+        let mut bounds: Option<(u32, u32, u32, u32)> = None;
+        for y in 0..frame.height {
+            for x in 0..frame.width {
+                let i = ((y * frame.width + x) * 4) as usize;
+                if frame.data[i..i + 3].iter().any(|&c| c > 100) {
+                    let (x0, y0, x1, y1) = bounds.unwrap_or((x, y, x, y));
+                    bounds = Some((x0.min(x), y0.min(y), x1.max(x), y1.max(y)));
+                }
+            }
+        }
+        bounds
+    }
+
+    #[test]
+    fn test_burn_in_placement() {
+        let mut renderer = CaptionRenderer::new(320, 180);
+
+        // Default placement: centred, near the bottom
+        let cue = Cue::new(1, 0, NANOS_PER_SECOND, "Hello");
+        let mut frame = composite::black_frame(320, 180);
+        renderer.draw(&mut frame, &[&cue]);
+        let (x0, y0, x1, y1) = lit_bounds(&frame).expect("text drawn");
+        assert!(y0 > 120 && y1 < 180 - 8, "text at {}..{}", y0, y1);
+        assert!(x0 > 100 && x1 < 220 && (x0 + x1) / 2 > 150 && (x0 + x1) / 2 < 170);
+
+        // Explicit position, left-aligned at the anchor
+        let cue = Cue::new(2, 0, NANOS_PER_SECOND, "Top")
+            .with_position(CuePosition { x: Some(0.1), y: Some(0.1) })
+            .with_style(CueStyle { align: CueAlign::Start, ..Default::default() });
+        let mut frame = composite::black_frame(320, 180);
+        renderer.draw(&mut frame, &[&cue]);
+        let (x0, y0, _, y1) = lit_bounds(&frame).expect("text drawn");
+        assert!(y0 >= 18 && y1 < 60, "text at {}..{}", y0, y1);
+        assert!((32..40).contains(&x0), "text starts at {}", x0);
+    }
+
+    #[test]
+    fn test_burn_in_colour_and_stacking() {
+        let mut renderer = CaptionRenderer::new(320, 180);
+        let first = Cue::new(1, 0, NANOS_PER_SECOND, "One")
+            .with_style(CueStyle { color: Some([255, 0, 0]), ..Default::default() });
+        let second = Cue::new(2, 0, NANOS_PER_SECOND, "Two");
+        let mut frame = composite::black_frame(320, 180);
+        renderer.draw(&mut frame, &[&first, &second]);
+
+        // The first cue is lowest, in red; the second is above it, in white
+        let (mut red_y, mut white_y) = (0, u32::MAX);
+        for y in 0..frame.height {
+            for x in 0..frame.width {
+                let i = ((y * frame.width + x) * 4) as usize;
+                match frame.data[i..i + 3] {
+                    [r, g, _] if r > 200 && g < 50 => red_y = red_y.max(y),
+                    [r, g, b] if r > 200 && g > 200 && b > 200 => white_y = white_y.min(y),
+                    _ => {}
+                }
+            }
+        }
+        assert!(red_y > 0 && white_y < red_y);
+
+        // No cues: the frame is untouched
+        let mut frame = composite::black_frame(32, 18);
+        CaptionRenderer::new(32, 18).draw(&mut frame, &[]);
+        assert_eq!(frame.data, composite::black_frame(32, 18).data);
+    }
+}
@@ -0,0 +1,285 @@
+//! CPU compositing of RGBA8 frames for offline export.
+//! The real-time path composites on the GPU (see `render::compositor`);
+//! export has no surface, so layers are blended here instead.
+
+use crate::decode::decoder::VideoFrame;
+use crate::render::renderer::Layer;
+use crate::timeline::TransitionKind;
+
+/// Create an opaque black RGBA8 frame.
+pub fn black_frame(width: u32, height: u32) -> VideoFrame {
+    let mut data = vec![0u8; (width * height * 4) as usize];
+    for pixel in data.chunks_exact_mut(4) {
+        pixel[3] = 255;
+    }
+    VideoFrame {
+        data,
+        width,
+        height,
+        timestamp: 0,
+    }
+}
+
+/// Create a fully transparent RGBA8 frame (a layer to composite onto).
+pub fn transparent_frame(width: u32, height: u32) -> VideoFrame {
+    VideoFrame {
+        data: vec![0u8; (width * height * 4) as usize],
+        width,
+        height,
+        timestamp: 0,
+    }
+}
+
+/// Create an RGBA8 frame filled with one colour.
+pub fn solid_frame(width: u32, height: u32, rgba: [u8; 4]) -> VideoFrame {
+    VideoFrame {
+        data: rgba.repeat((width * height) as usize),
+        width,
+        height,
+        timestamp: 0,
+    }
+}
+
+/// Render a transition `progress` (0.0-1.0) of the way from `from` to `to`
+/// as a layer of `width` x `height`.
+///
+/// A missing frame (e.g. a decode failure) leaves its part transparent.
+/// Dips fade the outgoing frame to their colour over the first half and
+/// the incoming frame up from it over the second.
+pub fn transition_frame(
+    kind: TransitionKind,
+    from: Option<&VideoFrame>,
+    to: Option<&VideoFrame>,
+    progress: f32,
+    width: u32,
+    height: u32,
+) -> VideoFrame {
+    let mut layer = transparent_frame(width, height);
+    match kind.dip_color() {
+        Some(color) => {
+            let (frame, amount) = if progress < 0.5 {
+                (from, progress * 2.0)
+            } else {
+                (to, (1.0 - progress) * 2.0)
+            };
+            if let Some(frame) = frame {
+                blend_over(&mut layer, frame, 1.0);
+            }
+            blend_over(&mut layer, &solid_frame(width, height, color), amount);
+        }
+        None => {
+            if let Some(from) = from {
+                blend_over(&mut layer, from, 1.0);
+            }
+            if let Some(to) = to {
+                blend_over(&mut layer, to, progress);
+            }
+        }
+    }
+    layer
+}
+
+/// Alpha-blend `src` over `dst` ("source over"), centred on `dst`.
+///
+/// `opacity` (0.0-1.0) multiplies the source alpha. Parts of `src` outside
+/// `dst` are clipped; no scaling is applied.
+pub fn blend_over(dst: &mut VideoFrame, src: &VideoFrame, opacity: f32) {
+    let opacity = opacity.clamp(0.0, 1.0);
+    if opacity == 0.0 {
+        return;
+    }
+
+    let offset_x = (dst.width as i64 - src.width as i64) / 2;
+    let offset_y = (dst.height as i64 - src.height as i64) / 2;
+
+    for src_y in 0..src.height as i64 {
+        let dst_y = src_y + offset_y;
+        if dst_y < 0 || dst_y >= dst.height as i64 {
+            continue;
+        }
+        for src_x in 0..src.width as i64 {
+            let dst_x = src_x + offset_x;
+            if dst_x < 0 || dst_x >= dst.width as i64 {
+                continue;
+            }
+            let s = ((src_y * src.width as i64 + src_x) * 4) as usize;
+            let d = ((dst_y * dst.width as i64 + dst_x) * 4) as usize;
+            blend_pixel(&mut dst.data[d..d + 4], &src.data[s..s + 4], opacity);
+        }
+    }
+}
+
+/// Draw `layer` onto `dst` with its transform, matching the GPU compositor
+/// (see `render::shader::VERTEX_SHADER`): the frame is cropped, then flipped,
+/// scaled and rotated about its anchor, and the anchor placed at its position.
+///
+/// Output pixels are mapped back into the frame and sampled nearest-neighbour,
+/// then blended "source over" at the layer's opacity.
+pub fn draw_layer(dst: &mut VideoFrame, layer: &Layer) {
+    let (src, transform) = (&layer.frame, &layer.transform);
+    let opacity = transform.opacity.clamp(0.0, 1.0);
+    let crop = transform.crop.clamped();
+    if opacity == 0.0 || src.width == 0 || src.height == 0 || crop.is_empty() {
+        return;
+    }
+
+    let flip = |flipped: bool| if flipped { -1.0 } else { 1.0 };
+    let scale_x = transform.scale.0 as f64 * flip(transform.flip_horizontal);
+    let scale_y = transform.scale.1 as f64 * flip(transform.flip_vertical);
+    if scale_x == 0.0 || scale_y == 0.0 {
+        return;
+    }
+    let (sin, cos) = (transform.rotation as f64).to_radians().sin_cos();
+    let (frame_w, frame_h) = (src.width as f64, src.height as f64);
+    let anchor = (transform.anchor.0 as f64 * frame_w, transform.anchor.1 as f64 * frame_h);
+    let origin = (transform.position.0 as f64 * dst.width as f64, transform.position.1 as f64 * dst.height as f64);
+
+    // Visible part of the frame, in frame pixels
+    let (left, right) = (crop.left as f64 * frame_w, (1.0 - crop.right as f64) * frame_w);
+    let (top, bottom) = (crop.top as f64 * frame_h, (1.0 - crop.bottom as f64) * frame_h);
+
+    // Only visit the output pixels the transformed crop rectangle can cover
+    let to_output = |x: f64, y: f64| {
+        let (px, py) = ((x - anchor.0) * scale_x, (y - anchor.1) * scale_y);
+        (origin.0 + px * cos - py * sin, origin.1 + px * sin + py * cos)
+    };
+    let corners = [to_output(left, top), to_output(right, top), to_output(left, bottom), to_output(right, bottom)];
+    let min = |f: fn(&(f64, f64)) -> f64| corners.iter().map(f).fold(f64::INFINITY, f64::min);
+    let max = |f: fn(&(f64, f64)) -> f64| corners.iter().map(f).fold(f64::NEG_INFINITY, f64::max);
+    let x_range = min(|c| c.0).floor().max(0.0) as u32..max(|c| c.0).ceil().min(dst.width as f64) as u32;
+    let y_range = min(|c| c.1).floor().max(0.0) as u32..max(|c| c.1).ceil().min(dst.height as f64) as u32;
+
+    for y in y_range {
+        for x in x_range.clone() {
+            // Undo rotation, then scale and flip, from the pixel centre
+            let (dx, dy) = (x as f64 + 0.5 - origin.0, y as f64 + 0.5 - origin.1);
+            let frame_x = (dx * cos + dy * sin) / scale_x + anchor.0;
+            let frame_y = (dy * cos - dx * sin) / scale_y + anchor.1;
+            if frame_x < left || frame_x >= right || frame_y < top || frame_y >= bottom {
+                continue;
+            }
+            let src_x = (frame_x as u32).min(src.width - 1);
+            let src_y = (frame_y as u32).min(src.height - 1);
+            let s = ((src_y * src.width + src_x) * 4) as usize;
+            let d = ((y * dst.width + x) * 4) as usize;
+            blend_pixel(&mut dst.data[d..d + 4], &src.data[s..s + 4], opacity);
+        }
+    }
+}
+
+/// Blend one RGBA8 pixel over another.
+#[inline]
+fn blend_pixel(dst: &mut [u8], src: &[u8], opacity: f32) {
+    let alpha = src[3] as f32 / 255.0 * opacity;
+    if alpha >= 1.0 {
+        dst.copy_from_slice(src);
+        return;
+    }
+    for c in 0..3 {
+        dst[c] = (src[c] as f32 * alpha + dst[c] as f32 * (1.0 - alpha)).round() as u8;
+    }
+    let dst_alpha = dst[3] as f32 / 255.0;
+    dst[3] = ((alpha + dst_alpha * (1.0 - alpha)) * 255.0).round() as u8;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opaque_layer_replaces() {
+        let mut dst = black_frame(4, 4);
+        blend_over(&mut dst, &solid_frame(4, 4, [200, 100, 50, 255]), 1.0);
+        assert_eq!(&dst.data[0..4], &[200, 100, 50, 255]);
+    }
+
+    #[test]
+    fn test_half_opacity() {
+        let mut dst = black_frame(2, 2);
+        blend_over(&mut dst, &solid_frame(2, 2, [200, 100, 50, 255]), 0.5);
+        assert_eq!(&dst.data[0..4], &[100, 50, 25, 255]);
+    }
+
+    #[test]
+    fn test_smaller_layer_is_centred() {
+        let mut dst = black_frame(4, 4);
+        blend_over(&mut dst, &solid_frame(2, 2, [255, 255, 255, 255]), 1.0);
+        // Corner untouched, centre covered
+        assert_eq!(&dst.data[0..4], &[0, 0, 0, 255]);
+        let centre = ((4 + 1) * 4) as usize;
+        assert_eq!(&dst.data[centre..centre + 4], &[255, 255, 255, 255]);
+    }
+
+    /// Pixel `(x, y)` of `frame`.
+    fn pixel(frame: &VideoFrame, x: u32, y: u32) -> &[u8] {
+        let i = ((y * frame.width + x) * 4) as usize;
+        &frame.data[i..i + 4]
+    }
+
+    /// A 2x1 layer: red on the left, blue on the right.
+    fn red_blue() -> Layer {
+        let mut frame = solid_frame(2, 1, [255, 0, 0, 255]);
+        frame.data[4..8].copy_from_slice(&[0, 0, 255, 255]);
+        Layer { frame, transform: Default::default() }
+    }
+
+    #[test]
+    fn test_draw_layer_matches_centred_blend() {
+        let layer = red_blue();
+        let mut drawn = black_frame(4, 3);
+        draw_layer(&mut drawn, &layer);
+        let mut blended = black_frame(4, 3);
+        blend_over(&mut blended, &layer.frame, 1.0);
+        assert_eq!(drawn.data, blended.data);
+    }
+
+    #[test]
+    fn test_draw_layer_transforms() {
+        use crate::timeline::Crop;
+
+        // Flipped: blue on the left
+        let mut layer = red_blue();
+        layer.transform.flip_horizontal = true;
+        let mut dst = black_frame(4, 3);
+        draw_layer(&mut dst, &layer);
+        assert_eq!(pixel(&dst, 1, 1), &[0, 0, 255, 255]);
+        assert_eq!(pixel(&dst, 2, 1), &[255, 0, 0, 255]);
+
+        // Rotated a quarter turn clockwise: red on top, blue below
+        let mut layer = red_blue();
+        layer.transform.rotation = 90.0;
+        let mut dst = black_frame(3, 4);
+        draw_layer(&mut dst, &layer);
+        assert_eq!(pixel(&dst, 1, 1), &[255, 0, 0, 255]);
+        assert_eq!(pixel(&dst, 1, 2), &[0, 0, 255, 255]);
+        assert_eq!(pixel(&dst, 0, 1), &[0, 0, 0, 255]);
+
+        // Cropped right half, doubled and moved to the top-left corner
+        let mut layer = red_blue();
+        layer.transform.crop = Crop::new(0.0, 0.0, 0.5, 0.0);
+        layer.transform.scale = (2.0, 2.0);
+        layer.transform.anchor = (0.0, 0.0);
+        layer.transform.position = (0.0, 0.0);
+        let mut dst = black_frame(4, 2);
+        draw_layer(&mut dst, &layer);
+        assert_eq!(pixel(&dst, 1, 1), &[255, 0, 0, 255]);
+        assert_eq!(pixel(&dst, 2, 0), &[0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_transition_frames() {
+        let from = solid_frame(2, 2, [200, 0, 0, 255]);
+        let to = solid_frame(2, 2, [0, 0, 200, 255]);
+
+        let dissolve = transition_frame(TransitionKind::CrossDissolve, Some(&from), Some(&to), 0.25, 2, 2);
+        assert_eq!(&dissolve.data[0..4], &[150, 0, 50, 255]);
+
+        // A dip is all colour at its midpoint and all clip at its ends
+        let dip = transition_frame(TransitionKind::DipToWhite, Some(&from), Some(&to), 0.5, 2, 2);
+        assert_eq!(&dip.data[0..4], &[255, 255, 255, 255]);
+        let dip = transition_frame(TransitionKind::DipToBlack, Some(&from), Some(&to), 0.0, 2, 2);
+        assert_eq!(&dip.data[0..4], &[200, 0, 0, 255]);
+        let dip = transition_frame(TransitionKind::DipToBlack, Some(&from), Some(&to), 0.75, 2, 2);
+        assert_eq!(&dip.data[0..4], &[0, 0, 100, 255]);
+    }
+}
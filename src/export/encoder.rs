@@ -3,6 +3,7 @@
 
 use std::path::Path;
 use crate::decode::decoder::VideoFrame;
+use crate::core::time::{FrameRate, TimeRange};
 
 /// Error type for encoding operations
 #[derive(Debug)]
@@ -26,6 +27,27 @@ impl std::fmt::Display for EncodeError {
 
 impl std::error::Error for EncodeError {}
 
+/// Chapter entry written to the container's chapter list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chapter {
+    pub range: TimeRange,  // Timeline time (nanoseconds)
+    pub title: String,
+}
+
+/// Timed text of a subtitle stream
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subtitle {
+    pub range: TimeRange,  // Timeline time (nanoseconds)
+    pub text: String,      // ASS dialogue text: `\N` line breaks and override tags such as `{\an8}` and `{\i1}`
+}
+
+/// Soft subtitle stream muxed into the output file as `mov_text`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubtitleStream {
+    pub title: String,
+    pub subtitles: Vec<Subtitle>, // Sorted by start; may overlap
+}
+
 /// Video encoder for exporting to MP4 (H.264 + AAC)
 pub struct Encoder {
     output_path: std::path::PathBuf,
@@ -34,7 +56,7 @@ pub struct Encoder {
     #[allow(dead_code)]
     height: u32,
     #[allow(dead_code)]
-    fps: f64,
+    fps: FrameRate,
     #[allow(dead_code)]
     video_bitrate: u64,
     #[allow(dead_code)]
@@ -43,6 +65,8 @@ pub struct Encoder {
     sample_rate: u32,
     #[allow(dead_code)]
     channels: u32,
+    chapters: Vec<Chapter>,
+    subtitle_streams: Vec<SubtitleStream>,
     // FFmpeg context would be stored here as an opaque pointer
     // In real implementation: inner: *mut FFmpegContext
     _inner: (),  // Placeholder
@@ -55,7 +79,7 @@ impl Encoder {
         output_path: &Path,
         width: u32,
         height: u32,
-        fps: f64,
+        fps: FrameRate,
         video_bitrate: u64,
         audio_bitrate: u64,
         sample_rate: u32,
@@ -78,10 +102,53 @@ impl Encoder {
             audio_bitrate,
             sample_rate,
             channels,
+            chapters: Vec::new(),
+            subtitle_streams: Vec::new(),
             _inner: (),
         })
     }
 
+    /// Set the chapter list written to the output file.
+    ///
+    /// Must be called before the first frame is encoded (chapters are part of
+    /// the container header).
+    pub fn set_chapters(&mut self, chapters: Vec<Chapter>) {
+        // TODO: Write chapters with FFmpeg
+        // This would involve unsafe FFmpeg API calls before avformat_write_header:
+        // - av_mallocz_array for format_ctx->chapters
+        // - One AVChapter per entry (time_base 1/1_000_000_000, start, end)
+        // - av_dict_set(&chapter->metadata, "title", ...)
+        self.chapters = chapters;
+    }
+
+    /// Chapters that will be written to the output file.
+    pub fn chapters(&self) -> &[Chapter] {
+        &self.chapters
+    }
+
+    /// Add a soft subtitle stream to the output file.
+    ///
+    /// Must be called before the first frame is encoded (streams are part of
+    /// the container header). Players can switch the stream on and off.
+    pub fn add_subtitle_stream(&mut self, stream: SubtitleStream) {
+        // TODO: Add the stream with FFmpeg
+        // This would involve unsafe FFmpeg API calls before avformat_write_header:
+        // - avcodec_find_encoder(AV_CODEC_ID_MOV_TEXT), avcodec_alloc_context3
+        // - subtitle_header: an ASS header with the default style (ff_ass_subtitle_header_default)
+        // - avcodec_open2, avformat_new_stream, avcodec_parameters_from_context
+        // - av_dict_set(&stream->metadata, "title", ...)
+        // While encoding frames, each subtitle whose start has been reached:
+        // - AVSubtitle with one SUBTITLE_ASS rect holding the dialogue line
+        // - avcodec_encode_subtitle, then av_interleaved_write_frame with pts and
+        //   duration from the range, in the stream's time base
+        self.subtitle_streams.push(stream);
+    }
+
+    /// Subtitle streams that will be written to the output file.
+    pub fn subtitle_streams(&self) -> &[SubtitleStream] {
+        &self.subtitle_streams
+    }
+
     /// Encode a video frame
     pub fn encode_video_frame(&mut self, _frame: &VideoFrame) -> Result<(), EncodeError> {
         // TODO: Encode frame using FFmpeg
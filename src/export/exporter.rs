@@ -2,13 +2,18 @@
 //! Per SPEC.md: No real-time preview, no UI, offline render only.
 //! Uses nanosecond time units (i64) throughout.
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::collections::{HashMap, HashSet};
-use crate::timeline::Timeline;
-use crate::core::time::{Time, from_seconds, to_seconds};
-use crate::export::encoder::Encoder;
+use crate::timeline::{Clip, ClipSource, SequenceId, Speed, Timeline};
+use crate::core::time::{Time, from_seconds, to_seconds, to_sample_index};
+use crate::export::encoder::{Encoder, Chapter};
+use crate::export::composite;
+use crate::export::captions::{self, CaptionMode, CaptionRenderer};
+use crate::audio::retime::{retime, ClipStretchers};
+use crate::audio::mixer::pan_gain;
 use crate::decode::decoder::{Decoder, DecodeError, VideoFrame};
-use crate::export::pipeline::{ExportSettings, ExportError};
+use crate::export::pipeline::{self, ExportSettings, ExportError};
+use crate::render::renderer::Layer;
 
 /// Exporter for offline rendering of timeline to MP4
 /// 
@@ -17,31 +22,44 @@ use crate::export::pipeline::{ExportSettings, ExportError};
 /// 2. Decodes video frames from clips at each frame time
 /// 3. Decodes and accumulates audio samples for each frame duration
 /// 4. Encodes frames and samples to MP4 (H.264 + AAC)
+/// 5. Writes the timeline's chapter markers as MP4 chapter metadata
+/// 6. Writes enabled caption tracks as soft subtitle streams or burns them
+///    into the frames (see `CaptionMode`)
 /// 
-/// Frame pacing: Each frame represents a fixed duration (1/fps seconds).
-/// Timeline time advances by frame_duration_ns for each frame.
+/// Frame pacing: Each frame's timestamp is computed from its index with the
+/// exact rational frame rate, so 23.976/29.97/59.94 output never drifts.
+/// Audio sample boundaries are derived from those timestamps the same way.
 /// 
 /// Sync behavior:
 /// - Video frames are decoded at exact timeline timestamps
 /// - Audio samples are accumulated per frame duration
 /// - Audio/video sync is maintained by encoding audio samples that correspond
 ///   to each video frame's time range
+/// - Enabled video tracks are composited bottom-up; audible audio tracks (unmuted,
+///   and only the soloed ones while any is soloed) are summed, as in playback
+/// - Compound clips are rendered and mixed from their nested sequences, at any depth;
+///   captions come from the top-level timeline only
+/// - Transitions mix the two clips at a cut, each playing on into its handles
 /// - Frame-perfect output: every frame at the target FPS is encoded
 /// 
 /// Error handling:
-/// - Decode errors for individual frames are logged and result in black frames
+/// - Decode errors for individual frames are logged and the layer is left out
 /// - Audio decode errors result in silence for that time range
 /// - Encoder errors propagate and abort the export
 /// - Timeline errors (missing decoders, invalid mappings) abort the export
+/// - Timelines `Timeline::validate` reports issues for are refused before
+///   rendering, with source ranges checked against the probed media
 /// 
 /// Known limitations:
 /// - Frame scaling is not implemented (relies on encoder)
 /// - Audio resampling is not implemented (assumes source matches export settings)
-/// - No support for multiple overlapping clips (takes first clip found)
-/// - Audio mixing for overlapping clips is simplified (volume only)
+/// - Clip layers are transformed (position, scale, rotation, crop, flips) with
+///   nearest-neighbour sampling, without filtering
+/// - Audio mixing is simplified (no clipping protection)
 pub struct Exporter {
     timeline: Timeline,
     settings: ExportSettings,
+    captions: CaptionMode,
 }
 
 impl Exporter {
@@ -50,9 +68,16 @@ impl Exporter {
         Self {
             timeline,
             settings,
+            captions: CaptionMode::default(),
         }
     }
 
+    /// Set how caption tracks are exported (builder style).
+    pub fn with_captions(mut self, captions: CaptionMode) -> Self {
+        self.captions = captions;
+        self
+    }
+
     /// Export the timeline to an MP4 file
     /// 
     /// This performs offline rendering:
@@ -62,16 +87,42 @@ impl Exporter {
     /// - Writes to MP4 file via FFmpeg encoder
     /// 
     /// Frame pacing strategy:
-    /// - Calculate frame duration: 1/fps seconds in nanoseconds
-    /// - For each frame, advance timeline_time_ns by frame_duration_ns
-    /// - Decode video frame at timeline_time_ns
-    /// - Accumulate audio samples for frame_duration_ns duration
-    /// - Encode when enough samples accumulated
+    /// - Frame N starts at `fps.frame_to_time(N)` (exact rational math)
+    /// - Decode video frame at that time
+    /// - Mix audio samples up to the next frame's start sample
+    /// - Encode the composited frame and the mixed samples
     /// 
     /// Returns Ok(()) on success, Err(ExportError) on failure.
     pub fn export<P: AsRef<Path>>(&self, output_path: P) -> Result<(), ExportError> {
         let output_path = output_path.as_ref();
 
+        // Compound clips are rendered by recursion, which must end
+        if let Some(sequence) = self.timeline.find_sequence_cycle() {
+            return Err(ExportError::Timeline(format!("Sequence {} contains itself", sequence)));
+        }
+
+        // Collect all unique source paths, including those of nested sequences
+        let mut source_paths = HashSet::new();
+        let nested_tracks = self.timeline.sequences.values().flat_map(|s| &s.timeline.tracks);
+        for clip in self.timeline.tracks.iter().chain(nested_tracks).flat_map(|t| &t.clips) {
+            if let Some(path) = clip.source_path() {
+                source_paths.insert(path.to_path_buf());
+            }
+        }
+
+        // Initialize decoders for all source files
+        let mut decoders: HashMap<PathBuf, Decoder> = HashMap::new();
+        for path in &source_paths {
+            decoders.insert(
+                path.clone(),
+                Decoder::new(path)
+                    .map_err(ExportError::Decode)?,
+            );
+        }
+        // Checked before the encoder opens the output file, so a refused
+        // timeline leaves nothing behind
+        pipeline::validate_timeline(&self.timeline, &decoders)?;
+
         // Create encoder
         let mut encoder = Encoder::new(
             output_path,
@@ -84,152 +135,76 @@ impl Exporter {
             self.settings.channels,
         )?;
 
+        // Chapter markers become MP4 chapter metadata
+        let chapters: Vec<Chapter> = self.timeline.chapters()
+            .into_iter()
+            .map(|(range, title)| Chapter { range, title })
+            .collect();
+        encoder.set_chapters(chapters);
+
+        // Caption tracks become subtitle streams, or are drawn per frame
+        let caption_tracks: Vec<_> = self.timeline.caption_tracks()
+            .filter(|t| t.enabled && !t.cues.is_empty())
+            .collect();
+        let mut caption_renderer = None;
+        match self.captions {
+            CaptionMode::Off => {}
+            CaptionMode::Soft => captions::add_subtitle_streams(&mut encoder, &self.timeline),
+            CaptionMode::BurnIn => {
+                caption_renderer = Some(CaptionRenderer::new(self.settings.width, self.settings.height));
+            }
+        }
+
         // Calculate frame timing
         let duration_ns = self.timeline.duration;
-        let frame_duration_seconds = 1.0 / self.settings.fps;
-        let frame_duration_ns = from_seconds(frame_duration_seconds);
-        
-        // Calculate audio samples per frame
-        let samples_per_frame = (self.settings.sample_rate as f64 * frame_duration_seconds) as usize;
+        let fps = self.settings.fps;
+        let sample_rate = self.settings.sample_rate;
 
-        // Collect all unique source paths
-        let mut source_paths = HashSet::new();
-        for clip in &self.timeline.video_track.clips {
-            source_paths.insert(clip.source_path.clone());
-        }
-        for clip in &self.timeline.audio_track.clips {
-            source_paths.insert(clip.source_path.clone());
+        // Spans without any video export as black frames
+        for gap in self.timeline.video_gaps() {
+            eprintln!("Warning: No video from {:.3}s to {:.3}s; exporting black frames",
+                     to_seconds(gap.start), to_seconds(gap.end));
         }
 
-        // Initialize decoders for all source files
-        let mut decoders: HashMap<std::path::PathBuf, Decoder> = HashMap::new();
-        for path in &source_paths {
-            decoders.insert(
-                path.clone(),
-                Decoder::new(path)
-                    .map_err(ExportError::Decode)?,
-            );
-        }
+        // Export frame by frame; timestamps are derived from the frame index
+        let total_frames = fps.frames_in(duration_ns);
+        let mut stretchers = ClipStretchers::default();
 
-        // Audio sample accumulation buffer
-        let mut audio_buffer: Vec<f32> = Vec::new();
+        for frame_num in 0..total_frames {
+            let timeline_time_ns: Time = fps.frame_to_time(frame_num);
+            let next_frame_time_ns = fps.frame_to_time(frame_num + 1);
 
-        // Export frame by frame (using nanosecond timestamps, not frame numbers)
-        let mut timeline_time_ns: Time = 0;
-        let mut frame_num = 0;
-        let total_frames = ((to_seconds(duration_ns) * self.settings.fps).ceil() as usize).max(1);
-        
-        while timeline_time_ns < duration_ns {
             // === VIDEO FRAME PROCESSING ===
-            // Find video clip at current timeline time
-            if let Some(video_clip) = self.timeline.video_track.clip_at(timeline_time_ns) {
-                // Convert timeline time to source time
-                if let Some(source_time_ns) = video_clip.timeline_to_source(timeline_time_ns) {
-                    let decoder = decoders.get_mut(&video_clip.source_path)
-                        .ok_or_else(|| ExportError::Timeline(
-                            format!("Decoder not found for source: {:?}", video_clip.source_path)
-                        ))?;
-                    
-                    // Decode video frame at source time
-                    match decoder.decode_video_frame_at(source_time_ns, video_clip.stream_index) {
-                        Ok(frame) => {
-                            // Scale frame to export resolution if needed
-                            let scaled_frame = self.scale_frame_if_needed(&frame)?;
-                            
-                            // Encode video frame
-                            encoder.encode_video_frame(&scaled_frame)?;
-                        }
-                        Err(e) => {
-                            // Log warning but continue - frame dropping allowed during export
-                            eprintln!("Warning: Failed to decode video frame at {}: {}", 
-                                     to_seconds(timeline_time_ns), e);
-                            // Encode black frame as fallback
-                            self.encode_black_frame(&mut encoder)?;
-                        }
-                    }
-                } else {
-                    // No valid source time mapping - encode black frame
-                    self.encode_black_frame(&mut encoder)?;
-                }
-            } else {
-                // No video clip at this time - encode black frame
-                self.encode_black_frame(&mut encoder)?;
+            // Composite every video track bottom-up onto a black canvas
+            let mut canvas = composite::black_frame(self.settings.width, self.settings.height);
+            self.composite_video(&self.timeline, timeline_time_ns, &mut canvas, &mut decoders)?;
+            if let Some(renderer) = &mut caption_renderer {
+                let cues: Vec<_> = caption_tracks.iter()
+                    .flat_map(|t| t.cues_at(timeline_time_ns))
+                    .collect();
+                renderer.draw(&mut canvas, &cues);
             }
+            encoder.encode_video_frame(&canvas)?;
 
             // === AUDIO SAMPLE PROCESSING ===
-            // Accumulate audio samples for this frame duration
-            let frame_end_time_ns = (timeline_time_ns + frame_duration_ns).min(duration_ns);
-            
-            // Find audio clips that overlap with this frame duration
-            let audio_clips = self.timeline.audio_track.clips_in_range(
+            // Mix every audio track into this frame's sample window. The window ends
+            // at the next frame's first sample, so no samples are lost or duplicated.
+            let frame_end_time_ns = next_frame_time_ns.min(duration_ns);
+            let frames = (to_sample_index(next_frame_time_ns, sample_rate)
+                - to_sample_index(timeline_time_ns, sample_rate)) as usize;
+            let frame_mix = self.mix_audio(
+                &self.timeline,
+                None,
                 timeline_time_ns,
-                frame_end_time_ns
-            );
+                frame_end_time_ns,
+                frames,
+                &mut decoders,
+                &mut stretchers,
+            )?;
+            stretchers.finish_window();
 
-            // Decode audio samples from overlapping clips
-            for audio_clip in &audio_clips {
-                // Calculate overlap range in timeline time
-                let clip_start = audio_clip.timeline_start.max(timeline_time_ns);
-                let clip_end = audio_clip.timeline_end.min(frame_end_time_ns);
-                
-                if clip_start < clip_end {
-                    // Convert timeline times to source times
-                    if let Some(source_start_ns) = audio_clip.timeline_to_source(clip_start) {
-                        if let Some(source_end_ns) = audio_clip.timeline_to_source(clip_end) {
-                            let decoder = decoders.get_mut(&audio_clip.source_path)
-                                .ok_or_else(|| ExportError::Timeline(
-                                    format!("Decoder not found for audio source: {:?}", audio_clip.source_path)
-                                ))?;
-                            
-                            // Decode audio samples for this range
-                            match self.decode_audio_range(
-                                decoder,
-                                source_start_ns,
-                                source_end_ns,
-                                audio_clip.stream_index,
-                            ) {
-                                Ok(samples) => {
-                                    // Apply track volume if not muted
-                                    let volume = if self.timeline.audio_track.muted {
-                                        0.0
-                                    } else {
-                                        self.timeline.audio_track.volume
-                                    };
-                                    
-                                    // Mix samples into buffer (apply volume)
-                                    let mixed_samples: Vec<f32> = samples
-                                        .iter()
-                                        .map(|s| s * volume)
-                                        .collect();
-                                    
-                                    audio_buffer.extend_from_slice(&mixed_samples);
-                                }
-                                Err(e) => {
-                                    eprintln!("Warning: Failed to decode audio at {}: {}", 
-                                             to_seconds(timeline_time_ns), e);
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-
-            // If no audio clips, add silence for this frame duration
-            if audio_clips.is_empty() {
-                let silence_samples = vec![0.0f32; samples_per_frame];
-                audio_buffer.extend_from_slice(&silence_samples);
-            }
-
-            // Encode audio when we have enough samples
-            // We encode in chunks matching the frame rate
-            while audio_buffer.len() >= samples_per_frame {
-                let samples_to_encode: Vec<f32> = audio_buffer
-                    .drain(..samples_per_frame)
-                    .collect();
-                
-                // Resample if needed (simplified - assumes decoder outputs correct sample rate)
-                encoder.encode_audio_samples(&samples_to_encode)?;
-            }
+            // Resample if needed (simplified - assumes decoder outputs correct sample rate)
+            encoder.encode_audio_samples(&frame_mix)?;
 
             // Progress reporting
             if frame_num % 30 == 0 {
@@ -237,26 +212,265 @@ impl Exporter {
                 eprintln!("Export progress: {:.1}% (frame {}/{}), timeline: {:.3}s", 
                          progress, frame_num, total_frames, to_seconds(timeline_time_ns));
             }
+        }
+
+        // Finalize encoding
+        encoder.finish()?;
+
+        eprintln!("Export complete: {} frames exported", total_frames);
+        Ok(())
+    }
+
+    /// Composite every enabled video track of `timeline` at `time` bottom-up onto `canvas`.
+    ///
+    /// Compound clips render their sequence onto a transparent layer at export
+    /// size (recursively), which is then blended like a decoded frame. Over a
+    /// transition both of its clips are rendered and mixed into one layer.
+    fn composite_video(
+        &self,
+        timeline: &Timeline,
+        time: Time,
+        canvas: &mut VideoFrame,
+        decoders: &mut HashMap<PathBuf, Decoder>,
+    ) -> Result<(), ExportError> {
+        for video_track in timeline.visible_tracks() {
+            if let Some(window) = video_track.transition_at(time) {
+                let from = self.clip_layer(window.from, time, decoders)?;
+                let to = self.clip_layer(window.to, time, decoders)?;
+                let layer = composite::transition_frame(
+                    window.transition.kind,
+                    from.as_ref(),
+                    to.as_ref(),
+                    window.progress(time),
+                    self.settings.width,
+                    self.settings.height,
+                );
+                composite::blend_over(canvas, &layer, 1.0);
+                continue;
+            }
+            let Some(video_clip) = video_track.clip_at(time) else {
+                continue;
+            };
+            if let Some(layer) = self.clip_layer(video_clip, time, decoders)? {
+                composite::blend_over(canvas, &layer, 1.0);
+            }
+        }
+        Ok(())
+    }
 
-            // Advance to next frame
-            timeline_time_ns += frame_duration_ns;
-            frame_num += 1;
+    /// Render the layer `clip` shows at `time`, which may be in its handles:
+    /// its frame drawn onto a transparent layer at export size with the clip's
+    /// visual properties and keyframes (see `Layer::for_clip`).
+    ///
+    /// Returns `None`, with a warning, if the frame cannot be decoded.
+    fn clip_layer(
+        &self,
+        clip: &Clip,
+        time: Time,
+        decoders: &mut HashMap<PathBuf, Decoder>,
+    ) -> Result<Option<VideoFrame>, ExportError> {
+        let Some(frame) = self.clip_frame(clip, time, decoders)? else {
+            return Ok(None);
+        };
+        let mut layer = composite::transparent_frame(self.settings.width, self.settings.height);
+        composite::draw_layer(&mut layer, &Layer::for_clip(frame, clip, time));
+        Ok(Some(layer))
+    }
+
+    /// Decode (or, for a compound clip, render) the frame `clip` shows at `time`.
+    fn clip_frame(
+        &self,
+        clip: &Clip,
+        time: Time,
+        decoders: &mut HashMap<PathBuf, Decoder>,
+    ) -> Result<Option<VideoFrame>, ExportError> {
+        match &clip.source {
+            ClipSource::Media(path) => {
+                let decoder = decoders.get_mut(path)
+                    .ok_or_else(|| ExportError::Timeline(
+                        format!("Decoder not found for source: {:?}", path)
+                    ))?;
+
+                // Decode the source frame the clip shows at this time
+                match Self::decode_clip_frame(decoder, clip, time) {
+                    // Scale frame to export resolution if needed
+                    Ok(frame) => self.scale_frame_if_needed(&frame).map(Some),
+                    Err(e) => {
+                        // Log warning but continue - the layer is left out of this frame
+                        eprintln!("Warning: Failed to decode video frame at {}: {}", 
+                                 to_seconds(time), e);
+                        Ok(None)
+                    }
+                }
+            }
+            ClipSource::Sequence(id) => {
+                let Some(sequence) = self.timeline.sequence(*id) else {
+                    return Ok(None);
+                };
+                // Reversed clips show the frame that ends at the mapped time
+                let source_time = clip.handle_source_at(time);
+                let source_time = if clip.speed.is_reverse() {
+                    (source_time - 1).max(0)
+                } else {
+                    source_time
+                };
+                let mut layer = composite::transparent_frame(self.settings.width, self.settings.height);
+                self.composite_video(&sequence.timeline, source_time, &mut layer, decoders)?;
+                Ok(Some(layer))
+            }
         }
+    }
 
-        // Flush remaining audio samples
-        if !audio_buffer.is_empty() {
-            // Pad with silence to match expected frame size
-            while audio_buffer.len() < samples_per_frame {
-                audio_buffer.push(0.0);
+    /// Mix every audible track of `timeline` (the sequence `sequence`, or the
+    /// top-level timeline) over `[start, end)` into `frames` interleaved
+    /// frames starting at `start`'s sample.
+    ///
+    /// Compound clips mix their sequence over the source range they play
+    /// (recursively) and fit it to their span like decoded audio. Clips in a
+    /// crossfade are heard over their transitions too, faded by its curve.
+    /// Pitch-preserving clips are stretched with their stretcher in
+    /// `stretchers`, continuing from the previous frame's window.
+    #[allow(clippy::too_many_arguments)]
+    fn mix_audio(
+        &self,
+        timeline: &Timeline,
+        sequence: Option<SequenceId>,
+        start: Time,
+        end: Time,
+        frames: usize,
+        decoders: &mut HashMap<PathBuf, Decoder>,
+        stretchers: &mut ClipStretchers,
+    ) -> Result<Vec<f32>, ExportError> {
+        let sample_rate = self.settings.sample_rate;
+        let channels = self.settings.channels as usize;
+        let start_sample = to_sample_index(start, sample_rate);
+        let mut mix = vec![0.0f32; frames * channels];
+
+        for audio_track in timeline.audible_tracks() {
+            // Decode audio samples from clips heard in this window
+            for audio_clip in &audio_track.clips {
+                // Calculate overlap range in timeline time, including transition handles
+                let (extent_start, extent_end) = audio_track.clip_extent(audio_clip);
+                let clip_start = extent_start.max(start);
+                let clip_end = extent_end.min(end);
+                if clip_start >= clip_end || audio_clip.speed.is_freeze() {
+                    continue;
+                }
+
+                // Convert timeline times to source times (reversed clips run backwards)
+                let source_a = audio_clip.handle_source_at(clip_start);
+                let source_b = audio_clip.handle_source_at(clip_end);
+                let (source_start, source_end) = (source_a.min(source_b), source_a.max(source_b));
+
+                let samples = match &audio_clip.source {
+                    ClipSource::Media(path) => {
+                        let decoder = decoders.get_mut(path)
+                            .ok_or_else(|| ExportError::Timeline(
+                                format!("Decoder not found for audio source: {:?}", path)
+                            ))?;
+
+                        // Decode audio samples for this range
+                        match self.decode_audio_range(decoder, source_start, source_end, audio_clip.stream_index) {
+                            Ok(samples) => samples,
+                            Err(e) => {
+                                eprintln!("Warning: Failed to decode audio at {}: {}", 
+                                         to_seconds(clip_start), e);
+                                continue;
+                            }
+                        }
+                    }
+                    ClipSource::Sequence(id) => {
+                        let Some(nested) = self.timeline.sequence(*id) else {
+                            continue;
+                        };
+                        let source_frames = (to_sample_index(source_end, sample_rate)
+                            - to_sample_index(source_start, sample_rate)) as usize;
+                        self.mix_audio(
+                            &nested.timeline,
+                            Some(*id),
+                            source_start,
+                            source_end,
+                            source_frames,
+                            decoders,
+                            stretchers,
+                        )?
+                    }
+                };
+
+                // Fit the source samples to the clip's span in this window
+                let offset_frames = to_sample_index(clip_start, sample_rate) - start_sample;
+                let span_frames = to_sample_index(clip_end, sample_rate)
+                    - to_sample_index(clip_start, sample_rate);
+                // A time-remap curve only sets the direction here;
+                // the span's length sets the rate
+                let speed = match audio_clip.time_remap {
+                    Some(_) if source_b < source_a => Speed::REVERSE,
+                    Some(_) => Speed::NORMAL,
+                    None => audio_clip.speed,
+                };
+                let stretcher = if audio_clip.preserve_pitch {
+                    Some(stretchers.get((sequence, audio_clip.id), channels, clip_start, clip_end))
+                } else {
+                    None
+                };
+                let samples = retime(&samples, channels, speed, span_frames as usize, stretcher);
+
+                // Sum into the mix at the clip's offset within the window, at
+                // the clip's keyframed volume and pan, and faded where a
+                // transition mixes it with its neighbour
+                let transitions = audio_track.transitions_of(audio_clip.id);
+                let animation = &audio_clip.animation;
+                let animated = !animation.volume.is_empty() || !animation.pan.is_empty();
+                let offset = (offset_frames as usize * channels).min(mix.len());
+                for (i, (mixed, sample)) in mix[offset..].iter_mut().zip(&samples).enumerate() {
+                    let mut gain = audio_track.volume;
+                    if !transitions.is_empty() || animated {
+                        let time = clip_start + (clip_end - clip_start) * (i / channels) as Time / span_frames.max(1);
+                        for window in &transitions {
+                            gain *= window.gain(audio_clip.id, time);
+                        }
+                        let clip_offset = time - audio_clip.timeline_start;
+                        gain *= animation.volume_at(clip_offset)
+                            * pan_gain(animation.pan_at(clip_offset), i % channels, channels);
+                    }
+                    *mixed += sample * gain;
+                }
             }
-            encoder.encode_audio_samples(&audio_buffer)?;
         }
+        Ok(mix)
+    }
 
-        // Finalize encoding
-        encoder.finish()?;
+    /// Decode the frame `clip` shows at `timeline_time_ns`, which may be in
+    /// its handles (see `Clip::handle_source_at`).
+    ///
+    /// Reversed clips show the frame that ends at the mapped time. Time-remapped
+    /// clips with frame blending mix the two source frames around the mapped
+    /// time, using the stream's frame rate.
+    fn decode_clip_frame(
+        decoder: &mut Decoder,
+        clip: &Clip,
+        timeline_time_ns: Time,
+    ) -> Result<VideoFrame, DecodeError> {
+        let source_time_ns = clip.handle_source_at(timeline_time_ns);
 
-        eprintln!("Export complete: {} frames exported", frame_num);
-        Ok(())
+        let blend = decoder.get_video_stream_info(clip.stream_index)
+            .ok()
+            .and_then(|info| clip.blend_frames_at(timeline_time_ns, info.fps));
+        if let Some((earlier, later, weight)) = blend {
+            let mut frame = decoder.decode_video_frame_at(earlier, clip.stream_index)?;
+            // Past the last source frame there is nothing to blend towards
+            if let Ok(next) = decoder.decode_video_frame_at(later, clip.stream_index) {
+                composite::blend_over(&mut frame, &next, weight);
+            }
+            return Ok(frame);
+        }
+
+        let source_time_ns = if clip.speed.is_reverse() {
+            (source_time_ns - 1).max(0)
+        } else {
+            source_time_ns
+        };
+        decoder.decode_video_frame_at(source_time_ns, clip.stream_index)
     }
 
     /// Scale frame to export resolution if dimensions don't match
@@ -277,20 +491,6 @@ impl Exporter {
         Ok(frame.clone())
     }
 
-    /// Encode a black frame (used when no video clip is present)
-    fn encode_black_frame(&self, encoder: &mut Encoder) -> Result<(), ExportError> {
-        // Create black RGBA8 frame
-        let black_video_frame = VideoFrame {
-            data: vec![0u8; (self.settings.width * self.settings.height * 4) as usize],
-            width: self.settings.width,
-            height: self.settings.height,
-            timestamp: 0, // Not used for encoding
-        };
-        
-        encoder.encode_video_frame(&black_video_frame)
-            .map_err(ExportError::Encode)
-    }
-
     /// Decode audio samples for a time range
     /// Returns interleaved PCM f32 samples
     /// 
@@ -306,8 +506,10 @@ impl Exporter {
         // Seek to start time
         decoder.seek(start_time_ns, stream_index)?;
         
-        let duration_seconds = to_seconds(end_time_ns - start_time_ns);
-        let expected_samples = (self.settings.sample_rate as f64 * duration_seconds) as usize;
+        // Interleaved sample count, derived from exact sample boundaries
+        let expected_samples = (to_sample_index(end_time_ns, self.settings.sample_rate)
+            - to_sample_index(start_time_ns, self.settings.sample_rate)) as usize
+            * self.settings.channels as usize;
         let mut samples = Vec::with_capacity(expected_samples);
         
         // Decode audio frames until we have enough samples
@@ -1,8 +1,11 @@
 pub mod encoder;
 pub mod pipeline;
 pub mod exporter;
+pub mod composite;
+pub mod captions;
 
-pub use encoder::{Encoder, EncodeError};
+pub use encoder::{Encoder, EncodeError, Chapter, Subtitle, SubtitleStream};
 pub use pipeline::{ExportPipeline, ExportSettings, ExportError};
 pub use exporter::Exporter;
+pub use captions::{CaptionMode, CaptionRenderer};
 
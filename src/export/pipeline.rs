@@ -1,10 +1,13 @@
 //! Export pipeline for rendering timeline to MP4 file.
 
-use std::path::Path;
-use crate::timeline::Timeline;
-use crate::core::time::{Time, ns_to_seconds, seconds_to_ns};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use crate::timeline::{Diagnostic, MediaDurations, Timeline};
+use crate::core::time::{Time, FrameRate, ns_to_seconds};
 use crate::export::encoder::{Encoder, EncodeError};
+use crate::export::composite;
 use crate::decode::decoder::{Decoder, DecodeError};
+use serde::{Deserialize, Serialize};
 
 /// Error type for export operations
 #[derive(Debug)]
@@ -12,6 +15,8 @@ pub enum ExportError {
     Encode(EncodeError),
     Decode(DecodeError),
     Timeline(String),
+    /// The timeline breaks invariants rendering relies on (see `Timeline::validate`)
+    Invalid(Vec<Diagnostic>),
 }
 
 impl std::fmt::Display for ExportError {
@@ -20,6 +25,10 @@ impl std::fmt::Display for ExportError {
             ExportError::Encode(e) => write!(f, "Encode error: {}", e),
             ExportError::Decode(e) => write!(f, "Decode error: {}", e),
             ExportError::Timeline(msg) => write!(f, "Timeline error: {}", msg),
+            ExportError::Invalid(diagnostics) => {
+                let issues: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+                write!(f, "Invalid timeline: {}", issues.join("; "))
+            }
         }
     }
 }
@@ -38,12 +47,43 @@ impl From<DecodeError> for ExportError {
     }
 }
 
+/// Check a timeline before export (see `Timeline::validate`), with source
+/// ranges checked against the durations probed by `decoders`.
+pub(crate) fn validate_timeline(
+    timeline: &Timeline,
+    decoders: &HashMap<PathBuf, Decoder>,
+) -> Result<(), ExportError> {
+    let media: MediaDurations = decoders
+        .iter()
+        .filter_map(|(path, decoder)| probed_duration(decoder).map(|d| (path.clone(), d)))
+        .collect();
+    let diagnostics = timeline.validate(&media);
+    if diagnostics.is_empty() {
+        Ok(())
+    } else {
+        Err(ExportError::Invalid(diagnostics))
+    }
+}
+
+/// Length of the longer of a file's first video and audio streams, if known.
+fn probed_duration(decoder: &Decoder) -> Option<Time> {
+    let video = decoder.find_video_stream()
+        .and_then(|index| decoder.get_video_stream_info(index))
+        .map(|info| info.stream_info.duration)
+        .ok();
+    let audio = decoder.find_audio_stream()
+        .and_then(|index| decoder.get_audio_stream_info(index))
+        .map(|info| info.stream_info.duration)
+        .ok();
+    video.max(audio)
+}
+
 /// Export settings
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExportSettings {
     pub width: u32,
     pub height: u32,
-    pub fps: f64,
+    pub fps: FrameRate,
     pub video_bitrate: u64,  // bits per second
     pub audio_bitrate: u64,  // bits per second
     pub sample_rate: u32,
@@ -55,7 +95,7 @@ impl Default for ExportSettings {
         Self {
             width: 1920,
             height: 1080,
-            fps: 30.0,
+            fps: FrameRate::FPS_30,
             video_bitrate: 5_000_000,  // 5 Mbps
             audio_bitrate: 192_000,     // 192 kbps
             sample_rate: 48000,
@@ -83,36 +123,15 @@ impl ExportPipeline {
     pub fn export<P: AsRef<Path>>(&self, output_path: P) -> Result<(), ExportError> {
         let output_path = output_path.as_ref();
 
-        // Create encoder
-        let mut encoder = Encoder::new(
-            output_path,
-            self.settings.width,
-            self.settings.height,
-            self.settings.fps,
-            self.settings.video_bitrate,
-            self.settings.audio_bitrate,
-            self.settings.sample_rate,
-            self.settings.channels,
-        )?;
-
-        // Get timeline duration in nanoseconds
-        let duration_ns = self.timeline.duration;
-        let duration_seconds = ns_to_seconds(duration_ns);
-        let frame_duration_seconds = 1.0 / self.settings.fps;
-        let frame_duration_ns = seconds_to_ns(frame_duration_seconds);
-        let _total_frames = (duration_seconds * self.settings.fps).ceil() as usize;
-
         // Create decoders for all source files
-        let mut decoders: std::collections::HashMap<std::path::PathBuf, Decoder> = 
-            std::collections::HashMap::new();
+        let mut decoders: HashMap<PathBuf, Decoder> = HashMap::new();
 
         // Collect all unique source paths
         let mut source_paths = std::collections::HashSet::new();
-        for clip in &self.timeline.video_track.clips {
-            source_paths.insert(clip.source_path.clone());
-        }
-        for clip in &self.timeline.audio_track.clips {
-            source_paths.insert(clip.source_path.clone());
+        for track in &self.timeline.tracks {
+            for path in track.clips.iter().filter_map(|c| c.source_path()) {
+                source_paths.insert(path.to_path_buf());
+            }
         }
 
         // Initialize decoders
@@ -123,30 +142,80 @@ impl ExportPipeline {
                     .map_err(ExportError::Decode)?,
             );
         }
+        // Checked before the encoder opens the output file
+        validate_timeline(&self.timeline, &decoders)?;
+
+        // Create encoder
+        let mut encoder = Encoder::new(
+            output_path,
+            self.settings.width,
+            self.settings.height,
+            self.settings.fps,
+            self.settings.video_bitrate,
+            self.settings.audio_bitrate,
+            self.settings.sample_rate,
+            self.settings.channels,
+        )?;
 
-        // Export frame by frame (using nanosecond timestamps, not frame numbers)
-        let mut timeline_time_ns: Time = 0;
-        let mut frame_num = 0;
-        
-        while timeline_time_ns < duration_ns {
-            // Get video frame
-            if let Some(video_clip) = self.timeline.video_track.clip_at(timeline_time_ns) {
-                if let Some(source_time_ns) = video_clip.timeline_to_source(timeline_time_ns) {
-                    let decoder = decoders.get_mut(&video_clip.source_path)
+        // Get timeline duration in nanoseconds
+        let duration_ns = self.timeline.duration;
+        let duration_seconds = ns_to_seconds(duration_ns);
+        let fps = self.settings.fps;
+        let total_frames = fps.frames_in(duration_ns);
+
+        // Export frame by frame. Each frame's timestamp is derived from its index
+        // with exact rational math, so frame N always lands on the same nanosecond.
+        for frame_num in 0..total_frames {
+            let timeline_time_ns: Time = fps.frame_to_time(frame_num);
+
+            // Get video frame from the top-most video track with a clip here
+            // (see `Exporter` for full multi-track compositing). Over a
+            // transition both of its clips are decoded and mixed.
+            let top_track = self.timeline.visible_tracks().rev().find(|t| t.clip_at(timeline_time_ns).is_some());
+            if let Some(track) = top_track {
+                let window = track.transition_at(timeline_time_ns);
+                let clips = match &window {
+                    Some(window) => vec![window.from, window.to],
+                    None => track.clip_at(timeline_time_ns).into_iter().collect(),
+                };
+
+                let mut frames = Vec::with_capacity(clips.len());
+                for video_clip in clips {
+                    // Compound clips are only rendered by `Exporter`
+                    let Some(path) = video_clip.source_path() else {
+                        frames.push(None);
+                        continue;
+                    };
+                    let decoder = decoders.get_mut(path)
                         .ok_or_else(|| ExportError::Timeline("Decoder not found".to_string()))?;
-                    
-                    // Decode frame
+
+                    // Decode frame (in the clip's handles over a transition)
+                    let source_time_ns = video_clip.handle_source_at(timeline_time_ns);
                     match decoder.decode_video_frame_at(source_time_ns, video_clip.stream_index) {
-                        Ok(frame) => {
-                            // TODO: Scale frame to export resolution if needed
-                            encoder.encode_video_frame(&frame)?;
-                        }
+                        Ok(frame) => frames.push(Some(frame)),
                         Err(e) => {
                             eprintln!("Warning: Failed to decode frame {}: {}", frame_num, e);
                             // Continue with next frame
+                            frames.push(None);
                         }
                     }
                 }
+
+                let frame = match &window {
+                    Some(window) => Some(composite::transition_frame(
+                        window.transition.kind,
+                        frames[0].as_ref(),
+                        frames[1].as_ref(),
+                        window.progress(timeline_time_ns),
+                        self.settings.width,
+                        self.settings.height,
+                    )),
+                    None => frames.pop().flatten(),
+                };
+                if let Some(frame) = frame {
+                    // TODO: Scale frame to export resolution if needed
+                    encoder.encode_video_frame(&frame)?;
+                }
             } else {
                 // No video clip at this time - encode black frame
                 // TODO: Create black frame and encode
@@ -165,10 +234,6 @@ impl ExportPipeline {
                 let progress = (ns_to_seconds(timeline_time_ns) / duration_seconds) * 100.0;
                 eprintln!("Export progress: {:.1}%", progress);
             }
-
-            // Advance to next frame
-            timeline_time_ns += frame_duration_ns;
-            frame_num += 1;
         }
 
         // Finalize encoding
@@ -0,0 +1,801 @@
+//! SubRip (`.srt`) and WebVTT (`.vtt`) captions.
+//!
+//! [`export_srt`] and [`export_webvtt`] write the cues of a caption track;
+//! [`import_srt`] and [`import_webvtt`] read a file into cues to add with
+//! `Timeline::add_cues`. Both formats carry:
+//! - Start and end times to the millisecond; exported times are rounded to
+//!   the nearest one
+//! - Multi-line text
+//! - Bold, italic and underline (`<b>`, `<i>`, `<u>`) around the whole cue
+//! - Colour: SRT `<font color>`, WebVTT colour classes (`<c.yellow>`)
+//! - Position: SRT `{\anN}` alignment tags (nine fixed spots); WebVTT
+//!   `line`, `position` and `align` cue settings
+//!
+//! Styling of only part of a cue, other tags and settings, and positions or
+//! colours the format cannot express are dropped or approximated and
+//! reported as [`CaptionIssue`]s.
+
+use std::fmt;
+use crate::core::time::constants::{NANOS_PER_MILLI, NANOS_PER_SECOND};
+use crate::core::time::Time;
+use crate::timeline::caption::{Cue, CueAlign, CueId, CuePosition, CueStyle};
+
+/// `CuePosition::y` of SRT's top row (`{\an7}` to `{\an9}`).
+const SRT_TOP: f32 = 0.05;
+
+/// `CuePosition::y` of SRT's middle row (`{\an4}` to `{\an6}`).
+const SRT_MIDDLE: f32 = 0.45;
+
+/// Height of a WebVTT line as a fraction of the frame, for `line` numbers.
+const VTT_LINE_HEIGHT: f32 = 0.0533;
+
+/// WebVTT's colour classes; SRT `<font color>` accepts the same names.
+const COLORS: [(&str, [u8; 3]); 8] = [
+    ("white", [255, 255, 255]),
+    ("lime", [0, 255, 0]),
+    ("cyan", [0, 255, 255]),
+    ("red", [255, 0, 0]),
+    ("yellow", [255, 255, 0]),
+    ("magenta", [255, 0, 255]),
+    ("blue", [0, 0, 255]),
+    ("black", [0, 0, 0]),
+];
+
+/// Something a caption export or import could not represent exactly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CaptionIssue {
+    /// A cue was left out of the file or approximated
+    Cue { cue_id: CueId, reason: String },
+    /// A line of the file was ignored or approximated (1-based line number)
+    Line { line: usize, reason: String },
+}
+
+impl fmt::Display for CaptionIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaptionIssue::Cue { cue_id, reason } => write!(f, "Cue {}: {}", cue_id, reason),
+            CaptionIssue::Line { line, reason } => write!(f, "Line {}: {}", line, reason),
+        }
+    }
+}
+
+/// Result of `export_srt` and `export_webvtt`.
+#[derive(Debug, Clone)]
+pub struct CaptionExport {
+    pub text: String,
+    pub issues: Vec<CaptionIssue>,
+}
+
+/// Result of `import_srt` and `import_webvtt`.
+#[derive(Debug, Clone)]
+pub struct CaptionImport {
+    pub cues: Vec<Cue>, // Sorted by start; IDs 1, 2, ... in file order
+    pub issues: Vec<CaptionIssue>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Srt,
+    WebVtt,
+}
+
+/// Write cues as a SubRip file.
+pub fn export_srt(cues: &[Cue]) -> CaptionExport {
+    export(cues, Format::Srt)
+}
+
+/// Write cues as a WebVTT file.
+pub fn export_webvtt(cues: &[Cue]) -> CaptionExport {
+    export(cues, Format::WebVtt)
+}
+
+fn export(cues: &[Cue], format: Format) -> CaptionExport {
+    let mut text = String::new();
+    let mut issues = Vec::new();
+    if format == Format::WebVtt {
+        text.push_str("WEBVTT\n\n");
+    }
+    let mut cues: Vec<&Cue> = cues.iter().collect();
+    cues.sort_by_key(|c| c.start);
+
+    let mut number = 0;
+    for cue in cues {
+        let mut report = |reason: &str| issues.push(CaptionIssue::Cue { cue_id: cue.id, reason: reason.to_string() });
+        let (start, end) = (to_millis(cue.start), to_millis(cue.end));
+        if end <= start {
+            report("shorter than a millisecond; left out");
+            continue;
+        }
+        // A blank line would end the cue
+        let lines: Vec<&str> = cue.text.lines().filter(|l| !l.trim().is_empty()).collect();
+        if lines.is_empty() {
+            report("no text; left out");
+            continue;
+        }
+        if lines.len() != cue.text.lines().count() {
+            report("blank lines removed");
+        }
+
+        number += 1;
+        match format {
+            Format::Srt => {
+                text.push_str(&format!("{}\n{} --> {}\n", number, timestamp(start, ','), timestamp(end, ',')));
+                text.push_str(&srt_anchor(cue, &mut report));
+                text.push_str(&srt_body(&lines.join("\n"), &cue.style));
+            }
+            Format::WebVtt => {
+                let settings = vtt_settings(cue);
+                text.push_str(&format!("{} --> {}{}\n", timestamp(start, '.'), timestamp(end, '.'), settings));
+                text.push_str(&vtt_body(&lines.join("\n"), &cue.style, &mut report));
+            }
+        }
+        text.push_str("\n\n");
+    }
+    CaptionExport { text, issues }
+}
+
+/// Time rounded to the nearest millisecond, in milliseconds.
+fn to_millis(time: Time) -> i64 {
+    (time.max(0) + NANOS_PER_MILLI / 2) / NANOS_PER_MILLI
+}
+
+/// `HH:MM:SS,mmm` (SRT) or `HH:MM:SS.mmm` (WebVTT).
+fn timestamp(millis: i64, separator: char) -> String {
+    let seconds = millis / 1000;
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        separator,
+        millis % 1000
+    )
+}
+
+/// `{\anN}` tag placing an SRT cue, or nothing for the default bottom centre.
+fn srt_anchor(cue: &Cue, report: &mut impl FnMut(&str)) -> String {
+    let column = match cue.style.align {
+        CueAlign::Start => 1,
+        CueAlign::Center => 2,
+        CueAlign::End => 3,
+    };
+    let row = match cue.position.y {
+        None => 0,
+        Some(y) => {
+            let (row, exact) = if y < (SRT_TOP + SRT_MIDDLE) / 2.0 {
+                (2, y == SRT_TOP)
+            } else if y < 0.7 {
+                (1, y == SRT_MIDDLE)
+            } else {
+                (0, false)
+            };
+            if !exact {
+                report("vertical position approximated by an SRT alignment tag");
+            }
+            row
+        }
+    };
+    if cue.position.x.is_some() {
+        report("horizontal position approximated by an SRT alignment tag");
+    }
+    match row * 3 + column {
+        2 => String::new(),
+        anchor => format!("{{\\an{}}}", anchor),
+    }
+}
+
+fn srt_body(text: &str, style: &CueStyle) -> String {
+    let mut body = text.to_string();
+    if let Some([r, g, b]) = style.color {
+        body = format!("<font color=\"#{:02x}{:02x}{:02x}\">{}</font>", r, g, b, body);
+    }
+    wrap_emphasis(body, style)
+}
+
+/// Cue settings after a WebVTT timing line, each with a leading space.
+fn vtt_settings(cue: &Cue) -> String {
+    let mut settings = String::new();
+    if let Some(y) = cue.position.y {
+        settings.push_str(&format!(" line:{}%", percent(y)));
+    }
+    if let Some(x) = cue.position.x {
+        settings.push_str(&format!(" position:{}%", percent(x)));
+    }
+    match cue.style.align {
+        CueAlign::Start => settings.push_str(" align:start"),
+        CueAlign::Center => {}
+        CueAlign::End => settings.push_str(" align:end"),
+    }
+    settings
+}
+
+/// A fraction as a percentage with at most three decimals.
+fn percent(fraction: f32) -> String {
+    let text = format!("{:.3}", fraction.clamp(0.0, 1.0) * 100.0);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+fn vtt_body(text: &str, style: &CueStyle, report: &mut impl FnMut(&str)) -> String {
+    let mut body = text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+    if let Some(color) = style.color {
+        let distance = |rgb: &[u8; 3]| -> i32 {
+            rgb.iter().zip(color).map(|(&a, b)| (a as i32 - b as i32).pow(2)).sum()
+        };
+        let (name, rgb) = COLORS.iter().min_by_key(|(_, rgb)| distance(rgb)).expect("palette is not empty");
+        if *rgb != color {
+            report(&format!("colour approximated by the {} class", name));
+        }
+        body = format!("<c.{}>{}</c>", name, body);
+    }
+    wrap_emphasis(body, style)
+}
+
+/// `body` inside `<u>`, `<i>` and `<b>` tags as the style asks.
+fn wrap_emphasis(mut body: String, style: &CueStyle) -> String {
+    for (on, tag) in [(style.underline, 'u'), (style.italic, 'i'), (style.bold, 'b')] {
+        if on {
+            body = format!("<{}>{}</{}>", tag, body, tag);
+        }
+    }
+    body
+}
+
+/// Read a SubRip file.
+///
+/// Blocks without a timing line and cues without length or text are left
+/// out and reported; the numbers before the timing lines are not checked.
+pub fn import_srt(text: &str) -> CaptionImport {
+    let mut import = Importer::new(Format::Srt);
+    for (line, block) in blocks(text) {
+        // The timing line follows the cue number, if there is one
+        match block.iter().position(|l| l.contains("-->")) {
+            Some(index) if index <= 1 => import.cue(line + index, block[index], &block[index + 1..]),
+            _ => import.issue(line, "block without a timing line ignored"),
+        }
+    }
+    import.finish()
+}
+
+/// Read a WebVTT file.
+///
+/// Comments (`NOTE` blocks) are skipped; style sheets and regions are
+/// ignored and reported.
+pub fn import_webvtt(text: &str) -> CaptionImport {
+    let mut import = Importer::new(Format::WebVtt);
+    let mut blocks = blocks(text).into_iter();
+    match blocks.next() {
+        Some((line, header)) if header[0].starts_with("WEBVTT") => {
+            // The header block may run straight into the first cue
+            if let Some(index) = header.iter().position(|l| l.contains("-->")) {
+                import.cue(line + index, header[index], &header[index + 1..]);
+            }
+        }
+        first => {
+            import.issue(1, "no WEBVTT header; read anyway");
+            blocks = first.into_iter().chain(blocks).collect::<Vec<_>>().into_iter();
+        }
+    }
+
+    for (line, block) in blocks {
+        let keyword = block[0].split_whitespace().next().unwrap_or("");
+        match keyword {
+            "NOTE" => {}
+            "STYLE" => import.issue(line, "style sheet ignored; only colour classes are read"),
+            "REGION" => import.issue(line, "region ignored"),
+            _ => match block.iter().position(|l| l.contains("-->")) {
+                // The timing line follows the cue identifier, if there is one
+                Some(index) if index <= 1 => import.cue(line + index, block[index], &block[index + 1..]),
+                _ => import.issue(line, "block without a timing line ignored"),
+            },
+        }
+    }
+    import.finish()
+}
+
+/// Runs of non-blank lines, with the 1-based number of their first line.
+fn blocks(text: &str) -> Vec<(usize, Vec<&str>)> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut blocks: Vec<(usize, Vec<&str>)> = Vec::new();
+    let mut in_block = false;
+    for (index, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            in_block = false;
+        } else if in_block {
+            blocks.last_mut().expect("a block is open").1.push(line);
+        } else {
+            blocks.push((index + 1, vec![line]));
+            in_block = true;
+        }
+    }
+    blocks
+}
+
+/// `HH:MM:SS,mmm`, `HH:MM:SS.mmm` or `MM:SS.mmm`, with any number of hour
+/// digits and up to nanosecond precision.
+fn parse_timestamp(text: &str) -> Option<Time> {
+    let digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    let (clock, fraction) = text.rsplit_once([',', '.'])?;
+    if !digits(fraction) || fraction.len() > 9 {
+        return None;
+    }
+    let fraction = fraction.parse::<i64>().ok()? * 10_i64.pow(9 - fraction.len() as u32);
+    let parts: Vec<i64> = clock
+        .split(':')
+        .map(|p| if digits(p) { p.parse().ok() } else { None })
+        .collect::<Option<_>>()?;
+    let (hours, minutes, seconds) = match parts[..] {
+        [minutes, seconds] => (0, minutes, seconds),
+        [hours, minutes, seconds] => (hours, minutes, seconds),
+        _ => return None,
+    };
+    if minutes >= 60 || seconds >= 60 {
+        return None;
+    }
+    Some(((hours * 60 + minutes) * 60 + seconds) * NANOS_PER_SECOND + fraction)
+}
+
+/// A percentage from 0% to 100% as a fraction.
+fn parse_percent(text: &str) -> Option<f32> {
+    let value: f32 = text.strip_suffix('%')?.parse().ok()?;
+    (0.0..=100.0).contains(&value).then_some(value / 100.0)
+}
+
+struct Importer {
+    format: Format,
+    cues: Vec<Cue>,
+    issues: Vec<CaptionIssue>,
+}
+
+impl Importer {
+    fn new(format: Format) -> Self {
+        Self { format, cues: Vec::new(), issues: Vec::new() }
+    }
+
+    fn issue(&mut self, line: usize, reason: impl Into<String>) {
+        self.issues.push(CaptionIssue::Line { line, reason: reason.into() });
+    }
+
+    /// Read a cue from its timing line (number `line`) and text lines.
+    fn cue(&mut self, line: usize, timing: &str, text: &[&str]) {
+        let (start, rest) = timing.split_once("-->").expect("timing lines contain -->");
+        let mut rest = rest.split_whitespace();
+        let times = parse_timestamp(start.trim()).zip(rest.next().and_then(parse_timestamp));
+        let Some((start, end)) = times else {
+            self.issue(line, "timing not understood; cue ignored");
+            return;
+        };
+        if end <= start {
+            self.issue(line, "cue without length ignored");
+            return;
+        }
+
+        let markup = parse_markup(&text.join("\n"), self.format);
+        for reason in &markup.dropped {
+            self.issue(line, reason.clone());
+        }
+        if markup.text.trim().is_empty() {
+            self.issue(line, "cue without text ignored");
+            return;
+        }
+        let mut style = markup.style;
+        let mut position = CuePosition::default();
+        match self.format {
+            Format::Srt => {
+                if rest.next().is_some() {
+                    self.issue(line, "cue box coordinates ignored");
+                }
+                if let Some(anchor) = markup.anchor {
+                    style.align = [CueAlign::Start, CueAlign::Center, CueAlign::End][(anchor as usize - 1) % 3];
+                    position.y = [None, Some(SRT_MIDDLE), Some(SRT_TOP)][(anchor as usize - 1) / 3];
+                }
+            }
+            Format::WebVtt => {
+                for setting in rest {
+                    if let Err(reason) = read_setting(setting, &mut position, &mut style) {
+                        self.issue(line, reason);
+                    }
+                }
+            }
+        }
+
+        let id = self.cues.len() as CueId + 1;
+        self.cues.push(Cue::new(id, start, end, markup.text).with_position(position).with_style(style));
+    }
+
+    fn finish(mut self) -> CaptionImport {
+        self.cues.sort_by_key(|c| c.start);
+        CaptionImport { cues: self.cues, issues: self.issues }
+    }
+}
+
+/// Apply one WebVTT cue setting. Returns what was dropped or approximated.
+fn read_setting(setting: &str, position: &mut CuePosition, style: &mut CueStyle) -> Result<(), String> {
+    let not_understood = || format!("cue setting {:?} not understood", setting);
+    let (name, value) = setting.split_once(':').ok_or_else(not_understood)?;
+    match name {
+        "line" => {
+            let (line, line_align) = value.split_once(',').unwrap_or((value, "start"));
+            if let Some(y) = parse_percent(line) {
+                position.y = Some(y);
+            } else {
+                let number: i32 = line.parse().map_err(|_| not_understood())?;
+                // -1, the last line, is where cues go by default
+                if number != -1 {
+                    let y = if number < 0 { 1.0 + number as f32 * VTT_LINE_HEIGHT } else { number as f32 * VTT_LINE_HEIGHT };
+                    position.y = Some(y.clamp(0.0, 1.0));
+                    return Err(format!("line number {} approximated", number));
+                }
+            }
+            if line_align != "start" {
+                return Err(format!("line alignment {:?} approximated by start", line_align));
+            }
+        }
+        "position" => {
+            let (x, _) = value.split_once(',').unwrap_or((value, ""));
+            if x != "auto" {
+                position.x = Some(parse_percent(x).ok_or_else(not_understood)?);
+            }
+            if value.contains(',') {
+                return Err("position alignment ignored; cues are anchored by their text alignment".to_string());
+            }
+        }
+        "align" => {
+            style.align = match value {
+                "start" | "left" => CueAlign::Start,
+                "center" | "middle" => CueAlign::Center,
+                "end" | "right" => CueAlign::End,
+                _ => return Err(not_understood()),
+            };
+        }
+        "size" if value == "100%" => {}
+        "size" | "vertical" | "region" => return Err(format!("cue setting {:?} ignored", setting)),
+        _ => return Err(not_understood()),
+    }
+    Ok(())
+}
+
+/// Styles in effect at a point of a cue's text.
+#[derive(Debug, Clone, Copy, Default)]
+struct Active {
+    bold: bool,
+    italic: bool,
+    underline: bool,
+    color: Option<[u8; 3]>,
+}
+
+/// How much of a cue's visible text each style covers.
+#[derive(Debug, Default)]
+struct Coverage {
+    chars: usize,
+    bold: usize,
+    italic: usize,
+    underline: usize,
+    colored: usize,
+    color: Option<[u8; 3]>,
+    mixed_colors: bool,
+}
+
+impl Coverage {
+    fn add(&mut self, active: Active) {
+        self.chars += 1;
+        self.bold += active.bold as usize;
+        self.italic += active.italic as usize;
+        self.underline += active.underline as usize;
+        if let Some(color) = active.color {
+            self.colored += 1;
+            self.mixed_colors |= self.color.is_some_and(|c| c != color);
+            self.color = Some(color);
+        }
+    }
+}
+
+/// Plain text of a cue and the styling that applies to all of it.
+#[derive(Debug, Default)]
+struct Markup {
+    text: String,
+    style: CueStyle,
+    anchor: Option<u8>,    // SRT `{\anN}`, 1-9
+    dropped: Vec<String>, // Reasons, each once
+}
+
+impl Markup {
+    fn drop(&mut self, reason: String) {
+        if !self.dropped.contains(&reason) {
+            self.dropped.push(reason);
+        }
+    }
+}
+
+/// Strip the tags from a cue's text, keeping the styles that cover all of it.
+fn parse_markup(raw: &str, format: Format) -> Markup {
+    let mut markup = Markup::default();
+    let mut coverage = Coverage::default();
+    let mut active = Active::default();
+    let mut open: Vec<(String, Active)> = Vec::new(); // Open tags with the styles before them
+    let mut rest = raw;
+    while let Some(c) = rest.chars().next() {
+        if c == '<' {
+            // A tag name follows straight after the bracket; otherwise the '<' is text
+            let tag = rest[1..]
+                .find(['<', '>'])
+                .filter(|&end| rest.as_bytes()[end + 1] == b'>')
+                .map(|end| &rest[1..end + 1])
+                .filter(|tag| tag.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '/'));
+            if let Some(tag) = tag {
+                read_tag(tag, format, &mut active, &mut open, &mut markup);
+                rest = &rest[tag.len() + 2..];
+                continue;
+            }
+        } else if c == '{' && format == Format::Srt && rest.starts_with("{\\") {
+            if let Some(end) = rest.find('}') {
+                read_overrides(&rest[2..end], &mut markup);
+                rest = &rest[end + 1..];
+                continue;
+            }
+        } else if c == '&' && format == Format::WebVtt {
+            if let Some((decoded, length)) = entity(rest) {
+                markup.text.push(decoded);
+                rest = &rest[length..];
+                continue;
+            }
+        }
+        markup.text.push(c);
+        if !c.is_whitespace() {
+            coverage.add(active);
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+
+    let covered = |count: usize| coverage.chars > 0 && count == coverage.chars;
+    for (count, name, flag) in [
+        (coverage.bold, "bold", &mut markup.style.bold),
+        (coverage.italic, "italics", &mut markup.style.italic),
+        (coverage.underline, "underline", &mut markup.style.underline),
+    ] {
+        *flag = covered(count);
+        if count > 0 && !*flag {
+            markup.dropped.push(format!("{} on part of the text dropped", name));
+        }
+    }
+    if covered(coverage.colored) && !coverage.mixed_colors {
+        markup.style.color = coverage.color;
+    } else if coverage.colored > 0 {
+        markup.dropped.push("colour of part of the text dropped".to_string());
+    }
+    markup
+}
+
+/// Apply an opening or closing tag (without its angle brackets).
+fn read_tag(tag: &str, format: Format, active: &mut Active, open: &mut Vec<(String, Active)>, markup: &mut Markup) {
+    if let Some(name) = tag.strip_prefix('/') {
+        let name = name.trim().to_ascii_lowercase();
+        if let Some(index) = open.iter().rposition(|(n, _)| *n == name) {
+            *active = open[index].1;
+            open.truncate(index);
+        }
+        return;
+    }
+    if format == Format::WebVtt && tag.starts_with(|c: char| c.is_ascii_digit()) {
+        markup.drop("timestamp tags dropped".to_string());
+        return;
+    }
+
+    let (head, annotation) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+    let mut classes = head.split('.');
+    let name = classes.next().unwrap_or("").to_ascii_lowercase();
+    let before = *active;
+    match (name.as_str(), format) {
+        ("b", _) => active.bold = true,
+        ("i", _) => active.italic = true,
+        ("u", _) => active.underline = true,
+        ("font", Format::Srt) => {
+            for attribute in annotation.split_whitespace() {
+                let (key, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+                let value = value.trim_matches(|c| c == '"' || c == '\'');
+                match parse_color(value) {
+                    Some(color) if key.eq_ignore_ascii_case("color") => active.color = Some(color),
+                    _ => markup.drop(format!("font {} {:?} dropped", key, value)),
+                }
+            }
+        }
+        ("c", Format::WebVtt) => {
+            for class in classes {
+                match COLORS.iter().find(|(n, _)| *n == class) {
+                    Some((_, color)) => active.color = Some(*color),
+                    None => markup.drop(format!("class {:?} dropped", class)),
+                }
+            }
+        }
+        _ => markup.drop(format!("<{}> tags dropped", name)),
+    }
+    open.push((name, before));
+}
+
+/// Apply the SRT override tags of one `{\...}` block.
+fn read_overrides(block: &str, markup: &mut Markup) {
+    for tag in block.split('\\') {
+        let anchor = tag.strip_prefix("an").and_then(|n| n.parse::<u8>().ok()).filter(|n| (1..=9).contains(n));
+        match anchor {
+            Some(anchor) => markup.anchor = Some(anchor),
+            None => markup.drop(format!("override tag {{\\{}}} dropped", tag)),
+        }
+    }
+}
+
+/// `#rrggbb` or a colour name.
+fn parse_color(text: &str) -> Option<[u8; 3]> {
+    if let Some(hex) = text.strip_prefix('#').filter(|h| h.len() == 6) {
+        let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        return Some([channel(0)?, channel(2)?, channel(4)?]);
+    }
+    COLORS.iter().find(|(name, _)| name.eq_ignore_ascii_case(text)).map(|(_, color)| *color)
+}
+
+/// The character of a WebVTT character reference at the start of `text`,
+/// and the reference's length.
+fn entity(text: &str) -> Option<(char, usize)> {
+    let end = text.find(';').filter(|&end| end <= 10)?;
+    let name = &text[1..end];
+    let decoded = match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "lrm" => '\u{200e}',
+        "rlm" => '\u{200f}',
+        _ => {
+            let number = name.strip_prefix('#')?;
+            let code = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => number.parse().ok()?,
+            };
+            char::from_u32(code)?
+        }
+    };
+    Some((decoded, end + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::time;
+
+    fn ms(millis: i64) -> Time {
+        time::from_millis(millis)
+    }
+
+    fn cues() -> Vec<Cue> {
+        let bold_yellow = CueStyle { bold: true, color: Some([255, 255, 0]), ..CueStyle::default() };
+        let top_left = CueStyle { italic: true, align: CueAlign::Start, ..CueStyle::default() };
+        vec![
+            Cue::new(1, ms(1000), ms(3500), "Hello,\nworld").with_style(bold_yellow),
+            Cue::new(2, ms(3000), ms(4000), "1 < 2 & on top")
+                .with_position(CuePosition { x: None, y: Some(SRT_TOP) })
+                .with_style(top_left),
+            Cue::new(3, ms(3_600_000), ms(3_601_001), "An hour in"),
+        ]
+    }
+
+    #[test]
+    fn test_srt_fixture() {
+        let export = export_srt(&cues());
+        assert!(export.issues.is_empty(), "{:?}", export.issues);
+        assert_eq!(
+            export.text,
+            "1\n00:00:01,000 --> 00:00:03,500\n<b><font color=\"#ffff00\">Hello,\nworld</font></b>\n\n\
+             2\n00:00:03,000 --> 00:00:04,000\n{\\an7}<i>1 < 2 & on top</i>\n\n\
+             3\n01:00:00,000 --> 01:00:01,001\nAn hour in\n\n"
+        );
+        let import = import_srt(&export.text);
+        assert!(import.issues.is_empty(), "{:?}", import.issues);
+        assert_eq!(import.cues, cues());
+    }
+
+    #[test]
+    fn test_webvtt_fixture() {
+        let export = export_webvtt(&cues());
+        assert!(export.issues.is_empty(), "{:?}", export.issues);
+        assert_eq!(
+            export.text,
+            "WEBVTT\n\n\
+             00:00:01.000 --> 00:00:03.500\n<b><c.yellow>Hello,\nworld</c></b>\n\n\
+             00:00:03.000 --> 00:00:04.000 line:5% align:start\n<i>1 &lt; 2 &amp; on top</i>\n\n\
+             01:00:00.000 --> 01:00:01.001\nAn hour in\n\n"
+        );
+        let import = import_webvtt(&export.text);
+        assert!(import.issues.is_empty(), "{:?}", import.issues);
+        assert_eq!(import.cues, cues());
+    }
+
+    #[test]
+    fn test_export_approximations() {
+        let cue = Cue::new(7, ms(1) / 4, 1_234_567_890, "Centre\n\nleft")
+            .with_position(CuePosition { x: Some(0.125), y: Some(0.3) })
+            .with_style(CueStyle { color: Some([250, 10, 20]), ..CueStyle::default() });
+        let tiny = Cue::new(8, 0, ms(1) / 4, "Blink");
+
+        let srt = export_srt(&[cue.clone(), tiny.clone()]);
+        assert_eq!(srt.text, "1\n00:00:00,000 --> 00:00:01,235\n{\\an5}<font color=\"#fa0a14\">Centre\nleft</font>\n\n");
+        let reasons: Vec<String> = srt.issues.iter().map(|i| i.to_string()).collect();
+        assert_eq!(reasons, vec![
+            "Cue 8: shorter than a millisecond; left out",
+            "Cue 7: blank lines removed",
+            "Cue 7: vertical position approximated by an SRT alignment tag",
+            "Cue 7: horizontal position approximated by an SRT alignment tag",
+        ]);
+
+        let vtt = export_webvtt(&[cue]);
+        assert!(vtt.text.contains("00:00:00.000 --> 00:00:01.235 line:30% position:12.5%\n<c.red>Centre\nleft</c>"));
+        assert!(vtt.issues.contains(&CaptionIssue::Cue { cue_id: 7, reason: "colour approximated by the red class".to_string() }));
+    }
+
+    #[test]
+    fn test_import_srt_leniently() {
+        let text = "\u{feff}1\r\n00:00:02,5 --> 00:00:04,000 X1:10 X2:20 Y1:30 Y2:40\r\n<i>Half</i> italic {\\an8}{\\pos(10,10)}\r\n\r\n\
+                    00:00:01.000 --> 00:00:02.000\n<FONT COLOR=red>No number, all red</FONT>\n\n\
+                    3\nnot a timing line\n\n\
+                    4\n00:00:05,000 --> 00:00:05,000\nEmpty\n";
+        let import = import_srt(text);
+        let cues: Vec<(Time, Time, &str)> = import.cues.iter().map(|c| (c.start, c.end, c.text.as_str())).collect();
+        assert_eq!(cues, vec![(ms(1000), ms(2000), "No number, all red"), (ms(2500), ms(4000), "Half italic ")]);
+        assert_eq!(import.cues[0].id, 2);
+        assert_eq!(import.cues[0].style.color, Some([255, 0, 0]));
+        let half = &import.cues[1];
+        assert!(!half.style.italic);
+        assert_eq!((half.style.align, half.position.y), (CueAlign::Center, Some(SRT_TOP)));
+
+        let reasons: Vec<String> = import.issues.iter().map(|i| i.to_string()).collect();
+        assert_eq!(reasons, vec![
+            "Line 2: override tag {\\pos(10,10)} dropped",
+            "Line 2: italics on part of the text dropped",
+            "Line 2: cue box coordinates ignored",
+            "Line 8: block without a timing line ignored",
+            "Line 12: cue without length ignored",
+        ]);
+    }
+
+    #[test]
+    fn test_import_webvtt_settings_and_tags() {
+        let text = "WEBVTT - Interview\nKind: captions\n\n\
+                    NOTE a comment\nover two lines\n\n\
+                    STYLE\n::cue { color: red }\n\n\
+                    intro\n00:01.000 --> 00:02.000 line:-1 position:25%,line-left align:left size:50%\n<v Ann><c.cyan.loud>Hi &lt;3</c></v>\n\n\
+                    00:02.000 --> 00:03.000 line:2 align:right\n<lang en>Two</lang> <00:02.500>words\n";
+        let import = import_webvtt(text);
+        assert_eq!(import.cues.len(), 2);
+        let intro = &import.cues[0];
+        assert_eq!(intro.text, "Hi <3");
+        assert_eq!(intro.position, CuePosition { x: Some(0.25), y: None });
+        assert_eq!((intro.style.align, intro.style.color), (CueAlign::Start, Some([0, 255, 255])));
+        let words = &import.cues[1];
+        assert_eq!((words.text.as_str(), words.style.align), ("Two words", CueAlign::End));
+        assert_eq!(words.position.y, Some(2.0 * VTT_LINE_HEIGHT));
+
+        let reasons: Vec<String> = import.issues.iter().map(|i| i.to_string()).collect();
+        assert_eq!(reasons, vec![
+            "Line 7: style sheet ignored; only colour classes are read",
+            "Line 11: <v> tags dropped",
+            "Line 11: class \"loud\" dropped",
+            "Line 11: position alignment ignored; cues are anchored by their text alignment",
+            "Line 11: cue setting \"size:50%\" ignored",
+            "Line 14: <lang> tags dropped",
+            "Line 14: timestamp tags dropped",
+            "Line 14: line number 2 approximated",
+        ]);
+
+        assert!(matches!(&import_webvtt("00:01.000 --> 00:02.000\nHi\n").issues[..], [CaptionIssue::Line { line: 1, .. }]));
+    }
+
+    #[test]
+    fn test_timestamps() {
+        assert_eq!(parse_timestamp("01:02:03,004"), Some(ms(3_723_004)));
+        assert_eq!(parse_timestamp("123:00:00.5"), Some(time::from_seconds(442_800.5)));
+        assert_eq!(parse_timestamp("02:03.000000001"), Some(ms(123_000) + 1));
+        assert_eq!(parse_timestamp("00:60.000"), None);
+        assert_eq!(parse_timestamp("1:2:3:4.000"), None);
+        assert_eq!(parse_timestamp("00:01"), None);
+        assert_eq!(timestamp(to_millis(ms(3_723_004) + 499_999), ','), "01:02:03,004");
+    }
+}
@@ -0,0 +1,764 @@
+//! CMX3600 edit decision lists.
+//!
+//! [`export_edl`] writes the first video track and the first two audio tracks
+//! of a timeline as CMX3600 events; [`import_edl`] rebuilds a timeline from
+//! such a list. Both report every event they could not represent exactly as
+//! an [`EdlIssue`] instead of failing.
+//!
+//! What the format carries:
+//! - One event per clip, with source timecode counted from 00:00:00:00 at the
+//!   start of the media and record timecode from `EdlOptions::format`'s start
+//! - Reel names derived from the clip's media file, with the full path in a
+//!   `* SOURCE FILE:` comment
+//! - Linked clips with the same ranges on several tracks as one event
+//!   (channels `B`, `AA`, `AA/V`, ...)
+//! - Cross dissolves and audio crossfades as `D` events; imported
+//!   transitions start at the cut
+//! - Constant forward speeds and freeze frames as `M2` motion effects, with
+//!   the source out point at source in plus the record length
+//!
+//! Everything else (other tracks, compound clips, reverse and remapped
+//! speeds, visual properties, keyframes) is left out or approximated, and
+//! reported.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use crate::core::time::{FrameRate, Time};
+use crate::core::timecode::{Timecode, TimecodeFormat};
+use crate::timeline::clip::{Clip, ClipId, LinkGroupId};
+use crate::timeline::speed::Speed;
+use crate::timeline::timeline::Timeline;
+use crate::timeline::track::{Track, TrackId, TrackType};
+use crate::timeline::transition::{FadeCurve, TransitionAlignment, TransitionKind};
+use crate::timeline::visual::VisualProperties;
+
+/// Reel names that stand for black rather than a source.
+const BLACK_REELS: [&str; 3] = ["BL", "BLK", "BLACK"];
+
+/// Longest reel name CMX3600 allows.
+const REEL_LENGTH: usize = 8;
+
+/// Curve given to audio crossfades read from an EDL.
+const IMPORTED_FADE_CURVE: FadeCurve = FadeCurve::EqualPower;
+
+/// Title and timecode settings of an EDL.
+#[derive(Debug, Clone)]
+pub struct EdlOptions {
+    pub title: String,
+    /// Rate and counting of all timecodes; `start` is the record timecode of
+    /// timeline position 0
+    pub format: TimecodeFormat,
+}
+
+impl EdlOptions {
+    /// Options for `rate`, with drop-frame counting where the rate has it and
+    /// the record timecode starting at 01:00:00:00.
+    pub fn new(title: impl Into<String>, rate: FrameRate) -> Self {
+        let format = TimecodeFormat::new(rate, rate.is_ntsc());
+        let start = Timecode::new(1, 0, 0, 0, format.drop_frame);
+        Self {
+            title: title.into(),
+            format: format.with_start(start).expect("01:00:00:00 exists at every rate"),
+        }
+    }
+}
+
+/// Something an EDL export or import could not represent exactly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EdlIssue {
+    /// A clip or its transition was left out of the EDL or approximated
+    Clip { clip_id: ClipId, reason: String },
+    /// A line of the EDL was ignored or approximated (1-based line number)
+    Line { line: usize, reason: String },
+}
+
+impl fmt::Display for EdlIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EdlIssue::Clip { clip_id, reason } => write!(f, "Clip {}: {}", clip_id, reason),
+            EdlIssue::Line { line, reason } => write!(f, "Line {}: {}", line, reason),
+        }
+    }
+}
+
+/// Result of `export_edl`.
+#[derive(Debug, Clone)]
+pub struct EdlExport {
+    pub text: String,
+    pub reels: BTreeMap<String, PathBuf>, // Reel name -> media file, for the importer
+    pub issues: Vec<EdlIssue>,
+}
+
+/// Result of `import_edl`.
+#[derive(Debug, Clone)]
+pub struct EdlImport {
+    pub title: String,
+    pub timeline: Timeline,
+    pub issues: Vec<EdlIssue>,
+}
+
+/// Track an EDL channel refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Channel {
+    Video,
+    Audio(usize), // 0 = A (A1)
+}
+
+impl Channel {
+    /// Stream of the media file a clip on this channel plays.
+    fn stream_index(self) -> usize {
+        match self {
+            Channel::Video => 0,
+            Channel::Audio(index) => index + 1,
+        }
+    }
+}
+
+/// Channel field for a set of channels (sorted, video first).
+fn channel_field(channels: &[Channel]) -> &'static str {
+    match channels {
+        [Channel::Video] => "V",
+        [Channel::Audio(0)] => "A",
+        [Channel::Audio(1)] => "A2",
+        [Channel::Audio(0), Channel::Audio(1)] => "AA",
+        [Channel::Video, Channel::Audio(0)] => "B",
+        [Channel::Video, Channel::Audio(1)] => "A2/V",
+        [Channel::Video, Channel::Audio(0), Channel::Audio(1)] => "AA/V",
+        _ => unreachable!("only V, A and A2 are exported"),
+    }
+}
+
+/// Channels named by a channel field, or `None` if it is not understood.
+fn parse_channels(field: &str) -> Option<Vec<Channel>> {
+    let mut channels = Vec::new();
+    for part in field.split('/') {
+        match part {
+            "V" => channels.push(Channel::Video),
+            "A" => channels.push(Channel::Audio(0)),
+            "AA" => channels.extend([Channel::Audio(0), Channel::Audio(1)]),
+            "B" => channels.extend([Channel::Video, Channel::Audio(0)]),
+            _ => {
+                let number: usize = part.strip_prefix('A')?.parse().ok()?;
+                channels.push(Channel::Audio(number.checked_sub(1)?));
+            }
+        }
+    }
+    channels.sort();
+    channels.dedup();
+    Some(channels)
+}
+
+/// A reel name for `path`: its file stem in upper case, reduced to letters,
+/// digits and underscores, and made unique among `reels`.
+fn reel_name(path: &Path, reels: &BTreeMap<String, PathBuf>) -> String {
+    let stem = path.file_stem().map(|s| s.to_string_lossy()).unwrap_or_default();
+    let mut base: String = stem
+        .chars()
+        .filter_map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '_' => Some(c.to_ascii_uppercase()),
+            ' ' | '-' | '.' => Some('_'),
+            _ => None,
+        })
+        .take(REEL_LENGTH)
+        .collect();
+    if base.is_empty() || BLACK_REELS.contains(&base.as_str()) {
+        base = "AX".to_string();
+    }
+    let mut name = base.clone();
+    let mut counter = 1;
+    while reels.contains_key(&name) {
+        counter += 1;
+        let suffix = counter.to_string();
+        let keep = base.len().min(REEL_LENGTH - suffix.len());
+        name = format!("{}{}", &base[..keep], suffix);
+    }
+    name
+}
+
+/// A clip as it goes into an event, in frames.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Piece {
+    reel: String,
+    source_in: i64,
+    record_in: i64,
+    record_out: i64,
+    motion: Option<(Speed, String)>,  // Speed and its M2 field
+    dissolve: Option<(i64, String, i64)>, // Length, outgoing reel and its source frame at the start
+}
+
+/// One event: a piece on one or more channels.
+struct Event {
+    piece: Piece,
+    channels: Vec<Channel>,
+    path: PathBuf,
+}
+
+/// Write the first video track and first two audio tracks of `timeline` as
+/// a CMX3600 EDL.
+///
+/// Times are rounded down to frames of `options.format.rate`.
+pub fn export_edl(timeline: &Timeline, options: &EdlOptions) -> EdlExport {
+    let rate = options.format.rate;
+    let source_format = TimecodeFormat::new(rate, options.format.drop_frame);
+    let frame = |time: Time| rate.time_to_frame(time);
+
+    let mut reels: BTreeMap<String, PathBuf> = BTreeMap::new();
+    let mut reel_of = HashMap::new();
+    let mut issues = Vec::new();
+    let mut events: Vec<Event> = Vec::new();
+    // Event a linked clip with the same piece can join
+    let mut shareable: HashMap<(Piece, LinkGroupId), usize> = HashMap::new();
+
+    let mut channels: Vec<(Channel, &Track)> = Vec::new();
+    channels.extend(timeline.video_tracks().next().map(|t| (Channel::Video, t)));
+    channels.extend(timeline.audio_tracks().take(2).enumerate().map(|(i, t)| (Channel::Audio(i), t)));
+    let skipped = timeline.tracks.iter().filter(|t| !channels.iter().any(|(_, c)| c.id == t.id));
+    for track in skipped {
+        for clip in &track.clips {
+            let reason = "CMX3600 carries one video and two audio tracks; clip left out".to_string();
+            issues.push(EdlIssue::Clip { clip_id: clip.id, reason });
+        }
+    }
+
+    for (channel, track) in channels {
+        let windows = track.transition_windows();
+        for clip in &track.clips {
+            let mut report = |reason: &str| issues.push(EdlIssue::Clip { clip_id: clip.id, reason: reason.to_string() });
+            let Some(path) = clip.source_path() else {
+                report("compound clips have no source media; clip left out");
+                continue;
+            };
+            let reel = reel_of
+                .entry(path.to_path_buf())
+                .or_insert_with(|| {
+                    let name = reel_name(path, &reels);
+                    reels.insert(name.clone(), path.to_path_buf());
+                    name
+                })
+                .clone();
+
+            if [clip.timeline_start, clip.timeline_end, clip.in_point].iter().any(|&t| rate.snap(t) != t) {
+                report("times are not on frame boundaries; rounded down");
+            }
+            if clip.visual != VisualProperties::default() {
+                report("visual properties are not represented");
+            }
+            if !clip.animation.is_empty() {
+                report("keyframes are not represented");
+            }
+
+            let motion = if clip.time_remap.is_some() {
+                report("time remapping is not represented; written at normal speed");
+                None
+            } else if clip.speed.is_reverse() {
+                report("reverse motion is not represented; written at normal speed");
+                None
+            } else if clip.speed == Speed::NORMAL {
+                None
+            } else {
+                let tenths = rate.nominal() as i64 * 10 * clip.speed.num as i64;
+                if tenths % clip.speed.den as i64 != 0 {
+                    report("speed rounded to a tenth of a frame per second");
+                }
+                let tenths = (tenths as f64 / clip.speed.den as f64).round() as i64;
+                let field = format!("{:05.1}", tenths as f64 / 10.0);
+                Some((clip.speed, field))
+            };
+
+            // An incoming transition starts this event early; an outgoing one
+            // ends it at the transition's start
+            let incoming = windows.iter().find(|w| w.to.id == clip.id);
+            let outgoing = windows.iter().find(|w| w.from.id == clip.id);
+            let start = incoming.map_or(clip.timeline_start, |w| w.start);
+            let end = outgoing.map_or(clip.timeline_end, |w| w.start);
+            let dissolve = incoming.and_then(|window| {
+                match window.transition.kind {
+                    TransitionKind::DipToBlack | TransitionKind::DipToWhite => {
+                        report("dips are written as dissolves");
+                    }
+                    TransitionKind::Crossfade(curve) if curve != IMPORTED_FADE_CURVE => {
+                        report("the crossfade curve is not represented");
+                    }
+                    _ => {}
+                }
+                let from_reel = reel_of.get(window.from.source_path()?)?.clone();
+                let from_source = frame(window.from.handle_source_at(window.start));
+                Some((frame(window.end) - frame(window.start), from_reel, from_source))
+            });
+            if incoming.is_some() && dissolve.is_none() {
+                report("transition from a compound clip left out");
+            }
+            if frame(end) <= frame(start) {
+                if outgoing.is_none() {
+                    report("shorter than a frame; clip left out");
+                }
+                continue;
+            }
+
+            let piece = Piece {
+                reel,
+                source_in: frame(clip.handle_source_at(start)),
+                record_in: frame(start),
+                record_out: frame(end),
+                motion,
+                dissolve,
+            };
+            // Linked clips with the same ranges share one event
+            let key = clip.link_group.map(|group| (piece.clone(), group));
+            let shared = key.as_ref()
+                .and_then(|key| shareable.get(key))
+                .map(|&index| &mut events[index])
+                .filter(|event| !event.channels.contains(&channel));
+            match shared {
+                Some(event) => {
+                    event.channels.push(channel);
+                    event.channels.sort();
+                }
+                None => {
+                    if let Some(key) = key {
+                        shareable.insert(key, events.len());
+                    }
+                    events.push(Event { piece, channels: vec![channel], path: path.to_path_buf() });
+                }
+            }
+        }
+    }
+    events.sort_by_key(|e| (e.piece.record_in, e.channels[0]));
+
+    let source_tc = |frame: i64| source_format.format(rate.frame_to_time(frame));
+    let record_tc = |frame: i64| options.format.format(rate.frame_to_time(frame));
+    let mut text = format!("TITLE: {}\n", options.title);
+    let fcm = if options.format.drop_frame { "DROP FRAME" } else { "NON-DROP FRAME" };
+    text.push_str(&format!("FCM: {}\n", fcm));
+    for (index, event) in events.iter().enumerate() {
+        let number = index + 1;
+        let piece = &event.piece;
+        let channels = channel_field(&event.channels);
+        let source_out = piece.source_in + piece.record_out - piece.record_in;
+        text.push('\n');
+        let (transition, length) = match &piece.dissolve {
+            Some((length, from_reel, from_source)) => {
+                text.push_str(&format!(
+                    "{:03}  {:<8} {:<5} {:<4} {:<3} {} {} {} {}\n",
+                    number, from_reel, channels, "C", "",
+                    source_tc(*from_source), source_tc(*from_source),
+                    record_tc(piece.record_in), record_tc(piece.record_in),
+                ));
+                ("D", format!("{:03}", length))
+            }
+            None => ("C", String::new()),
+        };
+        text.push_str(&format!(
+            "{:03}  {:<8} {:<5} {:<4} {:<3} {} {} {} {}\n",
+            number, piece.reel, channels, transition, length,
+            source_tc(piece.source_in), source_tc(source_out),
+            record_tc(piece.record_in), record_tc(piece.record_out),
+        ));
+        if let Some((_, field)) = &piece.motion {
+            text.push_str(&format!("M2   {:<8}       {:>5}                {}\n", piece.reel, field, source_tc(piece.source_in)));
+        }
+        let name = event.path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+        text.push_str(&format!("* FROM CLIP NAME: {}\n", name));
+        text.push_str(&format!("* SOURCE FILE: {}\n", event.path.display()));
+    }
+
+    EdlExport { text, reels, issues }
+}
+
+/// How an event line moves from the previous source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EdlTransition {
+    Cut,
+    Dissolve(i64), // Length in frames
+    Wipe,
+    Key,
+}
+
+/// One event line, with times in nanoseconds.
+#[derive(Debug, Clone)]
+struct EventLine {
+    line: usize,
+    reel: String,
+    channels: Vec<Channel>,
+    transition: EdlTransition,
+    source_in: Time,
+    record_in: Time,
+    record_out: Time,
+}
+
+/// The lines sharing an event number, with the `M2` lines and comments after them.
+#[derive(Debug, Default)]
+struct EventGroup {
+    number: String,
+    lines: Vec<EventLine>,
+    motion: Vec<(usize, String, f64)>, // Line, reel, frames per second
+    source_files: Vec<PathBuf>,
+}
+
+/// Parse an event line, or say why it cannot be read.
+fn parse_event_line(
+    line: usize,
+    tokens: &[&str],
+    source_format: &TimecodeFormat,
+    record_format: &TimecodeFormat,
+) -> Result<EventLine, String> {
+    let (reel, channels, code, rest) = match tokens {
+        [_, reel, channels, code, rest @ ..] => (*reel, *channels, *code, rest),
+        _ => return Err("too few fields".to_string()),
+    };
+    let (length, times) = match rest {
+        times if times.len() == 4 => (None, times),
+        [length, times @ ..] if times.len() == 4 => (Some(*length), times),
+        _ => return Err("expected four timecodes".to_string()),
+    };
+    let channels = parse_channels(channels).ok_or_else(|| format!("unknown channels {:?}", channels))?;
+    let length = length.map(|l| l.parse::<i64>().map_err(|_| format!("bad transition length {:?}", l)));
+    let transition = match code.chars().next() {
+        Some('C') => EdlTransition::Cut,
+        Some('D') => EdlTransition::Dissolve(length.ok_or("dissolve without a length")??),
+        Some('W') => EdlTransition::Wipe,
+        Some('K') => EdlTransition::Key,
+        _ => return Err(format!("unknown transition {:?}", code)),
+    };
+    let source = |tc: &str| source_format.parse(tc).map_err(|e| e.to_string());
+    let record = |tc: &str| record_format.parse(tc).map_err(|e| e.to_string());
+    Ok(EventLine {
+        line,
+        reel: reel.to_string(),
+        channels,
+        transition,
+        source_in: source(times[0])?,
+        record_in: record(times[2])?,
+        record_out: record(times[3])?,
+    })
+}
+
+/// Rebuild a timeline from a CMX3600 EDL.
+///
+/// `reels` maps reel names to media files; reels missing from it fall back
+/// to the event's `* SOURCE FILE:` comment. Events on black are gaps. The
+/// frame rate and record start come from `options` (an EDL does not state
+/// its rate); drop-frame counting follows the `FCM:` line.
+pub fn import_edl(text: &str, options: &EdlOptions, reels: &HashMap<String, PathBuf>) -> EdlImport {
+    let rate = options.format.rate;
+    let mut record_format = options.format;
+    let mut title = String::new();
+    let mut issues = Vec::new();
+    let mut groups: Vec<EventGroup> = Vec::new();
+
+    for (index, raw) in text.lines().enumerate() {
+        let line = index + 1;
+        let trimmed = raw.trim();
+        let tokens: Vec<&str> = trimmed.split_whitespace().collect();
+        if trimmed.is_empty() {
+            continue;
+        } else if let Some(rest) = trimmed.strip_prefix("TITLE:") {
+            title = rest.trim().to_string();
+        } else if let Some(rest) = trimmed.strip_prefix("FCM:") {
+            let drop_frame = rest.trim() == "DROP FRAME";
+            record_format = TimecodeFormat::new(rate, drop_frame)
+                .with_start(options.format.start)
+                .unwrap_or(record_format);
+            if record_format.drop_frame != drop_frame {
+                issues.push(EdlIssue::Line { line, reason: format!("{} fps has no drop-frame counting", rate) });
+            }
+        } else if let Some(comment) = trimmed.strip_prefix('*') {
+            if let (Some(group), Some(path)) = (groups.last_mut(), comment.trim().strip_prefix("SOURCE FILE:")) {
+                group.source_files.push(PathBuf::from(path.trim()));
+            }
+        } else if tokens[0] == "M2" {
+            let speed = tokens.get(2).and_then(|s| s.parse::<f64>().ok());
+            match (groups.last_mut(), tokens.get(1), speed) {
+                (Some(group), Some(reel), Some(speed)) => group.motion.push((line, reel.to_string(), speed)),
+                _ => issues.push(EdlIssue::Line { line, reason: "unreadable motion effect".to_string() }),
+            }
+        } else if tokens[0].bytes().all(|b| b.is_ascii_digit()) {
+            let source_format = TimecodeFormat::new(rate, record_format.drop_frame);
+            match parse_event_line(line, &tokens, &source_format, &record_format) {
+                Ok(event) => {
+                    if groups.last().is_none_or(|g| g.number != tokens[0]) {
+                        groups.push(EventGroup { number: tokens[0].to_string(), ..Default::default() });
+                    }
+                    groups.last_mut().expect("group was pushed").lines.push(event);
+                }
+                Err(reason) => issues.push(EdlIssue::Line { line, reason }),
+            }
+        } else {
+            issues.push(EdlIssue::Line { line, reason: format!("{:?} is not supported; ignored", tokens[0]) });
+        }
+    }
+
+    let mut importer = Importer { timeline: Timeline::new(), issues, rate };
+    for group in &groups {
+        importer.add_group(group, reels);
+    }
+    importer.timeline.update_duration();
+    importer.issues.sort_by_key(|issue| match issue {
+        EdlIssue::Line { line, .. } => *line,
+        EdlIssue::Clip { .. } => 0,
+    });
+    EdlImport { title, timeline: importer.timeline, issues: importer.issues }
+}
+
+/// Timeline being rebuilt from event groups.
+struct Importer {
+    timeline: Timeline,
+    issues: Vec<EdlIssue>,
+    rate: FrameRate,
+}
+
+impl Importer {
+    fn report(&mut self, line: usize, reason: impl Into<String>) {
+        self.issues.push(EdlIssue::Line { line, reason: reason.into() });
+    }
+
+    /// Track for `channel`, adding audio tracks as needed.
+    fn track_for(&mut self, channel: Channel) -> TrackId {
+        let (track_type, index) = match channel {
+            Channel::Video => (TrackType::Video, 0),
+            Channel::Audio(index) => (TrackType::Audio, index),
+        };
+        while self.timeline.tracks_of_type(track_type).count() <= index {
+            self.timeline.add_track(track_type);
+        }
+        self.timeline.tracks_of_type(track_type).nth(index).expect("track was added").id
+    }
+
+    fn add_group(&mut self, group: &EventGroup, reels: &HashMap<String, PathBuf>) {
+        let (from, to) = match group.lines.as_slice() {
+            [from, to] if to.transition != EdlTransition::Cut => (Some(from), to),
+            [single] => (None, single),
+            lines => {
+                for line in lines {
+                    self.add_line(line, group, reels, EdlTransition::Cut);
+                }
+                return;
+            }
+        };
+
+        let mut transition = to.transition;
+        if let Some(from) = from {
+            // The outgoing line is normally empty: its clip is the previous event
+            self.add_line(from, group, reels, EdlTransition::Cut);
+            if BLACK_REELS.contains(&from.reel.as_str()) && transition != EdlTransition::Cut {
+                self.report(to.line, "transitions from black are not supported; imported as a cut");
+                transition = EdlTransition::Cut;
+            }
+        } else if transition != EdlTransition::Cut {
+            self.report(to.line, "transition without an outgoing source; imported as a cut");
+            transition = EdlTransition::Cut;
+        }
+        self.add_line(to, group, reels, transition);
+    }
+
+    /// Place the clips of one event line and their incoming transition.
+    fn add_line(&mut self, event: &EventLine, group: &EventGroup, reels: &HashMap<String, PathBuf>, transition: EdlTransition) {
+        let length = event.record_out - event.record_in;
+        if BLACK_REELS.contains(&event.reel.as_str()) || length == 0 {
+            return;
+        }
+        if length < 0 || event.record_in < 0 {
+            self.report(event.line, "record range is empty or before the record start; ignored");
+            return;
+        }
+        let path = match (reels.get(&event.reel), group.source_files.as_slice()) {
+            (Some(path), _) => path.clone(),
+            (None, [path]) => path.clone(),
+            _ => {
+                self.report(event.line, format!("reel {} is not mapped to a file; ignored", event.reel));
+                return;
+            }
+        };
+
+        let mut speed = Speed::NORMAL;
+        if let Some((line, _, fps)) = group.motion.iter().find(|(_, reel, _)| *reel == event.reel) {
+            let tenths = (fps * 10.0).round() as i32;
+            if tenths < 0 {
+                self.report(*line, "reverse motion is not supported; imported at normal speed");
+            } else {
+                speed = Speed::new(tenths, self.rate.nominal() * 10);
+            }
+        }
+
+        let mut clip_ids = Vec::new();
+        for &channel in &event.channels {
+            let track_id = self.track_for(channel);
+            let id = self.timeline.allocate_clip_id();
+            let clip = if speed.is_freeze() {
+                Clip::freeze_frame(id, path.clone(), event.source_in, event.record_in, length, channel.stream_index())
+            } else {
+                let source_out = event.source_in + speed.to_source(length).max(1);
+                Clip::new(id, path.clone(), event.source_in, source_out, event.record_in, channel.stream_index())
+                    .with_speed(speed)
+            };
+            match self.timeline.add_clip(track_id, clip) {
+                Ok(()) => clip_ids.push((id, channel)),
+                Err(e) => self.report(event.line, format!("{}; ignored", e)),
+            }
+        }
+        if clip_ids.len() > 1 {
+            let ids: Vec<ClipId> = clip_ids.iter().map(|(id, _)| *id).collect();
+            self.timeline.link(&ids).expect("new clips are on unlocked tracks");
+        }
+
+        match transition {
+            EdlTransition::Cut => {}
+            EdlTransition::Wipe => self.report(event.line, "wipes are not supported; imported as a cut"),
+            EdlTransition::Key => self.report(event.line, "keys are not supported; imported as a cut"),
+            EdlTransition::Dissolve(frames) => {
+                let start = self.rate.time_to_frame(event.record_in);
+                let duration = self.rate.frame_to_time(start + frames) - event.record_in;
+                for (clip_id, channel) in clip_ids {
+                    let kind = match channel {
+                        Channel::Video => TransitionKind::CrossDissolve,
+                        Channel::Audio(_) => TransitionKind::Crossfade(IMPORTED_FADE_CURVE),
+                    };
+                    if duration <= 0 {
+                        self.report(event.line, "dissolve has no length; imported as a cut");
+                        break;
+                    }
+                    let added = self.timeline.add_transition(clip_id, kind, TransitionAlignment::StartAtCut, duration);
+                    if let Err(e) = added {
+                        self.report(event.line, format!("{}; dissolve imported as a cut", e));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: FrameRate = FrameRate::FPS_25;
+
+    fn f(frames: i64) -> Time {
+        RATE.frame_to_time(frames)
+    }
+
+    fn options() -> EdlOptions {
+        EdlOptions::new("Conform", RATE)
+    }
+
+    /// V1: a (linked with A1) | b with a 10-frame dissolve in; A2: c at half speed.
+    fn timeline() -> Timeline {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        let a1 = timeline.tracks[1].id;
+        let a2 = timeline.add_track(TrackType::Audio);
+        timeline.add_clip(v1, Clip::new(1, PathBuf::from("/media/Interview A.mov"), f(50), f(150), 0, 0)).unwrap();
+        timeline.add_clip(a1, Clip::new(2, PathBuf::from("/media/Interview A.mov"), f(50), f(150), 0, 1)).unwrap();
+        timeline.link(&[1, 2]).unwrap();
+        timeline.add_clip(v1, Clip::new(3, PathBuf::from("/media/b-roll.mp4"), f(25), f(75), f(100), 0)).unwrap();
+        timeline.add_transition(3, TransitionKind::CrossDissolve, TransitionAlignment::StartAtCut, f(10)).unwrap();
+        let music = Clip::new(4, PathBuf::from("/media/music.wav"), 0, f(50), f(25), 3).with_speed(Speed::new(1, 2));
+        timeline.add_clip(a2, music).unwrap();
+        timeline
+    }
+
+    fn reels(export: &EdlExport) -> HashMap<String, PathBuf> {
+        export.reels.iter().map(|(k, v)| (k.clone(), v.clone())).collect()
+    }
+
+    /// Media, ranges and speed of every clip, track by track, for comparing timelines.
+    fn layout(timeline: &Timeline) -> Vec<String> {
+        timeline.tracks.iter().map(|track| {
+            track.clips.iter().map(|c| {
+                format!("{:?} {}-{} at {}-{} x{:?}; ", c.source_path(), c.in_point, c.out_point, c.timeline_start, c.timeline_end, c.speed)
+            }).collect()
+        }).collect()
+    }
+
+    #[test]
+    fn test_export() {
+        let export = export_edl(&timeline(), &options());
+        assert!(export.issues.is_empty(), "{:?}", export.issues);
+        assert_eq!(export.reels["INTERVIE"], PathBuf::from("/media/Interview A.mov"));
+        assert_eq!(export.reels["B_ROLL"], PathBuf::from("/media/b-roll.mp4"));
+
+        let lines: Vec<&str> = export.text.lines().collect();
+        assert_eq!(lines[0], "TITLE: Conform");
+        assert_eq!(lines[1], "FCM: NON-DROP FRAME");
+        assert!(lines.contains(&"001  INTERVIE B     C        00:00:02:00 00:00:06:00 01:00:00:00 01:00:04:00"));
+        // The music event at half speed: M2 field in frames per second
+        assert!(lines.contains(&"002  MUSIC    A2    C        00:00:00:00 00:00:04:00 01:00:01:00 01:00:05:00"));
+        assert!(lines.contains(&"M2   MUSIC          012.5                00:00:00:00"));
+        // The dissolve: outgoing source at the cut, then the incoming clip
+        assert!(lines.contains(&"003  INTERVIE V     C        00:00:06:00 00:00:06:00 01:00:04:00 01:00:04:00"));
+        assert!(lines.contains(&"003  B_ROLL   V     D    010 00:00:01:00 00:00:03:00 01:00:04:00 01:00:06:00"));
+        assert!(lines.contains(&"* SOURCE FILE: /media/b-roll.mp4"));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let original = timeline();
+        let export = export_edl(&original, &options());
+        let import = import_edl(&export.text, &options(), &reels(&export));
+        assert!(import.issues.is_empty(), "{:?}", import.issues);
+        assert_eq!(import.title, "Conform");
+
+        let timeline = import.timeline;
+        assert_eq!(layout(&timeline), layout(&original));
+        assert_eq!(timeline.duration, original.duration);
+        // Linked clips stay linked; the dissolve comes back
+        let clips = |i: usize| &timeline.tracks[i].clips;
+        assert!(clips(0)[0].link_group.is_some());
+        assert_eq!(clips(0)[0].link_group, clips(1)[0].link_group);
+        let transition = &timeline.tracks[0].transitions[0];
+        assert_eq!((transition.clip_id, transition.duration), (clips(0)[1].id, f(10)));
+        assert_eq!(transition.kind, TransitionKind::CrossDissolve);
+    }
+
+    #[test]
+    fn test_reports_what_it_cannot_export() {
+        let mut timeline = timeline();
+        let v2 = timeline.add_track(TrackType::Video);
+        timeline.add_clip(v2, Clip::new(10, PathBuf::from("/media/title.png"), 0, f(25), 0, 0)).unwrap();
+        let reverse = Clip::new(11, PathBuf::from("/media/c.mp4"), 0, f(25), f(200), 0).with_speed(Speed::REVERSE);
+        timeline.add_clip(timeline.tracks[0].id, reverse).unwrap();
+        timeline.add_clip(timeline.tracks[0].id, Clip::new(12, PathBuf::from("/media/c.mp4"), 0, f(25) + 1, f(300), 0)).unwrap();
+
+        let export = export_edl(&timeline, &options());
+        let reported: Vec<ClipId> = export.issues.iter().map(|issue| match issue {
+            EdlIssue::Clip { clip_id, .. } => *clip_id,
+            EdlIssue::Line { .. } => unreachable!(),
+        }).collect();
+        assert_eq!(reported, vec![10, 11, 12]);
+        // Reels stay unique
+        assert!(export.reels.contains_key("C") && !export.reels.contains_key("C2"));
+    }
+
+    #[test]
+    fn test_import_reports_what_it_cannot_represent() {
+        let text = "\
+TITLE: Online
+FCM: NON-DROP FRAME
+
+001  TAPE1    AA/V  C        10:00:00:00 10:00:02:00 01:00:00:00 01:00:02:00
+002  BL       V     C        00:00:00:00 00:00:01:00 01:00:02:00 01:00:03:00
+003  TAPE2    V     W001 025 10:00:00:00 10:00:01:00 01:00:03:00 01:00:04:00
+004  TAPE9    V     C        00:00:00:00 00:00:01:00 01:00:04:00 01:00:05:00
+SPLIT:   AUDIO DELAY=  00:00:00:05
+005  TAPE1    NONE  C        10:00:00:00 10:00:01:00 01:00:05:00 01:00:06:00
+";
+        let reels = HashMap::from([
+            ("TAPE1".to_string(), PathBuf::from("tape1.mov")),
+            ("TAPE2".to_string(), PathBuf::from("tape2.mov")),
+        ]);
+        let import = import_edl(text, &options(), &reels);
+        let lines: Vec<usize> = import.issues.iter().map(|issue| match issue {
+            EdlIssue::Line { line, .. } => *line,
+            EdlIssue::Clip { .. } => unreachable!(),
+        }).collect();
+        // The wipe (no outgoing line), the unmapped reel, SPLIT and the unknown channel
+        assert_eq!(lines, vec![6, 7, 8, 9]);
+
+        let timeline = import.timeline;
+        assert_eq!(timeline.audio_tracks().count(), 2);
+        assert_eq!(timeline.tracks[0].clips.len(), 2);
+        assert_eq!(timeline.tracks[0].clips[0].in_point, RATE.frame_to_time(25 * 36_000));
+        assert_eq!(timeline.tracks[0].clips[1].timeline_start, f(75));
+    }
+}
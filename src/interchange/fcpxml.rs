@@ -0,0 +1,827 @@
+//! Final Cut Pro XML (`.fcpxml`) export.
+//!
+//! [`export_fcpxml`] writes a timeline as a project in an event of an FCPXML
+//! 1.9 library, which Final Cut Pro and DaVinci Resolve both import. The
+//! mapping:
+//! - Each media file becomes an `asset`, with a `format` for the size and
+//!   rate of its video stream (from `FcpxmlOptions::media`); assets with the
+//!   same size and rate share a format
+//! - The first video track becomes the primary storyline (`spine`): its clips
+//!   as `asset-clip`s, the space between them as `gap`s
+//! - Clips on every other track become connected clips on a lane of their
+//!   own, attached to the spine item they start over: video tracks above the
+//!   spine (lanes 1, 2, ...), audio tracks below it (lanes -1, -2, ...)
+//! - Audio clips carry an audio role named after their track, with the
+//!   track's volume as `adjust-volume`
+//! - Compound clips become `ref-clip`s of a `media` resource holding their
+//!   sequence
+//! - Chapter markers become `chapter-marker`s, to-do markers unfinished
+//!   to-do `marker`s, and other markers plain `marker`s
+//!
+//! Times are exact rational seconds: whole frames where a time falls on a
+//! frame boundary (`1001/24000s` is one frame at 23.976), nanoseconds
+//! otherwise. Transitions, speed changes, visual properties, keyframes and
+//! caption tracks are left out and reported as [`FcpxmlIssue`]s.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use crate::core::time::constants::NANOS_PER_SECOND;
+use crate::core::time::{FrameRate, Time};
+use crate::core::timecode::TimecodeFormat;
+use crate::decode::stream_info::{AudioStreamInfo, VideoStreamInfo};
+use crate::export::pipeline::ExportSettings;
+use crate::interchange::file_url;
+use crate::timeline::clip::{Clip, ClipId, ClipSource};
+use crate::timeline::marker::{Marker, MarkerKind};
+use crate::timeline::sequence::SequenceId;
+use crate::timeline::speed::Speed;
+use crate::timeline::timeline::Timeline;
+use crate::timeline::track::{Track, TrackId, TrackType};
+use crate::timeline::visual::VisualProperties;
+
+/// FCPXML version written; 1.9 is read by Final Cut Pro 10.5 and later and
+/// by DaVinci Resolve 17 and later.
+const VERSION: &str = "1.9";
+
+/// Audio roles Final Cut Pro has built in.
+const STANDARD_ROLES: [&str; 3] = ["dialogue", "music", "effects"];
+
+/// Project name, sequence format and media information of an FCPXML export.
+#[derive(Debug, Clone)]
+pub struct FcpxmlOptions {
+    pub name: String,          // Event and project name
+    pub width: u32,            // Sequence frame size
+    pub height: u32,
+    pub rate: FrameRate,       // Sequence rate
+    pub sample_rate: u32,      // Sequence audio rate
+    pub channels: u32,         // Sequence audio channels (1 = mono, 2 = stereo, more = surround)
+    pub media: HashMap<PathBuf, FcpxmlMedia>, // Stream information per media file
+}
+
+impl FcpxmlOptions {
+    /// Options for a sequence in the format of `settings`, without media
+    /// information.
+    pub fn new(name: impl Into<String>, settings: &ExportSettings) -> Self {
+        Self {
+            name: name.into(),
+            width: settings.width,
+            height: settings.height,
+            rate: settings.fps,
+            sample_rate: settings.sample_rate,
+            channels: settings.channels,
+            media: HashMap::new(),
+        }
+    }
+
+    /// Add the stream information of a media file.
+    pub fn with_media(mut self, path: impl Into<PathBuf>, media: FcpxmlMedia) -> Self {
+        self.media.insert(path.into(), media);
+        self
+    }
+}
+
+/// Streams of a media file, as probed by the decoder.
+#[derive(Debug, Clone, Default)]
+pub struct FcpxmlMedia {
+    pub video: Option<VideoStreamInfo>,
+    pub audio: Option<AudioStreamInfo>,
+}
+
+/// Something an FCPXML export could not represent exactly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FcpxmlIssue {
+    /// A clip or part of it was left out or approximated
+    Clip { clip_id: ClipId, reason: String },
+    /// A media file's asset was written without stream information
+    Media { path: PathBuf, reason: String },
+    /// A whole track was left out
+    Track { track_id: TrackId, reason: String },
+}
+
+impl fmt::Display for FcpxmlIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FcpxmlIssue::Clip { clip_id, reason } => write!(f, "Clip {}: {}", clip_id, reason),
+            FcpxmlIssue::Media { path, reason } => write!(f, "{}: {}", path.display(), reason),
+            FcpxmlIssue::Track { track_id, reason } => write!(f, "Track {}: {}", track_id, reason),
+        }
+    }
+}
+
+/// Result of `export_fcpxml`.
+#[derive(Debug, Clone)]
+pub struct FcpxmlExport {
+    pub xml: String,
+    pub issues: Vec<FcpxmlIssue>,
+}
+
+/// Write `timeline` as an FCPXML library holding one event with one project.
+pub fn export_fcpxml(timeline: &Timeline, options: &FcpxmlOptions) -> FcpxmlExport {
+    let mut writer = Writer::new(timeline, options);
+    let format = writer.format(options.width, options.height, options.rate);
+    let sequence = writer.sequence(timeline, &format);
+    let project = Element::new("project").attr("name", &options.name).child(sequence);
+    let event = Element::new("event").attr("name", &options.name).child(project);
+    let document = Element::new("fcpxml")
+        .attr("version", VERSION)
+        .child(Element { children: writer.resources, ..Element::new("resources") })
+        .child(Element::new("library").child(event));
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE fcpxml>\n\n");
+    document.write(&mut xml, 0);
+    FcpxmlExport { xml, issues: writer.issues }
+}
+
+/// An exact, reduced time in seconds, `num / den`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Seconds {
+    num: i128,
+    den: i128, // > 0
+}
+
+impl Seconds {
+    const ZERO: Seconds = Seconds { num: 0, den: 1 };
+
+    /// `time` as whole frames of `rate` if a frame starts there, else as
+    /// nanoseconds.
+    fn at(time: Time, rate: FrameRate) -> Self {
+        if rate.snap(time) == time {
+            Self::reduced(rate.time_to_frame(time) as i128 * rate.den as i128, rate.num as i128)
+        } else {
+            Self::reduced(time as i128, NANOS_PER_SECOND as i128)
+        }
+    }
+
+    /// One frame of `rate`.
+    fn frame(rate: FrameRate) -> Self {
+        Self::reduced(rate.den as i128, rate.num as i128)
+    }
+
+    /// Time from `start` to `end`, each taken at `rate`.
+    fn span(start: Time, end: Time, rate: FrameRate) -> Self {
+        Self::at(end, rate).minus(Self::at(start, rate))
+    }
+
+    fn reduced(num: i128, den: i128) -> Self {
+        let divisor = gcd(num, den);
+        Self { num: num / divisor, den: den / divisor }
+    }
+
+    fn plus(self, other: Self) -> Self {
+        Self::reduced(self.num * other.den + other.num * self.den, self.den * other.den)
+    }
+
+    fn minus(self, other: Self) -> Self {
+        self.plus(Self { num: -other.num, ..other })
+    }
+}
+
+impl fmt::Display for Seconds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.den == 1 {
+            write!(f, "{}s", self.num)
+        } else {
+            write!(f, "{}/{}s", self.num, self.den)
+        }
+    }
+}
+
+/// Greatest common divisor, always positive (`den` is).
+fn gcd(mut a: i128, mut b: i128) -> i128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a.abs()
+}
+
+/// An XML element being built.
+#[derive(Debug, Clone)]
+struct Element {
+    name: &'static str,
+    attributes: Vec<(&'static str, String)>,
+    children: Vec<Element>,
+}
+
+impl Element {
+    fn new(name: &'static str) -> Self {
+        Self { name, attributes: Vec::new(), children: Vec::new() }
+    }
+
+    fn attr(mut self, key: &'static str, value: impl ToString) -> Self {
+        self.attributes.push((key, value.to_string()));
+        self
+    }
+
+    fn child(mut self, child: Element) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    /// Append the element, indented by `depth` levels.
+    fn write(&self, out: &mut String, depth: usize) {
+        let indent = "    ".repeat(depth);
+        out.push_str(&indent);
+        out.push('<');
+        out.push_str(self.name);
+        for (key, value) in &self.attributes {
+            out.push_str(&format!(" {}=\"{}\"", key, escape(value)));
+        }
+        if self.children.is_empty() {
+            out.push_str("/>\n");
+            return;
+        }
+        out.push_str(">\n");
+        for child in &self.children {
+            child.write(out, depth + 1);
+        }
+        out.push_str(&format!("{}</{}>\n", indent, self.name));
+    }
+}
+
+/// `text` escaped for an attribute value.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\n' => escaped.push_str("&#10;"),
+            '\t' => escaped.push_str("&#9;"),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// `48k`-style audio rate of a sequence.
+fn audio_rate(sample_rate: u32) -> String {
+    format!("{}k", sample_rate as f64 / 1000.0)
+}
+
+/// Audio role of clips on a track: a standard role if the track is named
+/// after one, else a dialogue subrole named after the track.
+fn audio_role(track_name: &str) -> String {
+    let lower = track_name.to_lowercase();
+    match STANDARD_ROLES.iter().find(|&&role| role == lower) {
+        Some(role) => role.to_string(),
+        None => format!("dialogue.{}", track_name),
+    }
+}
+
+/// Track volume in decibels, as `adjust-volume` takes it.
+fn volume_db(volume: f32) -> String {
+    if volume <= 0.0 {
+        return "-96dB".to_string();
+    }
+    format!("{:.1}dB", 20.0 * volume.log10())
+}
+
+/// How a media file is used across the root timeline and its sequences,
+/// for assets without stream information.
+#[derive(Debug, Clone, Copy, Default)]
+struct Usage {
+    video: bool, // Used on a video track
+    audio: bool, // Used on an audio track
+    end: Time,   // Longest known source duration, or latest out point
+}
+
+/// A written `asset`.
+#[derive(Debug, Clone)]
+struct Asset {
+    id: String,
+    name: String,
+    rate: FrameRate, // Rate source times are counted in
+}
+
+/// An item of a spine: a clip of the primary track, or a gap.
+#[derive(Debug, Clone, Copy)]
+struct SpineItem<'t> {
+    clip: Option<&'t Clip>,
+    start: Time,
+    end: Time,
+    local_start: Seconds, // Time inside the item at `start` (its `start` attribute)
+}
+
+/// Builds the resources and sequences of an export.
+struct Writer<'a> {
+    root: &'a Timeline,
+    options: &'a FcpxmlOptions,
+    resources: Vec<Element>, // Every resource after the ones it refers to
+    formats: HashMap<(u32, u32, FrameRate), String>,
+    assets: HashMap<PathBuf, Asset>,
+    media: HashMap<SequenceId, String>,
+    usage: HashMap<PathBuf, Usage>,
+    issues: Vec<FcpxmlIssue>,
+}
+
+impl<'a> Writer<'a> {
+    fn new(root: &'a Timeline, options: &'a FcpxmlOptions) -> Self {
+        let mut usage: HashMap<PathBuf, Usage> = HashMap::new();
+        let timelines = std::iter::once(root).chain(root.sequences.values().map(|s| &s.timeline));
+        for track in timelines.flat_map(|t| &t.tracks) {
+            for clip in &track.clips {
+                if let Some(path) = clip.source_path() {
+                    let usage = usage.entry(path.to_path_buf()).or_default();
+                    usage.video |= track.track_type == TrackType::Video;
+                    usage.audio |= track.track_type == TrackType::Audio;
+                    usage.end = usage.end.max(clip.source_duration.unwrap_or(clip.out_point));
+                }
+            }
+        }
+        Self {
+            root,
+            options,
+            resources: Vec::new(),
+            formats: HashMap::new(),
+            assets: HashMap::new(),
+            media: HashMap::new(),
+            usage,
+            issues: Vec::new(),
+        }
+    }
+
+    fn next_id(&self) -> String {
+        format!("r{}", self.resources.len() + 1)
+    }
+
+    /// ID of the format for a frame size and rate, written on first use.
+    fn format(&mut self, width: u32, height: u32, rate: FrameRate) -> String {
+        if let Some(id) = self.formats.get(&(width, height, rate)) {
+            return id.clone();
+        }
+        let id = self.next_id();
+        self.resources.push(
+            Element::new("format")
+                .attr("id", &id)
+                .attr("frameDuration", Seconds::frame(rate))
+                .attr("width", width)
+                .attr("height", height),
+        );
+        self.formats.insert((width, height, rate), id.clone());
+        id
+    }
+
+    /// The asset of a media file, written on first use.
+    fn asset(&mut self, path: &Path) -> Asset {
+        if let Some(asset) = self.assets.get(path) {
+            return asset.clone();
+        }
+        let usage = self.usage.get(path).copied().unwrap_or_default();
+        let media = self.options.media.get(path).cloned().unwrap_or_else(|| {
+            let reason = "no stream information; format and duration taken from the sequence and its clips";
+            self.issues.push(FcpxmlIssue::Media { path: path.to_path_buf(), reason: reason.to_string() });
+            FcpxmlMedia::default()
+        });
+        let known = media.video.is_some() || media.audio.is_some();
+        let has_video = if known { media.video.is_some() } else { usage.video };
+        let has_audio = if known { media.audio.is_some() } else { usage.audio };
+
+        let rate = media.video.as_ref().map_or(self.options.rate, |video| video.fps);
+        let duration = [media.video.as_ref().map(|v| &v.stream_info), media.audio.as_ref().map(|a| &a.stream_info)]
+            .into_iter()
+            .flatten()
+            .map(|info| info.duration)
+            .max()
+            .unwrap_or(usage.end);
+        let name = path.file_stem().map_or_else(|| path.to_string_lossy(), |stem| stem.to_string_lossy()).into_owned();
+
+        let format = has_video.then(|| {
+            let (width, height) = media.video.as_ref().map_or((self.options.width, self.options.height), |v| (v.width, v.height));
+            self.format(width, height, rate)
+        });
+        let id = self.next_id();
+        let mut element = Element::new("asset")
+            .attr("id", &id)
+            .attr("name", &name)
+            .attr("start", Seconds::ZERO)
+            .attr("duration", Seconds::at(duration, rate));
+        if let Some(format) = format {
+            element = element.attr("hasVideo", 1).attr("format", format).attr("videoSources", 1);
+        }
+        if has_audio {
+            let (sample_rate, channels) = media
+                .audio
+                .as_ref()
+                .map_or((self.options.sample_rate, self.options.channels), |a| (a.sample_rate, a.channels));
+            element = element
+                .attr("hasAudio", 1)
+                .attr("audioSources", 1)
+                .attr("audioChannels", channels)
+                .attr("audioRate", sample_rate);
+        }
+        let src = file_url(path);
+        self.resources.push(element.child(Element::new("media-rep").attr("kind", "original-media").attr("src", src)));
+
+        let asset = Asset { id, name, rate };
+        self.assets.insert(path.to_path_buf(), asset.clone());
+        asset
+    }
+
+    /// ID of the `media` holding a sequence, written (after the resources
+    /// it uses) on first use; `None` if the sequence does not exist.
+    fn media(&mut self, id: SequenceId) -> Option<String> {
+        if let Some(media_id) = self.media.get(&id) {
+            return Some(media_id.clone());
+        }
+        let sequence = self.root.sequence(id)?;
+        let format = self.format(self.options.width, self.options.height, self.options.rate);
+        let element = self.sequence(&sequence.timeline, &format);
+        let media_id = self.next_id();
+        self.resources.push(
+            Element::new("media").attr("id", &media_id).attr("name", &sequence.name).child(element),
+        );
+        self.media.insert(id, media_id.clone());
+        Some(media_id)
+    }
+
+    /// A `sequence` element for `timeline`.
+    fn sequence(&mut self, timeline: &Timeline, format: &str) -> Element {
+        let rate = self.options.rate;
+        let clip_tracks = || timeline.tracks.iter().filter(|t| t.track_type != TrackType::Caption);
+        let end = clip_tracks().map(|t| t.duration()).max().unwrap_or(0);
+        let primary = timeline.video_tracks().next().or_else(|| clip_tracks().next());
+
+        // The primary track's clips with gaps between them, to the end
+        let mut items: Vec<SpineItem> = Vec::new();
+        let mut cursor = 0;
+        for clip in primary.iter().flat_map(|t| &t.clips) {
+            if clip.timeline_start > cursor {
+                items.push(SpineItem { clip: None, start: cursor, end: clip.timeline_start, local_start: Seconds::ZERO });
+            }
+            let local_start = Seconds::at(clip.in_point, self.source_rate(clip));
+            items.push(SpineItem { clip: Some(clip), start: clip.timeline_start, end: clip.timeline_end, local_start });
+            cursor = clip.timeline_end;
+        }
+        if end > cursor {
+            items.push(SpineItem { clip: None, start: cursor, end, local_start: Seconds::ZERO });
+        }
+
+        // Markers are written after connected clips, as the DTD orders them
+        let mut elements = Vec::new();
+        let mut markers = Vec::new();
+        for item in &items {
+            let offset = Seconds::at(item.start, rate);
+            let written = match (item.clip, primary) {
+                (Some(clip), Some(track)) => {
+                    let written = self.clip(timeline, clip, track, None, offset);
+                    if written.is_none() {
+                        self.report(clip.id, "its sequence does not exist; written as a gap");
+                    }
+                    written
+                }
+                _ => None,
+            };
+            let (element, item_markers) = written.unwrap_or_else(|| {
+                let gap = Element::new("gap")
+                    .attr("name", "Gap")
+                    .attr("offset", offset)
+                    .attr("start", Seconds::ZERO)
+                    .attr("duration", Seconds::span(item.start, item.end, rate));
+                (gap, Vec::new())
+            });
+            elements.push(element);
+            markers.push(item_markers);
+        }
+
+        let (mut video_lane, mut audio_lane) = (0, 0);
+        for track in &timeline.tracks {
+            for window in track.transition_windows() {
+                self.report(window.to.id, "transitions are not represented; written as a cut");
+            }
+            if primary.is_some_and(|p| p.id == track.id) {
+                continue;
+            }
+            let lane = match track.track_type {
+                TrackType::Video => {
+                    video_lane += 1;
+                    video_lane
+                }
+                TrackType::Audio => {
+                    audio_lane -= 1;
+                    audio_lane
+                }
+                TrackType::Caption => {
+                    if !track.cues.is_empty() {
+                        let reason = "captions are not represented; track left out".to_string();
+                        self.issues.push(FcpxmlIssue::Track { track_id: track.id, reason });
+                    }
+                    continue;
+                }
+            };
+            for clip in &track.clips {
+                // Every clip starts over an item: the spine reaches the end
+                let index = items.partition_point(|item| item.start <= clip.timeline_start) - 1;
+                let parent = &items[index];
+                let offset = parent.local_start.plus(Seconds::span(parent.start, clip.timeline_start, rate));
+                match self.clip(timeline, clip, track, Some(lane), offset) {
+                    Some((mut element, clip_markers)) => {
+                        element.children.extend(clip_markers);
+                        elements[index].children.push(element);
+                    }
+                    None => self.report(clip.id, "its sequence does not exist; clip left out"),
+                }
+            }
+        }
+
+        for marker in &timeline.markers {
+            let index = items.partition_point(|item| item.start <= marker.position).max(1) - 1;
+            let Some(parent) = items.get(index) else {
+                continue;
+            };
+            let start = parent.local_start.plus(Seconds::span(parent.start, marker.position, rate));
+            markers[index].push(marker_element(marker, start, rate));
+        }
+        for (element, markers) in elements.iter_mut().zip(markers) {
+            element.children.extend(markers);
+        }
+
+        let format_field = TimecodeFormat::new(rate, rate.is_ntsc());
+        let audio_layout = match self.options.channels {
+            1 => "mono",
+            2 => "stereo",
+            _ => "surround",
+        };
+        Element::new("sequence")
+            .attr("format", format)
+            .attr("duration", Seconds::at(end, rate))
+            .attr("tcStart", Seconds::ZERO)
+            .attr("tcFormat", if format_field.drop_frame { "DF" } else { "NDF" })
+            .attr("audioLayout", audio_layout)
+            .attr("audioRate", audio_rate(self.options.sample_rate))
+            .child(Element { children: elements, ..Element::new("spine") })
+    }
+
+    /// Rate a clip's source times are counted in.
+    fn source_rate(&mut self, clip: &Clip) -> FrameRate {
+        match &clip.source {
+            ClipSource::Media(path) => self.asset(path).rate,
+            ClipSource::Sequence(_) => self.options.rate,
+        }
+    }
+
+    /// A clip element at `offset` in its parent's time, with its markers
+    /// returned separately; `None` if it is a compound clip whose sequence
+    /// does not exist.
+    fn clip(
+        &mut self,
+        timeline: &Timeline,
+        clip: &Clip,
+        track: &Track,
+        lane: Option<i32>,
+        offset: Seconds,
+    ) -> Option<(Element, Vec<Element>)> {
+        let (mut element, name, rate) = match &clip.source {
+            ClipSource::Media(path) => {
+                let asset = self.asset(path);
+                (Element::new("asset-clip").attr("ref", asset.id), asset.name, asset.rate)
+            }
+            ClipSource::Sequence(id) => {
+                let media = self.media(*id)?;
+                let name = self.root.sequence(*id).map(|s| s.name.clone()).unwrap_or_default();
+                (Element::new("ref-clip").attr("ref", media), name, self.options.rate)
+            }
+        };
+        self.report_unsupported(clip);
+
+        if let Some(lane) = lane {
+            element = element.attr("lane", lane);
+        }
+        element = element
+            .attr("offset", offset)
+            .attr("name", name)
+            .attr("start", Seconds::at(clip.in_point, rate))
+            .attr("duration", Seconds::span(clip.timeline_start, clip.timeline_end, self.options.rate));
+        match track.track_type {
+            TrackType::Video => {
+                element = element.attr("srcEnable", "video");
+                if !track.enabled {
+                    element = element.attr("enabled", 0);
+                }
+            }
+            TrackType::Audio => {
+                let track_name = timeline.track_name(track.id).unwrap_or_default();
+                element = element.attr("srcEnable", "audio").attr("audioRole", audio_role(&track_name));
+                if track.muted {
+                    element = element.attr("enabled", 0);
+                }
+                if track.volume != 1.0 {
+                    element = element.child(Element::new("adjust-volume").attr("amount", volume_db(track.volume)));
+                }
+            }
+            TrackType::Caption => unreachable!("caption tracks hold no clips"),
+        }
+
+        let markers = clip
+            .markers
+            .iter()
+            .filter(|m| m.position >= clip.in_point && m.position < clip.out_point)
+            .map(|m| marker_element(m, Seconds::at(m.position, rate), rate))
+            .collect();
+        Some((element, markers))
+    }
+
+    fn report(&mut self, clip_id: ClipId, reason: &str) {
+        self.issues.push(FcpxmlIssue::Clip { clip_id, reason: reason.to_string() });
+    }
+
+    /// Report the properties of a clip FCPXML export leaves out.
+    fn report_unsupported(&mut self, clip: &Clip) {
+        if clip.time_remap.is_some() || clip.speed != Speed::NORMAL {
+            self.report(clip.id, "speed changes are not represented; written at normal speed");
+        }
+        if clip.visual != VisualProperties::default() {
+            self.report(clip.id, "visual properties are not represented");
+        }
+        if !clip.animation.is_empty() {
+            self.report(clip.id, "keyframes are not represented");
+        }
+    }
+}
+
+/// A marker element starting at `start` in its parent's time.
+fn marker_element(marker: &Marker, start: Seconds, rate: FrameRate) -> Element {
+    let duration = match marker.duration {
+        Some(duration) if duration > 0 => Seconds::span(marker.position, marker.position + duration, rate),
+        _ => Seconds::frame(rate),
+    };
+    let name = if marker.kind == MarkerKind::Chapter { "chapter-marker" } else { "marker" };
+    let mut element = Element::new(name)
+        .attr("start", start)
+        .attr("duration", duration)
+        .attr("value", &marker.name);
+    if !marker.note.is_empty() {
+        element = element.attr("note", &marker.note);
+    }
+    match marker.kind {
+        MarkerKind::Chapter => element.attr("posterOffset", Seconds::ZERO),
+        MarkerKind::Todo => element.attr("completed", 0),
+        MarkerKind::Comment => element,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::stream_info::StreamInfo;
+    use crate::timeline::caption::Cue;
+    use crate::timeline::marker::Marker;
+
+    fn video(width: u32, height: u32, fps: FrameRate, duration: Time) -> Option<VideoStreamInfo> {
+        let stream_info = StreamInfo { index: 0, duration, codec_name: "h264".to_string() };
+        Some(VideoStreamInfo { stream_info, width, height, fps, pixel_format: "yuv420p".to_string() })
+    }
+
+    fn audio(duration: Time) -> Option<AudioStreamInfo> {
+        let stream_info = StreamInfo { index: 1, duration, codec_name: "aac".to_string() };
+        Some(AudioStreamInfo { stream_info, sample_rate: 48000, channels: 2, sample_format: "fltp".to_string() })
+    }
+
+    fn options(rate: FrameRate) -> FcpxmlOptions {
+        let settings = ExportSettings { width: 1920, height: 1080, fps: rate, ..ExportSettings::default() };
+        FcpxmlOptions::new("Cut", &settings)
+    }
+
+    const FIXTURE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE fcpxml>
+
+<fcpxml version="1.9">
+    <resources>
+        <format id="r1" frameDuration="1/25s" width="1920" height="1080"/>
+        <asset id="r2" name="a" start="0s" duration="10s" hasVideo="1" format="r1" videoSources="1" hasAudio="1" audioSources="1" audioChannels="2" audioRate="48000">
+            <media-rep kind="original-media" src="file:///media/a.mov"/>
+        </asset>
+        <asset id="r3" name="title" start="0s" duration="1s" hasVideo="1" format="r1" videoSources="1">
+            <media-rep kind="original-media" src="file:///media/title.png"/>
+        </asset>
+    </resources>
+    <library>
+        <event name="Cut">
+            <project name="Cut">
+                <sequence format="r1" duration="4s" tcStart="0s" tcFormat="NDF" audioLayout="stereo" audioRate="48k">
+                    <spine>
+                        <gap name="Gap" offset="0s" start="0s" duration="1s">
+                            <chapter-marker start="12/25s" duration="1/25s" value="Intro &amp; titles" posterOffset="0s"/>
+                        </gap>
+                        <asset-clip ref="r2" offset="1s" name="a" start="2s" duration="3s" srcEnable="video">
+                            <asset-clip ref="r2" lane="-1" offset="2s" name="a" start="2s" duration="3s" srcEnable="audio" audioRole="dialogue"/>
+                            <asset-clip ref="r3" lane="1" offset="3s" name="title" start="0s" duration="1s" srcEnable="video"/>
+                            <marker start="3s" duration="1/25s" value="Fix colour" note="Too warm" completed="0"/>
+                        </asset-clip>
+                    </spine>
+                </sequence>
+            </project>
+        </event>
+    </library>
+</fcpxml>
+"#;
+
+    /// V1: a gap, then a (linked with A1 "Dialogue"); V2: a title over a.
+    #[test]
+    fn test_fixture() {
+        let rate = FrameRate::FPS_25;
+        let f = |frames| rate.frame_to_time(frames);
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        let a1 = timeline.tracks[1].id;
+        let v2 = timeline.add_track(TrackType::Video);
+        timeline.track_mut(a1).unwrap().name = "Dialogue".to_string();
+        timeline.add_clip(v1, Clip::new(1, PathBuf::from("/media/a.mov"), f(50), f(125), f(25), 0)).unwrap();
+        timeline.add_clip(a1, Clip::new(2, PathBuf::from("/media/a.mov"), f(50), f(125), f(25), 1)).unwrap();
+        timeline.link(&[1, 2]).unwrap();
+        timeline.add_clip(v2, Clip::new(3, PathBuf::from("/media/title.png"), 0, f(25), f(50), 0)).unwrap();
+        timeline.add_marker(Marker::new(1, f(12), MarkerKind::Chapter).with_name("Intro & titles"));
+        let todo = Marker::new(2, f(75), MarkerKind::Todo).with_name("Fix colour").with_note("Too warm");
+        timeline.add_clip_marker(1, todo).unwrap();
+
+        let media = FcpxmlMedia { video: video(1920, 1080, rate, f(250)), audio: audio(f(250)) };
+        let export = export_fcpxml(&timeline, &options(rate).with_media("/media/a.mov", media));
+        assert_eq!(export.xml, FIXTURE);
+        assert!(matches!(&export.issues[..], [FcpxmlIssue::Media { path, .. }] if path.ends_with("title.png")));
+    }
+
+    /// At 23.976: V1 a | compound "Titles" (b, c); V2 a title over the
+    /// compound clip; V3 a sped-up title over a; A2 "Music" at half volume,
+    /// starting between frames.
+    #[test]
+    fn test_connected_clips_and_compound_clips() {
+        let rate = FrameRate::FPS_23_976;
+        let f = |frames| rate.frame_to_time(frames);
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        let v2 = timeline.add_track(TrackType::Video);
+        let v3 = timeline.add_track(TrackType::Video);
+        let a2 = timeline.add_track(TrackType::Audio);
+        timeline.track_mut(a2).unwrap().name = "Music".to_string();
+        timeline.track_mut(a2).unwrap().set_volume(0.5);
+        timeline.add_clip(v1, Clip::new(1, PathBuf::from("/media/a.mov"), f(48), f(96), 0, 0)).unwrap();
+        timeline.add_clip(v1, Clip::new(2, PathBuf::from("/media/b.mov"), 0, f(48), f(48), 0)).unwrap();
+        timeline.add_clip(v1, Clip::new(3, PathBuf::from("/media/c.mov"), 0, f(24), f(96), 0)).unwrap();
+        timeline.compound_clips(&[2, 3], "Titles").unwrap();
+        timeline.add_clip(v2, Clip::new(4, PathBuf::from("/media/title.png"), 0, f(12), f(60), 0)).unwrap();
+        let sped_up = Clip::new(5, PathBuf::from("/media/title.png"), 0, f(24), f(10), 0).with_speed(Speed::new(2, 1));
+        timeline.add_clip(v3, sped_up).unwrap();
+        timeline.add_clip(a2, Clip::new(6, PathBuf::from("/media/music.wav"), 0, f(90) - 1_300_000_000, 1_300_000_000, 1)).unwrap();
+
+        let options = options(rate)
+            .with_media("/media/a.mov", FcpxmlMedia { video: video(1920, 1080, rate, f(240)), audio: None })
+            .with_media("/media/b.mov", FcpxmlMedia { video: video(3840, 2160, rate, f(48)), audio: None })
+            .with_media("/media/music.wav", FcpxmlMedia { video: None, audio: audio(f(240)) });
+        let export = export_fcpxml(&timeline, &options);
+        let lines: Vec<&str> = export.xml.lines().map(str::trim).collect();
+        let position = |line: &str| lines.iter().position(|l| *l == line).unwrap_or_else(|| panic!("{} not in\n{}", line, export.xml));
+
+        // Formats per size and rate; the compound clip's media after its assets
+        position(r#"<format id="r1" frameDuration="1001/24000s" width="1920" height="1080"/>"#);
+        position(r#"<format id="r3" frameDuration="1001/24000s" width="3840" height="2160"/>"#);
+        assert!(lines.iter().any(|l| l.starts_with(r#"<asset id="r4" name="b" start="0s" duration="1001/500s" hasVideo="1" format="r3""#)));
+        let media = position(r#"<media id="r6" name="Titles">"#);
+        assert!(media > position(r#"<media-rep kind="original-media" src="file:///media/c.mov"/>"#));
+        assert!(media < position(r#"<asset-clip ref="r2" offset="0s" name="a" start="1001/500s" duration="1001/500s" srcEnable="video">"#));
+        position(r#"<sequence format="r1" duration="3003/1000s" tcStart="0s" tcFormat="NDF" audioLayout="stereo" audioRate="48k">"#);
+        assert!(lines.iter().any(|l| l.starts_with(r#"<asset id="r8" name="music" start="0s" duration="1001/100s" hasAudio="1""#)));
+
+        // Connected clips are offset in the time of the item they start over
+        let ref_clip = position(r#"<ref-clip ref="r6" offset="1001/500s" name="Titles" start="0s" duration="3003/1000s" srcEnable="video">"#);
+        assert_eq!(
+            lines[ref_clip + 1],
+            r#"<asset-clip ref="r7" lane="1" offset="1001/2000s" name="title" start="0s" duration="1001/2000s" srcEnable="video"/>"#
+        );
+        position(r#"<asset-clip ref="r7" lane="2" offset="29029/12000s" name="title" start="0s" duration="1001/2000s" srcEnable="video"/>"#);
+        // Off the frame grid: 2.002s into a, plus 1.3s
+        let music = position(
+            r#"<asset-clip ref="r8" lane="-2" offset="1651/500s" name="music" start="0s" duration="1963/800s" srcEnable="audio" audioRole="music">"#,
+        );
+        assert_eq!(lines[music + 1], r#"<adjust-volume amount="-6.0dB"/>"#);
+
+        assert!(export.issues.contains(&FcpxmlIssue::Clip {
+            clip_id: 5,
+            reason: "speed changes are not represented; written at normal speed".to_string()
+        }));
+        let missing: Vec<_> = export.issues.iter().filter_map(|i| match i {
+            FcpxmlIssue::Media { path, .. } => Some(path.clone()),
+            _ => None,
+        }).collect();
+        assert_eq!(missing, [PathBuf::from("/media/c.mov"), PathBuf::from("/media/title.png")]);
+    }
+
+    #[test]
+    fn test_drop_frame_and_empty_timeline() {
+        let export = export_fcpxml(&Timeline::new(), &options(FrameRate::FPS_29_97));
+        assert!(export.xml.contains(r#"<format id="r1" frameDuration="1001/30000s" width="1920" height="1080"/>"#));
+        assert!(export.xml.contains(r#"duration="0s" tcStart="0s" tcFormat="DF""#));
+        assert!(export.xml.contains("<spine/>"));
+        assert!(export.issues.is_empty());
+
+        // Caption tracks never become the spine and are left out
+        let mut timeline = Timeline::empty();
+        let c1 = timeline.add_track(TrackType::Caption);
+        timeline.add_cue(c1, Cue::new(1, 0, NANOS_PER_SECOND * 2, "Hello")).unwrap();
+        let export = export_fcpxml(&timeline, &options(FrameRate::FPS_29_97));
+        assert!(export.xml.contains("<spine/>"));
+        assert!(matches!(&export.issues[..], [FcpxmlIssue::Track { track_id, .. }] if *track_id == c1));
+    }
+}
@@ -0,0 +1,30 @@
+//! Exchanging timelines with other applications.
+
+pub mod captions;
+pub mod edl;
+pub mod fcpxml;
+pub mod otio;
+
+pub use captions::{export_srt, export_webvtt, import_srt, import_webvtt, CaptionExport, CaptionImport, CaptionIssue};
+pub use edl::{export_edl, import_edl, EdlExport, EdlImport, EdlIssue, EdlOptions};
+pub use fcpxml::{export_fcpxml, FcpxmlExport, FcpxmlIssue, FcpxmlMedia, FcpxmlOptions};
+pub use otio::{export_otio, import_otio, OtioError, OtioImport, OtioIssue, OtioOptions};
+
+use std::path::Path;
+
+/// `file://` URL of an absolute path; relative paths are written as they are.
+pub(crate) fn file_url(path: &Path) -> String {
+    let text = path.to_string_lossy().replace('\\', "/");
+    if !path.is_absolute() {
+        return text;
+    }
+    let mut url = String::from(if text.starts_with('/') { "file://" } else { "file:///" });
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-._~:".contains(&byte) {
+            url.push(byte as char);
+        } else {
+            url.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    url
+}
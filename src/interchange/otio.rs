@@ -0,0 +1,1450 @@
+//! OpenTimelineIO (`.otio`) timelines.
+//!
+//! [`export_otio`] writes a timeline as an OTIO `Timeline.1` document;
+//! [`import_otio`] reads one back. The mapping:
+//! - Tracks become `Track.1`s of kind `Video` or `Audio`, in the timeline's
+//!   track order (bottom-up, as OTIO stacks are)
+//! - Clips become `Clip.2`s with an `ExternalReference.1` to their media file.
+//!   The `source_range` starts at the in point and lasts as long as the clip
+//!   is on the track; the media's `available_range` is the source duration
+//! - Empty spans between clips become `Gap.1`s
+//! - Compound clips become nested `Stack.1`s holding the tracks of their
+//!   sequence
+//! - Cross dissolves, dips and crossfades become `SMPTE_Dissolve` transitions
+//! - Speed changes become `LinearTimeWarp.1` and `FreezeFrame.1` effects
+//! - Timeline and clip markers become `Marker.2`s
+//! - Caption tracks become `Track.1`s of kind `Caption` with no children;
+//!   OTIO has no schema for their cues, which are kept in the metadata
+//!
+//! Times are written as `RationalTime.1`s at `OtioOptions::rate`: whole frames
+//! where a time falls on a frame boundary, fractions of a frame otherwise.
+//! Both read back to the same nanosecond.
+//!
+//! What OTIO has no schema for (IDs, links, track settings, the kind and
+//! alignment of transitions, time remapping, visual properties, keyframes)
+//! is kept under an `aves` key in the `metadata` of the object it belongs
+//! to, so a timeline survives a round trip unchanged. Other applications
+//! may drop or leave stale metadata: on import it only fills in what the
+//! OTIO fields leave open, and IDs are given out afresh if any are missing
+//! or repeated. Every OTIO feature the import ignores or approximates is
+//! reported as an [`OtioIssue`].
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use crate::core::time::constants::NANOS_PER_SECOND;
+use crate::core::time::{FrameRate, Time};
+use crate::interchange::file_url;
+use crate::timeline::caption::{Cue, CueId};
+use crate::timeline::clip::{Clip, ClipId, ClipSource, LinkGroupId};
+use crate::timeline::keyframe::ClipAnimation;
+use crate::timeline::marker::{Marker, MarkerColor, MarkerId, MarkerKind};
+use crate::timeline::remap::TimeRemap;
+use crate::timeline::sequence::{Sequence, SequenceId};
+use crate::timeline::speed::Speed;
+use crate::timeline::timeline::{IdAllocator, Timeline};
+use crate::timeline::track::{Track, TrackId, TrackType};
+use crate::timeline::transition::{
+    FadeCurve, Transition, TransitionAlignment, TransitionId, TransitionKind, TransitionWindow,
+};
+use crate::timeline::visual::VisualProperties;
+
+/// Key of the aves data in OTIO `metadata` objects.
+const METADATA_KEY: &str = "aves";
+
+/// Media reference key of the clips this module writes.
+const MEDIA_KEY: &str = "DEFAULT_MEDIA";
+
+/// `effect_name` of the effect standing for a time-remap curve.
+const REMAP_EFFECT: &str = "TimeRemap";
+
+/// The one transition type OTIO defines besides custom ones.
+const DISSOLVE: &str = "SMPTE_Dissolve";
+
+/// Curve given to audio crossfades read without aves metadata.
+const IMPORTED_FADE_CURVE: FadeCurve = FadeCurve::EqualPower;
+
+/// Name and time base of an exported OTIO timeline.
+#[derive(Debug, Clone)]
+pub struct OtioOptions {
+    pub name: String,
+    pub rate: FrameRate, // Rate of every `RationalTime` written
+}
+
+impl OtioOptions {
+    pub fn new(name: impl Into<String>, rate: FrameRate) -> Self {
+        Self { name: name.into(), rate }
+    }
+}
+
+/// Errors that stop an OTIO import.
+#[derive(Debug)]
+pub enum OtioError {
+    Json(serde_json::Error),
+    /// The file is JSON, but its root is not an OTIO `Timeline`
+    NotATimeline,
+    /// An object lacks a field the import needs, such as a readable time
+    Invalid(String),
+}
+
+impl fmt::Display for OtioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OtioError::Json(e) => write!(f, "JSON error: {}", e),
+            OtioError::NotATimeline => write!(f, "Not an OpenTimelineIO timeline"),
+            OtioError::Invalid(msg) => write!(f, "Invalid OTIO timeline: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for OtioError {}
+
+impl From<serde_json::Error> for OtioError {
+    fn from(err: serde_json::Error) -> Self {
+        OtioError::Json(err)
+    }
+}
+
+/// An OTIO feature an import ignored or approximated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OtioIssue {
+    pub location: String, // Path of the OTIO object, e.g. `tracks.children[1].children[3]`
+    pub reason: String,
+}
+
+impl fmt::Display for OtioIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.location, self.reason)
+    }
+}
+
+/// Result of reading an OTIO timeline.
+#[derive(Debug)]
+pub struct OtioImport {
+    pub name: String,
+    pub timeline: Timeline,
+    pub issues: Vec<OtioIssue>,
+}
+
+/// aves data of the `Timeline`.
+#[derive(Serialize, Deserialize)]
+struct TimelineMetadata {
+    playhead: Time,
+}
+
+/// aves data of a `Track`.
+#[derive(Serialize, Deserialize)]
+struct TrackMetadata {
+    id: TrackId,
+    name: String,
+    muted: bool,
+    solo: bool,
+    locked: bool,
+    volume: f32,
+    color: [u8; 3],
+    height: f32,
+    #[serde(default)]
+    dormant_transitions: Vec<Transition>, // Transitions of clips on the track that do not play
+    #[serde(default)]
+    cues: Vec<Cue>,                       // Caption tracks only
+}
+
+/// aves data of a `Clip`, or of a `Stack` standing for a compound clip.
+#[derive(Serialize, Deserialize)]
+struct ClipMetadata {
+    id: ClipId,
+    stream_index: usize,
+    link_group: Option<LinkGroupId>,
+    sync_origin: Time,
+    speed: Speed,
+    preserve_pitch: bool,
+    time_remap: Option<TimeRemap>,
+    visual: VisualProperties,
+    animation: ClipAnimation,
+    source_span: Time, // Exact `out_point - in_point`, valid while the OTIO duration is `duration`
+    duration: Time,
+    #[serde(default)]
+    sequence: Option<SequenceMetadata>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SequenceMetadata {
+    id: SequenceId,
+    name: String,
+}
+
+/// aves data of a `Transition`.
+#[derive(Serialize, Deserialize)]
+struct TransitionMetadata {
+    id: TransitionId,
+    kind: TransitionKind,
+    alignment: TransitionAlignment,
+    duration: Time,
+    before: Time, // `in_offset` and `out_offset` as written; the rest is
+    after: Time,  // only used while they are unchanged
+}
+
+/// aves data of a `Marker`.
+#[derive(Serialize, Deserialize)]
+struct MarkerMetadata {
+    id: MarkerId,
+    kind: MarkerKind,
+}
+
+impl ClipMetadata {
+    fn new(clip: &Clip, sequence: Option<SequenceMetadata>) -> Self {
+        Self {
+            id: clip.id,
+            stream_index: clip.stream_index,
+            link_group: clip.link_group,
+            sync_origin: clip.sync_origin,
+            speed: clip.speed,
+            preserve_pitch: clip.preserve_pitch,
+            time_remap: clip.time_remap.clone(),
+            visual: clip.visual,
+            animation: clip.animation.clone(),
+            source_span: clip.source_span(),
+            duration: clip.duration(),
+            sequence,
+        }
+    }
+}
+
+/// A `metadata` object holding `value` under the aves key.
+fn metadata<T: Serialize>(value: &T) -> Value {
+    let value = serde_json::to_value(value).expect("metadata types serialize to JSON");
+    json!({ METADATA_KEY: value })
+}
+
+/// Name of a marker colour in OTIO's palette.
+fn color_name(color: MarkerColor) -> &'static str {
+    match color {
+        MarkerColor::Red => "RED",
+        MarkerColor::Orange => "ORANGE",
+        MarkerColor::Yellow => "YELLOW",
+        MarkerColor::Green => "GREEN",
+        MarkerColor::Cyan => "CYAN",
+        MarkerColor::Blue => "BLUE",
+        MarkerColor::Purple => "PURPLE",
+    }
+}
+
+/// Marker colour of an OTIO colour name, or `None` if the palette lacks it.
+fn marker_color(name: &str) -> Option<MarkerColor> {
+    [
+        MarkerColor::Red,
+        MarkerColor::Orange,
+        MarkerColor::Yellow,
+        MarkerColor::Green,
+        MarkerColor::Cyan,
+        MarkerColor::Blue,
+        MarkerColor::Purple,
+    ]
+    .into_iter()
+    .find(|&color| color_name(color) == name)
+}
+
+/// Media file of an `ExternalReference` URL, or `None` if it is not a local file.
+fn media_path(url: &str) -> Option<PathBuf> {
+    let Some(rest) = url.strip_prefix("file://") else {
+        return (!url.is_empty() && !url.contains("://")).then(|| PathBuf::from(url));
+    };
+    let rest = rest.strip_prefix("localhost").unwrap_or(rest);
+    if !rest.starts_with('/') {
+        return None; // A file on another host
+    }
+    let decoded = percent_decode(rest)?;
+    // `file:///C:/...` names a Windows drive
+    match decoded.as_bytes() {
+        [b'/', drive, b':', ..] if drive.is_ascii_alphabetic() => Some(PathBuf::from(&decoded[1..])),
+        _ => Some(PathBuf::from(decoded)),
+    }
+}
+
+fn percent_decode(text: &str) -> Option<String> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            decoded.push(u8::from_str_radix(text.get(i + 1..i + 3)?, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// Write `timeline`, with the sequences its compound clips use, as an OTIO
+/// document.
+pub fn export_otio(timeline: &Timeline, options: &OtioOptions) -> String {
+    let writer = Writer { root: timeline, rate: options.rate };
+    let document = json!({
+        "OTIO_SCHEMA": "Timeline.1",
+        "name": options.name,
+        "global_start_time": null,
+        "metadata": metadata(&TimelineMetadata { playhead: timeline.playhead }),
+        "tracks": {
+            "OTIO_SCHEMA": "Stack.1",
+            "name": "tracks",
+            "source_range": null,
+            "enabled": true,
+            "effects": [],
+            "markers": writer.markers(&timeline.markers),
+            "metadata": {},
+            "children": writer.tracks(timeline),
+        },
+    });
+    serde_json::to_string_pretty(&document).expect("JSON values serialize")
+}
+
+struct Writer<'a> {
+    root: &'a Timeline, // Holds the sequences
+    rate: FrameRate,
+}
+
+impl Writer<'_> {
+    fn time(&self, time: Time) -> Value {
+        let rate = self.rate;
+        let value = if rate.snap(time) == time {
+            rate.time_to_frame(time) as f64
+        } else {
+            (time as i128 * rate.num as i128) as f64 / (rate.den as i128 * NANOS_PER_SECOND as i128) as f64
+        };
+        json!({ "OTIO_SCHEMA": "RationalTime.1", "rate": rate.as_f64(), "value": value })
+    }
+
+    fn range(&self, start: Time, duration: Time) -> Value {
+        json!({
+            "OTIO_SCHEMA": "TimeRange.1",
+            "start_time": self.time(start),
+            "duration": self.time(duration),
+        })
+    }
+
+    fn tracks(&self, timeline: &Timeline) -> Vec<Value> {
+        timeline.tracks.iter().map(|track| self.track(timeline, track)).collect()
+    }
+
+    fn track(&self, timeline: &Timeline, track: &Track) -> Value {
+        let windows = track.transition_windows();
+        let mut children = Vec::new();
+        let mut cursor = 0;
+        for clip in &track.clips {
+            if clip.timeline_start > cursor {
+                children.push(self.gap(clip.timeline_start - cursor));
+            }
+            if let Some(window) = windows.iter().find(|w| w.to.id == clip.id) {
+                children.push(self.transition(window));
+            }
+            children.push(self.clip(clip));
+            cursor = clip.timeline_end;
+        }
+
+        let dormant_transitions = track.transitions
+            .iter()
+            .filter(|t| windows.iter().all(|w| w.transition.id != t.id))
+            .filter(|t| track.clips.iter().any(|c| c.id == t.clip_id))
+            .cloned()
+            .collect();
+        let track_metadata = TrackMetadata {
+            id: track.id,
+            name: track.name.clone(),
+            muted: track.muted,
+            solo: track.solo,
+            locked: track.locked,
+            volume: track.volume,
+            color: track.color,
+            height: track.height,
+            dormant_transitions,
+            cues: track.cues.clone(),
+        };
+        let kind = match track.track_type {
+            TrackType::Video => "Video",
+            TrackType::Audio => "Audio",
+            TrackType::Caption => "Caption",
+        };
+        json!({
+            "OTIO_SCHEMA": "Track.1",
+            "name": timeline.track_name(track.id).unwrap_or_default(),
+            "kind": kind,
+            "source_range": null,
+            "enabled": track.enabled,
+            "effects": [],
+            "markers": [],
+            "metadata": metadata(&track_metadata),
+            "children": children,
+        })
+    }
+
+    fn gap(&self, duration: Time) -> Value {
+        json!({
+            "OTIO_SCHEMA": "Gap.1",
+            "name": "",
+            "source_range": self.range(0, duration),
+            "enabled": true,
+            "effects": [],
+            "markers": [],
+            "metadata": {},
+        })
+    }
+
+    fn transition(&self, window: &TransitionWindow<'_>) -> Value {
+        let transition = window.transition;
+        let (before, after) = (window.cut - window.start, window.end - window.cut);
+        let transition_metadata = TransitionMetadata {
+            id: transition.id,
+            kind: transition.kind,
+            alignment: transition.alignment,
+            duration: transition.duration,
+            before,
+            after,
+        };
+        json!({
+            "OTIO_SCHEMA": "Transition.1",
+            "name": "",
+            "transition_type": DISSOLVE,
+            "in_offset": self.time(before),
+            "out_offset": self.time(after),
+            "metadata": metadata(&transition_metadata),
+        })
+    }
+
+    fn clip(&self, clip: &Clip) -> Value {
+        let sequence = match &clip.source {
+            ClipSource::Media(path) => return self.media_clip(clip, path),
+            ClipSource::Sequence(id) => match self.root.sequence(*id) {
+                Some(sequence) => sequence,
+                // Not playable either; keep the clip's place on the track
+                None => return self.gap(clip.duration()),
+            },
+        };
+        let sequence_metadata = SequenceMetadata { id: sequence.id, name: sequence.name.clone() };
+        json!({
+            "OTIO_SCHEMA": "Stack.1",
+            "name": sequence.name,
+            "source_range": self.range(clip.in_point, clip.duration()),
+            "enabled": true,
+            "effects": effects(clip),
+            "markers": self.markers(&clip.markers),
+            "metadata": metadata(&ClipMetadata::new(clip, Some(sequence_metadata))),
+            "children": self.tracks(&sequence.timeline),
+        })
+    }
+
+    fn media_clip(&self, clip: &Clip, path: &Path) -> Value {
+        let available_range = clip.source_duration.map(|duration| self.range(0, duration));
+        json!({
+            "OTIO_SCHEMA": "Clip.2",
+            "name": path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default(),
+            "source_range": self.range(clip.in_point, clip.duration()),
+            "media_references": {
+                MEDIA_KEY: {
+                    "OTIO_SCHEMA": "ExternalReference.1",
+                    "name": "",
+                    "target_url": file_url(path),
+                    "available_range": available_range,
+                    "metadata": {},
+                },
+            },
+            "active_media_reference_key": MEDIA_KEY,
+            "enabled": true,
+            "effects": effects(clip),
+            "markers": self.markers(&clip.markers),
+            "metadata": metadata(&ClipMetadata::new(clip, None)),
+        })
+    }
+
+    fn markers(&self, markers: &[Marker]) -> Vec<Value> {
+        markers.iter().map(|marker| {
+            json!({
+                "OTIO_SCHEMA": "Marker.2",
+                "name": marker.name,
+                "comment": marker.note,
+                "color": color_name(marker.color),
+                "marked_range": self.range(marker.position, marker.duration.unwrap_or(0)),
+                "metadata": metadata(&MarkerMetadata { id: marker.id, kind: marker.kind }),
+            })
+        }).collect()
+    }
+}
+
+/// Time effects standing for the clip's speed or time remap.
+fn effects(clip: &Clip) -> Vec<Value> {
+    let effect = if clip.time_remap.is_some() {
+        json!({ "OTIO_SCHEMA": "TimeEffect.1", "name": "", "effect_name": REMAP_EFFECT, "metadata": {} })
+    } else if clip.speed.is_freeze() {
+        json!({
+            "OTIO_SCHEMA": "FreezeFrame.1",
+            "name": "",
+            "effect_name": "FreezeFrame",
+            "time_scalar": 0.0,
+            "metadata": {},
+        })
+    } else if clip.speed != Speed::NORMAL {
+        json!({
+            "OTIO_SCHEMA": "LinearTimeWarp.1",
+            "name": "",
+            "effect_name": "LinearTimeWarp",
+            "time_scalar": clip.speed.as_f64(),
+            "metadata": {},
+        })
+    } else {
+        return Vec::new();
+    };
+    vec![effect]
+}
+
+/// Read an OTIO timeline.
+///
+/// Fails only if the document is not an OTIO timeline or lacks something
+/// the layout depends on (an unreadable time, a gap without a range); what
+/// can be left out is left out and reported.
+pub fn import_otio(json: &str) -> Result<OtioImport, OtioError> {
+    let document: Value = serde_json::from_str(json)?;
+    if schema(&document) != Some("Timeline") {
+        return Err(OtioError::NotATimeline);
+    }
+    let result = match Importer::new(true).run(&document) {
+        Err(Abort::IdConflict) => Importer::new(false).run(&document),
+        result => result,
+    };
+    result.map_err(|abort| match abort {
+        Abort::Failed(e) => e,
+        Abort::IdConflict => unreachable!("new IDs never conflict"),
+    })
+}
+
+/// Schema of an OTIO object without its version ("Clip" for "Clip.2").
+fn schema(object: &Value) -> Option<&str> {
+    object.get("OTIO_SCHEMA")?.as_str()?.split('.').next()
+}
+
+fn array<'a>(object: &'a Value, key: &str) -> &'a [Value] {
+    object.get(key).and_then(Value::as_array).map_or(&[], Vec::as_slice)
+}
+
+fn text<'a>(object: &'a Value, key: &str) -> &'a str {
+    object.get(key).and_then(Value::as_str).unwrap_or_default()
+}
+
+fn flag(object: &Value, key: &str, default: bool) -> bool {
+    object.get(key).and_then(Value::as_bool).unwrap_or(default)
+}
+
+fn invalid(location: &str, reason: &str) -> OtioError {
+    OtioError::Invalid(format!("{}: {}", location, reason))
+}
+
+/// Read a `RationalTime`.
+fn read_time(value: &Value, location: &str) -> Result<Time, OtioError> {
+    let number = |key| value.get(key).and_then(Value::as_f64).filter(|v| v.is_finite());
+    let rate = number("rate").and_then(FrameRate::from_f64);
+    let (Some(rate), Some(frames)) = (rate, number("value")) else {
+        return Err(invalid(location, "unreadable time"));
+    };
+    if frames.fract() == 0.0 {
+        Ok(rate.frame_to_time(frames as i64))
+    } else {
+        Ok((frames * rate.den as f64 * NANOS_PER_SECOND as f64 / rate.num as f64).round() as Time)
+    }
+}
+
+/// Read a `TimeRange` as `(start, duration)`.
+fn read_range(value: &Value, location: &str) -> Result<(Time, Time), OtioError> {
+    let field = |key| value.get(key).ok_or_else(|| invalid(location, "incomplete time range"));
+    let start = read_time(field("start_time")?, location)?;
+    let duration = read_time(field("duration")?, location)?;
+    if duration < 0 {
+        return Err(invalid(location, "negative duration"));
+    }
+    Ok((start, duration))
+}
+
+/// The `source_range` of an item, if it has one.
+fn source_range(item: &Value, location: &str) -> Result<Option<(Time, Time)>, OtioError> {
+    match item.get("source_range") {
+        Some(range) if !range.is_null() => read_range(range, location).map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// Why an import run stopped.
+enum Abort {
+    Failed(OtioError),
+    /// IDs from the metadata are missing or repeated: run again with new IDs
+    IdConflict,
+}
+
+impl From<OtioError> for Abort {
+    fn from(err: OtioError) -> Self {
+        Abort::Failed(err)
+    }
+}
+
+/// How a clip's source time runs.
+enum Retime {
+    Speed(Speed),
+    Remap(TimeRemap),
+}
+
+/// Where an OTIO clip or nested stack sits, before effects and metadata.
+struct Placement {
+    source: ClipSource,
+    in_point: Time,
+    media_start: Time, // Source time OTIO counts from (start of the available range)
+    start: Time,
+    duration: Time,
+    source_duration: Option<Time>,
+}
+
+/// A transition waiting for the clip after it.
+struct PendingTransition {
+    location: String,
+    id: Option<TransitionId>, // From the metadata
+    kind: TransitionKind,
+    alignment: TransitionAlignment,
+    duration: Time,
+}
+
+/// A timeline being imported, with the IDs used in it so far.
+struct Scope {
+    timeline: Timeline,
+    track_ids: HashSet<TrackId>,
+    clip_ids: HashSet<ClipId>,
+    marker_ids: HashSet<MarkerId>,
+    transition_ids: HashSet<TransitionId>,
+    cue_ids: HashSet<CueId>,
+    link_groups: HashMap<LinkGroupId, LinkGroupId>, // Group in the metadata -> new group
+}
+
+impl Scope {
+    fn new() -> Self {
+        Self {
+            timeline: Timeline::empty(),
+            track_ids: HashSet::new(),
+            clip_ids: HashSet::new(),
+            marker_ids: HashSet::new(),
+            transition_ids: HashSet::new(),
+            cue_ids: HashSet::new(),
+            link_groups: HashMap::new(),
+        }
+    }
+}
+
+struct Importer {
+    keep_ids: bool, // Use the IDs in the metadata rather than new ones
+    sequences: BTreeMap<SequenceId, Sequence>,
+    sequence_ids: HashMap<SequenceId, SequenceId>, // Sequence in the metadata -> imported sequence
+    new_sequence_ids: IdAllocator<SequenceId>,
+    issues: Vec<OtioIssue>,
+}
+
+impl Importer {
+    fn new(keep_ids: bool) -> Self {
+        Self {
+            keep_ids,
+            sequences: BTreeMap::new(),
+            sequence_ids: HashMap::new(),
+            new_sequence_ids: IdAllocator::default(),
+            issues: Vec::new(),
+        }
+    }
+
+    fn issue(&mut self, location: &str, reason: impl Into<String>) {
+        self.issues.push(OtioIssue { location: location.to_string(), reason: reason.into() });
+    }
+
+    /// The ID from the metadata, or a new one from `allocate` when not keeping IDs.
+    fn id(&self, used: &mut HashSet<u64>, kept: Option<u64>, allocate: impl FnOnce() -> u64) -> Result<u64, Abort> {
+        if !self.keep_ids {
+            return Ok(allocate());
+        }
+        match kept {
+            Some(id) if used.insert(id) => Ok(id),
+            _ => Err(Abort::IdConflict),
+        }
+    }
+
+    /// The aves metadata of an object, if it has any that can be read.
+    fn read_metadata<T: DeserializeOwned>(&mut self, object: &Value, location: &str) -> Option<T> {
+        let value = object.get("metadata")?.get(METADATA_KEY)?;
+        match serde_json::from_value(value.clone()) {
+            Ok(metadata) => Some(metadata),
+            Err(e) => {
+                self.issue(location, format!("unreadable aves metadata ignored ({})", e));
+                None
+            }
+        }
+    }
+
+    fn run(mut self, document: &Value) -> Result<OtioImport, Abort> {
+        if let Some(start) = document.get("global_start_time").filter(|v| !v.is_null()) {
+            if read_time(start, "global_start_time")? != 0 {
+                self.issue("global_start_time", "start time ignored; the timeline starts at 0");
+            }
+        }
+        let stack = document.get("tracks")
+            .filter(|stack| schema(stack) == Some("Stack"))
+            .ok_or_else(|| invalid("tracks", "not a stack"))?;
+        let mut scope = self.read_stack(stack, "tracks")?;
+        self.report_effects(stack, "tracks");
+        for marker in self.read_markers(&mut scope, stack, 0, "tracks")? {
+            scope.timeline.add_marker(marker);
+        }
+
+        let mut timeline = scope.timeline;
+        if let Some(metadata) = self.read_metadata::<TimelineMetadata>(document, "metadata") {
+            timeline.set_playhead(metadata.playhead);
+        }
+        for (id, sequence) in std::mem::take(&mut self.sequences) {
+            timeline.reserve_sequence_id(id);
+            timeline.sequences.insert(id, sequence);
+        }
+        Ok(OtioImport { name: text(document, "name").to_string(), timeline, issues: self.issues })
+    }
+
+    fn report_effects(&mut self, object: &Value, location: &str) {
+        for (index, effect) in array(object, "effects").iter().enumerate() {
+            let location = format!("{}.effects[{}]", location, index);
+            self.issue(&location, format!("{} effect ignored", effect_name(effect)));
+        }
+    }
+
+    /// Read the tracks of a stack into a new timeline.
+    fn read_stack(&mut self, stack: &Value, location: &str) -> Result<Scope, Abort> {
+        let mut scope = Scope::new();
+        let tracks = stack.get("children")
+            .and_then(Value::as_array)
+            .ok_or_else(|| invalid(location, "stack without children"))?;
+        for (index, track) in tracks.iter().enumerate() {
+            let location = format!("{}.children[{}]", location, index);
+            self.read_track(&mut scope, track, &location)?;
+        }
+        Ok(scope)
+    }
+
+    fn read_track(&mut self, scope: &mut Scope, track: &Value, location: &str) -> Result<(), Abort> {
+        if schema(track) != Some("Track") {
+            self.issue(location, format!("{} in a stack ignored", text(track, "OTIO_SCHEMA")));
+            return Ok(());
+        }
+        let track_type = match text(track, "kind") {
+            "Video" => TrackType::Video,
+            "Audio" => TrackType::Audio,
+            "Caption" => TrackType::Caption,
+            kind => {
+                self.issue(location, format!("{} track ignored", kind));
+                return Ok(());
+            }
+        };
+        let metadata: Option<TrackMetadata> = self.read_metadata(track, location);
+        let fresh_id = scope.timeline.tracks.len() as TrackId + 1;
+        let id = self.id(&mut scope.track_ids, metadata.as_ref().map(|m| m.id), || fresh_id)?;
+        let mut new_track = Track::new(id, track_type);
+        new_track.enabled = flag(track, "enabled", true);
+        self.report_effects(track, location);
+        if !array(track, "markers").is_empty() {
+            self.issue(location, "track markers ignored");
+        }
+
+        let mut items = track.get("children")
+            .and_then(Value::as_array)
+            .ok_or_else(|| invalid(location, "track without children"))?
+            .as_slice();
+        if track_type == TrackType::Caption {
+            // Cues travel in the metadata; nothing else fits on a caption track
+            for (index, item) in items.iter().enumerate().filter(|(_, item)| schema(item) != Some("Gap")) {
+                let location = format!("{}.children[{}]", location, index);
+                self.issue(&location, format!("{} on a caption track ignored", text(item, "OTIO_SCHEMA")));
+            }
+            items = &[];
+        }
+        let mut cursor = 0;
+        let mut pending: Option<PendingTransition> = None;
+        for (index, item) in items.iter().enumerate() {
+            let location = format!("{}.children[{}]", location, index);
+            if schema(item) == Some("Transition") {
+                if let Some(unused) = pending.take() {
+                    self.issue(&unused.location, "transition not between two clips ignored");
+                }
+                let after_clip = new_track.clips.last().is_some_and(|c| c.timeline_end == cursor);
+                pending = self.read_transition(item, track_type, after_clip, &location)?;
+                continue;
+            }
+
+            let (duration, clip) = self.read_item(scope, item, track_type, cursor, &location)?;
+            match (clip, pending.take()) {
+                (Some(clip), Some(transition)) => {
+                    let id = self.id(&mut scope.transition_ids, transition.id, || scope.timeline.allocate_transition_id())?;
+                    let transition = Transition::new(id, clip.id, transition.kind, transition.alignment, transition.duration);
+                    new_track.transitions.push(transition);
+                    new_track.clips.push(clip);
+                }
+                (Some(clip), None) => new_track.clips.push(clip),
+                (None, Some(transition)) => self.issue(&transition.location, "transition not between two clips ignored"),
+                (None, None) => {}
+            }
+            cursor += duration;
+        }
+        if let Some(unused) = pending {
+            self.issue(&unused.location, "transition not between two clips ignored");
+        }
+
+        let otio_name = text(track, "name");
+        let named = metadata.is_some();
+        if let Some(metadata) = metadata {
+            new_track.name = metadata.name;
+            new_track.muted = metadata.muted;
+            new_track.solo = metadata.solo;
+            new_track.locked = metadata.locked;
+            new_track.set_volume(metadata.volume);
+            new_track.color = metadata.color;
+            new_track.set_height(metadata.height);
+            if self.keep_ids {
+                for transition in metadata.dormant_transitions {
+                    let fits = transition.duration > 0
+                        && transition.kind.track_type() == track_type
+                        && new_track.clips.iter().any(|c| c.id == transition.clip_id)
+                        && new_track.transitions.iter().all(|t| t.clip_id != transition.clip_id);
+                    if fits {
+                        self.id(&mut scope.transition_ids, Some(transition.id), || unreachable!())?;
+                        new_track.transitions.push(transition);
+                    }
+                }
+            }
+            if track_type != TrackType::Caption && !metadata.cues.is_empty() {
+                self.issue(location, "cues on a track that is not a caption track ignored");
+            } else {
+                for mut cue in metadata.cues {
+                    if cue.end <= cue.start {
+                        self.issue(location, format!("cue {} without length ignored", cue.id));
+                        continue;
+                    }
+                    cue.id = self.id(&mut scope.cue_ids, Some(cue.id), || scope.timeline.allocate_cue_id())?;
+                    new_track.cues.push(cue);
+                }
+                new_track.cues.sort_by_key(|c| c.start);
+            }
+        }
+        let index = scope.timeline.tracks.len();
+        scope.timeline.insert_existing_track(index, new_track)
+            .map_err(|e| invalid(location, &e.to_string()))?;
+        // Without metadata, a name like "V2" at its own position is the default label
+        if !named && scope.timeline.track_name(id).as_deref() != Some(otio_name) {
+            scope.timeline.tracks[index].name = otio_name.to_string();
+        }
+        Ok(())
+    }
+
+    fn read_transition(
+        &mut self,
+        item: &Value,
+        track_type: TrackType,
+        after_clip: bool,
+        location: &str,
+    ) -> Result<Option<PendingTransition>, Abort> {
+        let transition_type = text(item, "transition_type");
+        if transition_type != DISSOLVE {
+            self.issue(location, format!("{} transition ignored", transition_type));
+            return Ok(None);
+        }
+        let offset = |key| item.get(key).ok_or_else(|| invalid(location, "transition without offsets"));
+        let before = read_time(offset("in_offset")?, location)?;
+        let after = read_time(offset("out_offset")?, location)?;
+        if !after_clip || before < 0 || after < 0 || before + after == 0 {
+            self.issue(location, "transition not between two clips ignored");
+            return Ok(None);
+        }
+
+        let metadata: Option<TransitionMetadata> = self.read_metadata(item, location);
+        let id = metadata.as_ref().map(|m| m.id);
+        let exact = metadata.filter(|m| {
+            (m.before, m.after) == (before, after) && m.kind.track_type() == track_type && m.duration > 0
+        });
+        let (kind, alignment, duration) = match exact {
+            Some(m) => (m.kind, m.alignment, m.duration),
+            None => {
+                let kind = match track_type {
+                    TrackType::Video => TransitionKind::CrossDissolve,
+                    TrackType::Audio => TransitionKind::Crossfade(IMPORTED_FADE_CURVE),
+                    TrackType::Caption => unreachable!("caption track items are not read"),
+                };
+                let alignment = if before == 0 {
+                    TransitionAlignment::StartAtCut
+                } else if after == 0 {
+                    TransitionAlignment::EndAtCut
+                } else {
+                    if !(0..=1).contains(&(after - before)) {
+                        self.issue(location, "uneven transition centred on the cut");
+                    }
+                    TransitionAlignment::CenterOnCut
+                };
+                (kind, alignment, before + after)
+            }
+        };
+        Ok(Some(PendingTransition { location: location.to_string(), id, kind, alignment, duration }))
+    }
+
+    /// Read a track item starting at `start`: its length on the track, and
+    /// the clip it becomes, if any.
+    fn read_item(
+        &mut self,
+        scope: &mut Scope,
+        item: &Value,
+        track_type: TrackType,
+        start: Time,
+        location: &str,
+    ) -> Result<(Time, Option<Clip>), Abort> {
+        match schema(item) {
+            Some("Clip") => self.read_clip(scope, item, track_type, start, location),
+            Some("Stack") => self.read_compound(scope, item, track_type, start, location),
+            Some("Gap") => {
+                let (_, duration) = source_range(item, location)?
+                    .ok_or_else(|| invalid(location, "gap without a source range"))?;
+                Ok((duration, None))
+            }
+            _ => {
+                let name = text(item, "OTIO_SCHEMA");
+                let (_, duration) = source_range(item, location)?
+                    .ok_or_else(|| invalid(location, &format!("{} without a source range", name)))?;
+                self.issue(location, format!("{} left out", name));
+                Ok((duration, None))
+            }
+        }
+    }
+
+    fn read_clip(
+        &mut self,
+        scope: &mut Scope,
+        item: &Value,
+        track_type: TrackType,
+        start: Time,
+        location: &str,
+    ) -> Result<(Time, Option<Clip>), Abort> {
+        let reference = match item.get("media_references") {
+            Some(references) => {
+                let key = item.get("active_media_reference_key").and_then(Value::as_str).unwrap_or(MEDIA_KEY);
+                references.get(key)
+            }
+            None => item.get("media_reference"),
+        }
+        .filter(|r| !r.is_null());
+        let available = match reference.and_then(|r| r.get("available_range")).filter(|r| !r.is_null()) {
+            Some(range) => Some(read_range(range, location)?),
+            None => None,
+        };
+        let (source_start, duration) = source_range(item, location)?
+            .or(available)
+            .ok_or_else(|| invalid(location, "clip without a source or available range"))?;
+
+        let path = match reference {
+            Some(reference) if schema(reference) == Some("ExternalReference") => {
+                let url = text(reference, "target_url");
+                let path = media_path(url);
+                if path.is_none() {
+                    self.issue(location, format!("media at {:?} is not a local file; clip left out", url));
+                }
+                path
+            }
+            Some(reference) => {
+                self.issue(location, format!("{} media not supported; clip left out", text(reference, "OTIO_SCHEMA")));
+                None
+            }
+            None => {
+                self.issue(location, "clip without media left out");
+                None
+            }
+        };
+        let Some(path) = path else {
+            return Ok((duration, None));
+        };
+        if !flag(item, "enabled", true) {
+            self.issue(location, "disabled clip left out");
+            return Ok((duration, None));
+        }
+
+        let media_start = available.map_or(0, |(start, _)| start);
+        let placement = Placement {
+            source: ClipSource::Media(path),
+            in_point: source_start - media_start,
+            media_start,
+            start,
+            duration,
+            source_duration: available.map(|(_, duration)| duration),
+        };
+        let metadata = self.read_metadata(item, location);
+        let clip = self.build_clip(scope, item, metadata, placement, track_type, location)?;
+        Ok((duration, clip))
+    }
+
+    /// Read a nested stack as a compound clip playing it as a sequence.
+    fn read_compound(
+        &mut self,
+        scope: &mut Scope,
+        item: &Value,
+        track_type: TrackType,
+        start: Time,
+        location: &str,
+    ) -> Result<(Time, Option<Clip>), Abort> {
+        let metadata: Option<ClipMetadata> = self.read_metadata(item, location);
+        let kept = metadata.as_ref().and_then(|m| m.sequence.as_ref());
+        let sequence_id = match kept.and_then(|s| self.sequence_ids.get(&s.id).copied()) {
+            // Another compound clip of the same sequence was read already
+            Some(id) => id,
+            None => {
+                let nested = self.read_stack(item, location)?.timeline;
+                let id = if self.keep_ids {
+                    match kept {
+                        Some(s) if !self.sequences.contains_key(&s.id) => s.id,
+                        _ => return Err(Abort::IdConflict),
+                    }
+                } else {
+                    self.new_sequence_ids.allocate()
+                };
+                if let Some(s) = kept {
+                    self.sequence_ids.insert(s.id, id);
+                }
+                let name = kept.map_or_else(|| text(item, "name").to_string(), |s| s.name.clone());
+                self.sequences.insert(id, Sequence { id, name, timeline: nested });
+                id
+            }
+        };
+        let length = self.sequences[&sequence_id].timeline.duration;
+        let (in_point, duration) = source_range(item, location)?.unwrap_or((0, length));
+        if !flag(item, "enabled", true) {
+            self.issue(location, "disabled clip left out");
+            return Ok((duration, None));
+        }
+
+        let placement = Placement {
+            source: ClipSource::Sequence(sequence_id),
+            in_point,
+            media_start: 0,
+            start,
+            duration,
+            source_duration: Some(length),
+        };
+        let clip = self.build_clip(scope, item, metadata, placement, track_type, location)?;
+        Ok((duration, clip))
+    }
+
+    fn build_clip(
+        &mut self,
+        scope: &mut Scope,
+        item: &Value,
+        metadata: Option<ClipMetadata>,
+        placement: Placement,
+        track_type: TrackType,
+        location: &str,
+    ) -> Result<Option<Clip>, Abort> {
+        let Placement { source, in_point, media_start, start, duration, source_duration } = placement;
+        if in_point < 0 || duration == 0 {
+            self.issue(location, "clip without length or starting before its media left out");
+            return Ok(None);
+        }
+        let retime = self.read_effects(item, metadata.as_ref(), location);
+        let id = self.id(&mut scope.clip_ids, metadata.as_ref().map(|m| m.id), || scope.timeline.allocate_clip_id())?;
+        let default_stream = match track_type {
+            TrackType::Video => 0,
+            TrackType::Audio => 1,
+            TrackType::Caption => unreachable!("caption track items are not read"),
+        };
+        let stream_index = metadata.as_ref().map_or(default_stream, |m| m.stream_index);
+
+        // The exact source range survives as long as the length is unchanged
+        let exact = metadata.as_ref().filter(|m| m.duration == duration).map(|m| m.source_span);
+        let span = match (exact, &retime) {
+            (Some(span), _) => span,
+            (None, Retime::Speed(speed)) => speed.to_source(duration).abs(),
+            (None, Retime::Remap(_)) => 1,
+        };
+        let mut clip = Clip::new(id, source, in_point, in_point + span.max(1), start, stream_index);
+        clip.timeline_end = start + duration;
+        match retime {
+            Retime::Speed(speed) => clip.speed = speed,
+            Retime::Remap(remap) if exact.is_some() => clip.time_remap = Some(remap),
+            Retime::Remap(remap) => clip.set_time_remap(Some(remap)),
+        }
+        clip.source_duration = source_duration.filter(|&d| clip.out_point <= d);
+        clip.markers = self.read_markers(scope, item, media_start, location)?;
+        clip.markers.sort_by_key(|m| m.position);
+
+        if let Some(metadata) = metadata {
+            clip.link_group = match metadata.link_group {
+                Some(group) if !self.keep_ids => {
+                    Some(*scope.link_groups.entry(group).or_insert_with(|| scope.timeline.allocate_link_group()))
+                }
+                group => group,
+            };
+            clip.sync_origin = metadata.sync_origin;
+            clip.preserve_pitch = metadata.preserve_pitch;
+            clip.visual = metadata.visual;
+            clip.animation = metadata.animation;
+        }
+        Ok(Some(clip))
+    }
+
+    /// The speed or time remap given by a clip's effects; other effects are
+    /// reported.
+    fn read_effects(&mut self, item: &Value, metadata: Option<&ClipMetadata>, location: &str) -> Retime {
+        let mut retime = Retime::Speed(Speed::NORMAL);
+        for (index, effect) in array(item, "effects").iter().enumerate() {
+            let location = format!("{}.effects[{}]", location, index);
+            let remap = metadata
+                .and_then(|m| m.time_remap.clone())
+                .filter(|r| !r.keyframes().is_empty());
+            match schema(effect) {
+                Some("FreezeFrame") => retime = Retime::Speed(Speed::FREEZE),
+                Some("LinearTimeWarp") => {
+                    let scalar = effect.get("time_scalar").and_then(Value::as_f64).unwrap_or(1.0);
+                    let speed = match metadata.map(|m| m.speed) {
+                        Some(speed) if (speed.as_f64() - scalar).abs() < 1e-9 => speed,
+                        _ => Speed::new((scalar * 1000.0).round() as i32, 1000),
+                    };
+                    retime = Retime::Speed(speed);
+                }
+                _ if text(effect, "effect_name") == REMAP_EFFECT && remap.is_some() => {
+                    retime = Retime::Remap(remap.expect("checked above"));
+                }
+                _ => self.issue(&location, format!("{} effect ignored", effect_name(effect))),
+            }
+        }
+        retime
+    }
+
+    /// Read the markers of an object, with positions counted from `offset`.
+    fn read_markers(&mut self, scope: &mut Scope, object: &Value, offset: Time, location: &str) -> Result<Vec<Marker>, Abort> {
+        let mut markers = Vec::new();
+        for (index, marker) in array(object, "markers").iter().enumerate() {
+            let location = format!("{}.markers[{}]", location, index);
+            if schema(marker) != Some("Marker") {
+                self.issue(&location, format!("{} ignored", text(marker, "OTIO_SCHEMA")));
+                continue;
+            }
+            let range = marker.get("marked_range").ok_or_else(|| invalid(&location, "marker without a range"))?;
+            let (position, duration) = read_range(range, &location)?;
+            let metadata: Option<MarkerMetadata> = self.read_metadata(marker, &location);
+            let id = self.id(&mut scope.marker_ids, metadata.as_ref().map(|m| m.id), || scope.timeline.allocate_marker_id())?;
+            let kind = metadata.map_or(MarkerKind::Comment, |m| m.kind);
+            let color_name = text(marker, "color");
+            let color = match marker_color(color_name) {
+                Some(color) => color,
+                None => {
+                    self.issue(&location, format!("marker colour {:?} approximated", color_name));
+                    match color_name {
+                        "PINK" => MarkerColor::Red,
+                        "MAGENTA" => MarkerColor::Purple,
+                        _ => kind.default_color(),
+                    }
+                }
+            };
+            let mut new_marker = Marker::new(id, position - offset, kind)
+                .with_name(text(marker, "name"))
+                .with_note(text(marker, "comment"))
+                .with_color(color);
+            if duration > 0 {
+                new_marker = new_marker.with_duration(duration);
+            }
+            markers.push(new_marker);
+        }
+        Ok(markers)
+    }
+}
+
+/// Name of an effect for reports: its `effect_name`, or its schema.
+fn effect_name(effect: &Value) -> String {
+    match text(effect, "effect_name") {
+        "" => text(effect, "OTIO_SCHEMA").to_string(),
+        name => format!("{:?}", name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::Project;
+    use crate::timeline::caption::{CueAlign, CuePosition, CueStyle};
+    use crate::timeline::keyframe::{Keyframe, KeyframeTrack};
+    use crate::timeline::remap::RemapKeyframe;
+
+    const RATE: FrameRate = FrameRate::FPS_24;
+
+    fn f(frames: i64) -> Time {
+        RATE.frame_to_time(frames)
+    }
+
+    fn options() -> OtioOptions {
+        OtioOptions::new("Cut", RATE)
+    }
+
+    /// V1: gap, a (linked with A1) | b with a dip in, then retimed clips off
+    /// the frame grid; A1: reverse music | music with a crossfade in;
+    /// V2 (disabled): a sequence of two titles, used twice; C1: two
+    /// overlapping cues.
+    fn timeline() -> Timeline {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        let a1 = timeline.tracks[1].id;
+        let interview = PathBuf::from("/media/Interview A.mov");
+        timeline.add_clip(v1, Clip::new(1, interview.clone(), f(48), f(120), f(24), 0).with_source_duration(f(2400))).unwrap();
+        timeline.add_clip(a1, Clip::new(2, interview, f(48), f(120), f(24), 1).with_source_duration(f(2400))).unwrap();
+        timeline.link(&[1, 2]).unwrap();
+        timeline.add_clip(v1, Clip::new(3, PathBuf::from("/media/b-roll.mp4"), f(24), f(96), f(96), 0)).unwrap();
+        timeline.add_transition(3, TransitionKind::DipToWhite, TransitionAlignment::CenterOnCut, f(12)).unwrap();
+        let sped = Clip::new(4, PathBuf::from("/media/c.mp4"), 1_234_567, f(48) + 7, f(200) + 3, 0).with_speed(Speed::new(2, 3));
+        timeline.add_clip(v1, sped).unwrap();
+        timeline.add_clip(v1, Clip::freeze_frame(5, PathBuf::from("/media/c.mp4"), f(10), f(300), f(30), 0)).unwrap();
+        let remap = TimeRemap::new(vec![RemapKeyframe::linear(0, 0), RemapKeyframe::linear(f(48), f(96))]);
+        timeline.add_clip(v1, Clip::new(6, PathBuf::from("/media/d.mp4"), 0, f(48), f(400), 0).with_time_remap(remap)).unwrap();
+        let reverse = Clip::new(7, PathBuf::from("/media/music.wav"), 0, f(96), f(204), 1).with_speed(Speed::REVERSE);
+        timeline.add_clip(a1, reverse).unwrap();
+        timeline.add_clip(a1, Clip::new(8, PathBuf::from("/media/music.wav"), f(100), f(200), f(300), 1)).unwrap();
+        timeline.add_transition(8, TransitionKind::Crossfade(FadeCurve::Linear), TransitionAlignment::EndAtCut, f(6)).unwrap();
+
+        timeline.set_clip_visual(3, VisualProperties { rotation: 12.5, opacity: 0.8, ..VisualProperties::default() }).unwrap();
+        let animation = ClipAnimation {
+            opacity: KeyframeTrack::new(vec![Keyframe::linear(0, 0.0), Keyframe::linear(f(12), 1.0)]),
+            ..ClipAnimation::default()
+        };
+        timeline.set_clip_animation(3, animation).unwrap();
+        let marker = timeline.allocate_marker_id();
+        timeline.add_clip_marker(3, Marker::new(marker, f(30), MarkerKind::Todo).with_name("Fix colour")).unwrap();
+        let marker = timeline.allocate_marker_id();
+        timeline.add_marker(Marker::new(marker, f(50), MarkerKind::Chapter).with_name("Intro").with_note("Cold open").with_duration(f(24)));
+
+        let v2 = timeline.add_track(TrackType::Video);
+        timeline.add_clip(v2, Clip::new(9, PathBuf::from("/media/title 1.png"), 0, f(48), f(24), 0)).unwrap();
+        timeline.add_clip(v2, Clip::new(10, PathBuf::from("/media/title 2.png"), 0, f(48), f(96), 0)).unwrap();
+        let sequence = timeline.compound_clips(&[9, 10], "Titles").unwrap();
+        let id = timeline.allocate_clip_id();
+        let reuse = Clip::new(id, ClipSource::Sequence(sequence), f(24), f(96), f(300), 0).with_source_duration(f(120));
+        timeline.add_clip(v2, reuse).unwrap();
+
+        let c1 = timeline.add_track(TrackType::Caption);
+        let style = CueStyle { bold: true, color: Some([255, 255, 0]), align: CueAlign::Start, ..CueStyle::default() };
+        timeline.add_cue(c1, Cue::new(1, f(24), f(60) + 5, "Hello\nthere").with_style(style)).unwrap();
+        let position = CuePosition { x: Some(0.25), y: None };
+        timeline.add_cue(c1, Cue::new(2, f(50), f(70), "Overlap").with_position(position)).unwrap();
+
+        let dialogue = timeline.track_mut(a1).unwrap();
+        dialogue.name = "Dialogue".to_string();
+        dialogue.set_volume(0.5);
+        dialogue.set_muted(true);
+        dialogue.color = [10, 20, 30];
+        timeline.track_mut(v2).unwrap().set_enabled(false);
+        timeline.track_mut(v1).unwrap().set_height(64.0);
+        timeline.track_mut(v1).unwrap().set_locked(true);
+        timeline.set_playhead(f(100));
+        timeline
+    }
+
+    /// Everything a project file keeps of a timeline, for comparing timelines.
+    fn project_json(timeline: Timeline) -> String {
+        Project::new("Cut", timeline).to_json(None).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip_is_lossless() {
+        for rate in [RATE, FrameRate::FPS_23_976, FrameRate::FPS_29_97, FrameRate::new(48000, 1)] {
+            let original = timeline();
+            let import = import_otio(&export_otio(&original, &OtioOptions::new("Cut", rate))).unwrap();
+            assert!(import.issues.is_empty(), "{:?}", import.issues);
+            assert_eq!(import.name, "Cut");
+            assert_eq!(import.timeline.duration, original.duration);
+            assert_eq!(project_json(import.timeline), project_json(original), "at {}", rate);
+        }
+
+        // ID counters continue past the imported IDs
+        let mut timeline = import_otio(&export_otio(&timeline(), &options())).unwrap().timeline;
+        let clip_id = timeline.allocate_clip_id();
+        let marker_id = timeline.allocate_marker_id();
+        assert!(timeline.find_clip(clip_id).is_none());
+        assert!(timeline.markers.iter().all(|m| m.id != marker_id));
+    }
+
+    #[test]
+    fn test_otio_structure() {
+        let document: Value = serde_json::from_str(&export_otio(&timeline(), &options())).unwrap();
+        assert_eq!(document["OTIO_SCHEMA"], "Timeline.1");
+        assert_eq!(document["name"], "Cut");
+        let stack = &document["tracks"];
+        assert_eq!(stack["markers"][0]["name"], "Intro");
+        assert_eq!(stack["markers"][0]["color"], "PURPLE");
+        assert_eq!(stack["markers"][0]["marked_range"]["duration"]["value"], 24.0);
+
+        let tracks = stack["children"].as_array().unwrap();
+        assert_eq!(tracks.len(), 4);
+        assert_eq!((&tracks[0]["kind"], &tracks[0]["name"]), (&json!("Video"), &json!("V1")));
+        assert_eq!((&tracks[1]["kind"], &tracks[1]["name"]), (&json!("Audio"), &json!("Dialogue")));
+        assert_eq!(tracks[2]["enabled"], false);
+        assert_eq!((&tracks[3]["kind"], &tracks[3]["children"]), (&json!("Caption"), &json!([])));
+        assert_eq!(tracks[3]["metadata"][METADATA_KEY]["cues"][0]["text"], "Hello\nthere");
+
+        // V1: a one-second gap, then the interview from 2s for 3s
+        let items = &tracks[0]["children"];
+        assert_eq!(items[0]["OTIO_SCHEMA"], "Gap.1");
+        assert_eq!(items[0]["source_range"]["duration"], json!({ "OTIO_SCHEMA": "RationalTime.1", "rate": 24.0, "value": 24.0 }));
+        assert_eq!(items[1]["OTIO_SCHEMA"], "Clip.2");
+        assert_eq!(items[1]["source_range"]["start_time"]["value"], 48.0);
+        assert_eq!(items[1]["source_range"]["duration"]["value"], 72.0);
+        let media = &items[1]["media_references"][MEDIA_KEY];
+        assert_eq!(media["OTIO_SCHEMA"], "ExternalReference.1");
+        assert_eq!(media["target_url"], "file:///media/Interview%20A.mov");
+        assert_eq!(media["available_range"]["duration"]["value"], 2400.0);
+        // The dip, half a second centred on the cut into the b-roll
+        assert_eq!(items[2]["OTIO_SCHEMA"], "Transition.1");
+        assert_eq!(items[2]["transition_type"], DISSOLVE);
+        assert_eq!((&items[2]["in_offset"]["value"], &items[2]["out_offset"]["value"]), (&json!(6.0), &json!(6.0)));
+        assert_eq!(items[3]["markers"][0]["color"], "ORANGE");
+        // Off the frame grid: fractions of a frame
+        assert_eq!(items[4]["OTIO_SCHEMA"], "Gap.1");
+        assert_eq!(items[5]["source_range"]["start_time"]["value"], 0.029629608);
+        assert_eq!(items[5]["effects"][0]["OTIO_SCHEMA"], "LinearTimeWarp.1");
+        assert_eq!(items[7]["effects"][0]["OTIO_SCHEMA"], "FreezeFrame.1");
+
+        // V2: the titles sequence as nested stacks
+        let items = &tracks[2]["children"];
+        assert_eq!(items[1]["OTIO_SCHEMA"], "Stack.1");
+        assert_eq!(items[1]["name"], "Titles");
+        assert_eq!(items[1]["children"][0]["kind"], "Video");
+        assert_eq!(items[1]["children"][0]["children"][0]["OTIO_SCHEMA"], "Clip.2");
+    }
+
+    #[test]
+    fn test_import_reports_ignored_features() {
+        let rt = |value: f64| json!({ "OTIO_SCHEMA": "RationalTime.1", "rate": 24.0, "value": value });
+        let range = |start: f64, duration: f64| json!({ "OTIO_SCHEMA": "TimeRange.1", "start_time": rt(start), "duration": rt(duration) });
+        let clip = |url: &str, source: Value| json!({
+            "OTIO_SCHEMA": "Clip.1",
+            "name": "",
+            "source_range": source,
+            "media_reference": { "OTIO_SCHEMA": "ExternalReference.1", "target_url": url, "available_range": null },
+        });
+        let mut tape = clip("file://localhost/Volumes/Media/A%20roll.mov", range(86448.0, 48.0));
+        tape["media_reference"]["available_range"] = range(86400.0, 2400.0);
+        tape["effects"] = json!([{ "OTIO_SCHEMA": "Effect.1", "effect_name": "Blur" }]);
+        let mut fast = clip("/media/b.mov", range(0.0, 72.0));
+        fast["effects"] = json!([{ "OTIO_SCHEMA": "LinearTimeWarp.1", "effect_name": "", "time_scalar": 2.0 }]);
+        let mut missing = clip("", range(0.0, 24.0));
+        missing["media_reference"] = json!({ "OTIO_SCHEMA": "MissingReference.1" });
+        let mut disabled = clip("/media/c.mov", range(0.0, 24.0));
+        disabled["enabled"] = json!(false);
+        let dissolve = json!({ "OTIO_SCHEMA": "Transition.1", "transition_type": DISSOLVE, "in_offset": rt(6.0), "out_offset": rt(6.0) });
+        let wipe = json!({ "OTIO_SCHEMA": "Transition.1", "transition_type": "Custom_Transition", "in_offset": rt(0.0), "out_offset": rt(6.0) });
+        let mut music = clip("/media/music.wav", Value::Null);
+        music["media_reference"]["available_range"] = json!({
+            "OTIO_SCHEMA": "TimeRange.1",
+            "start_time": { "OTIO_SCHEMA": "RationalTime.1", "rate": 48000.0, "value": 0.0 },
+            "duration": { "OTIO_SCHEMA": "RationalTime.1", "rate": 48000.0, "value": 480000.0 },
+        });
+        music["markers"] = json!([{ "OTIO_SCHEMA": "Marker.2", "name": "Drop", "color": "RED", "marked_range": range(48.0, 0.0) }]);
+
+        let document = json!({
+            "OTIO_SCHEMA": "Timeline.1",
+            "name": "Online",
+            "global_start_time": rt(86400.0),
+            "tracks": {
+                "OTIO_SCHEMA": "Stack.1",
+                "markers": [{ "OTIO_SCHEMA": "Marker.2", "name": "Review", "color": "MAGENTA", "marked_range": range(48.0, 0.0) }],
+                "children": [
+                    {
+                        "OTIO_SCHEMA": "Track.1",
+                        "name": "V1",
+                        "kind": "Video",
+                        "children": [
+                            { "OTIO_SCHEMA": "Gap.1", "source_range": range(0.0, 24.0) },
+                            tape, dissolve, fast, wipe, missing, disabled,
+                        ],
+                    },
+                    { "OTIO_SCHEMA": "Track.1", "name": "Music", "kind": "Audio", "children": [music] },
+                    { "OTIO_SCHEMA": "Track.1", "name": "Data", "kind": "Data", "children": [] },
+                ],
+            },
+        });
+        let import = import_otio(&document.to_string()).unwrap();
+        assert_eq!(import.name, "Online");
+        let locations: Vec<&str> = import.issues.iter().map(|issue| issue.location.as_str()).collect();
+        assert_eq!(locations, vec![
+            "global_start_time",
+            "tracks.children[0].children[1].effects[0]",
+            "tracks.children[0].children[4]",
+            "tracks.children[0].children[5]",
+            "tracks.children[0].children[6]",
+            "tracks.children[2]",
+            "tracks.markers[0]",
+        ], "{:?}", import.issues);
+
+        let timeline = &import.timeline;
+        assert_eq!(timeline.tracks.len(), 2);
+        assert_eq!(timeline.tracks[0].name, "");
+        assert_eq!(timeline.tracks[1].name, "Music");
+        // Source times count from the start of the media's available range
+        let clips = &timeline.tracks[0].clips;
+        assert_eq!(clips.len(), 2);
+        assert_eq!(clips[0].source_path(), Some(Path::new("/Volumes/Media/A roll.mov")));
+        assert_eq!((clips[0].in_point, clips[0].out_point, clips[0].timeline_start), (f(48), f(96), f(24)));
+        assert_eq!(clips[0].source_duration, Some(f(2400)));
+        assert_eq!(clips[1].speed, Speed::new(2, 1));
+        assert_eq!((clips[1].out_point, clips[1].timeline_start, clips[1].timeline_end), (f(144), f(72), f(144)));
+        let transition = &timeline.tracks[0].transitions[0];
+        assert_eq!((transition.clip_id, transition.kind), (clips[1].id, TransitionKind::CrossDissolve));
+        assert_eq!((transition.alignment, transition.duration), (TransitionAlignment::CenterOnCut, f(12)));
+
+        let music = &timeline.tracks[1].clips[0];
+        assert_eq!((music.in_point, music.out_point, music.stream_index), (0, f(240), 1));
+        assert_eq!(music.markers[0].position, f(48));
+        assert_eq!(timeline.markers[0].color, MarkerColor::Purple);
+    }
+
+    #[test]
+    fn test_unusable_rates_are_import_errors() {
+        let rt = |rate: f64, value: f64| json!({ "OTIO_SCHEMA": "RationalTime.1", "rate": rate, "value": value });
+        assert_eq!(read_time(&rt(0.005, 1.0), "t").unwrap(), 200 * NANOS_PER_SECOND);
+        for rate in [0.0, -24.0, 0.0001, 1e12] {
+            assert!(matches!(read_time(&rt(rate, 1.0), "t"), Err(OtioError::Invalid(_))), "{rate}");
+        }
+    }
+
+    #[test]
+    fn test_repeated_ids_are_replaced() {
+        // A clip copied in another application keeps the metadata of the original
+        let mut document: Value = serde_json::from_str(&export_otio(&timeline(), &options())).unwrap();
+        let items = document["tracks"]["children"][0]["children"].as_array_mut().unwrap();
+        items.push(items[1].clone());
+        let import = import_otio(&document.to_string()).unwrap();
+        assert!(import.issues.is_empty(), "{:?}", import.issues);
+
+        let timeline = import.timeline;
+        let mut ids: Vec<ClipId> = timeline.tracks.iter().flat_map(|t| &t.clips).map(|c| c.id).collect();
+        let count = ids.len();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), count);
+        // Links, transitions and shared sequences survive
+        let v1 = &timeline.tracks[0].clips;
+        assert_eq!(v1.len(), 6);
+        assert!(v1[0].link_group.is_some());
+        assert_eq!(v1[5].link_group, v1[0].link_group);
+        assert_eq!(timeline.tracks[1].clips[0].link_group, v1[0].link_group);
+        assert_eq!(timeline.tracks[0].transitions[0].clip_id, v1[1].id);
+        assert_eq!(timeline.sequences.len(), 1);
+    }
+
+    #[test]
+    fn test_media_urls() {
+        let path = Path::new("/media/Ünïcode #1.mov");
+        let url = file_url(path);
+        assert_eq!(url, "file:///media/%C3%9Cn%C3%AFcode%20%231.mov");
+        assert_eq!(media_path(&url).as_deref(), Some(path));
+        assert_eq!(media_path("clips/a.mov"), Some(PathBuf::from("clips/a.mov")));
+        assert_eq!(media_path("file:///C:/Media/a.mov"), Some(PathBuf::from("C:/Media/a.mov")));
+        assert_eq!(media_path("https://example.com/a.mov"), None);
+        assert_eq!(media_path("file://server/share/a.mov"), None);
+    }
+}
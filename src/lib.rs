@@ -6,5 +6,7 @@ pub mod render;
 pub mod audio;
 pub mod playback;
 pub mod export;
+pub mod project;
+pub mod interchange;
 pub mod ui;
 
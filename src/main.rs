@@ -16,10 +16,22 @@ fn main() -> eframe::Result<()> {
         ..Default::default()
     };
 
+    // Open the project given on the command line, if any
+    let project_path = std::env::args_os().nth(1);
+
     // Run the application with EditorApp
     eframe::run_native(
         "AVES",
         native_options,
-        Box::new(|cc| Box::new(EditorApp::new(cc))),
+        Box::new(move |cc| {
+            let app = match project_path {
+                Some(path) => EditorApp::open(cc, &path).unwrap_or_else(|e| {
+                    eprintln!("Could not open {}: {}", path.to_string_lossy(), e);
+                    EditorApp::new(cc)
+                }),
+                None => EditorApp::new(cc),
+            };
+            Box::new(app)
+        }),
     )
 }
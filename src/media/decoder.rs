@@ -6,7 +6,7 @@ use std::path::{Path, PathBuf};
 use std::ffi::CString;
 use std::sync::Arc;
 use crossbeam::channel;
-use crate::core::time::Time;
+use crate::core::time::{Time, FrameRate};
 
 /// Error type for decoding operations
 #[derive(Debug)]
@@ -72,7 +72,7 @@ pub struct VideoStreamInfo {
     pub stream_info: StreamInfo,
     pub width: u32,
     pub height: u32,
-    pub fps: f64,
+    pub fps: FrameRate,
     pub pixel_format: String,
 }
 
@@ -371,13 +371,13 @@ impl MediaDecoder {
             let width = (*codec_ctx).width as u32;
             let height = (*codec_ctx).height as u32;
             
-            // Calculate FPS
+            // Frame rate as an exact rational (no f64 rounding for NTSC rates)
             let fps = {
                 let r_frame_rate = (*stream).r_frame_rate;
-                if r_frame_rate.den != 0 {
-                    r_frame_rate.num as f64 / r_frame_rate.den as f64
+                if r_frame_rate.num > 0 && r_frame_rate.den > 0 {
+                    FrameRate::new(r_frame_rate.num as u32, r_frame_rate.den as u32)
                 } else {
-                    30.0  // Default
+                    FrameRate::FPS_30  // Default
                 }
             };
 
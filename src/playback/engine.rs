@@ -114,6 +114,12 @@ impl PlaybackEngine {
                 let _elapsed_ns = master_clock.load(std::sync::atomic::Ordering::Acquire);
                 
                 // TODO: Get current timeline position, find clip, decode frame, render
+                // Decode at `Clip::timeline_to_source` (which follows speed and time
+                // remaps) and blend the pair from `Clip::blend_frames_at` when set,
+                // as the exporter does, placed by `Layer::for_clip` (the clip's
+                // visual properties and keyframes). Over a `Track::transition_at`
+                // window, decode both clips at `Clip::handle_source_at` and draw
+                // their layers with `renderer::transition_layers`
                 // Rendering is done via the Renderer which is owned by the main thread
                 // Video frames are sent via channel to the render thread
                 // For now, just sleep to prevent busy-waiting
@@ -0,0 +1,451 @@
+//! Periodic autosave and recovery after a crash.
+//!
+//! [`Autosave`] hands snapshots of the project to a background job running
+//! on its own tokio runtime, which serializes and writes them, so the UI
+//! thread only pays for cloning the project. Each write goes to
+//! `autosave-1.aves` in the session's directory, after the older backups
+//! are rotated to `autosave-2.aves` and so on.
+//!
+//! Every project has its own autosave directory (see
+//! [`AutosaveConfig::for_project`]), and every session of it its own
+//! `session-<pid>-<start time>` directory inside that one, so editors running
+//! side by side never write over each other's backups. A session holds a
+//! lock on the `session.lock` file in its directory while it runs and
+//! removes the directory when the `Autosave` is dropped normally. A session
+//! directory that is left behind but not locked belongs to a session that did
+//! not close cleanly, and its backups are offered as a [`Recovery`].
+
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::watch;
+use crate::project::file::{self, Project, ProjectError};
+use crate::timeline::validate::Diagnostic;
+
+/// File locked by a session while it runs.
+const SESSION_MARKER: &str = "session.lock";
+
+/// Prefix of the session directories in an autosave directory.
+const SESSION_PREFIX: &str = "session-";
+
+/// Where and how often to autosave.
+#[derive(Debug, Clone)]
+pub struct AutosaveConfig {
+    pub dir: PathBuf,       // Autosave directory of the project
+    pub interval: Duration, // Shortest time between two autosaves
+    pub backups: usize,     // Number of autosaves kept, newest first
+}
+
+impl Default for AutosaveConfig {
+    /// Autosave for projects that have not been saved yet.
+    fn default() -> Self {
+        Self::for_project(None)
+    }
+}
+
+impl AutosaveConfig {
+    /// Autosave into the directory of the project saved at `project_path`,
+    /// or of unsaved projects if `None`.
+    pub fn for_project(project_path: Option<&Path>) -> Self {
+        Self {
+            dir: default_dir().join(project_key(project_path)),
+            interval: Duration::from_secs(60),
+            backups: 5,
+        }
+    }
+}
+
+/// `aves/autosave` in the user's data directory, or in the temporary
+/// directory if there is none.
+fn default_dir() -> PathBuf {
+    let data_dir = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share")))
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+        .unwrap_or_else(std::env::temp_dir);
+    data_dir.join("aves").join("autosave")
+}
+
+/// Name of a project's autosave directory: the file stem and a hash of the
+/// full path, or `untitled` for unsaved projects.
+fn project_key(project_path: Option<&Path>) -> String {
+    let Some(path) = project_path else {
+        return "untitled".to_string();
+    };
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    // FNV-1a, so the name stays the same across builds
+    let hash = path.as_os_str()
+        .as_encoded_bytes()
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, &byte| (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3));
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    format!("{}-{:016x}", stem, hash)
+}
+
+/// Path of the `index`th backup (1 = newest).
+fn backup_path(dir: &Path, index: usize) -> PathBuf {
+    dir.join(format!("autosave-{}.{}", index, Project::FILE_EXTENSION))
+}
+
+/// Session directories in `dir` left behind by sessions that are no longer
+/// running.
+fn crashed_sessions(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut sessions = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_session = path.file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with(SESSION_PREFIX));
+        if !is_session || !path.is_dir() {
+            continue;
+        }
+        // A running session holds the lock; the lock goes with its process
+        let unlocked = File::open(path.join(SESSION_MARKER)).is_ok_and(|marker| marker.try_lock().is_ok());
+        if unlocked {
+            sessions.push(path);
+        }
+    }
+    Ok(sessions)
+}
+
+/// Backups of sessions that did not close cleanly.
+#[derive(Debug, Clone)]
+pub struct Recovery {
+    sessions: Vec<PathBuf>, // Session directories holding the backups
+    backups: Vec<PathBuf>,  // Newest first
+}
+
+impl Recovery {
+    /// Gather the backups of `sessions`, or `None` if they have none.
+    fn new(sessions: Vec<PathBuf>, keep: usize) -> Option<Self> {
+        let mut backups: Vec<(SystemTime, PathBuf)> = sessions
+            .iter()
+            .flat_map(|dir| (1..=keep).map(move |index| backup_path(dir, index)))
+            .filter_map(|path| Some((std::fs::metadata(&path).ok()?.modified().ok()?, path)))
+            .collect();
+        if backups.is_empty() {
+            return None;
+        }
+        backups.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+        Some(Self { sessions, backups: backups.into_iter().map(|(_, path)| path).collect() })
+    }
+
+    /// Backup files, newest first.
+    pub fn backups(&self) -> &[PathBuf] {
+        &self.backups
+    }
+
+    /// Load the newest backup that is a valid project, with what was
+    /// repaired on loading (see `Project::load`).
+    ///
+    /// Returns the error of the oldest backup if none loads.
+    pub fn load(&self) -> Result<(Project, Vec<Diagnostic>), ProjectError> {
+        let mut last_error = ProjectError::NotAProject;
+        for path in &self.backups {
+            match Project::load(path) {
+                Ok(loaded) => return Ok(loaded),
+                Err(e) => last_error = e,
+            }
+        }
+        Err(last_error)
+    }
+
+    /// Delete the backups, once they were recovered or declined.
+    pub fn discard(&self) -> Result<(), ProjectError> {
+        for dir in &self.sessions {
+            std::fs::remove_dir_all(dir)?;
+        }
+        Ok(())
+    }
+}
+
+/// A snapshot for the worker, with the history revision it was taken at.
+type Snapshot = Option<(u64, Project)>;
+
+/// Background autosave of a project.
+///
+/// Call [`Autosave::update`] every frame; it takes a snapshot when the
+/// project changed and the interval has passed. Dropping the `Autosave`
+/// waits for the last write and marks the session as closed cleanly.
+pub struct Autosave {
+    config: AutosaveConfig,
+    session_dir: PathBuf,
+    _lock: File, // Held while the session runs
+    sender: Option<watch::Sender<Snapshot>>,
+    written: mpsc::Receiver<(u64, Result<(), ProjectError>)>,
+    worker: Option<JoinHandle<()>>,
+    saved_revision: u64,           // Newest revision known to be on disk
+    pending_revision: Option<u64>, // Revision handed to the worker, not yet written
+    last_snapshot: Instant,
+}
+
+impl Autosave {
+    /// Start autosaving into a new session directory in `config.dir`,
+    /// treating history revision 0 as already saved.
+    ///
+    /// Returns the backups of earlier sessions of the project that did not
+    /// close cleanly and left any.
+    pub fn start(config: AutosaveConfig) -> Result<(Self, Option<Recovery>), ProjectError> {
+        std::fs::create_dir_all(&config.dir)?;
+        let backups = config.backups.max(1);
+        let crashed = crashed_sessions(&config.dir)?;
+        let recovery = Recovery::new(crashed.clone(), backups);
+        if recovery.is_none() {
+            // Nothing to offer from sessions that crashed before their first autosave
+            for dir in crashed {
+                let _ = std::fs::remove_dir_all(dir);
+            }
+        }
+
+        let started = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let session_dir = config.dir.join(format!("{}{}-{}", SESSION_PREFIX, std::process::id(), started.as_millis()));
+        std::fs::create_dir_all(&session_dir)?;
+        let lock = File::create(session_dir.join(SESSION_MARKER))?;
+        lock.lock()?;
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .map_err(ProjectError::Io)?;
+        let (sender, receiver) = watch::channel(None);
+        let (written_sender, written) = mpsc::channel();
+        let dir = session_dir.clone();
+        let worker = std::thread::Builder::new()
+            .name("autosave".to_string())
+            .spawn(move || runtime.block_on(run_worker(receiver, written_sender, dir, backups)))?;
+
+        let autosave = Self {
+            config,
+            session_dir,
+            _lock: lock,
+            sender: Some(sender),
+            written,
+            worker: Some(worker),
+            saved_revision: 0,
+            pending_revision: None,
+            last_snapshot: Instant::now(),
+        };
+        Ok((autosave, recovery))
+    }
+
+    /// Shortest time between two autosaves (e.g. to schedule a repaint).
+    pub fn interval(&self) -> Duration {
+        self.config.interval
+    }
+
+    /// Autosave the project if `revision` (see `History::revision`) differs
+    /// from the last autosaved one and the interval has passed.
+    ///
+    /// `snapshot` is only called when a save is due. Writing happens in the
+    /// background; a slow write is skipped in favour of the newest snapshot.
+    /// A revision counts as saved once it is written; if writing fails, the
+    /// error is returned by a later call and the next snapshot is retried.
+    pub fn update(&mut self, revision: u64, snapshot: impl FnOnce() -> Project) -> Result<(), ProjectError> {
+        let result = self.collect_written();
+        let due = revision != self.saved_revision
+            && self.pending_revision != Some(revision)
+            && self.last_snapshot.elapsed() >= self.config.interval;
+        if due {
+            if let Some(sender) = &self.sender {
+                sender.send_replace(Some((revision, snapshot())));
+                self.pending_revision = Some(revision);
+            }
+            self.last_snapshot = Instant::now();
+        }
+        result
+    }
+
+    /// Record the writes the worker finished, returning the last failure.
+    fn collect_written(&mut self) -> Result<(), ProjectError> {
+        let mut result = Ok(());
+        while let Ok((revision, written)) = self.written.try_recv() {
+            match written {
+                Ok(()) => self.saved_revision = revision,
+                Err(e) => result = Err(e),
+            }
+            if self.pending_revision == Some(revision) {
+                self.pending_revision = None;
+            }
+        }
+        result
+    }
+
+    /// Treat `revision` as saved, e.g. after loading or saving the project.
+    pub fn mark_saved(&mut self, revision: u64) {
+        self.saved_revision = revision;
+    }
+}
+
+impl Drop for Autosave {
+    fn drop(&mut self) {
+        // Closing the channel ends the worker after its last write
+        self.sender = None;
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+        // A panic is not a clean shutdown: keep the backups for recovery
+        if !std::thread::panicking() {
+            let _ = std::fs::remove_dir_all(&self.session_dir);
+        }
+    }
+}
+
+/// Write every snapshot received until the sender is dropped, reporting
+/// each write on `written`.
+async fn run_worker(
+    mut receiver: watch::Receiver<Snapshot>,
+    written: mpsc::Sender<(u64, Result<(), ProjectError>)>,
+    dir: PathBuf,
+    backups: usize,
+) {
+    while receiver.changed().await.is_ok() {
+        let snapshot = receiver.borrow_and_update().clone();
+        if let Some((revision, project)) = snapshot {
+            let _ = written.send((revision, write_backup(&project, &dir, backups)));
+        }
+    }
+}
+
+/// Rotate the backups and write `project` as the newest one.
+fn write_backup(project: &Project, dir: &Path, backups: usize) -> Result<(), ProjectError> {
+    // Media paths stay absolute: the autosave directory is not the project's
+    let json = project.to_json(None)?;
+    for index in (1..backups).rev() {
+        let from = backup_path(dir, index);
+        if from.exists() {
+            std::fs::rename(&from, backup_path(dir, index + 1))?;
+        }
+    }
+    file::write_atomic(&backup_path(dir, 1), json.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::time;
+    use crate::timeline::clip::Clip;
+    use crate::timeline::timeline::Timeline;
+
+    fn config(name: &str) -> AutosaveConfig {
+        let dir = std::env::temp_dir().join(format!("aves-autosave-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        AutosaveConfig { dir, interval: Duration::ZERO, backups: 3 }
+    }
+
+    fn project(clips: u64) -> Project {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        for id in 1..=clips {
+            let start = time::from_seconds(id as f64);
+            timeline.add_clip(v1, Clip::new(id, PathBuf::from("/media/a.mp4"), 0, time::from_seconds(1.0), start, 0)).unwrap();
+        }
+        Project::new("Cut", timeline)
+    }
+
+    /// Autosave `revision` and wait for the worker to report back.
+    fn written(autosave: &mut Autosave, revision: u64, clips: u64) -> Result<(), ProjectError> {
+        autosave.update(revision, || project(clips))?;
+        while autosave.pending_revision.is_some() {
+            std::thread::sleep(Duration::from_millis(1));
+            autosave.collect_written()?;
+        }
+        Ok(())
+    }
+
+    /// Leave a session behind as a crash would, with a backup of `clips` clips.
+    fn crashed_session(config: &AutosaveConfig, name: &str, clips: u64) -> PathBuf {
+        let dir = config.dir.join(format!("{}{}", SESSION_PREFIX, name));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(SESSION_MARKER), "").unwrap();
+        write_backup(&project(clips), &dir, config.backups).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_rotating_backups() {
+        let config = config("rotate");
+        let (mut autosave, recovery) = Autosave::start(config.clone()).unwrap();
+        assert!(recovery.is_none());
+
+        // Revision 0 counts as saved
+        autosave.update(0, || unreachable!()).unwrap();
+        for revision in 1..=4 {
+            written(&mut autosave, revision, revision).unwrap();
+            assert_eq!(autosave.saved_revision, revision);
+        }
+        let dir = autosave.session_dir.clone();
+        let clips = |index| Project::load(backup_path(&dir, index)).unwrap().0.timeline.tracks[0].clips.len();
+        assert_eq!((clips(1), clips(2), clips(3)), (4, 3, 2));
+        assert!(!backup_path(&dir, 4).exists());
+
+        // A clean shutdown leaves nothing to recover
+        drop(autosave);
+        assert!(!dir.exists());
+        std::fs::remove_dir_all(&config.dir).unwrap();
+    }
+
+    #[test]
+    fn test_interval() {
+        let config = AutosaveConfig { interval: Duration::from_secs(3600), ..config("interval") };
+        let (mut autosave, _) = Autosave::start(config.clone()).unwrap();
+        autosave.update(1, || unreachable!()).unwrap();
+        assert!(!backup_path(&autosave.session_dir, 1).exists());
+        drop(autosave);
+        std::fs::remove_dir_all(&config.dir).unwrap();
+    }
+
+    #[test]
+    fn test_failed_write_is_not_saved() {
+        let config = config("failed");
+        let (mut autosave, _) = Autosave::start(config.clone()).unwrap();
+        let dir = autosave.session_dir.clone();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(written(&mut autosave, 1, 2).is_err());
+        assert_eq!(autosave.saved_revision, 0);
+
+        // The same revision is written again
+        std::fs::create_dir_all(&dir).unwrap();
+        written(&mut autosave, 1, 2).unwrap();
+        assert_eq!(autosave.saved_revision, 1);
+        drop(autosave);
+        std::fs::remove_dir_all(&config.dir).unwrap();
+    }
+
+    #[test]
+    fn test_recovery_after_unclean_shutdown() {
+        let config = config("recover");
+        let older = crashed_session(&config, "1-0", 1);
+        std::thread::sleep(Duration::from_millis(20));
+        let newer = crashed_session(&config, "2-0", 2);
+        // A corrupt newest backup falls back to the next one
+        std::thread::sleep(Duration::from_millis(20));
+        std::fs::rename(backup_path(&newer, 1), backup_path(&newer, 2)).unwrap();
+        std::fs::write(backup_path(&newer, 1), "{").unwrap();
+
+        // Running sessions are not offered
+        let (mut running, recovery) = Autosave::start(config.clone()).unwrap();
+        written(&mut running, 1, 5).unwrap();
+        let recovery = recovery.unwrap();
+        assert_eq!(recovery.backups().len(), 3);
+        assert_eq!(recovery.backups()[0], backup_path(&newer, 1));
+        assert_eq!(recovery.load().unwrap().0.timeline.tracks[0].clips.len(), 2);
+        let (_other, again) = Autosave::start(config.clone()).unwrap();
+        assert_eq!(again.unwrap().backups().len(), 3);
+        assert!(running.session_dir.exists());
+
+        recovery.discard().unwrap();
+        assert!(!older.exists() && !newer.exists());
+        std::fs::remove_dir_all(&config.dir).unwrap();
+    }
+
+    #[test]
+    fn test_directory_per_project() {
+        let a = AutosaveConfig::for_project(Some(Path::new("/projects/a/cut.aves")));
+        let b = AutosaveConfig::for_project(Some(Path::new("/projects/b/cut.aves")));
+        assert_ne!(a.dir, b.dir);
+        assert!(a.dir.file_name().unwrap().to_string_lossy().starts_with("cut-"));
+        assert_eq!(AutosaveConfig::default().dir.file_name().unwrap(), "untitled");
+    }
+}
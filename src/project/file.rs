@@ -0,0 +1,705 @@
+//! Project files: saving and loading a timeline with its export settings.
+//!
+//! A project file is JSON of the form
+//! `{"format": "aves-project", "version": N, "project": {...}}`. Loading reads
+//! the envelope first, upgrades older versions (see `migrate`), then reads the
+//! project and checks it. Anything that would make `Clip::new` or another
+//! constructor panic, or that cannot be fixed without guessing (duplicate
+//! track IDs, IDs too large for the timeline's ID allocators, missing
+//! sequences, a sequence containing itself), is reported as
+//! `ProjectError::Invalid`. What `Timeline::validate` reports (overlapping
+//! clips, duplicate clip IDs, ranges that disagree) is fixed with
+//! `Timeline::repair`, and the project is returned with the diagnostics.
+//!
+//! Media is not probed when loading: clip source ranges are checked against
+//! the source durations stored in the clips, not the files on disk. A file
+//! that has since been replaced by a shorter one is only caught by the export
+//! checks (see `pipeline::validate_timeline`).
+//!
+//! Media paths under the project file's directory are stored relative to it,
+//! so a project folder can be moved with its media.
+
+use std::collections::{BTreeSet, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::core::time::Time;
+use crate::export::pipeline::ExportSettings;
+use crate::project::migrate::{self, CURRENT_VERSION};
+use crate::timeline::clip::{Clip, ClipSource};
+use crate::timeline::keyframe::{Animatable, KeyframeTrack};
+use crate::timeline::marker::Marker;
+use crate::timeline::remap::TimeRemap;
+use crate::timeline::sequence::{Sequence, SequenceId};
+use crate::timeline::speed::Speed;
+use crate::timeline::timeline::{Timeline, ID_LIMIT};
+use crate::timeline::track::{Track, TrackId, TrackType};
+use crate::timeline::validate::{Diagnostic, MediaDurations};
+
+/// Value of the envelope's `format` field.
+const FORMAT: &str = "aves-project";
+
+/// Errors from saving or loading a project.
+#[derive(Debug)]
+pub enum ProjectError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    /// The file is JSON, but not a project file
+    NotAProject,
+    /// Written by a newer build, or not a version that ever existed
+    UnsupportedVersion { version: u32, supported: u32 },
+    /// The project breaks an invariant of the timeline
+    Invalid(String),
+}
+
+impl std::fmt::Display for ProjectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProjectError::Io(e) => write!(f, "IO error: {}", e),
+            ProjectError::Json(e) => write!(f, "JSON error: {}", e),
+            ProjectError::NotAProject => write!(f, "Not a project file"),
+            ProjectError::UnsupportedVersion { version, supported } => write!(
+                f,
+                "Project file version {} is not supported (this build reads versions 1 to {})",
+                version, supported
+            ),
+            ProjectError::Invalid(msg) => write!(f, "Invalid project: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ProjectError {}
+
+impl From<std::io::Error> for ProjectError {
+    fn from(err: std::io::Error) -> Self {
+        ProjectError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for ProjectError {
+    fn from(err: serde_json::Error) -> Self {
+        ProjectError::Json(err)
+    }
+}
+
+/// A project file as written; loading reads the envelope as a `Value` first.
+#[derive(Serialize)]
+struct Envelope {
+    format: &'static str,
+    version: u32,
+    project: ProjectFile,
+}
+
+/// The `project` object of a project file.
+#[derive(Debug, Serialize, Deserialize)]
+struct ProjectFile {
+    name: String,
+    media: Vec<PathBuf>, // Every media file the clips use, for tools that collect or relink media
+    timeline: TimelineFile,
+    sequences: Vec<SequenceFile>,
+    export_settings: ExportSettings,
+}
+
+/// A timeline without its ID counters, which are rebuilt on load.
+#[derive(Debug, Serialize, Deserialize)]
+struct TimelineFile {
+    tracks: Vec<Track>,
+    markers: Vec<Marker>,
+    playhead: Time,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SequenceFile {
+    id: SequenceId,
+    name: String,
+    timeline: TimelineFile,
+}
+
+impl TimelineFile {
+    fn new(timeline: &Timeline) -> Self {
+        Self {
+            tracks: timeline.tracks.clone(),
+            markers: timeline.markers.clone(),
+            playhead: timeline.playhead,
+        }
+    }
+
+    fn clips_mut(&mut self) -> impl Iterator<Item = &mut Clip> {
+        self.tracks.iter_mut().flat_map(|t| &mut t.clips)
+    }
+}
+
+/// A timeline, with the sequences its compound clips use, and how to export it.
+#[derive(Debug, Clone)]
+pub struct Project {
+    pub name: String,
+    pub timeline: Timeline,
+    pub export_settings: ExportSettings,
+}
+
+impl Project {
+    /// Extension of project files, without the dot.
+    pub const FILE_EXTENSION: &'static str = "aves";
+
+    /// Create a project with default export settings.
+    pub fn new(name: impl Into<String>, timeline: Timeline) -> Self {
+        Self { name: name.into(), timeline, export_settings: ExportSettings::default() }
+    }
+
+    /// Media files used by the timeline and its sequences, sorted.
+    pub fn media(&self) -> Vec<PathBuf> {
+        let nested = self.timeline.sequences.values().map(|s| &s.timeline);
+        std::iter::once(&self.timeline)
+            .chain(nested)
+            .flat_map(|t| &t.tracks)
+            .flat_map(|t| &t.clips)
+            .filter_map(|c| match &c.source {
+                ClipSource::Media(path) => Some(path.clone()),
+                ClipSource::Sequence(_) => None,
+            })
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Write the project to `path`, with media paths relative to its directory.
+    ///
+    /// The file is replaced atomically (see `write_atomic`).
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ProjectError> {
+        let path = path.as_ref();
+        let json = self.to_json(path.parent())?;
+        write_atomic(path, json.as_bytes())?;
+        Ok(())
+    }
+
+    /// Read and check a project file.
+    ///
+    /// Returns the project with what was repaired on loading (see
+    /// `from_json`). Source ranges are checked against the durations stored
+    /// in the clips; the media files are not opened (see the module
+    /// documentation).
+    pub fn load(path: impl AsRef<Path>) -> Result<(Self, Vec<Diagnostic>), ProjectError> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path)?;
+        Self::from_json(&json, path.parent())
+    }
+
+    /// The project as the contents of a project file.
+    ///
+    /// Media paths under `base_dir` are written relative to it.
+    pub fn to_json(&self, base_dir: Option<&Path>) -> Result<String, ProjectError> {
+        let mut timeline = TimelineFile::new(&self.timeline);
+        let mut sequences: Vec<SequenceFile> = self.timeline.sequences
+            .values()
+            .map(|s| SequenceFile { id: s.id, name: s.name.clone(), timeline: TimelineFile::new(&s.timeline) })
+            .collect();
+        let mut media = self.media();
+
+        if let Some(base_dir) = base_dir {
+            let relative = |path: &mut PathBuf| {
+                if let Ok(stripped) = path.strip_prefix(base_dir) {
+                    *path = stripped.to_path_buf();
+                }
+            };
+            media.iter_mut().for_each(&relative);
+            let nested = sequences.iter_mut().flat_map(|s| s.timeline.clips_mut());
+            for clip in timeline.clips_mut().chain(nested) {
+                if let ClipSource::Media(path) = &mut clip.source {
+                    relative(path);
+                }
+            }
+        }
+
+        let envelope = Envelope {
+            format: FORMAT,
+            version: CURRENT_VERSION,
+            project: ProjectFile {
+                name: self.name.clone(),
+                media,
+                timeline,
+                sequences,
+                export_settings: self.export_settings.clone(),
+            },
+        };
+        Ok(serde_json::to_string_pretty(&envelope)?)
+    }
+
+    /// Read and check the contents of a project file.
+    ///
+    /// Relative media paths are resolved against `base_dir`. Issues
+    /// `Timeline::validate` finds are repaired (see `Timeline::repair`) and
+    /// returned with the project, so the caller can tell the user.
+    pub fn from_json(json: &str, base_dir: Option<&Path>) -> Result<(Self, Vec<Diagnostic>), ProjectError> {
+        let mut document: Value = serde_json::from_str(json)?;
+        if document.get("format").and_then(Value::as_str) != Some(FORMAT) {
+            return Err(ProjectError::NotAProject);
+        }
+        let version = document.get("version")
+            .and_then(Value::as_u64)
+            .ok_or(ProjectError::NotAProject)?;
+        let version = u32::try_from(version)
+            .map_err(|_| ProjectError::UnsupportedVersion { version: u32::MAX, supported: CURRENT_VERSION })?;
+        migrate::migrate(&mut document, version)?;
+
+        let project = document.get_mut("project").map(Value::take).ok_or(ProjectError::NotAProject)?;
+        let mut file: ProjectFile = serde_json::from_value(project)?;
+
+        if let Some(base_dir) = base_dir {
+            let nested = file.sequences.iter_mut().flat_map(|s| s.timeline.clips_mut());
+            for clip in file.timeline.clips_mut().chain(nested) {
+                if let ClipSource::Media(path) = &mut clip.source {
+                    if path.is_relative() {
+                        *path = base_dir.join(&*path);
+                    }
+                }
+            }
+        }
+
+        check_export_settings(&file.export_settings)?;
+        let mut timeline = build_timeline(file.timeline)?;
+        for sequence in file.sequences {
+            if timeline.sequences.contains_key(&sequence.id) {
+                return Err(invalid(format!("sequence {} appears twice", sequence.id)));
+            }
+            check_id("sequence", sequence.id)?;
+            let nested = build_timeline(sequence.timeline)
+                .map_err(|e| in_sequence(e, sequence.id))?;
+            timeline.reserve_sequence_id(sequence.id);
+            timeline.sequences.insert(sequence.id, Sequence { id: sequence.id, name: sequence.name, timeline: nested });
+        }
+
+        let nested = timeline.sequences.values().map(|s| &s.timeline);
+        let missing = std::iter::once(&timeline)
+            .chain(nested)
+            .flat_map(|t| &t.tracks)
+            .flat_map(|t| &t.clips)
+            .find_map(|c| c.sequence_id().filter(|id| !timeline.sequences.contains_key(id)).map(|id| (c.id, id)));
+        if let Some((clip_id, sequence)) = missing {
+            return Err(invalid(format!("clip {} plays sequence {}, which does not exist", clip_id, sequence)));
+        }
+        if let Some(sequence) = timeline.find_sequence_cycle() {
+            return Err(invalid(format!("sequence {} contains itself", sequence)));
+        }
+        // Clip ranges, clip IDs and overlaps; media is not probed here, so
+        // source ranges are checked against the durations stored in the clips
+        let media = MediaDurations::new();
+        let repaired = timeline.repair(&media);
+        if let Some(diagnostic) = timeline.validate(&media).into_iter().next() {
+            return Err(invalid(format!("{} (left after repair)", diagnostic)));
+        }
+
+        let project = Self { name: file.name, timeline, export_settings: file.export_settings };
+        Ok((project, repaired))
+    }
+}
+
+/// Replace `path` with `contents` without ever leaving a partly written file.
+///
+/// The contents go to a temporary file next to `path`, are flushed to disk,
+/// and the temporary file is renamed over `path`: after a crash `path` holds
+/// either the old or the new contents.
+pub(crate) fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    let result = (|| {
+        let mut file = std::fs::File::create(&temp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        std::fs::rename(&temp_path, path)
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result
+}
+
+fn invalid(msg: String) -> ProjectError {
+    ProjectError::Invalid(msg)
+}
+
+/// Prefix an error found in a nested timeline with its sequence.
+fn in_sequence(err: ProjectError, sequence: SequenceId) -> ProjectError {
+    match err {
+        ProjectError::Invalid(msg) => invalid(format!("sequence {}: {}", sequence, msg)),
+        other => other,
+    }
+}
+
+fn check_export_settings(settings: &ExportSettings) -> Result<(), ProjectError> {
+    if settings.width == 0 || settings.height == 0 {
+        return Err(invalid("export size must not be zero".to_string()));
+    }
+    if settings.fps.num == 0 || settings.fps.den == 0 {
+        return Err(invalid("export frame rate must be positive".to_string()));
+    }
+    if settings.sample_rate == 0 || settings.channels == 0 {
+        return Err(invalid("export sample rate and channel count must not be zero".to_string()));
+    }
+    Ok(())
+}
+
+/// Check a timeline read from a file and rebuild it with its ID counters.
+///
+/// Clips, cues, clip markers and keyframes are sorted, and values the setters
+/// would clamp (volume, height, crop) are clamped.
+fn build_timeline(file: TimelineFile) -> Result<Timeline, ProjectError> {
+    let mut track_ids = HashSet::new();
+    let mut transition_ids = HashSet::new();
+    let mut cue_ids = HashSet::new();
+    let mut marker_ids: HashSet<_> = HashSet::new();
+    for marker in &file.markers {
+        check_id("marker", marker.id)?;
+        if !marker_ids.insert(marker.id) {
+            return Err(invalid(format!("marker {} appears twice", marker.id)));
+        }
+    }
+
+    let mut timeline = Timeline::empty();
+    for mut track in file.tracks {
+        check_id("track", track.id)?;
+        if !track_ids.insert(track.id) {
+            return Err(invalid(format!("track {} appears twice", track.id)));
+        }
+        for clip in &mut track.clips {
+            check_id("clip", clip.id)?;
+            if let Some(group) = clip.link_group {
+                check_id("link group", group)?;
+            }
+            check_clip(clip, track.id)?;
+            for marker in &clip.markers {
+                check_id("marker", marker.id)?;
+                if !marker_ids.insert(marker.id) {
+                    return Err(invalid(format!("marker {} appears twice", marker.id)));
+                }
+            }
+        }
+        for transition in &track.transitions {
+            check_id("transition", transition.id)?;
+            if !transition_ids.insert(transition.id) {
+                return Err(invalid(format!("transition {} appears twice", transition.id)));
+            }
+        }
+        for cue in &track.cues {
+            check_id("cue", cue.id)?;
+            if !cue_ids.insert(cue.id) {
+                return Err(invalid(format!("cue {} appears twice", cue.id)));
+            }
+        }
+        check_track(&mut track)?;
+        let index = timeline.tracks.len();
+        timeline.insert_existing_track(index, track)
+            .map_err(|e| invalid(e.to_string()))?;
+    }
+
+    for marker in file.markers {
+        if marker.duration.is_some_and(|d| d <= 0) {
+            return Err(invalid(format!("range marker {} has no length", marker.id)));
+        }
+        timeline.add_marker(marker);
+    }
+    timeline.set_playhead(file.playhead);
+    Ok(timeline)
+}
+
+/// Refuse an ID the timeline could not mark as used (see `ID_LIMIT`), as it
+/// would be handed out again.
+fn check_id(what: &str, id: u64) -> Result<(), ProjectError> {
+    if id >= ID_LIMIT {
+        return Err(invalid(format!("{} {}: ID is out of range", what, id)));
+    }
+    Ok(())
+}
+
+/// Sort a track's clips, and check that its transitions suit the track and
+/// that only caption tracks hold cues (and no clips). Overlaps are left to
+/// `Timeline::validate`.
+fn check_track(track: &mut Track) -> Result<(), ProjectError> {
+    if track.track_type == TrackType::Caption {
+        if let Some(clip) = track.clips.first() {
+            return Err(invalid(format!("clip {} is on caption track {}", clip.id, track.id)));
+        }
+    } else if let Some(cue) = track.cues.first() {
+        return Err(invalid(format!("cue {} is on track {}, which is not a caption track", cue.id, track.id)));
+    }
+    track.cues.sort_by_key(|c| c.start);
+    if let Some(cue) = track.cues.iter().find(|c| c.end <= c.start) {
+        return Err(invalid(format!("cue {} on track {}: end must be after start", cue.id, track.id)));
+    }
+
+    track.clips.sort_by_key(|c| (c.timeline_start, c.timeline_end));
+
+    // Transitions whose clip was removed or moved stay behind for undo; with no
+    // history to undo, they are dropped
+    let clips: HashSet<_> = track.clips.iter().map(|c| c.id).collect();
+    track.transitions.retain(|t| clips.contains(&t.clip_id));
+    let mut incoming = HashSet::new();
+    for transition in &track.transitions {
+        let context = format!("transition {} on track {}", transition.id, track.id);
+        if transition.duration <= 0 {
+            return Err(invalid(format!("{}: duration must be positive", context)));
+        }
+        if transition.kind.track_type() != track.track_type {
+            return Err(invalid(format!("{}: {:?} does not apply to this track type", context, transition.kind)));
+        }
+        if !incoming.insert(transition.clip_id) {
+            return Err(invalid(format!("{}: clip {} already has a transition", context, transition.clip_id)));
+        }
+    }
+
+    track.set_volume(track.volume);
+    track.set_height(track.height);
+    Ok(())
+}
+
+/// Check the values of a clip that constructors would panic on and normalise
+/// what they would. Its ranges are left to `Timeline::validate`.
+fn check_clip(clip: &mut Clip, track_id: TrackId) -> Result<(), ProjectError> {
+    let error = |what: &str| invalid(format!("clip {} on track {}: {}", clip.id, track_id, what));
+    if clip.speed.den == 0 {
+        return Err(error("speed has a zero denominator"));
+    }
+    if clip.time_remap.as_ref().is_some_and(|r| r.keyframes().is_empty()) {
+        return Err(error("time remap has no keyframes"));
+    }
+    if let Some(marker) = clip.markers.iter().find(|m| m.duration.is_some_and(|d| d <= 0)) {
+        return Err(error(&format!("range marker {} has no length", marker.id)));
+    }
+
+    clip.speed = Speed::new(clip.speed.num, clip.speed.den);
+    if let Some(remap) = &clip.time_remap {
+        clip.time_remap = Some(TimeRemap::new(remap.keyframes().to_vec()).with_frame_blend(remap.frame_blend));
+    }
+    clip.visual.crop = clip.visual.crop.clamped();
+    let animation = &mut clip.animation;
+    sort_keyframes(&mut animation.position);
+    sort_keyframes(&mut animation.scale);
+    sort_keyframes(&mut animation.opacity);
+    sort_keyframes(&mut animation.rotation);
+    sort_keyframes(&mut animation.volume);
+    sort_keyframes(&mut animation.pan);
+    clip.markers.sort_by_key(|m| m.position);
+    Ok(())
+}
+
+fn sort_keyframes<T: Animatable>(track: &mut KeyframeTrack<T>) {
+    *track = KeyframeTrack::new(track.keyframes().to_vec());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::time::{self, FrameRate};
+    use crate::timeline::caption::{Cue, CueStyle};
+    use crate::timeline::keyframe::Keyframe;
+    use crate::timeline::marker::MarkerKind;
+    use crate::timeline::transition::{FadeCurve, TransitionAlignment, TransitionKind};
+    use crate::timeline::validate::TimelineIssue;
+
+    fn project() -> Project {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        let a1 = timeline.tracks[1].id;
+        timeline.add_clip(v1, Clip::new(1, PathBuf::from("/media/a.mp4"), 0, time::from_seconds(4.0), 0, 0)).unwrap();
+        timeline.add_clip(v1, Clip::new(2, PathBuf::from("/media/b.mp4"), time::from_seconds(1.0), time::from_seconds(5.0), time::from_seconds(4.0), 0)).unwrap();
+        timeline.add_clip(a1, Clip::new(3, PathBuf::from("/media/a.mp4"), 0, time::from_seconds(4.0), 0, 1)).unwrap();
+        timeline.link(&[1, 3]).unwrap();
+        timeline.add_transition(2, TransitionKind::CrossDissolve, TransitionAlignment::CenterOnCut, time::from_seconds(0.5)).unwrap();
+        timeline.track_mut(a1).unwrap().set_volume(0.5);
+        timeline.track_mut(a1).unwrap().name = "Dialogue".to_string();
+
+        let mut animation = timeline.find_clip(2).unwrap().1.animation.clone();
+        animation.opacity = KeyframeTrack::new(vec![Keyframe::linear(0, 0.0), Keyframe::linear(time::from_seconds(1.0), 1.0)]);
+        timeline.set_clip_animation(2, animation).unwrap();
+        timeline.add_marker(Marker::new(1, time::from_seconds(2.0), MarkerKind::Chapter));
+        let v2 = timeline.add_track(TrackType::Video);
+        timeline.add_clip(v2, Clip::new(4, PathBuf::from("/media/title.png"), 0, time::from_seconds(2.0), time::from_seconds(1.0), 0)).unwrap();
+        timeline.compound_clips(&[4], "Nest").unwrap();
+        timeline.set_playhead(time::from_seconds(3.0));
+
+        let mut project = Project::new("Cut", timeline);
+        project.export_settings.fps = FrameRate::FPS_29_97;
+        project
+    }
+
+    /// The project file with `edit` applied to its `project` object.
+    fn edited(edit: impl FnOnce(&mut Value)) -> String {
+        let mut document: Value = serde_json::from_str(&project().to_json(None).unwrap()).unwrap();
+        edit(&mut document["project"]);
+        document.to_string()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let project = project();
+        let json = project.to_json(None).unwrap();
+        let (loaded, repaired) = Project::from_json(&json, None).unwrap();
+
+        assert!(repaired.is_empty());
+        assert_eq!(loaded.to_json(None).unwrap(), json);
+        assert_eq!(loaded.name, "Cut");
+        assert_eq!(loaded.export_settings.fps, FrameRate::FPS_29_97);
+        assert_eq!(loaded.timeline.duration, project.timeline.duration);
+        assert_eq!(loaded.timeline.playhead, time::from_seconds(3.0));
+        assert_eq!(loaded.timeline.find_clip(1).unwrap().1, project.timeline.find_clip(1).unwrap().1);
+        assert_eq!(loaded.timeline.track_name(loaded.timeline.tracks[1].id).as_deref(), Some("Dialogue"));
+        assert_eq!(loaded.timeline.sequences.len(), 1);
+        assert_eq!(loaded.media().len(), 3);
+
+        // ID counters continue past the loaded IDs
+        let mut timeline = loaded.timeline;
+        let clip_id = timeline.allocate_clip_id();
+        let sequence = timeline.allocate_sequence_id();
+        assert!(timeline.find_clip(clip_id).is_none());
+        assert!(!timeline.sequences.contains_key(&sequence));
+    }
+
+    #[test]
+    fn test_media_paths_relative_to_project() {
+        let project = project();
+        let json = project.to_json(Some(Path::new("/media"))).unwrap();
+        assert!(json.contains("\"a.mp4\""));
+        assert!(!json.contains("/media/a.mp4"));
+
+        let (loaded, _) = Project::from_json(&json, Some(Path::new("/elsewhere"))).unwrap();
+        assert_eq!(loaded.media()[0], PathBuf::from("/elsewhere/a.mp4"));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir().join(format!("aves-project-test-{}.aves", std::process::id()));
+        project().save(&path).unwrap();
+        project().save(&path).unwrap();
+        let loaded = Project::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap().0.timeline.tracks.len(), 3);
+        // The temporary file was renamed over the project
+        assert!(!path.with_extension("aves.tmp").exists());
+    }
+
+    /// Load a project file that needs repairs; returns the repaired project
+    /// and the issues found.
+    fn repaired(json: &str) -> (Project, Vec<TimelineIssue>) {
+        let (project, diagnostics) = Project::from_json(json, None).unwrap();
+        assert!(project.timeline.validate(&MediaDurations::new()).is_empty());
+        (project, diagnostics.into_iter().map(|d| d.issue).collect())
+    }
+
+    #[test]
+    fn test_rejects_invalid_clips() {
+        let json = edited(|p| p["timeline"]["tracks"][0]["clips"][0]["speed"]["den"] = Value::from(0));
+        assert!(matches!(Project::from_json(&json, None), Err(ProjectError::Invalid(_))));
+
+        let mut project = project();
+        let marker = Marker::new(2, time::from_seconds(1.0), MarkerKind::Comment).with_duration(time::from_seconds(1.0));
+        project.timeline.add_clip_marker(1, marker).unwrap();
+        let mut document: Value = serde_json::from_str(&project.to_json(None).unwrap()).unwrap();
+        document["project"]["timeline"]["tracks"][0]["clips"][0]["markers"][0]["duration"] = Value::from(0);
+        assert!(matches!(
+            Project::from_json(&document.to_string(), None),
+            Err(ProjectError::Invalid(msg)) if msg.contains("range marker 2")
+        ));
+
+        let json = edited(|p| p["timeline"]["tracks"][0]["clips"][0]["id"] = Value::from(u64::MAX));
+        assert!(matches!(
+            Project::from_json(&json, None),
+            Err(ProjectError::Invalid(msg)) if msg.contains("ID is out of range")
+        ));
+    }
+
+    #[test]
+    fn test_repairs_clip_ranges() {
+        let v1 = project().timeline.tracks[0].id;
+        let (project, issues) = repaired(&edited(|p| p["timeline"]["tracks"][0]["clips"][0]["out_point"] = Value::from(0)));
+        assert!(issues.contains(&TimelineIssue::ZeroLength { track_id: v1, clip_id: 1 }));
+        assert!(project.timeline.find_clip(1).is_none());
+
+        let source_duration = time::from_seconds(1.0);
+        let (project, issues) = repaired(&edited(|p| p["timeline"]["tracks"][0]["clips"][0]["source_duration"] = Value::from(source_duration)));
+        assert_eq!(issues, vec![TimelineIssue::SourceOutOfRange { track_id: v1, clip_id: 1, source_duration }]);
+        assert_eq!(project.timeline.find_clip(1).unwrap().1.out_point, source_duration);
+
+        // Fields that disagree with each other (see `Timeline::validate`)
+        let expected = time::from_seconds(4.0);
+        let (project, issues) = repaired(&edited(|p| p["timeline"]["tracks"][0]["clips"][0]["timeline_end"] = Value::from(time::from_seconds(3.0))));
+        assert_eq!(issues, vec![TimelineIssue::EndMismatch { track_id: v1, clip_id: 1, expected }]);
+        assert_eq!(project.timeline.find_clip(1).unwrap().1.timeline_end, expected);
+    }
+
+    #[test]
+    fn test_repairs_overlaps_and_duplicate_clips() {
+        let v1 = project().timeline.tracks[0].id;
+        // Clip 3 moved onto the video track, on top of clip 1
+        let (project, issues) = repaired(&edited(|p| {
+            let clip = p["timeline"]["tracks"][1]["clips"][0].take();
+            p["timeline"]["tracks"][1]["clips"] = Value::Array(Vec::new());
+            p["timeline"]["tracks"][0]["clips"].as_array_mut().unwrap().push(clip);
+        }));
+        assert!(issues.iter().any(|issue| matches!(issue, TimelineIssue::Overlap { track_id, .. } if *track_id == v1)));
+        assert_eq!(project.timeline.track(v1).unwrap().clips.len(), 2);
+
+        let (project, issues) = repaired(&edited(|p| p["timeline"]["tracks"][1]["clips"][0]["id"] = Value::from(1)));
+        assert!(matches!(issues[..], [TimelineIssue::DuplicateClipId { clip_id: 1, .. }]));
+        assert_eq!(project.timeline.tracks[1].clips.len(), 1);
+        assert_ne!(project.timeline.tracks[1].clips[0].id, 1);
+
+        // Repeated track IDs are not guessed at
+        let json = edited(|p| p["timeline"]["tracks"][1]["id"] = p["timeline"]["tracks"][0]["id"].clone());
+        assert!(matches!(Project::from_json(&json, None), Err(ProjectError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_rejects_missing_sequence() {
+        let json = edited(|p| p["sequences"] = Value::Array(Vec::new()));
+        assert!(matches!(Project::from_json(&json, None), Err(ProjectError::Invalid(msg)) if msg.contains("does not exist")));
+    }
+
+    #[test]
+    fn test_rejects_other_files() {
+        assert!(matches!(Project::from_json("{}", None), Err(ProjectError::NotAProject)));
+        assert!(matches!(Project::from_json("not json", None), Err(ProjectError::Json(_))));
+
+        let mut document: Value = serde_json::from_str(&project().to_json(None).unwrap()).unwrap();
+        document["version"] = Value::from(CURRENT_VERSION + 1);
+        assert!(matches!(
+            Project::from_json(&document.to_string(), None),
+            Err(ProjectError::UnsupportedVersion { .. })
+        ));
+    }
+
+    #[test]
+    fn test_caption_tracks() {
+        let mut project = project();
+        let c1 = project.timeline.add_track(TrackType::Caption);
+        project.timeline.add_cue(c1, Cue::new(7, time::from_seconds(1.0), time::from_seconds(2.5), "Hello\nthere").with_style(CueStyle { italic: true, ..CueStyle::default() })).unwrap();
+        let json = project.to_json(None).unwrap();
+        let mut timeline = Project::from_json(&json, None).unwrap().0.timeline;
+        assert_eq!(timeline.find_cue(7).unwrap().1, project.timeline.find_cue(7).unwrap().1);
+        assert_eq!(timeline.allocate_cue_id(), 8);
+
+        let mut document: Value = serde_json::from_str(&json).unwrap();
+        let index = project.timeline.track_index(c1).unwrap();
+        let cues = document["project"]["timeline"]["tracks"][index]["cues"].take();
+        document["project"]["timeline"]["tracks"][index]["cues"] = Value::Array(Vec::new());
+        document["project"]["timeline"]["tracks"][0]["cues"] = cues;
+        assert!(matches!(
+            Project::from_json(&document.to_string(), None),
+            Err(ProjectError::Invalid(msg)) if msg.contains("not a caption track")
+        ));
+    }
+
+    #[test]
+    fn test_transitions() {
+        let json = edited(|p| {
+            p["timeline"]["tracks"][0]["transitions"][0]["kind"] =
+                serde_json::to_value(TransitionKind::Crossfade(FadeCurve::EqualPower)).unwrap();
+        });
+        assert!(matches!(Project::from_json(&json, None), Err(ProjectError::Invalid(msg)) if msg.contains("transition")));
+
+        // A transition left behind by a removed clip is dropped
+        let json = edited(|p| p["timeline"]["tracks"][0]["transitions"][0]["clip_id"] = Value::from(99));
+        assert!(Project::from_json(&json, None).unwrap().0.timeline.tracks[0].transitions.is_empty());
+    }
+}
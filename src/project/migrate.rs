@@ -0,0 +1,126 @@
+//! Project file versions and the migrations between them.
+//!
+//! A project file records the version of the layout it was written with.
+//! Loading an older file runs the migrations from its version up to
+//! [`CURRENT_VERSION`] on the raw JSON document, before it is read into the
+//! current types, so old layouts never need to be understood by the loader.
+//!
+//! To change the layout: bump `CURRENT_VERSION` and append a migration that
+//! rewrites a document of the previous version into the new one.
+
+use serde_json::Value;
+use crate::project::file::ProjectError;
+
+/// Version of the layout this build writes.
+pub const CURRENT_VERSION: u32 = 2;
+
+/// Rewrites a document of one version into the next.
+pub(crate) type Migration = fn(&mut Value) -> Result<(), ProjectError>;
+
+/// `MIGRATIONS[i]` upgrades a version `i + 1` document to version `i + 2`.
+const MIGRATIONS: &[Migration] = &[add_track_cues];
+
+const _: () = assert!(MIGRATIONS.len() == CURRENT_VERSION as usize - 1);
+
+/// Upgrade a `version` document to `CURRENT_VERSION`.
+///
+/// Fails with `ProjectError::UnsupportedVersion` for versions newer than this
+/// build (or 0, which was never written).
+pub(crate) fn migrate(document: &mut Value, version: u32) -> Result<(), ProjectError> {
+    migrate_with(document, version, MIGRATIONS)
+}
+
+fn migrate_with(document: &mut Value, version: u32, migrations: &[Migration]) -> Result<(), ProjectError> {
+    let current = migrations.len() as u32 + 1;
+    if version == 0 || version > current {
+        return Err(ProjectError::UnsupportedVersion { version, supported: current });
+    }
+    for migration in &migrations[version as usize - 1..] {
+        migration(document)?;
+    }
+    document["version"] = Value::from(current);
+    Ok(())
+}
+
+/// Version 2 added caption cues to tracks: every track gets an empty list.
+fn add_track_cues(document: &mut Value) -> Result<(), ProjectError> {
+    let project = document.get_mut("project").ok_or(ProjectError::NotAProject)?;
+    add_cues(project.get_mut("timeline"));
+    if let Some(sequences) = project.get_mut("sequences").and_then(Value::as_array_mut) {
+        for sequence in sequences {
+            add_cues(sequence.get_mut("timeline"));
+        }
+    }
+    Ok(())
+}
+
+fn add_cues(timeline: Option<&mut Value>) {
+    let tracks = timeline
+        .and_then(|t| t.get_mut("tracks"))
+        .and_then(Value::as_array_mut);
+    for track in tracks.into_iter().flatten().filter_map(Value::as_object_mut) {
+        track.entry("cues").or_insert_with(|| Value::Array(Vec::new()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Version 2 renamed `title` to `name`.
+    fn rename_title(document: &mut Value) -> Result<(), ProjectError> {
+        let project = document["project"].as_object_mut()
+            .ok_or_else(|| ProjectError::Invalid("project is not an object".to_string()))?;
+        if let Some(title) = project.remove("title") {
+            project.insert("name".to_string(), title);
+        }
+        Ok(())
+    }
+
+    /// Version 3 added `tags`.
+    fn add_tags(document: &mut Value) -> Result<(), ProjectError> {
+        document["project"]["tags"] = json!([]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrations_run_in_order_from_the_file_version() {
+        let migrations: &[Migration] = &[rename_title, add_tags];
+        let mut document = json!({ "version": 1, "project": { "title": "Cut" } });
+        migrate_with(&mut document, 1, migrations).unwrap();
+        assert_eq!(document, json!({ "version": 3, "project": { "name": "Cut", "tags": [] } }));
+
+        let mut document = json!({ "version": 2, "project": { "title": "Kept", "name": "Cut" } });
+        migrate_with(&mut document, 2, migrations).unwrap();
+        assert_eq!(document["project"]["title"], "Kept");
+        assert_eq!(document["version"], 3);
+    }
+
+    #[test]
+    fn test_version_1_tracks_get_cues() {
+        let track = json!({ "id": 1, "clips": [] });
+        let mut document = json!({
+            "version": 1,
+            "project": {
+                "timeline": { "tracks": [track.clone()] },
+                "sequences": [{ "id": 1, "timeline": { "tracks": [track] } }],
+            },
+        });
+        migrate(&mut document, 1).unwrap();
+        assert_eq!(document["project"]["timeline"]["tracks"][0]["cues"], json!([]));
+        assert_eq!(document["project"]["sequences"][0]["timeline"]["tracks"][0]["cues"], json!([]));
+        assert_eq!(document["version"], 2);
+    }
+
+    #[test]
+    fn test_unknown_versions_are_refused() {
+        let mut document = json!({});
+        assert!(matches!(
+            migrate(&mut document, CURRENT_VERSION + 1),
+            Err(ProjectError::UnsupportedVersion { version, supported: CURRENT_VERSION }) if version == CURRENT_VERSION + 1
+        ));
+        assert!(matches!(migrate(&mut document, 0), Err(ProjectError::UnsupportedVersion { .. })));
+        assert!(migrate(&mut document, CURRENT_VERSION).is_ok());
+    }
+}
@@ -0,0 +1,7 @@
+pub mod file;
+pub mod migrate;
+pub mod autosave;
+
+pub use file::{Project, ProjectError};
+pub use migrate::CURRENT_VERSION;
+pub use autosave::{Autosave, AutosaveConfig, Recovery};
@@ -29,13 +29,16 @@ impl std::error::Error for CompositorError {}
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
 struct TransformUniform {
-    position: [f32; 2],      // Normalized position (0.0-1.0)
+    position: [f32; 2],      // Normalized anchor position (0.0-1.0)
     scale: [f32; 2],          // Scale factors
-    opacity: f32,             // Opacity (0.0-1.0)
-    _padding: f32,            // Padding for alignment
+    anchor: [f32; 2],        // Normalized pivot within the frame
     output_size: [f32; 2],   // Output dimensions (width, height)
     frame_size: [f32; 2],    // Frame dimensions (width, height)
-    _padding2: [f32; 2],     // Additional padding
+    opacity: f32,             // Opacity (0.0-1.0)
+    rotation: f32,            // Radians clockwise
+    crop: [f32; 4],          // Left, top, right, bottom (fractions of the frame, 16-byte aligned)
+    flip: [f32; 2],          // -1.0 mirrors an axis, 1.0 leaves it
+    _padding: [f32; 2],      // Pads the struct to a multiple of 16 bytes
 }
 
 /// Compositor for rendering layered video frames to a surface
@@ -46,7 +49,7 @@ pub struct Compositor {
     surface_config: SurfaceConfiguration,
     render_pipeline: RenderPipeline,
     bind_group_layout: BindGroupLayout,
-    uniform_buffer: Buffer,
+    uniform_buffers: Vec<Buffer>, // One per layer: queued writes all land before the draw
     texture_cache: Vec<Texture>,  // Cache textures for layers
 }
 
@@ -155,14 +158,6 @@ impl Compositor {
             ],
         });
 
-        // Create uniform buffer
-        let uniform_buffer = device.create_buffer(&BufferDescriptor {
-            label: Some("Transform Uniform Buffer"),
-            size: std::mem::size_of::<TransformUniform>() as u64,
-            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-
         // Create render pipeline
         let shader = compile_shader(&device, FRAGMENT_SHADER);
         let vertex_shader = compile_shader(&device, VERTEX_SHADER);
@@ -208,7 +203,8 @@ impl Compositor {
                 topology: PrimitiveTopology::TriangleList,
                 strip_index_format: None,
                 front_face: FrontFace::Ccw,
-                cull_mode: Some(Face::Back),
+                // Flipped layers wind the other way
+                cull_mode: None,
                 polygon_mode: PolygonMode::Fill,
                 unclipped_depth: false,
                 conservative: false,
@@ -229,7 +225,7 @@ impl Compositor {
             surface_config,
             render_pipeline,
             bind_group_layout,
-            uniform_buffer,
+            uniform_buffers: Vec::new(),
             texture_cache: Vec::new(),
         })
     }
@@ -314,20 +310,37 @@ impl Compositor {
             .texture
             .create_view(&TextureViewDescriptor::default());
 
+        // Each layer needs its own uniform buffer: writes are only applied at submit,
+        // so a shared buffer would give every layer the last layer's transform
+        // (and break opacity blends such as transitions)
+        while self.uniform_buffers.len() < layers.len() {
+            self.uniform_buffers.push(self.device.create_buffer(&BufferDescriptor {
+                label: Some("Transform Uniform Buffer"),
+                size: std::mem::size_of::<TransformUniform>() as u64,
+                usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+        }
+
         // Pre-create all bind groups so they live long enough (before render_pass)
         let mut bind_groups = Vec::with_capacity(layers.len());
         for (i, layer) in layers.iter().enumerate() {
             let texture = &self.texture_cache[i];
             
             // Prepare transform uniform
+            let transform = &layer.transform;
+            let flip = |flipped: bool| if flipped { -1.0 } else { 1.0 };
             let transform_uniform = TransformUniform {
-                position: [layer.transform.position.0, layer.transform.position.1],
-                scale: [layer.transform.scale.0, layer.transform.scale.1],
-                opacity: layer.transform.opacity,
-                _padding: 0.0,
+                position: [transform.position.0, transform.position.1],
+                scale: [transform.scale.0, transform.scale.1],
+                anchor: [transform.anchor.0, transform.anchor.1],
                 output_size: [self.surface_config.width as f32, self.surface_config.height as f32],
                 frame_size: [layer.frame.width as f32, layer.frame.height as f32],
-                _padding2: [0.0, 0.0],
+                opacity: transform.opacity,
+                rotation: transform.rotation.to_radians(),
+                crop: [transform.crop.left, transform.crop.top, transform.crop.right, transform.crop.bottom],
+                flip: [flip(transform.flip_horizontal), flip(transform.flip_vertical)],
+                _padding: [0.0, 0.0],
             };
 
             // Update uniform buffer
@@ -338,7 +351,7 @@ impl Compositor {
                     std::mem::size_of::<TransformUniform>(),
                 )
             };
-            self.queue.write_buffer(&self.uniform_buffer, 0, bytes);
+            self.queue.write_buffer(&self.uniform_buffers[i], 0, bytes);
 
             // Create bind group for this layer
             let bind_group = self.device.create_bind_group(&BindGroupDescriptor {
@@ -355,7 +368,7 @@ impl Compositor {
                     },
                     BindGroupEntry {
                         binding: 2,
-                        resource: self.uniform_buffer.as_entire_binding(),
+                        resource: self.uniform_buffers[i].as_entire_binding(),
                     },
                 ],
             });
@@ -390,7 +403,7 @@ impl Compositor {
         // Render each layer using the pre-created bind groups
         for bind_group in &bind_groups {
             render_pass.set_bind_group(0, bind_group, &[]);
-            render_pass.draw(0..3, 0..1);
+            render_pass.draw(0..6, 0..1);
         }
         
         // Explicitly drop render_pass to release borrow on encoder
@@ -5,4 +5,4 @@ pub mod renderer;
 
 pub use compositor::Compositor;
 pub use texture::Texture;
-pub use renderer::{Renderer, Layer, Transform, RenderError};
+pub use renderer::{Renderer, Layer, Transform, RenderError, transition_layers};
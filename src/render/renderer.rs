@@ -1,10 +1,13 @@
 //! Public GPU rendering interface for layered video frames.
-//! Per SPEC.md: Accepts RGBA8 frames, supports basic transforms (scale, position, opacity).
+//! Per SPEC.md: Accepts RGBA8 frames, supports basic transforms (scale, position, opacity),
+//! plus rotation, anchor point, crop and flips.
 
 use wgpu::*;
 use winit::window::Window;
 use crate::decode::decoder::VideoFrame;
 use crate::render::compositor::Compositor;
+use crate::core::time::Time;
+use crate::timeline::{Clip, Crop, TransitionKind, VisualProperties};
 
 /// Error type for rendering operations
 #[derive(Debug)]
@@ -27,16 +30,27 @@ impl std::fmt::Display for RenderError {
 impl std::error::Error for RenderError {}
 
 /// Transform parameters for a video layer
-/// All coordinates are normalized (0.0-1.0) relative to output dimensions
+/// Positions are normalized (0.0-1.0) relative to output dimensions; the
+/// anchor and crop are normalized relative to the layer's frame
 #[derive(Debug, Clone, Copy)]
 pub struct Transform {
     /// Position in normalized coordinates (0.0, 0.0) = top-left, (1.0, 1.0) = bottom-right
-    /// Position represents the center of the layer
+    /// Position is where the layer's anchor point is placed
     pub position: (f32, f32),
     /// Scale factors (1.0 = original size, 2.0 = double size)
     pub scale: (f32, f32),
     /// Opacity (0.0 = transparent, 1.0 = opaque)
     pub opacity: f32,
+    /// Rotation in degrees, clockwise about the anchor
+    pub rotation: f32,
+    /// Point of the frame placed at `position` and pivoted on, (0.5, 0.5) = frame centre
+    pub anchor: (f32, f32),
+    /// Fraction of the frame hidden at each edge
+    pub crop: Crop,
+    /// Mirror the layer left-right about the anchor
+    pub flip_horizontal: bool,
+    /// Mirror the layer top-bottom about the anchor
+    pub flip_vertical: bool,
 }
 
 impl Default for Transform {
@@ -45,10 +59,37 @@ impl Default for Transform {
             position: (0.5, 0.5),  // Center
             scale: (1.0, 1.0),      // Original size
             opacity: 1.0,           // Fully opaque
+            rotation: 0.0,
+            anchor: (0.5, 0.5),     // Frame centre
+            crop: Crop::default(),  // Uncropped
+            flip_horizontal: false,
+            flip_vertical: false,
         }
     }
 }
 
+impl From<VisualProperties> for Transform {
+    fn from(visual: VisualProperties) -> Self {
+        Self {
+            position: visual.position,
+            scale: visual.scale,
+            opacity: visual.opacity,
+            rotation: visual.rotation,
+            anchor: visual.anchor,
+            crop: visual.crop,
+            flip_horizontal: visual.flip_horizontal,
+            flip_vertical: visual.flip_vertical,
+        }
+    }
+}
+
+impl Transform {
+    /// Transform of `clip`'s layer at `timeline_position`, including its keyframes.
+    pub fn for_clip(clip: &Clip, timeline_position: Time) -> Self {
+        clip.visual_at(timeline_position).into()
+    }
+}
+
 /// A single layer to render
 /// Layers are composited in order (first = back, last = front)
 #[derive(Debug, Clone)]
@@ -59,6 +100,71 @@ pub struct Layer {
     pub transform: Transform,
 }
 
+impl Layer {
+    /// A layer of one RGBA colour at `width` x `height`, untransformed.
+    pub fn solid(width: u32, height: u32, rgba: [u8; 4]) -> Self {
+        Self {
+            frame: VideoFrame {
+                data: rgba.repeat((width * height) as usize),
+                width,
+                height,
+                timestamp: 0,
+            },
+            transform: Transform::default(),
+        }
+    }
+
+    /// The layer `clip` shows at `timeline_position`: `frame`, placed by the
+    /// clip's visual properties.
+    pub fn for_clip(frame: VideoFrame, clip: &Clip, timeline_position: Time) -> Self {
+        Self {
+            frame,
+            transform: Transform::for_clip(clip, timeline_position),
+        }
+    }
+}
+
+/// Layers (back to front) that show a transition `progress` (0.0-1.0) of the
+/// way from layer `from` to layer `to` on an output of `width` x `height`.
+///
+/// Matches the export path (`export::composite::transition_frame`): a dissolve
+/// draws the incoming layer over the outgoing one, its opacity scaled by
+/// `progress`; a dip draws one layer under its colour, which peaks halfway.
+/// Each layer keeps its own transform (see `Layer::for_clip`). A missing
+/// layer (e.g. not decoded yet) is left out.
+pub fn transition_layers(
+    kind: TransitionKind,
+    from: Option<Layer>,
+    to: Option<Layer>,
+    progress: f32,
+    width: u32,
+    height: u32,
+) -> Vec<Layer> {
+    let progress = progress.clamp(0.0, 1.0);
+    let mut layers = Vec::with_capacity(2);
+    match kind.dip_color() {
+        Some(color) => {
+            let (layer, amount) = if progress < 0.5 {
+                (from, progress * 2.0)
+            } else {
+                (to, (1.0 - progress) * 2.0)
+            };
+            layers.extend(layer);
+            let mut solid = Layer::solid(width, height, color);
+            solid.transform.opacity = amount.min(1.0);
+            layers.push(solid);
+        }
+        None => {
+            layers.extend(from);
+            layers.extend(to.map(|mut layer| {
+                layer.transform.opacity *= progress;
+                layer
+            }));
+        }
+    }
+    layers
+}
+
 /// GPU renderer for layered video frames
 /// Handles wgpu initialization and provides a clean API for rendering
 pub struct Renderer {
@@ -82,7 +188,8 @@ impl Renderer {
 
     /// Render multiple layers to the surface
     /// Layers are composited in order (first layer = back, last layer = front)
-    /// Each layer can have independent transforms (position, scale, opacity)
+    /// Each layer can have independent transforms (position, scale, opacity,
+    /// rotation, anchor, crop and flips)
     pub fn render_layers(&mut self, layers: &[Layer]) -> Result<(), RenderError> {
         // Validate layers
         for (i, layer) in layers.iter().enumerate() {
@@ -101,6 +208,11 @@ impl Renderer {
                     format!("Layer {} has invalid opacity (must be 0.0-1.0)", i)
                 ));
             }
+            if layer.transform.crop != layer.transform.crop.clamped() {
+                return Err(RenderError::InvalidLayer(
+                    format!("Layer {} has invalid crop (edges must be 0.0-1.0 and not overlap)", i)
+                ));
+            }
         }
 
         self.compositor.render_layers(layers)
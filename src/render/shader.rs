@@ -3,39 +3,50 @@
 use wgpu::*;
 
 /// Transform uniform buffer structure (must match TransformUniform in compositor.rs)
-/// Layout: position[2], scale[2], opacity, padding, output_size[2], frame_size[2], padding2[2]
+/// Layout (WGSL uniform alignment, 80 bytes): position[2], scale[2], anchor[2],
+/// output_size[2], frame_size[2], opacity, rotation, crop[4], flip[2], padding[2]
 #[allow(dead_code)]
 #[repr(C)]
 struct TransformUniform {
     position: [f32; 2],
     scale: [f32; 2],
-    opacity: f32,
-    _padding: f32,
+    anchor: [f32; 2],
     output_size: [f32; 2],
     frame_size: [f32; 2],
-    _padding2: [f32; 2],
+    opacity: f32,
+    rotation: f32,
+    crop: [f32; 4],
+    flip: [f32; 2],
+    _padding: [f32; 2],
 }
 
-/// Vertex shader for rendering video frames with position and scale transforms
+/// Vertex shader for rendering video frames with position, scale, rotation and crop
 /// 
 /// Shader Interface:
-/// - Input: vertex_index (builtin) - generates fullscreen quad
-/// - Uniform: Transform buffer (binding 2) - contains position, scale, output_size, frame_size
+/// - Input: vertex_index (builtin) - 6 vertices, two triangles of the layer quad
+/// - Uniform: Transform buffer (binding 2) - contains the layer transform, output_size, frame_size
 /// - Output: clip_position (NDC coordinates), tex_coords (texture coordinates)
 /// 
-/// Transform logic:
-/// - Position: Normalized (0.0-1.0) center position, converted to NDC
-/// - Scale: Applied to frame dimensions before positioning
-/// - Texture coordinates: Calculated from clip position and frame/output aspect ratios
+/// Transform logic (all in output pixels, y down):
+/// - Crop: the quad only covers the frame between the crop edges (left, top, right, bottom)
+/// - Anchor: normalized point in the frame that scale, flip and rotation pivot on
+/// - Flip: -1.0 mirrors an axis about the anchor (1.0 leaves it)
+/// - Scale: Applied to frame dimensions (1.0 = native pixel size)
+/// - Rotation: Radians clockwise about the anchor
+/// - Position: Normalized (0.0-1.0) output position of the anchor, converted to NDC
+/// - Texture coordinates: the quad corner's place in the frame, so cropped parts are never sampled
 pub const VERTEX_SHADER: &str = r#"
     struct TransformUniform {
         position: vec2<f32>,
         scale: vec2<f32>,
-        opacity: f32,
-        _padding: f32,
+        anchor: vec2<f32>,
         output_size: vec2<f32>,
         frame_size: vec2<f32>,
-        _padding2: vec2<f32>,
+        opacity: f32,
+        rotation: f32,
+        crop: vec4<f32>,
+        flip: vec2<f32>,
+        _padding: vec2<f32>,
     };
 
     @group(0) @binding(2) var<uniform> transform: TransformUniform;
@@ -48,37 +59,41 @@ pub const VERTEX_SHADER: &str = r#"
     @vertex
     fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
         var out: VertexOutput;
-        
-        // Generate fullscreen quad vertices (-1 to 1 in NDC)
-        var x = f32((in_vertex_index << 1u) & 2u) * 2.0 - 1.0;
-        var y = f32(in_vertex_index & 2u) * 2.0 - 1.0;
-        
-        // Calculate aspect ratios
-        let output_aspect = transform.output_size.x / transform.output_size.y;
-        let frame_aspect = transform.frame_size.x / transform.frame_size.y;
-        
-        // Calculate scaled frame size in normalized coordinates
-        let scaled_width = transform.scale.x * (transform.frame_size.x / transform.output_size.x);
-        let scaled_height = transform.scale.y * (transform.frame_size.y / transform.output_size.y);
-        
-        // Apply scale to quad
-        x *= scaled_width;
-        y *= scaled_height;
-        
-        // Apply position offset (convert from 0.0-1.0 to -1.0 to 1.0)
-        x += (transform.position.x - 0.5) * 2.0;
-        y -= (transform.position.y - 0.5) * 2.0;  // Flip Y for screen coordinates
-        
-        out.clip_position = vec4<f32>(x, y, 0.0, 1.0);
-        
-        // Calculate texture coordinates
-        // Map from clip space (-1 to 1) to texture space (0 to 1)
-        // Account for frame aspect ratio
-        let tex_x = (x + 1.0) * 0.5;
-        let tex_y = 1.0 - (y + 1.0) * 0.5;  // Flip Y for texture coordinates
-        
-        out.tex_coords = vec2<f32>(tex_x, tex_y);
-        
+
+        // Quad corners as fractions of the frame (two triangles)
+        var corners = array<vec2<f32>, 6>(
+            vec2<f32>(0.0, 0.0),
+            vec2<f32>(1.0, 0.0),
+            vec2<f32>(0.0, 1.0),
+            vec2<f32>(0.0, 1.0),
+            vec2<f32>(1.0, 0.0),
+            vec2<f32>(1.0, 1.0),
+        );
+        let corner = corners[in_vertex_index];
+
+        // Move the corner in to the crop edges
+        let crop_min = transform.crop.xy;
+        let crop_max = vec2<f32>(1.0, 1.0) - transform.crop.zw;
+        let uv = mix(crop_min, crop_max, corner);
+
+        // Pixel offset from the anchor, flipped and scaled
+        var p = (uv - transform.anchor) * transform.frame_size;
+        p *= transform.scale * transform.flip;
+
+        // Rotate clockwise on screen (y points down)
+        let c = cos(transform.rotation);
+        let s = sin(transform.rotation);
+        p = vec2<f32>(p.x * c - p.y * s, p.x * s + p.y * c);
+
+        // Place the anchor and convert output pixels to NDC
+        let screen = transform.position * transform.output_size + p;
+        let ndc = vec2<f32>(
+            screen.x / transform.output_size.x * 2.0 - 1.0,
+            1.0 - screen.y / transform.output_size.y * 2.0,
+        );
+
+        out.clip_position = vec4<f32>(ndc, 0.0, 1.0);
+        out.tex_coords = uv;
         return out;
     }
 "#;
@@ -98,11 +113,14 @@ pub const FRAGMENT_SHADER: &str = r#"
     struct TransformUniform {
         position: vec2<f32>,
         scale: vec2<f32>,
-        opacity: f32,
-        _padding: f32,
+        anchor: vec2<f32>,
         output_size: vec2<f32>,
         frame_size: vec2<f32>,
-        _padding2: vec2<f32>,
+        opacity: f32,
+        rotation: f32,
+        crop: vec4<f32>,
+        flip: vec2<f32>,
+        _padding: vec2<f32>,
     };
 
     @group(0) @binding(0) var t_texture: texture_2d<f32>;
@@ -0,0 +1,380 @@
+//! Caption tracks: timed text cues for subtitles and closed captions.
+//!
+//! A track of type `TrackType::Caption` holds cues instead of clips. Cues may
+//! overlap (two speakers at once) and are kept sorted by start. They sit at
+//! timeline time, so they move with the edits that ripple their own track:
+//! `Timeline::extract` closes the hole on caption tracks too, and lifting a
+//! range trims the cues it cuts into. Ripple trims and insert edits on other
+//! tracks leave captions where they are, as they do audio.
+//!
+//! SRT and WebVTT import/export live in `interchange::captions`; captions are
+//! burnt into the picture or written as a subtitle stream by `export::captions`.
+
+use crate::timeline::timeline::Timeline;
+use crate::timeline::track::{Track, TrackError, TrackId, TrackType};
+use crate::core::time::{Time, TimeRange};
+use serde::{Deserialize, Serialize};
+
+/// Unique identifier for a cue
+pub type CueId = u64;
+
+/// How the lines of a cue line up with each other and with its anchor.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CueAlign {
+    /// Left-aligned, anchored at the left edge of the text
+    Start,
+    /// Centred on the anchor
+    #[default]
+    Center,
+    /// Right-aligned, anchored at the right edge of the text
+    End,
+}
+
+/// Where a cue is drawn, as fractions of the frame (0.0-1.0, top-left origin).
+///
+/// A coordinate left at `None` uses the default placement: the bottom of the
+/// frame, and horizontally the left edge, centre or right edge by alignment,
+/// inside a title-safe margin.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct CuePosition {
+    pub x: Option<f32>, // Horizontal anchor of the text (see `CueAlign`)
+    pub y: Option<f32>, // Top of the first line
+}
+
+impl CuePosition {
+    /// Whether both coordinates use the default placement.
+    pub fn is_default(&self) -> bool {
+        self.x.is_none() && self.y.is_none()
+    }
+}
+
+/// Look of a cue's text. Applies to the whole cue.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CueStyle {
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub color: Option<[u8; 3]>, // RGB text colour; `None` for white
+    pub align: CueAlign,
+}
+
+/// A piece of caption text shown over a timeline range.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cue {
+    pub id: CueId,
+    pub start: Time,            // Timeline time of the first frame showing the cue
+    pub end: Time,              // Exclusive
+    pub text: String,           // Plain text; lines separated by '\n'
+    pub position: CuePosition,
+    pub style: CueStyle,
+}
+
+impl Cue {
+    /// Create a cue with the default position and style.
+    pub fn new(id: CueId, start: Time, end: Time, text: impl Into<String>) -> Self {
+        Self {
+            id,
+            start,
+            end,
+            text: text.into(),
+            position: CuePosition::default(),
+            style: CueStyle::default(),
+        }
+    }
+
+    /// Set the cue position (builder style).
+    pub fn with_position(mut self, position: CuePosition) -> Self {
+        self.position = position;
+        self
+    }
+
+    /// Set the cue style (builder style).
+    pub fn with_style(mut self, style: CueStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Timeline range the cue is shown over.
+    pub fn range(&self) -> TimeRange {
+        TimeRange::new(self.start, self.end)
+    }
+
+    /// Whether the cue is shown at `time`.
+    pub fn contains(&self, time: Time) -> bool {
+        self.start <= time && time < self.end
+    }
+}
+
+impl Track {
+    /// Return `Err(TrackError::TrackTypeMismatch)` unless this is a caption track.
+    fn check_caption_track(&self) -> Result<(), TrackError> {
+        if self.track_type != TrackType::Caption {
+            return Err(TrackError::TrackTypeMismatch { track_id: self.id });
+        }
+        Ok(())
+    }
+
+    /// Add a cue, keeping cues sorted by start. Cues may overlap.
+    ///
+    /// Fails on a locked or non-caption track, and with `TrackError::EmptyCue`
+    /// if the cue does not end after it starts.
+    pub fn add_cue(&mut self, cue: Cue) -> Result<(), TrackError> {
+        self.check_unlocked()?;
+        self.check_caption_track()?;
+        if cue.end <= cue.start {
+            return Err(TrackError::EmptyCue { cue_id: cue.id });
+        }
+        let index = self.cues.partition_point(|c| c.start <= cue.start);
+        self.cues.insert(index, cue);
+        Ok(())
+    }
+
+    /// Remove a cue by ID.
+    pub fn remove_cue(&mut self, cue_id: CueId) -> Result<Cue, TrackError> {
+        self.check_unlocked()?;
+        let index = self.cues
+            .iter()
+            .position(|c| c.id == cue_id)
+            .ok_or(TrackError::CueNotFound { cue_id })?;
+        Ok(self.cues.remove(index))
+    }
+
+    /// Replace the cue with the same ID (e.g. after editing its text or
+    /// timing), keeping cues sorted. Returns the old cue.
+    pub fn replace_cue(&mut self, cue: Cue) -> Result<Cue, TrackError> {
+        if cue.end <= cue.start {
+            return Err(TrackError::EmptyCue { cue_id: cue.id });
+        }
+        let old = self.remove_cue(cue.id)?;
+        self.add_cue(cue)?;
+        Ok(old)
+    }
+
+    /// Look up a cue by ID.
+    pub fn cue(&self, cue_id: CueId) -> Option<&Cue> {
+        self.cues.iter().find(|c| c.id == cue_id)
+    }
+
+    /// Cues shown at `time`, in start order.
+    pub fn cues_at(&self, time: Time) -> Vec<&Cue> {
+        let candidates = self.cues.partition_point(|c| c.start <= time);
+        self.cues[..candidates]
+            .iter()
+            .filter(|c| c.contains(time))
+            .collect()
+    }
+
+    /// Remove the cue text in `[start, end)`: cues inside the range are
+    /// removed and cues straddling an edge are trimmed to it. A cue spanning
+    /// the whole range is kept whole, so a line of dialogue is not cut in two.
+    pub(crate) fn clear_cue_range(&mut self, start: Time, end: Time) {
+        self.cues.retain_mut(|cue| {
+            if cue.end <= start || cue.start >= end || (cue.start < start && cue.end > end) {
+                return true;
+            }
+            if cue.start < start {
+                cue.end = start;
+            } else if cue.end > end {
+                cue.start = end;
+            } else {
+                return false;
+            }
+            true
+        });
+        self.cues.sort_by_key(|c| c.start);
+    }
+
+    /// Move every cue edge at or after `position` by `delta`.
+    ///
+    /// A cue spanning `position` keeps its start and grows or shrinks; the
+    /// caller must ensure, for negative `delta`, that no cue edge lies in the
+    /// space being closed.
+    pub(crate) fn ripple_cues_from(&mut self, position: Time, delta: Time) {
+        for cue in &mut self.cues {
+            if cue.start >= position {
+                cue.start += delta;
+            }
+            if cue.end >= position {
+                cue.end += delta;
+            }
+        }
+    }
+}
+
+impl Timeline {
+    /// All caption tracks, in track order.
+    pub fn caption_tracks(&self) -> impl DoubleEndedIterator<Item = &Track> {
+        self.tracks_of_type(TrackType::Caption)
+    }
+
+    /// Allocate a cue ID not used by any cue added to this timeline so far.
+    pub fn allocate_cue_id(&mut self) -> CueId {
+        self.ids.cue.allocate()
+    }
+
+    /// Mark a cue ID as used.
+    pub(crate) fn reserve_cue_id(&mut self, id: CueId) {
+        self.ids.cue.reserve(id);
+    }
+
+    /// Add a cue to a caption track. Updates the timeline duration.
+    pub fn add_cue(&mut self, track_id: TrackId, cue: Cue) -> Result<(), TrackError> {
+        let id = cue.id;
+        self.track_mut(track_id)
+            .ok_or(TrackError::TrackNotFound { track_id })?
+            .add_cue(cue)?;
+        self.reserve_cue_id(id);
+        self.update_duration();
+        Ok(())
+    }
+
+    /// Add imported cues to a caption track under fresh IDs.
+    ///
+    /// Either every cue is added or, on error, none. Returns the new IDs in
+    /// the order of `cues`.
+    pub fn add_cues(&mut self, track_id: TrackId, cues: Vec<Cue>) -> Result<Vec<CueId>, TrackError> {
+        let first_id = self.ids.cue.peek();
+        let mut track = self.track(track_id)
+            .ok_or(TrackError::TrackNotFound { track_id })?
+            .clone();
+        let mut ids = Vec::with_capacity(cues.len());
+        for (id, mut cue) in (first_id..).zip(cues) {
+            cue.id = id;
+            track.add_cue(cue)?;
+            ids.push(id);
+        }
+        *self.track_mut(track_id).expect("track exists") = track;
+        if let Some(&last) = ids.last() {
+            self.reserve_cue_id(last);
+        }
+        self.update_duration();
+        Ok(ids)
+    }
+
+    /// Remove a cue from whichever track holds it.
+    ///
+    /// Returns the track it was on and the cue.
+    pub fn remove_cue(&mut self, cue_id: CueId) -> Result<(TrackId, Cue), TrackError> {
+        let track = self.tracks
+            .iter_mut()
+            .find(|t| t.cue(cue_id).is_some())
+            .ok_or(TrackError::CueNotFound { cue_id })?;
+        let cue = track.remove_cue(cue_id)?;
+        let track_id = track.id;
+        self.update_duration();
+        Ok((track_id, cue))
+    }
+
+    /// Replace the cue with the same ID on whichever track holds it (see
+    /// `Track::replace_cue`). Returns the old cue.
+    pub fn replace_cue(&mut self, cue: Cue) -> Result<Cue, TrackError> {
+        let cue_id = cue.id;
+        let old = self.tracks
+            .iter_mut()
+            .find(|t| t.cue(cue_id).is_some())
+            .ok_or(TrackError::CueNotFound { cue_id })?
+            .replace_cue(cue)?;
+        self.update_duration();
+        Ok(old)
+    }
+
+    /// Find a cue on any track.
+    pub fn find_cue(&self, cue_id: CueId) -> Option<(&Track, &Cue)> {
+        self.tracks.iter().find_map(|track| track.cue(cue_id).map(|cue| (track, cue)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::time;
+    use crate::timeline::clip::Clip;
+    use std::path::PathBuf;
+
+    /// Timeline with V1, A1 and C1; C1 has cues at [1s, 3s), [2s, 4s) and [6s, 8s).
+    fn captioned() -> (Timeline, TrackId) {
+        let mut timeline = Timeline::new();
+        let c1 = timeline.add_track(TrackType::Caption);
+        timeline.add_cue(c1, Cue::new(3, time::from_seconds(6.0), time::from_seconds(8.0), "Three")).unwrap();
+        timeline.add_cue(c1, Cue::new(1, time::from_seconds(1.0), time::from_seconds(3.0), "One")).unwrap();
+        timeline.add_cue(c1, Cue::new(2, time::from_seconds(2.0), time::from_seconds(4.0), "Two")).unwrap();
+        (timeline, c1)
+    }
+
+    fn spans(timeline: &Timeline, track_id: TrackId) -> Vec<(CueId, Time, Time)> {
+        timeline.track(track_id).unwrap().cues.iter().map(|c| (c.id, c.start, c.end)).collect()
+    }
+
+    #[test]
+    fn test_add_and_query_cues() {
+        let (mut timeline, c1) = captioned();
+        assert_eq!(timeline.duration, time::from_seconds(8.0));
+        assert_eq!(timeline.track_name(c1).as_deref(), Some("C1"));
+
+        let track = timeline.track(c1).unwrap();
+        assert_eq!(track.cues.iter().map(|c| c.id).collect::<Vec<_>>(), vec![1, 2, 3]);
+        let ids = |at| track.cues_at(time::from_seconds(at)).iter().map(|c| c.id).collect::<Vec<_>>();
+        assert_eq!(ids(2.5), vec![1, 2]);
+        assert_eq!(ids(3.0), vec![2]);
+        assert!(ids(5.0).is_empty());
+        assert_eq!(timeline.allocate_cue_id(), 4);
+
+        // Cues only go on caption tracks, and clips never do
+        let v1 = timeline.tracks[0].id;
+        assert_eq!(timeline.add_cue(v1, Cue::new(5, 0, time::from_seconds(1.0), "x")), Err(TrackError::TrackTypeMismatch { track_id: v1 }));
+        let clip = Clip::new(1, PathBuf::from("a.mp4"), 0, time::from_seconds(1.0), 0, 0);
+        assert_eq!(timeline.add_clip(c1, clip), Err(TrackError::TrackTypeMismatch { track_id: c1 }));
+        assert_eq!(timeline.add_cue(c1, Cue::new(5, time::from_seconds(1.0), time::from_seconds(1.0), "x")), Err(TrackError::EmptyCue { cue_id: 5 }));
+
+        assert_eq!(timeline.remove_cue(3).map(|(track, cue)| (track, cue.id)), Ok((c1, 3)));
+        assert_eq!(timeline.duration, time::from_seconds(4.0));
+        assert_eq!(timeline.remove_cue(3), Err(TrackError::CueNotFound { cue_id: 3 }));
+    }
+
+    #[test]
+    fn test_add_imported_cues() {
+        let (mut timeline, c1) = captioned();
+        let imported = vec![Cue::new(1, time::from_seconds(10.0), time::from_seconds(11.0), "Four"), Cue::new(2, time::from_seconds(9.0), time::from_seconds(10.0), "Five")];
+        assert_eq!(timeline.add_cues(c1, imported), Ok(vec![4, 5]));
+        assert_eq!(timeline.find_cue(5).unwrap().1.text, "Five");
+        assert_eq!(timeline.track(c1).unwrap().cues.last().unwrap().id, 4);
+
+        // One bad cue adds nothing
+        let imported = vec![Cue::new(1, time::from_seconds(12.0), time::from_seconds(13.0), "Six"), Cue::new(2, time::from_seconds(14.0), time::from_seconds(14.0), "")];
+        assert_eq!(timeline.add_cues(c1, imported), Err(TrackError::EmptyCue { cue_id: 7 }));
+        assert_eq!(timeline.track(c1).unwrap().cues.len(), 5);
+    }
+
+    #[test]
+    fn test_cues_follow_ripple_edits() {
+        let (mut timeline, c1) = captioned();
+
+        // Extract [3s, 5s): cue 2 is trimmed to 3s, cue 3 ripples left
+        timeline.extract(time::from_seconds(3.0), time::from_seconds(5.0));
+        assert_eq!(spans(&timeline, c1), vec![(1, time::from_seconds(1.0), time::from_seconds(3.0)), (2, time::from_seconds(2.0), time::from_seconds(3.0)), (3, time::from_seconds(4.0), time::from_seconds(6.0))]);
+
+        // Lift [1.5s, 2.5s): cue 1 spans it and stays whole, cue 2 loses its head
+        timeline.lift(time::from_seconds(1.5), time::from_seconds(2.5));
+        assert_eq!(spans(&timeline, c1), vec![(1, time::from_seconds(1.0), time::from_seconds(3.0)), (2, time::from_seconds(2.5), time::from_seconds(3.0)), (3, time::from_seconds(4.0), time::from_seconds(6.0))]);
+
+        // Extracting inside a cue shortens it
+        timeline.extract(time::from_seconds(4.5), time::from_seconds(5.0));
+        assert_eq!(spans(&timeline, c1)[2], (3, time::from_seconds(4.0), time::from_seconds(5.5)));
+        assert_eq!(timeline.duration, time::from_seconds(5.5));
+
+        // Locked caption tracks do not move
+        timeline.track_mut(c1).unwrap().set_locked(true);
+        timeline.extract(0, time::from_seconds(1.0));
+        assert_eq!(spans(&timeline, c1)[0], (1, time::from_seconds(1.0), time::from_seconds(3.0)));
+    }
+
+    #[test]
+    fn test_replace_cue() {
+        let (mut timeline, c1) = captioned();
+        let track = timeline.track_mut(c1).unwrap();
+        let edited = Cue::new(1, time::from_seconds(5.0), time::from_seconds(7.0), "One, later").with_style(CueStyle { italic: true, ..CueStyle::default() });
+        assert_eq!(track.replace_cue(edited).unwrap().text, "One");
+        assert_eq!(track.cues.iter().map(|c| c.id).collect::<Vec<_>>(), vec![2, 1, 3]);
+        assert!(track.cue(1).unwrap().style.italic);
+    }
+}
@@ -1,27 +1,72 @@
 //! Clip data structure representing a segment of video/audio on the timeline.
 //! Per SPEC.md: Timeline time ≠ source time. Clips have in/out points (source time).
 
-use std::path::PathBuf;
-use crate::core::time::Time;
+use std::path::{Path, PathBuf};
+use crate::core::time::{FrameRate, Time};
+use crate::timeline::marker::Marker;
+use crate::timeline::speed::Speed;
+use crate::timeline::remap::TimeRemap;
+use crate::timeline::keyframe::ClipAnimation;
+use crate::timeline::visual::VisualProperties;
+use crate::timeline::sequence::SequenceId;
+use serde::{Deserialize, Serialize};
 
 /// Unique identifier for a clip
 pub type ClipId = u64;
 
+/// Identifier shared by clips that are linked together (e.g. interview video and audio)
+pub type LinkGroupId = u64;
+
+/// What a clip plays: a media file, or a nested sequence (a compound clip).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ClipSource {
+    /// Stream `stream_index` of a media file
+    Media(PathBuf),
+    /// The timeline of a sequence in the root timeline's `sequences`
+    Sequence(SequenceId),
+}
+
+impl From<PathBuf> for ClipSource {
+    fn from(path: PathBuf) -> Self {
+        ClipSource::Media(path)
+    }
+}
+
 /// A clip represents a segment of source media placed on the timeline.
 /// 
 /// Key concepts:
 /// - **Source time** (in_point, out_point): Time within the source media file
 /// - **Timeline time** (timeline_start, timeline_end): Position on the timeline
 /// - These are independent - a clip can start at source time 5s but be placed at timeline time 0s
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// - For a compound clip (`ClipSource::Sequence`), source time is time on the
+///   nested sequence's timeline
+/// - `speed` relates the two lengths: at 50% the source range plays for twice
+///   as long on the timeline; negative speeds play it backwards, and a freeze
+///   frame holds the frame at `in_point` for the whole timeline range
+/// - `time_remap`, when set, replaces `speed` with a keyframed curve (speed
+///   ramps); `in_point`/`out_point` are then the lowest and highest source
+///   times the curve reaches
+/// - `animation` keyframes properties such as opacity and volume, at offsets
+///   from `timeline_start`; keyframes stay in place on the timeline when the
+///   clip's start is trimmed or split
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Clip {
     pub id: ClipId,
-    pub source_path: PathBuf,
+    pub source: ClipSource,
     pub in_point: Time,        // Start time in source media (nanoseconds)
     pub out_point: Time,       // End time in source media (nanoseconds)
     pub timeline_start: Time,  // Position on timeline (nanoseconds)
     pub timeline_end: Time,    // End position on timeline (nanoseconds)
     pub stream_index: usize,   // Which stream in source file (0 = first video, 1 = first audio, etc.)
+    pub source_duration: Option<Time>, // Length of the source media, if known (bounds trims)
+    pub link_group: Option<LinkGroupId>, // Clips in the same group move, trim, split and delete together
+    pub sync_origin: Time,     // `in_point - timeline_start` when linked; drift from it is a sync offset
+    pub markers: Vec<Marker>,  // Clip markers, positioned in source time so they follow the clip
+    pub speed: Speed,          // Playback speed (negative = reverse, zero = freeze frame)
+    pub preserve_pitch: bool,  // Time-stretch audio at non-normal speeds instead of resampling
+    pub time_remap: Option<TimeRemap>, // Keyframed source time, offset from timeline_start (overrides speed)
+    pub visual: VisualProperties, // Position, scale, rotation, crop etc. of a video clip's frame
+    pub animation: ClipAnimation, // Keyframed properties, offset from timeline_start
 }
 
 impl Clip {
@@ -29,7 +74,7 @@ impl Clip {
     /// 
     /// # Arguments
     /// - `id`: Unique identifier for the clip
-    /// - `source_path`: Path to the source media file
+    /// - `source`: Source media file (a `PathBuf`) or nested sequence
     /// - `in_point`: Start time in source media (nanoseconds)
     /// - `out_point`: End time in source media (nanoseconds)
     /// - `timeline_start`: Position on timeline where clip starts (nanoseconds)
@@ -39,7 +84,7 @@ impl Clip {
     /// Panics if `out_point <= in_point` (invalid duration).
     pub fn new(
         id: ClipId,
-        source_path: PathBuf,
+        source: impl Into<ClipSource>,
         in_point: Time,
         out_point: Time,
         timeline_start: Time,
@@ -52,21 +97,183 @@ impl Clip {
 
         Self {
             id,
-            source_path,
+            source: source.into(),
             in_point,
             out_point,
             timeline_start,
             timeline_end,
             stream_index,
+            source_duration: None,
+            link_group: None,
+            sync_origin: 0,
+            markers: Vec::new(),
+            speed: Speed::NORMAL,
+            preserve_pitch: false,
+            time_remap: None,
+            visual: VisualProperties::default(),
+            animation: ClipAnimation::default(),
+        }
+    }
+
+    /// Create a freeze-frame clip holding the frame at `source_time` for `length`.
+    /// 
+    /// # Panics
+    /// Panics if `length <= 0`.
+    pub fn freeze_frame(
+        id: ClipId,
+        source: impl Into<ClipSource>,
+        source_time: Time,
+        timeline_start: Time,
+        length: Time,
+        stream_index: usize,
+    ) -> Self {
+        assert!(length > 0, "Freeze frame length must be > 0");
+        let mut clip = Self::new(id, source, source_time, source_time + 1, timeline_start, stream_index);
+        clip.speed = Speed::FREEZE;
+        clip.timeline_end = timeline_start + length;
+        clip
+    }
+
+    /// Set the playback speed (builder style). See `set_speed`.
+    pub fn with_speed(mut self, speed: Speed) -> Self {
+        self.set_speed(speed);
+        self
+    }
+
+    /// Change the playback speed, keeping `timeline_start` and the source range.
+    /// 
+    /// The timeline length becomes the source range's length at the new speed;
+    /// switching to a freeze frame keeps the current timeline length. Any time
+    /// remap is removed.
+    pub fn set_speed(&mut self, speed: Speed) {
+        self.speed = speed;
+        self.time_remap = None;
+        self.timeline_end = self.timeline_start + self.retimed_duration();
+    }
+
+    /// Path of the media file this clip plays, or `None` for a compound clip.
+    pub fn source_path(&self) -> Option<&Path> {
+        match &self.source {
+            ClipSource::Media(path) => Some(path),
+            ClipSource::Sequence(_) => None,
         }
     }
 
-    /// Get the duration of the clip in nanoseconds.
-    /// Duration is the same in both source and timeline space.
+    /// Sequence this compound clip plays, or `None` for a media clip.
+    pub fn sequence_id(&self) -> Option<SequenceId> {
+        match self.source {
+            ClipSource::Sequence(id) => Some(id),
+            ClipSource::Media(_) => None,
+        }
+    }
+
+    /// Set a time-remap curve (builder style). See `set_time_remap`.
+    pub fn with_time_remap(mut self, remap: TimeRemap) -> Self {
+        self.set_time_remap(Some(remap));
+        self
+    }
+
+    /// Replace the clip's speed with a time-remap curve, or remove it (`None`).
+    /// 
+    /// The timeline range is kept; the source range becomes the span of source
+    /// times the curve shows in it. Removing the curve leaves that source range
+    /// at normal speed.
+    pub fn set_time_remap(&mut self, remap: Option<TimeRemap>) {
+        match remap {
+            Some(remap) => {
+                (self.in_point, self.out_point) = remap_source_range(&remap, self.duration());
+                self.speed = Speed::NORMAL;
+                self.time_remap = Some(remap);
+            }
+            None => {
+                self.time_remap = None;
+                self.timeline_end = self.timeline_start + self.retimed_duration();
+            }
+        }
+    }
+
+    /// Set the known length of the source media (builder style).
+    /// 
+    /// Trim operations will not extend the clip past `[0, source_duration]`.
+    pub fn with_source_duration(mut self, source_duration: Time) -> Self {
+        self.source_duration = Some(source_duration);
+        self
+    }
+
+    /// Get the duration of the clip on the timeline in nanoseconds.
+    /// At normal speed this is the same as `source_span`.
     pub fn duration(&self) -> Time {
+        self.timeline_end - self.timeline_start
+    }
+
+    /// Length of the source range `[in_point, out_point]` in nanoseconds.
+    pub fn source_span(&self) -> Time {
         self.out_point - self.in_point
     }
 
+    /// Timeline length that the source range plays for at the clip's speed.
+    /// 
+    /// Use after changing `in_point`/`out_point` to recompute the timeline range.
+    /// Freeze frames and time-remapped clips keep their current length.
+    pub fn retimed_duration(&self) -> Time {
+        if self.speed.is_freeze() || self.time_remap.is_some() {
+            self.duration()
+        } else {
+            self.speed.to_timeline(self.source_span())
+        }
+    }
+
+    /// Source range the clip would play if its timeline range became
+    /// `[new_start, new_end]`, with every remaining frame kept in place.
+    /// 
+    /// Used by edits that move a clip edge in timeline time (roll, slide):
+    /// the source edge that plays at that timeline edge moves with it.
+    /// Returns `(in_point, out_point)`; the result may be out of source bounds.
+    ///
+    /// The source edge at a timeline edge that does not move stays put, and
+    /// the other is placed the new length (at the clip's speed) away from it,
+    /// so the source span is exactly `speed.to_source` of the timeline length.
+    pub fn source_range_for(&self, new_start: Time, new_end: Time) -> (Time, Time) {
+        if let Some(remap) = &self.time_remap {
+            let trimmed = self.trimmed_remap(remap, new_start, new_end);
+            return remap_source_range(&trimmed, new_end - new_start);
+        }
+        if self.speed.is_freeze() {
+            return (self.in_point, self.out_point);
+        }
+        let span = self.speed.to_source(new_end - new_start).abs();
+        let start_shift = self.speed.to_source(new_start - self.timeline_start);
+        match (self.speed.is_reverse(), new_end == self.timeline_end) {
+            // The end stays: its source edge is the anchor
+            (false, true) => (self.out_point - span, self.out_point),
+            (true, true) => (self.in_point, self.in_point + span),
+            // Otherwise the start's source edge moves with the start
+            (false, false) => (self.in_point + start_shift, self.in_point + start_shift + span),
+            (true, false) => (self.out_point + start_shift - span, self.out_point + start_shift),
+        }
+    }
+
+    /// Move the clip's timeline edges to `[new_start, new_end]`, adjusting the
+    /// source range to match (see `source_range_for`). Keyframes stay in place
+    /// on the timeline.
+    pub fn set_timeline_range(&mut self, new_start: Time, new_end: Time) {
+        let (in_point, out_point) = self.source_range_for(new_start, new_end);
+        if let Some(remap) = &self.time_remap {
+            self.time_remap = Some(self.trimmed_remap(remap, new_start, new_end));
+        }
+        self.animation.shift(self.timeline_start - new_start);
+        self.in_point = in_point;
+        self.out_point = out_point;
+        self.timeline_start = new_start;
+        self.timeline_end = new_end;
+    }
+
+    /// The remap curve for timeline range `[new_start, new_end]`: kept in place
+    /// on the timeline, with offsets rebased to `new_start`.
+    fn trimmed_remap(&self, remap: &TimeRemap, new_start: Time, new_end: Time) -> TimeRemap {
+        remap.trimmed(new_start - self.timeline_start, new_end - self.timeline_start)
+    }
+
     /// Check if a timeline position is within this clip's timeline range.
     pub fn contains(&self, timeline_position: Time) -> bool {
         timeline_position >= self.timeline_start && timeline_position <= self.timeline_end
@@ -79,20 +286,28 @@ impl Clip {
     /// # Example
     /// If clip has source range [5s, 10s] and timeline range [0s, 5s]:
     /// - timeline position 2s → source position 7s
+    /// 
+    /// At other speeds the offset into the clip is scaled; a reversed clip maps
+    /// its timeline start to `out_point` and runs back to `in_point`, and a freeze
+    /// frame maps every position to `in_point`. A time-remapped clip follows
+    /// its curve.
     pub fn timeline_to_source(&self, timeline_position: Time) -> Option<Time> {
         if !self.contains(timeline_position) {
             return None;
         }
-
-        let offset = timeline_position - self.timeline_start;
-        let source_time = self.in_point + offset;
-        
-        // Validate the source time is within clip bounds
-        if source_time < self.in_point || source_time > self.out_point {
-            return None;
+        if let Some(remap) = &self.time_remap {
+            return Some(remap.source_at(timeline_position - self.timeline_start));
         }
+
+        let offset = self.speed.to_source(timeline_position - self.timeline_start);
+        let source_time = if self.speed.is_reverse() {
+            self.out_point + offset
+        } else {
+            self.in_point + offset
+        };
         
-        Some(source_time)
+        // Clamp rounding at the far edge to the source range
+        Some(source_time.clamp(self.in_point, self.out_point))
     }
 
     /// Convert a source position to the corresponding timeline position.
@@ -102,14 +317,30 @@ impl Clip {
     /// # Example
     /// If clip has source range [5s, 10s] and timeline range [0s, 5s]:
     /// - source position 7s → timeline position 2s
+    /// 
+    /// Speed and direction are taken into account; for a freeze frame only
+    /// `in_point` maps (to `timeline_start`). A time-remapped clip may show a
+    /// source position more than once: the first time is returned.
     pub fn source_to_timeline(&self, source_position: Time) -> Option<Time> {
         if source_position < self.in_point || source_position > self.out_point {
             return None;
         }
+        if let Some(remap) = &self.time_remap {
+            return remap
+                .offset_of(source_position, self.duration())
+                .map(|offset| self.timeline_start + offset);
+        }
+        if self.speed.is_freeze() {
+            return (source_position == self.in_point).then_some(self.timeline_start);
+        }
 
-        let offset = source_position - self.in_point;
-        let timeline_time = self.timeline_start + offset;
-        Some(timeline_time)
+        let offset = if self.speed.is_reverse() {
+            self.out_point - source_position
+        } else {
+            source_position - self.in_point
+        };
+        let timeline_time = self.timeline_start + self.speed.to_timeline(offset);
+        Some(timeline_time.min(self.timeline_end))
     }
 
     /// Trim the start of the clip (move in_point forward in source).
@@ -118,20 +349,23 @@ impl Clip {
     /// - Moves `in_point` forward in source time
     /// - Moves `timeline_start` forward by the same amount
     /// - Keeps `timeline_end` unchanged (duration decreases)
+    /// - Keeps keyframes in place on the timeline
     /// 
     /// # Arguments
     /// - `new_in_point`: New in_point in source time (must be >= current in_point and < out_point)
     /// 
     /// # Returns
-    /// `true` if successful, `false` if `new_in_point` is invalid.
+    /// `true` if successful, `false` if `new_in_point` is invalid or the clip
+    /// is time-remapped (its source range follows the curve).
     pub fn trim_in(&mut self, new_in_point: Time) -> bool {
-        if new_in_point < self.in_point || new_in_point >= self.out_point {
+        if self.time_remap.is_some() || new_in_point < self.in_point || new_in_point >= self.out_point {
             return false;
         }
 
-        let trim_amount = new_in_point - self.in_point;
         self.in_point = new_in_point;
-        self.timeline_start += trim_amount;
+        let old_start = self.timeline_start;
+        self.timeline_start = self.timeline_end - self.retimed_duration();
+        self.animation.shift(old_start - self.timeline_start);
         // timeline_end stays the same (duration decreases)
         true
     }
@@ -147,15 +381,15 @@ impl Clip {
     /// - `new_out_point`: New out_point in source time (must be > in_point and <= current out_point)
     /// 
     /// # Returns
-    /// `true` if successful, `false` if `new_out_point` is invalid.
+    /// `true` if successful, `false` if `new_out_point` is invalid or the clip
+    /// is time-remapped.
     pub fn trim_out(&mut self, new_out_point: Time) -> bool {
-        if new_out_point <= self.in_point || new_out_point > self.out_point {
+        if self.time_remap.is_some() || new_out_point <= self.in_point || new_out_point > self.out_point {
             return false;
         }
 
         self.out_point = new_out_point;
-        let new_duration = self.duration();
-        self.timeline_end = self.timeline_start + new_duration;
+        self.timeline_end = self.timeline_start + self.retimed_duration();
         true
     }
 
@@ -174,6 +408,97 @@ impl Clip {
         self.set_timeline_start(new_timeline_start);
     }
 
+    /// Split the clip at a timeline position (razor cut).
+    /// 
+    /// This clip is shortened to end at `timeline_position` and the right half is
+    /// returned as a new clip with ID `new_id`, starting at `timeline_position` and
+    /// continuing from the matching source time (the earlier source time, for a
+    /// reversed clip). Clip markers go with the half whose source range contains them.
+    /// 
+    /// # Returns
+    /// The right half, or `None` (clip unchanged) if `timeline_position` is not
+    /// strictly inside the clip.
+    pub fn split_at(&mut self, timeline_position: Time, new_id: ClipId) -> Option<Clip> {
+        if timeline_position <= self.timeline_start || timeline_position >= self.timeline_end {
+            return None;
+        }
+
+        let mut right = self.clone();
+        right.id = new_id;
+        right.set_timeline_range(timeline_position, self.timeline_end);
+        self.set_timeline_range(self.timeline_start, timeline_position);
+
+        // A freeze frame's halves share one source frame: markers stay on the left
+        let (right_in, right_out, freeze) = (right.in_point, right.out_point, right.speed.is_freeze());
+        let on_right = |m: &Marker| !freeze && right_in <= m.position && m.position < right_out;
+        self.markers.retain(|m| !on_right(m));
+        right.markers.retain(on_right);
+        Some(right)
+    }
+
+    /// Source frames to blend for the frame shown at `timeline_position`.
+    /// 
+    /// Only for time-remapped clips with frame blending on, when the curve lands
+    /// between two frames of the source at `source_rate`. Returns the earlier
+    /// and later frame's start times and the weight of the later one (0.0-1.0).
+    pub fn blend_frames_at(&self, timeline_position: Time, source_rate: FrameRate) -> Option<(Time, Time, f32)> {
+        if !self.time_remap.as_ref()?.frame_blend {
+            return None;
+        }
+        let source_time = self.timeline_to_source(timeline_position)?;
+        let frame = source_rate.time_to_frame(source_time);
+        let (earlier, later) = (source_rate.frame_to_time(frame), source_rate.frame_to_time(frame + 1));
+        if source_time == earlier {
+            return None;
+        }
+        let weight = (source_time - earlier) as f64 / (later - earlier) as f64;
+        Some((earlier, later, weight as f32))
+    }
+
+    /// Timeline time the clip could run on before its start and past its end
+    /// into its handles (the source media outside `[in_point, out_point]`).
+    ///
+    /// Returns `(head, tail)`; `None` is unlimited: the source length is not
+    /// known, or the clip holds a frame there (freeze frames and the ends of
+    /// a time-remap curve).
+    pub fn handles(&self) -> (Option<Time>, Option<Time>) {
+        if self.speed.is_freeze() || self.time_remap.is_some() {
+            return (None, None);
+        }
+        let before_in = Some(self.in_point);
+        let after_out = self.source_duration.map(|d| (d - self.out_point).max(0));
+        let (head, tail) = if self.speed.is_reverse() {
+            (after_out, before_in)
+        } else {
+            (before_in, after_out)
+        };
+        let to_timeline = |source: Option<Time>| source.map(|s| self.speed.to_timeline(s));
+        (to_timeline(head), to_timeline(tail))
+    }
+
+    /// Source position at `timeline_position`, running on into the handles
+    /// outside the clip (see `handles`) at the clip's speed.
+    ///
+    /// Used to render transitions, which play the media beyond a cut. The
+    /// result is clamped to the source media.
+    pub fn handle_source_at(&self, timeline_position: Time) -> Time {
+        if let Some(source_time) = self.timeline_to_source(timeline_position) {
+            return source_time;
+        }
+        let source_time = match &self.time_remap {
+            Some(remap) => remap.source_at(timeline_position - self.timeline_start),
+            None => {
+                let offset = self.speed.to_source(timeline_position - self.timeline_start);
+                if self.speed.is_reverse() {
+                    self.out_point + offset
+                } else {
+                    self.in_point + offset
+                }
+            }
+        };
+        source_time.clamp(0, self.source_duration.unwrap_or(Time::MAX))
+    }
+
     /// Check if this clip overlaps with another clip on the timeline.
     /// 
     /// Two clips overlap if their timeline ranges intersect.
@@ -185,6 +510,15 @@ impl Clip {
     }
 }
 
+/// Source range `(in_point, out_point)` a remap curve covers over `[0, length]`.
+///
+/// A curve that holds one source time gets a one-nanosecond range, like a
+/// freeze frame, so `out_point > in_point` still holds.
+fn remap_source_range(remap: &TimeRemap, length: Time) -> (Time, Time) {
+    let (low, high) = remap.source_bounds(length);
+    (low, high.max(low + 1))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -438,6 +772,36 @@ mod tests {
         assert!(!clip4.overlaps_with(&clip1));
     }
 
+    #[test]
+    fn test_split_at() {
+        let mut clip = Clip::new(
+            1,
+            PathBuf::from("test.mp4"),
+            time::from_seconds(2.0),
+            time::from_seconds(8.0),
+            time::from_seconds(10.0),
+            0,
+        );
+
+        let right = clip.split_at(time::from_seconds(12.0), 7).unwrap();
+        assert_eq!(clip.in_point, time::from_seconds(2.0));
+        assert_eq!(clip.out_point, time::from_seconds(4.0));
+        assert_eq!(clip.timeline_end, time::from_seconds(12.0));
+
+        assert_eq!(right.id, 7);
+        assert_eq!(right.in_point, time::from_seconds(4.0));
+        assert_eq!(right.out_point, time::from_seconds(8.0));
+        assert_eq!(right.timeline_start, time::from_seconds(12.0));
+        assert_eq!(right.timeline_end, time::from_seconds(16.0));
+        assert!(!clip.overlaps_with(&right));
+
+        // Boundaries and outside positions do not split
+        assert!(clip.split_at(time::from_seconds(10.0), 8).is_none());
+        assert!(clip.split_at(time::from_seconds(12.0), 8).is_none());
+        assert!(clip.split_at(time::from_seconds(20.0), 8).is_none());
+        assert_eq!(clip.duration(), time::from_seconds(2.0));
+    }
+
     #[test]
     fn test_time_mapping_edge_cases() {
         let clip = Clip::new(
@@ -461,5 +825,147 @@ mod tests {
             Some(time::from_seconds(1.0))
         );
     }
+
+    #[test]
+    fn test_speed_mapping() {
+        // Source [2s, 6s] at 200%: 2s on the timeline from 10s
+        let fast = Clip::new(
+            1,
+            PathBuf::from("test.mp4"),
+            time::from_seconds(2.0),
+            time::from_seconds(6.0),
+            time::from_seconds(10.0),
+            0,
+        )
+        .with_speed(Speed::percent(200));
+        assert_eq!(fast.duration(), time::from_seconds(2.0));
+        assert_eq!(fast.timeline_to_source(time::from_seconds(11.0)), Some(time::from_seconds(4.0)));
+        assert_eq!(fast.source_to_timeline(time::from_seconds(5.0)), Some(time::from_seconds(11.5)));
+
+        // Reversed: timeline start plays the out point
+        let reverse = fast.clone().with_speed(Speed::REVERSE);
+        assert_eq!(reverse.duration(), time::from_seconds(4.0));
+        assert_eq!(reverse.timeline_to_source(time::from_seconds(10.0)), Some(time::from_seconds(6.0)));
+        assert_eq!(reverse.timeline_to_source(time::from_seconds(13.0)), Some(time::from_seconds(3.0)));
+        assert_eq!(reverse.source_to_timeline(time::from_seconds(3.0)), Some(time::from_seconds(13.0)));
+    }
+
+    #[test]
+    fn test_split_reversed_clip() {
+        let mut clip = Clip::new(
+            1,
+            PathBuf::from("test.mp4"),
+            time::from_seconds(2.0),
+            time::from_seconds(6.0),
+            0,
+            0,
+        )
+        .with_speed(Speed::REVERSE);
+        let right = clip.split_at(time::from_seconds(1.0), 2).unwrap();
+        // The left half plays the end of the source range
+        assert_eq!((clip.in_point, clip.out_point), (time::from_seconds(5.0), time::from_seconds(6.0)));
+        assert_eq!((right.in_point, right.out_point), (time::from_seconds(2.0), time::from_seconds(5.0)));
+        assert_eq!(right.timeline_end, time::from_seconds(4.0));
+    }
+
+    #[test]
+    fn test_freeze_frame() {
+        let mut clip = Clip::freeze_frame(
+            1,
+            PathBuf::from("test.mp4"),
+            time::from_seconds(3.0),  // Held source frame
+            time::from_seconds(1.0),  // Timeline start
+            time::from_seconds(5.0),  // Hold length
+            0,
+        );
+        assert_eq!(clip.duration(), time::from_seconds(5.0));
+        assert_eq!(clip.timeline_to_source(time::from_seconds(4.0)), Some(time::from_seconds(3.0)));
+        assert_eq!(clip.source_to_timeline(time::from_seconds(3.0)), Some(time::from_seconds(1.0)));
+
+        let right = clip.split_at(time::from_seconds(2.0), 2).unwrap();
+        assert_eq!(right.duration(), time::from_seconds(4.0));
+        assert_eq!(right.timeline_to_source(time::from_seconds(5.0)), Some(time::from_seconds(3.0)));
+        clip.set_timeline_start(time::from_seconds(10.0));
+        assert_eq!(clip.duration(), time::from_seconds(1.0));
+    }
+
+    #[test]
+    fn test_time_remap() {
+        use crate::timeline::remap::{BezierHandles, RemapKeyframe};
+
+        // Ramp from normal speed into 300% and back down with an ease
+        let remap = TimeRemap::new(vec![
+            RemapKeyframe::linear(0, time::from_seconds(2.0)),
+            RemapKeyframe::linear(time::from_seconds(1.0), time::from_seconds(3.0)),
+            RemapKeyframe::bezier(time::from_seconds(2.0), time::from_seconds(6.0), BezierHandles::EASE_OUT),
+            RemapKeyframe::linear(time::from_seconds(4.0), time::from_seconds(8.0)),
+        ]);
+        let mut clip = Clip::new(
+            1,
+            PathBuf::from("test.mp4"),
+            time::from_seconds(0.0),
+            time::from_seconds(4.0),
+            time::from_seconds(10.0),
+            0,
+        )
+        .with_time_remap(remap);
+        assert_eq!((clip.in_point, clip.out_point), (time::from_seconds(2.0), time::from_seconds(8.0)));
+        assert_eq!(clip.duration(), time::from_seconds(4.0));
+        assert_eq!(clip.timeline_to_source(time::from_seconds(11.5)), Some(time::from_seconds(4.5)));
+        assert_eq!(clip.source_to_timeline(time::from_seconds(4.5)), Some(time::from_seconds(11.5)));
+
+        // Splitting keeps the curve continuous across the cut
+        let before = clip.timeline_to_source(time::from_seconds(12.5)).unwrap();
+        let right = clip.split_at(time::from_seconds(12.2), 2).unwrap();
+        let after = right.timeline_to_source(time::from_seconds(12.5)).unwrap();
+        assert!((before - after).abs() < time::from_millis(1));
+        assert_eq!(clip.out_point, clip.timeline_to_source(time::from_seconds(12.2)).unwrap());
+        assert_eq!(right.out_point, time::from_seconds(8.0));
+    }
+
+    #[test]
+    fn test_blend_frames_at() {
+        use crate::core::time::FrameRate;
+        use crate::timeline::remap::RemapKeyframe;
+
+        // 25% speed over 1s of 24fps source: most frames land between source frames
+        let remap = TimeRemap::new(vec![
+            RemapKeyframe::linear(0, 0),
+            RemapKeyframe::linear(time::from_seconds(4.0), time::from_seconds(1.0)),
+        ])
+        .with_frame_blend(true);
+        let clip = Clip::new(1, PathBuf::from("test.mp4"), 0, time::from_seconds(4.0), 0, 0)
+            .with_time_remap(remap.clone());
+        let rate = FrameRate::FPS_24;
+        let position = rate.frame_to_time(2) * 4 + time::from_seconds(0.5 / 24.0) * 4;
+        let (earlier, later, weight) = clip.blend_frames_at(position, rate).unwrap();
+        assert_eq!((earlier, later), (rate.frame_to_time(2), rate.frame_to_time(3)));
+        assert!((weight - 0.5).abs() < 1e-3);
+
+        // On a source frame, or without blending, a single frame is shown
+        assert_eq!(clip.blend_frames_at(rate.frame_to_time(2) * 4, rate), None);
+        let plain = clip.clone().with_time_remap(remap.with_frame_blend(false));
+        assert_eq!(plain.blend_frames_at(position, rate), None);
+    }
+
+    #[test]
+    fn test_split_keeps_keyframes_in_place() {
+        use crate::timeline::keyframe::{Keyframe, KeyframeTrack};
+
+        let mut clip = Clip::new(1, PathBuf::from("test.mp4"), 0, time::from_seconds(4.0), time::from_seconds(10.0), 0);
+        clip.animation.opacity = KeyframeTrack::new(vec![
+            Keyframe::linear(0, 0.0),
+            Keyframe::linear(time::from_seconds(4.0), 1.0),
+        ]);
+        let right = clip.split_at(time::from_seconds(11.0), 2).unwrap();
+        assert_eq!(right.animation.opacity.value_at(0), Some(0.25));
+        assert_eq!(right.animation.opacity.value_at(time::from_seconds(1.0)), Some(0.5));
+        assert_eq!(clip.animation.opacity.value_at(time::from_seconds(1.0)), Some(0.25));
+
+        // Moving a clip takes its keyframes along
+        let mut moved = right.clone();
+        moved.move_to(time::from_seconds(20.0));
+        assert_eq!(moved.animation, right.animation);
+    }
 }
 
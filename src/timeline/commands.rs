@@ -0,0 +1,1413 @@
+//! Reversible timeline edit commands for use with [`History`](crate::timeline::history::History).
+//!
+//! Each command records whatever it needs to revert itself the first time it
+//! is applied, so redo after undo reproduces exactly the same result
+//! (including clip and track IDs).
+
+use std::any::Any;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use crate::timeline::clip::{Clip, ClipId, LinkGroupId};
+use crate::timeline::history::Command;
+use crate::timeline::diff::TimelineDiff;
+use crate::timeline::timeline::Timeline;
+use crate::timeline::track::{Track, TrackError, TrackId, TrackType};
+use crate::timeline::trim::TrimEdge;
+use crate::timeline::marker::{Marker, MarkerId};
+use crate::timeline::caption::{Cue, CueId};
+use crate::timeline::speed::Speed;
+use crate::timeline::remap::TimeRemap;
+use crate::timeline::keyframe::ClipAnimation;
+use crate::timeline::visual::VisualProperties;
+use crate::timeline::sequence::SequenceId;
+use crate::timeline::transition::{Transition, TransitionAlignment, TransitionId, TransitionKind};
+use crate::core::time::Time;
+
+/// Look up a clip and its track ID.
+fn locate_clip(timeline: &Timeline, clip_id: ClipId) -> Result<(TrackId, Clip), TrackError> {
+    timeline
+        .find_clip(clip_id)
+        .map(|(track, clip)| (track.id, clip.clone()))
+        .ok_or(TrackError::ClipNotFound { clip_id })
+}
+
+fn track_type_name(track_type: TrackType) -> &'static str {
+    match track_type {
+        TrackType::Video => "Video",
+        TrackType::Audio => "Audio",
+        TrackType::Caption => "Caption",
+    }
+}
+
+/// Add a new empty track.
+#[derive(Debug)]
+pub struct AddTrack {
+    track_type: TrackType,
+    index: Option<usize>,
+    track_id: Option<TrackId>,
+    /// Track kept across undo so redo restores the same ID
+    removed: Option<Track>,
+}
+
+impl AddTrack {
+    /// Append a track of `track_type` (top-most layer for video).
+    pub fn new(track_type: TrackType) -> Self {
+        Self { track_type, index: None, track_id: None, removed: None }
+    }
+
+    /// Insert a track of `track_type` at `index` in the track list.
+    pub fn at(track_type: TrackType, index: usize) -> Self {
+        Self { track_type, index: Some(index), track_id: None, removed: None }
+    }
+
+    /// ID of the added track, once applied.
+    pub fn track_id(&self) -> Option<TrackId> {
+        self.track_id
+    }
+}
+
+impl Command for AddTrack {
+    fn apply(&mut self, timeline: &mut Timeline) -> Result<(), TrackError> {
+        let index = *self.index.get_or_insert(timeline.tracks.len());
+        match self.removed.take() {
+            Some(track) => timeline.insert_existing_track(index, track),
+            None => {
+                self.track_id = Some(timeline.insert_track(index, self.track_type));
+                Ok(())
+            }
+        }
+    }
+
+    fn undo(&mut self, timeline: &mut Timeline) -> Result<(), TrackError> {
+        let track_id = self.track_id.expect("undo before apply");
+        self.removed = Some(
+            timeline
+                .remove_track(track_id)
+                .ok_or(TrackError::TrackNotFound { track_id })?,
+        );
+        Ok(())
+    }
+
+    fn label(&self) -> String {
+        format!("Add {} Track", track_type_name(self.track_type))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Remove a track together with its clips. Locked tracks are refused.
+#[derive(Debug)]
+pub struct RemoveTrack {
+    track_id: TrackId,
+    removed: Option<(usize, Track)>,
+}
+
+impl RemoveTrack {
+    pub fn new(track_id: TrackId) -> Self {
+        Self { track_id, removed: None }
+    }
+}
+
+impl Command for RemoveTrack {
+    fn apply(&mut self, timeline: &mut Timeline) -> Result<(), TrackError> {
+        let track_id = self.track_id;
+        let index = timeline
+            .track_index(track_id)
+            .ok_or(TrackError::TrackNotFound { track_id })?;
+        timeline.tracks[index].check_unlocked()?;
+        let track = timeline.remove_track(track_id).expect("track index just found");
+        self.removed = Some((index, track));
+        Ok(())
+    }
+
+    fn undo(&mut self, timeline: &mut Timeline) -> Result<(), TrackError> {
+        let (index, track) = self.removed.take().expect("undo before apply");
+        timeline.insert_existing_track(index, track)
+    }
+
+    fn label(&self) -> String {
+        match &self.removed {
+            Some((_, track)) => format!("Remove {} Track", track_type_name(track.track_type)),
+            None => "Remove Track".to_string(),
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Move a track to another position in the track list.
+///
+/// Consecutive moves of the same track merge into one step.
+#[derive(Debug)]
+pub struct MoveTrack {
+    track_id: TrackId,
+    to_index: usize,
+    from_index: Option<usize>,
+}
+
+impl MoveTrack {
+    pub fn new(track_id: TrackId, to_index: usize) -> Self {
+        Self { track_id, to_index, from_index: None }
+    }
+}
+
+impl Command for MoveTrack {
+    fn apply(&mut self, timeline: &mut Timeline) -> Result<(), TrackError> {
+        let track_id = self.track_id;
+        let from = timeline
+            .track_index(track_id)
+            .ok_or(TrackError::TrackNotFound { track_id })?;
+        self.from_index.get_or_insert(from);
+        timeline.move_track(track_id, self.to_index)
+    }
+
+    fn undo(&mut self, timeline: &mut Timeline) -> Result<(), TrackError> {
+        let from = self.from_index.expect("undo before apply");
+        timeline.move_track(self.track_id, from)
+    }
+
+    fn label(&self) -> String {
+        "Move Track".to_string()
+    }
+
+    fn merge(&mut self, next: &dyn Command) -> bool {
+        match next.as_any().downcast_ref::<MoveTrack>() {
+            Some(next) if next.track_id == self.track_id => {
+                self.to_index = next.to_index;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Add a clip to a track.
+#[derive(Debug)]
+pub struct AddClip {
+    track_id: TrackId,
+    clip: Clip,
+}
+
+impl AddClip {
+    pub fn new(track_id: TrackId, clip: Clip) -> Self {
+        Self { track_id, clip }
+    }
+}
+
+impl Command for AddClip {
+    fn apply(&mut self, timeline: &mut Timeline) -> Result<(), TrackError> {
+        timeline.add_clip(self.track_id, self.clip.clone())
+    }
+
+    fn undo(&mut self, timeline: &mut Timeline) -> Result<(), TrackError> {
+        timeline.remove_clip(self.clip.id).map(|_| ())
+    }
+
+    fn label(&self) -> String {
+        "Add Clip".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Remove a clip, and every clip linked to it, from whichever tracks hold them,
+/// along with the transitions into them.
+#[derive(Debug)]
+pub struct RemoveClip {
+    clip_id: ClipId,
+    removed: Vec<(TrackId, Clip)>,
+    transitions: Vec<(TrackId, Transition)>, // Transitions into the removed clips
+}
+
+impl RemoveClip {
+    pub fn new(clip_id: ClipId) -> Self {
+        Self { clip_id, removed: Vec::new(), transitions: Vec::new() }
+    }
+}
+
+impl Command for RemoveClip {
+    fn apply(&mut self, timeline: &mut Timeline) -> Result<(), TrackError> {
+        let members = timeline.linked_clips(self.clip_id);
+        let transitions: Vec<_> = timeline.tracks
+            .iter()
+            .flat_map(|track| track.transitions.iter().map(move |t| (track.id, t)))
+            .filter(|(_, t)| members.contains(&t.clip_id))
+            .map(|(track_id, t)| (track_id, t.clone()))
+            .collect();
+        self.removed = timeline.remove_linked(self.clip_id)?;
+        self.transitions = transitions;
+        Ok(())
+    }
+
+    fn undo(&mut self, timeline: &mut Timeline) -> Result<(), TrackError> {
+        for (track_id, clip) in self.removed.drain(..) {
+            timeline.add_clip(track_id, clip)?;
+        }
+        for (track_id, transition) in self.transitions.drain(..) {
+            if let Some(track) = timeline.track_mut(track_id) {
+                track.transitions.push(transition);
+            }
+        }
+        Ok(())
+    }
+
+    fn label(&self) -> String {
+        "Remove Clip".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Move a clip (and the clips linked to it) to a new timeline position,
+/// optionally moving the clip itself onto another track.
+///
+/// Consecutive moves of the same clip (a drag) merge into one step.
+#[derive(Debug)]
+pub struct MoveClip {
+    clip_id: ClipId,
+    to_start: Time,
+    to_track: Option<TrackId>,
+    /// Original track and start, recorded on first apply
+    from: Option<(TrackId, Time)>,
+}
+
+impl MoveClip {
+    /// Move a clip along its current track.
+    pub fn new(clip_id: ClipId, to_start: Time) -> Self {
+        Self { clip_id, to_start, to_track: None, from: None }
+    }
+
+    /// Move a clip onto `track_id` at `to_start`.
+    pub fn to_track(clip_id: ClipId, track_id: TrackId, to_start: Time) -> Self {
+        Self { clip_id, to_start, to_track: Some(track_id), from: None }
+    }
+}
+
+impl Command for MoveClip {
+    fn apply(&mut self, timeline: &mut Timeline) -> Result<(), TrackError> {
+        let (track_id, clip) = locate_clip(timeline, self.clip_id)?;
+        let target = self.to_track.unwrap_or(track_id);
+        timeline.move_linked(self.clip_id, target, self.to_start)?;
+        self.from.get_or_insert((track_id, clip.timeline_start));
+        Ok(())
+    }
+
+    fn undo(&mut self, timeline: &mut Timeline) -> Result<(), TrackError> {
+        let (from_track, from_start) = self.from.expect("undo before apply");
+        timeline.move_linked(self.clip_id, from_track, from_start)
+    }
+
+    fn label(&self) -> String {
+        "Move Clip".to_string()
+    }
+
+    fn merge(&mut self, next: &dyn Command) -> bool {
+        match next.as_any().downcast_ref::<MoveClip>() {
+            Some(next) if next.clip_id == self.clip_id => {
+                self.to_start = next.to_start;
+                self.to_track = next.to_track.or(self.to_track);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Split a clip, and the linked clips under the same position, in two (razor cut).
+#[derive(Debug)]
+pub struct SplitClip {
+    clip_id: ClipId,
+    position: Time,
+    /// Unsplit clips, recorded on first apply
+    before: Vec<(TrackId, Clip)>,
+    /// Left and right halves, recorded on first apply so redo reuses their IDs
+    lefts: Vec<(TrackId, Clip)>,
+    rights: Vec<(TrackId, Clip)>,
+}
+
+impl SplitClip {
+    pub fn new(clip_id: ClipId, position: Time) -> Self {
+        Self { clip_id, position, before: Vec::new(), lefts: Vec::new(), rights: Vec::new() }
+    }
+}
+
+impl Command for SplitClip {
+    fn apply(&mut self, timeline: &mut Timeline) -> Result<(), TrackError> {
+        if !self.rights.is_empty() {
+            timeline.replace_clips(self.lefts.clone())?;
+            for (track_id, clip) in &self.rights {
+                timeline.add_clip(*track_id, clip.clone())?;
+            }
+            return Ok(());
+        }
+
+        let before = timeline.linked_with_tracks(self.clip_id)?;
+        let cuts = timeline.split_linked(self.clip_id, self.position)?;
+        for (left, right) in cuts {
+            self.before.extend(before.iter().filter(|(_, c)| c.id == left).cloned());
+            self.lefts.push(locate_clip(timeline, left)?);
+            self.rights.push(locate_clip(timeline, right)?);
+        }
+        Ok(())
+    }
+
+    fn undo(&mut self, timeline: &mut Timeline) -> Result<(), TrackError> {
+        for (_, right) in &self.rights {
+            timeline.remove_clip(right.id)?;
+        }
+        timeline.replace_clips(self.before.clone())
+    }
+
+    fn label(&self) -> String {
+        "Split Clip".to_string()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Trim a clip's in or out point (and those of the clips linked to it),
+/// shortening or lengthening it.
+///
+/// Consecutive trims of the same clip edge (a drag) merge into one step.
+#[derive(Debug)]
+pub struct TrimClip {
+    clip_id: ClipId,
+    edge: TrimEdge,
+    source_point: Time,
+    before: Option<Vec<(TrackId, Clip)>>,
+}
+
+impl TrimClip {
+    /// Set a clip's in point (source time).
+    pub fn trim_in(clip_id: ClipId, new_in_point: Time) -> Self {
+        Self { clip_id, edge: TrimEdge::In, source_point: new_in_point, before: None }
+    }
+
+    /// Set a clip's out point (source time).
+    pub fn trim_out(clip_id: ClipId, new_out_point: Time) -> Self {
+        Self { clip_id, edge: TrimEdge::Out, source_point: new_out_point, before: None }
+    }
+}
+
+impl Command for TrimClip {
+    fn apply(&mut self, timeline: &mut Timeline) -> Result<(), TrackError> {
+        let members = timeline.linked_with_tracks(self.clip_id)?;
+        timeline.trim_linked(self.clip_id, self.edge, self.source_point)?;
+        self.before.get_or_insert(members);
+        Ok(())
+    }
+
+    fn undo(&mut self, timeline: &mut Timeline) -> Result<(), TrackError> {
+        let before = self.before.clone().expect("undo before apply");
+        timeline.replace_clips(before)
+    }
+
+    fn label(&self) -> String {
+        match self.edge {
+            TrimEdge::In => "Trim Clip Start".to_string(),
+            TrimEdge::Out => "Trim Clip End".to_string(),
+        }
+    }
+
+    fn merge(&mut self, next: &dyn Command) -> bool {
+        match next.as_any().downcast_ref::<TrimClip>() {
+            Some(next) if next.clip_id == self.clip_id && next.edge == self.edge => {
+                self.source_point = next.source_point;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// A track setting changed by [`SetTrackProperty`], holding its value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrackProperty {
+    /// Lock or unlock the track for editing
+    Locked(bool),
+    /// Solo or unsolo the track
+    Solo(bool),
+    /// Show or hide a video track in the composite
+    Enabled(bool),
+    /// Track name; an empty name restores the default label
+    Name(String),
+    /// Colour the track's clips are drawn in
+    Color([u8; 3]),
+    /// Audio volume, clamped to 0.0 to 1.0
+    Volume(f32),
+    /// Mute or unmute the track
+    Muted(bool),
+    /// Lane height in the timeline view, clamped to the track's limits
+    Height(f32),
+}
+
+impl TrackProperty {
+    /// The same property with `track`'s current value.
+    fn read(&self, track: &Track) -> Self {
+        match self {
+            TrackProperty::Locked(_) => TrackProperty::Locked(track.locked),
+            TrackProperty::Solo(_) => TrackProperty::Solo(track.solo),
+            TrackProperty::Enabled(_) => TrackProperty::Enabled(track.enabled),
+            TrackProperty::Name(_) => TrackProperty::Name(track.name.clone()),
+            TrackProperty::Color(_) => TrackProperty::Color(track.color),
+            TrackProperty::Volume(_) => TrackProperty::Volume(track.volume),
+            TrackProperty::Muted(_) => TrackProperty::Muted(track.muted),
+            TrackProperty::Height(_) => TrackProperty::Height(track.height),
+        }
+    }
+
+    /// Set this value on `track`.
+    fn write(&self, track: &mut Track) {
+        match self {
+            TrackProperty::Locked(locked) => track.set_locked(*locked),
+            TrackProperty::Solo(solo) => track.set_solo(*solo),
+            TrackProperty::Enabled(enabled) => track.set_enabled(*enabled),
+            TrackProperty::Name(name) => track.name.clone_from(name),
+            TrackProperty::Color(color) => track.color = *color,
+            TrackProperty::Volume(volume) => track.set_volume(*volume),
+            TrackProperty::Muted(muted) => track.set_muted(*muted),
+            TrackProperty::Height(height) => track.set_height(*height),
+        }
+    }
+}
+
+/// Change one setting of a track (see [`TrackProperty`]).
+///
+/// Consecutive volume or height changes of the same track (a slider or lane
+/// drag) merge.
+#[derive(Debug)]
+pub struct SetTrackProperty {
+    track_id: TrackId,
+    value: TrackProperty,
+    previous: Option<TrackProperty>,
+}
+
+impl SetTrackProperty {
+    pub fn new(track_id: TrackId, value: TrackProperty) -> Self {
+        Self { track_id, value, previous: None }
+    }
+}
+
+impl Command for SetTrackProperty {
+    fn apply(&mut self, timeline: &mut Timeline) -> Result<(), TrackError> {
+        let track_id = self.track_id;
+        let track = timeline
+            .track_mut(track_id)
+            .ok_or(TrackError::TrackNotFound { track_id })?;
+        self.previous.get_or_insert_with(|| self.value.read(track));
+        self.value.write(track);
+        Ok(())
+    }
+
+    fn undo(&mut self, timeline: &mut Timeline) -> Result<(), TrackError> {
+        let track_id = self.track_id;
+        let track = timeline
+            .track_mut(track_id)
+            .ok_or(TrackError::TrackNotFound { track_id })?;
+        self.previous.as_ref().expect("undo before apply").write(track);
+        Ok(())
+    }
+
+    fn label(&self) -> String {
+        match &self.value {
+            TrackProperty::Locked(locked) => if *locked { "Lock Track" } else { "Unlock Track" },
+            TrackProperty::Solo(solo) => if *solo { "Solo Track" } else { "Unsolo Track" },
+            TrackProperty::Enabled(enabled) => if *enabled { "Enable Track" } else { "Disable Track" },
+            TrackProperty::Name(_) => "Rename Track",
+            TrackProperty::Color(_) => "Change Track Colour",
+            TrackProperty::Volume(_) => "Change Track Volume",
+            TrackProperty::Muted(muted) => if *muted { "Mute Track" } else { "Unmute Track" },
+            TrackProperty::Height(_) => "Resize Track",
+        }
+        .to_string()
+    }
+
+    fn merge(&mut self, next: &dyn Command) -> bool {
+        let Some(next) = next.as_any().downcast_ref::<SetTrackProperty>() else {
+            return false;
+        };
+        match (&self.value, &next.value) {
+            (TrackProperty::Volume(_), TrackProperty::Volume(_))
+            | (TrackProperty::Height(_), TrackProperty::Height(_)) if next.track_id == self.track_id => {
+                self.value = next.value.clone();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+type TrackEditFn = Box<dyn FnOnce(&mut Track) -> Result<(), TrackError> + Send>;
+
+/// An edit confined to one track, made reversible by snapshotting that track.
+///
+/// For ripple, roll, slip and slide use [`LinkedEdit`], which keeps linked
+/// clips on other tracks in sync.
+///
+/// Consecutive edits with the same label on the same track (a drag) merge.
+pub struct EditTrack {
+    track_id: TrackId,
+    label: String,
+    edit: Option<TrackEditFn>,
+    before: Option<Track>,
+    after: Option<Track>,
+}
+
+impl EditTrack {
+    pub fn new<F>(track_id: TrackId, label: impl Into<String>, edit: F) -> Self
+    where
+        F: FnOnce(&mut Track) -> Result<(), TrackError> + Send + 'static,
+    {
+        Self {
+            track_id,
+            label: label.into(),
+            edit: Some(Box::new(edit)),
+            before: None,
+            after: None,
+        }
+    }
+
+    fn replace(timeline: &mut Timeline, snapshot: &Track) -> Result<(), TrackError> {
+        let track_id = snapshot.id;
+        let track = timeline
+            .track_mut(track_id)
+            .ok_or(TrackError::TrackNotFound { track_id })?;
+        track.clone_from(snapshot);
+        timeline.update_duration();
+        Ok(())
+    }
+}
+
+impl fmt::Debug for EditTrack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EditTrack")
+            .field("track_id", &self.track_id)
+            .field("label", &self.label)
+            .field("applied", &self.after.is_some())
+            .finish()
+    }
+}
+
+impl Command for EditTrack {
+    fn apply(&mut self, timeline: &mut Timeline) -> Result<(), TrackError> {
+        if let Some(after) = &self.after {
+            return Self::replace(timeline, after);
+        }
+        let track_id = self.track_id;
+        let track = timeline
+            .track_mut(track_id)
+            .ok_or(TrackError::TrackNotFound { track_id })?;
+        let before = track.clone();
+        let edit = self.edit.take().expect("edit function already consumed");
+        if let Err(e) = edit(track) {
+            track.clone_from(&before);
+            return Err(e);
+        }
+        self.after = Some(track.clone());
+        self.before = Some(before);
+        timeline.update_duration();
+        Ok(())
+    }
+
+    fn undo(&mut self, timeline: &mut Timeline) -> Result<(), TrackError> {
+        Self::replace(timeline, self.before.as_ref().expect("undo before apply"))
+    }
+
+    fn label(&self) -> String {
+        self.label.clone()
+    }
+
+    fn merge(&mut self, next: &dyn Command) -> bool {
+        match next.as_any().downcast_ref::<EditTrack>() {
+            Some(next) if next.track_id == self.track_id && next.label == self.label => {
+                self.after = next.after.clone();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+type EditFn = Box<dyn FnOnce(&mut Timeline) -> Result<(), TrackError> + Send>;
+
+/// Locked tracks an edit left in place, filled in when the edit runs (see
+/// `TimelineEdit::insert`, `lift` and `extract`). Clones share one list, so
+/// the caller keeps a clone while the edit goes into the history.
+#[derive(Debug, Clone, Default)]
+pub struct SkippedTracks(Arc<Mutex<Vec<TrackId>>>);
+
+impl SkippedTracks {
+    /// The locked tracks the edit skipped; empty until it has run.
+    pub fn get(&self) -> Vec<TrackId> {
+        self.0.lock().map(|ids| ids.clone()).unwrap_or_default()
+    }
+
+    fn set(&self, track_ids: Vec<TrackId>) {
+        if let Ok(mut ids) = self.0.lock() {
+            *ids = track_ids;
+        }
+    }
+}
+
+/// A ripple, roll, slip or slide of a clip and the clips linked to it, made
+/// reversible by snapshotting every track it changed.
+///
+/// Consecutive edits with the same label on the same clip (a drag) merge.
+pub struct LinkedEdit {
+    clip_id: ClipId,
+    label: String,
+    edit: Option<EditFn>,
+    before: Vec<Track>, // Changed tracks as they were before the edit
+    after: Vec<Track>,  // The same tracks after it
+}
+
+impl LinkedEdit {
+    pub fn new<F>(clip_id: ClipId, label: impl Into<String>, edit: F) -> Self
+    where
+        F: FnOnce(&mut Timeline) -> Result<(), TrackError> + Send + 'static,
+    {
+        Self {
+            clip_id,
+            label: label.into(),
+            edit: Some(Box::new(edit)),
+            before: Vec::new(),
+            after: Vec::new(),
+        }
+    }
+
+    /// Ripple trim a clip's in or out point (see `Timeline::ripple_trim_linked`).
+    pub fn ripple_trim(clip_id: ClipId, edge: TrimEdge, source_point: Time) -> Self {
+        Self::new(clip_id, "Ripple Trim", move |tl| tl.ripple_trim_linked(clip_id, edge, source_point))
+    }
+
+    /// Roll the edit point after a clip (see `Timeline::roll_linked`).
+    pub fn roll(left_clip_id: ClipId, new_edit_point: Time) -> Self {
+        Self::new(left_clip_id, "Roll Edit", move |tl| tl.roll_linked(left_clip_id, new_edit_point))
+    }
+
+    /// Slip a clip's source range (see `Timeline::slip_linked`).
+    pub fn slip(clip_id: ClipId, delta: Time) -> Self {
+        Self::new(clip_id, "Slip Clip", move |tl| tl.slip_linked(clip_id, delta))
+    }
+
+    /// Slide a clip between its neighbours (see `Timeline::slide_linked`).
+    pub fn slide(clip_id: ClipId, delta: Time) -> Self {
+        Self::new(clip_id, "Slide Clip", move |tl| tl.slide_linked(clip_id, delta))
+    }
+
+    /// Put snapshots of tracks back; all of the tracks must still exist.
+    fn replace(timeline: &mut Timeline, snapshots: &[Track]) -> Result<(), TrackError> {
+        for snapshot in snapshots {
+            let track_id = snapshot.id;
+            timeline.track(track_id).ok_or(TrackError::TrackNotFound { track_id })?;
+        }
+        for snapshot in snapshots {
+            if let Some(track) = timeline.track_mut(snapshot.id) {
+                track.clone_from(snapshot);
+            }
+        }
+        timeline.update_duration();
+        Ok(())
+    }
+}
+
+impl fmt::Debug for LinkedEdit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LinkedEdit")
+            .field("clip_id", &self.clip_id)
+            .field("label", &self.label)
+            .field("tracks", &self.after.iter().map(|t| t.id).collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl Command for LinkedEdit {
+    fn apply(&mut self, timeline: &mut Timeline) -> Result<(), TrackError> {
+        // A failed edit is never recorded, so it is not applied again
+        let Some(edit) = self.edit.take() else {
+            return Self::replace(timeline, &self.after);
+        };
+        let before = timeline.tracks.clone();
+        edit(timeline)?;
+        for (old, new) in before.into_iter().zip(&timeline.tracks) {
+            if old != *new {
+                self.after.push(new.clone());
+                self.before.push(old);
+            }
+        }
+        Ok(())
+    }
+
+    fn undo(&mut self, timeline: &mut Timeline) -> Result<(), TrackError> {
+        Self::replace(timeline, &self.before)
+    }
+
+    fn label(&self) -> String {
+        self.label.clone()
+    }
+
+    fn merge(&mut self, next: &dyn Command) -> bool {
+        match next.as_any().downcast_ref::<LinkedEdit>() {
+            Some(next) if next.clip_id == self.clip_id && next.label == self.label => {
+                for track in &next.before {
+                    if !self.before.iter().any(|t| t.id == track.id) {
+                        self.before.push(track.clone());
+                    }
+                }
+                for track in &next.after {
+                    match self.after.iter_mut().find(|t| t.id == track.id) {
+                        Some(after) => after.clone_from(track),
+                        None => self.after.push(track.clone()),
+                    }
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Any compound edit, made reversible by recording what it changed.
+///
+/// The edit function runs once; a failing edit is rolled back from a copy
+/// taken before it ran. Only the parts it changed (clips, transitions, cues,
+/// markers, tracks, sequences) are kept, as a [`TimelineDiff`] that undo and
+/// redo swap back and forth. The playhead is not part of the diff.
+pub struct TimelineEdit {
+    label: String,
+    edit: Option<EditFn>,
+    diff: Option<TimelineDiff>,
+}
+
+impl TimelineEdit {
+    pub fn new<F>(label: impl Into<String>, edit: F) -> Self
+    where
+        F: FnOnce(&mut Timeline) -> Result<(), TrackError> + Send + 'static,
+    {
+        Self {
+            label: label.into(),
+            edit: Some(Box::new(edit)),
+            diff: None,
+        }
+    }
+
+    /// Insert edit rippling every unlocked track (see `Timeline::insert_edit`).
+    /// The locked tracks it leaves in place are reported through the returned
+    /// `SkippedTracks`.
+    pub fn insert(track_id: TrackId, clip: Clip) -> (Self, SkippedTracks) {
+        let skipped = SkippedTracks::default();
+        let report = skipped.clone();
+        let edit = Self::new("Insert", move |tl| {
+            report.set(tl.insert_edit(track_id, clip)?);
+            Ok(())
+        });
+        (edit, skipped)
+    }
+
+    /// Overwrite edit on one track (see `Timeline::overwrite_edit`).
+    pub fn overwrite(track_id: TrackId, clip: Clip) -> Self {
+        Self::new("Overwrite", move |tl| tl.overwrite_edit(track_id, clip).map(|_| ()))
+    }
+
+    /// Lift a range on all unlocked tracks (see `Timeline::lift`). The locked
+    /// tracks are reported through the returned `SkippedTracks`.
+    pub fn lift(start: Time, end: Time) -> (Self, SkippedTracks) {
+        let skipped = SkippedTracks::default();
+        let report = skipped.clone();
+        let edit = Self::new("Lift", move |tl| {
+            report.set(tl.lift(start, end).skipped);
+            Ok(())
+        });
+        (edit, skipped)
+    }
+
+    /// Extract a range on all unlocked tracks (see `Timeline::extract`). The
+    /// locked tracks are reported through the returned `SkippedTracks`.
+    pub fn extract(start: Time, end: Time) -> (Self, SkippedTracks) {
+        let skipped = SkippedTracks::default();
+        let report = skipped.clone();
+        let edit = Self::new("Extract", move |tl| {
+            report.set(tl.extract(start, end).skipped);
+            Ok(())
+        });
+        (edit, skipped)
+    }
+
+    /// Link clips into a new group (see `Timeline::link`).
+    pub fn link(clip_ids: Vec<ClipId>) -> Self {
+        Self::new("Link Clips", move |tl| tl.link(&clip_ids).map(|_| ()))
+    }
+
+    /// Unlink a clip from its group (see `Timeline::unlink`).
+    pub fn unlink(clip_id: ClipId) -> Self {
+        Self::new("Unlink Clip", move |tl| tl.unlink(clip_id).map(|_| ()))
+    }
+
+    /// Put a clip back into a link group (see `Timeline::relink`).
+    pub fn relink(clip_id: ClipId, group: LinkGroupId) -> Self {
+        Self::new("Relink Clip", move |tl| tl.relink(clip_id, group))
+    }
+
+    /// Change the speed of a clip and its linked clips (see `Timeline::set_clip_speed`).
+    pub fn set_speed(clip_id: ClipId, speed: Speed) -> Self {
+        Self::new("Change Speed", move |tl| tl.set_clip_speed(clip_id, speed))
+    }
+
+    /// Collapse clips into a compound clip (see `Timeline::compound_clips`).
+    pub fn compound(clip_ids: Vec<ClipId>, name: impl Into<String>) -> Self {
+        let name = name.into();
+        Self::new("Make Compound Clip", move |tl| tl.compound_clips(&clip_ids, name).map(|_| ()))
+    }
+
+    /// Run `edit` inside a nested sequence instead of on the root timeline
+    /// (see `Timeline::edit_sequence`). Keeps `edit`'s label.
+    ///
+    /// `edit` must not have been executed yet; an edit that already ran has
+    /// nothing left to run and changes nothing.
+    pub fn in_sequence(sequence: SequenceId, edit: TimelineEdit) -> Self {
+        let TimelineEdit { label, edit, .. } = edit;
+        Self::new(label, move |tl| match edit {
+            Some(edit) => tl.edit_sequence(sequence, edit),
+            None => Ok(()),
+        })
+    }
+
+    /// Set or remove a clip's time-remap curve (see `Timeline::set_clip_time_remap`).
+    pub fn set_time_remap(clip_id: ClipId, remap: Option<TimeRemap>) -> Self {
+        Self::new("Time Remap", move |tl| tl.set_clip_time_remap(clip_id, remap))
+    }
+
+    /// Replace a clip's keyframed properties (see `Timeline::set_clip_animation`).
+    pub fn set_animation(clip_id: ClipId, animation: ClipAnimation) -> Self {
+        Self::new("Edit Keyframes", move |tl| tl.set_clip_animation(clip_id, animation))
+    }
+
+    /// Replace a clip's static visual properties (see `Timeline::set_clip_visual`).
+    pub fn set_visual(clip_id: ClipId, visual: VisualProperties) -> Self {
+        Self::new("Edit Clip Transform", move |tl| tl.set_clip_visual(clip_id, visual))
+    }
+
+    /// Add a transition at the cut into a clip (see `Timeline::add_transition`).
+    pub fn add_transition(
+        clip_id: ClipId,
+        kind: TransitionKind,
+        alignment: TransitionAlignment,
+        duration: Time,
+    ) -> Self {
+        Self::new("Add Transition", move |tl| tl.add_transition(clip_id, kind, alignment, duration).map(|_| ()))
+    }
+
+    /// Remove a transition.
+    pub fn remove_transition(id: TransitionId) -> Self {
+        Self::new("Remove Transition", move |tl| tl.remove_transition(id).map(|_| ()))
+    }
+
+    /// Add a timeline marker.
+    pub fn add_marker(marker: Marker) -> Self {
+        Self::new("Add Marker", move |tl| {
+            tl.add_marker(marker);
+            Ok(())
+        })
+    }
+
+    /// Add a marker to a clip (see `Timeline::add_clip_marker`).
+    pub fn add_clip_marker(clip_id: ClipId, marker: Marker) -> Self {
+        Self::new("Add Clip Marker", move |tl| tl.add_clip_marker(clip_id, marker))
+    }
+
+    /// Remove a timeline or clip marker. Fails with
+    /// `TrackError::MarkerNotFound` if the marker does not exist.
+    pub fn remove_marker(id: MarkerId) -> Self {
+        Self::new("Remove Marker", move |tl| {
+            tl.remove_marker(id)
+                .map(|_| ())
+                .ok_or(TrackError::MarkerNotFound { marker_id: id })
+        })
+    }
+
+    /// Add a cue to a caption track (see `Timeline::add_cue`).
+    pub fn add_cue(track_id: TrackId, cue: Cue) -> Self {
+        Self::new("Add Caption", move |tl| tl.add_cue(track_id, cue))
+    }
+
+    /// Add imported cues to a caption track (see `Timeline::add_cues`).
+    pub fn import_cues(track_id: TrackId, cues: Vec<Cue>) -> Self {
+        Self::new("Import Captions", move |tl| tl.add_cues(track_id, cues).map(|_| ()))
+    }
+
+    /// Replace a cue's text, timing, position or style (see `Timeline::replace_cue`).
+    pub fn replace_cue(cue: Cue) -> Self {
+        Self::new("Edit Caption", move |tl| tl.replace_cue(cue).map(|_| ()))
+    }
+
+    /// Remove a cue (see `Timeline::remove_cue`).
+    pub fn remove_cue(id: CueId) -> Self {
+        Self::new("Remove Caption", move |tl| tl.remove_cue(id).map(|_| ()))
+    }
+
+    /// Close all gaps on a track (see `Timeline::close_gaps`).
+    pub fn close_gaps(track_id: TrackId) -> Self {
+        Self::new("Close Gaps", move |tl| tl.close_gaps(track_id))
+    }
+
+}
+
+impl fmt::Debug for TimelineEdit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TimelineEdit")
+            .field("label", &self.label)
+            .field("applied", &self.diff.is_some())
+            .finish()
+    }
+}
+
+impl Command for TimelineEdit {
+    fn apply(&mut self, timeline: &mut Timeline) -> Result<(), TrackError> {
+        if let Some(diff) = &self.diff {
+            diff.redo(timeline);
+            return Ok(());
+        }
+        // A failed edit is never recorded, so it is not applied again
+        let Some(edit) = self.edit.take() else {
+            return Ok(());
+        };
+        let before = timeline.clone();
+        if let Err(e) = edit(timeline) {
+            *timeline = before;
+            return Err(e);
+        }
+        timeline.update_duration();
+        self.diff = Some(TimelineDiff::between(&before, timeline));
+        Ok(())
+    }
+
+    fn undo(&mut self, timeline: &mut Timeline) -> Result<(), TrackError> {
+        if let Some(diff) = &self.diff {
+            diff.undo(timeline);
+        }
+        Ok(())
+    }
+
+    fn label(&self) -> String {
+        self.label.clone()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::time;
+    use crate::timeline::history::History;
+    use crate::timeline::marker::MarkerKind;
+    use std::path::PathBuf;
+
+    fn clip(id: u64, start: f64, length: f64) -> Clip {
+        Clip::new(
+            id,
+            PathBuf::from("test.mp4"),
+            time::from_seconds(0.0),
+            time::from_seconds(length),
+            time::from_seconds(start),
+            0,
+        )
+    }
+
+    #[test]
+    fn test_move_clip_between_tracks() {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        let mut history = History::new();
+        history.execute(&mut timeline, AddClip::new(v1, clip(1, 0.0, 2.0))).unwrap();
+        let v2 = timeline.add_track(TrackType::Video);
+
+        history
+            .execute(&mut timeline, MoveClip::to_track(1, v2, time::from_seconds(3.0)))
+            .unwrap();
+        let (track, moved) = timeline.find_clip(1).unwrap();
+        assert_eq!(track.id, v2);
+        assert_eq!(moved.timeline_start, time::from_seconds(3.0));
+
+        history.undo(&mut timeline).unwrap();
+        let (track, moved) = timeline.find_clip(1).unwrap();
+        assert_eq!(track.id, v1);
+        assert_eq!(moved.timeline_start, 0);
+    }
+
+    #[test]
+    fn test_locked_track_refuses_commands() {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        let mut history = History::new();
+        history.execute(&mut timeline, AddClip::new(v1, clip(1, 0.0, 2.0))).unwrap();
+        history.execute(&mut timeline, SetTrackProperty::new(v1, TrackProperty::Locked(true))).unwrap();
+
+        let locked = Err(TrackError::TrackLocked { track_id: v1 });
+        assert_eq!(history.execute(&mut timeline, RemoveClip::new(1)), locked);
+        assert_eq!(history.execute(&mut timeline, RemoveTrack::new(v1)), locked);
+        assert_eq!(history.undo_label(), Some("Lock Track"));
+
+        // Undoing the lock makes the track editable again
+        history.undo(&mut timeline).unwrap();
+        history.execute(&mut timeline, RemoveClip::new(1)).unwrap();
+        assert!(timeline.find_clip(1).is_none());
+    }
+
+    #[test]
+    fn test_move_clip_rejects_overlap() {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        let mut history = History::new();
+        history.execute(&mut timeline, AddClip::new(v1, clip(1, 0.0, 2.0))).unwrap();
+        history.execute(&mut timeline, AddClip::new(v1, clip(2, 5.0, 2.0))).unwrap();
+
+        let result = history.execute(&mut timeline, MoveClip::new(1, time::from_seconds(4.0)));
+        assert_eq!(result, Err(TrackError::Overlap { clip_id: 1 }));
+        assert_eq!(timeline.find_clip(1).unwrap().1.timeline_start, 0);
+    }
+
+    #[test]
+    fn test_trim_extends_and_shrinks() {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        let mut history = History::new();
+        let mut source = clip(1, 4.0, 2.0);
+        source.in_point = time::from_seconds(3.0);
+        source.out_point = time::from_seconds(5.0);
+        history.execute(&mut timeline, AddClip::new(v1, source)).unwrap();
+        history.seal();
+
+        // Drag the in point out by 1s, then in by 0.5s: one step
+        history.execute(&mut timeline, TrimClip::trim_in(1, time::from_seconds(2.0))).unwrap();
+        history.execute(&mut timeline, TrimClip::trim_in(1, time::from_seconds(2.5))).unwrap();
+        let trimmed = timeline.find_clip(1).unwrap().1.clone();
+        assert_eq!(trimmed.timeline_start, time::from_seconds(3.5));
+        assert_eq!(trimmed.timeline_end, time::from_seconds(6.0));
+        assert_eq!(history.undo_labels().count(), 2);
+
+        history.execute(&mut timeline, TrimClip::trim_out(1, time::from_seconds(4.0))).unwrap();
+        assert_eq!(timeline.find_clip(1).unwrap().1.timeline_end, time::from_seconds(5.0));
+
+        history.undo(&mut timeline).unwrap();
+        history.undo(&mut timeline).unwrap();
+        let restored = &timeline.find_clip(1).unwrap().1;
+        assert_eq!(restored.timeline_start, time::from_seconds(4.0));
+        assert_eq!(restored.in_point, time::from_seconds(3.0));
+    }
+
+    #[test]
+    fn test_split_undo_redo_keeps_ids() {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        let mut history = History::new();
+        history.execute(&mut timeline, AddClip::new(v1, clip(1, 0.0, 4.0))).unwrap();
+
+        history.execute(&mut timeline, SplitClip::new(1, time::from_seconds(1.0))).unwrap();
+        history.execute(&mut timeline, MoveClip::new(2, time::from_seconds(6.0))).unwrap();
+
+        history.undo(&mut timeline).unwrap();
+        history.undo(&mut timeline).unwrap();
+        assert_eq!(timeline.tracks[0].clips.len(), 1);
+        assert_eq!(timeline.find_clip(1).unwrap().1.timeline_end, time::from_seconds(4.0));
+
+        // Redo splits with the same right-half ID, so the move still applies
+        history.redo(&mut timeline).unwrap();
+        history.redo(&mut timeline).unwrap();
+        assert_eq!(timeline.find_clip(2).unwrap().1.timeline_start, time::from_seconds(6.0));
+    }
+
+    #[test]
+    fn test_linked_edits_undo() {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        let a1 = timeline.tracks[1].id;
+        let mut history = History::new();
+        history.execute(&mut timeline, AddClip::new(v1, clip(1, 0.0, 4.0))).unwrap();
+        history.execute(&mut timeline, AddClip::new(a1, clip(2, 0.0, 4.0))).unwrap();
+        history.execute(&mut timeline, TimelineEdit::link(vec![1, 2])).unwrap();
+        history.seal();
+
+        history.execute(&mut timeline, MoveClip::new(2, time::from_seconds(1.0))).unwrap();
+        history.execute(&mut timeline, TrimClip::trim_out(1, time::from_seconds(3.0))).unwrap();
+        history.execute(&mut timeline, SplitClip::new(1, time::from_seconds(2.0))).unwrap();
+        assert_eq!(timeline.tracks[1].clips.len(), 2);
+        assert_eq!(timeline.find_clip(2).unwrap().1.timeline_end, time::from_seconds(2.0));
+
+        history.undo(&mut timeline).unwrap();
+        history.undo(&mut timeline).unwrap();
+        assert_eq!(timeline.tracks[1].clips.len(), 1);
+        assert_eq!(timeline.find_clip(2).unwrap().1.timeline_end, time::from_seconds(5.0));
+        history.undo(&mut timeline).unwrap();
+        assert_eq!(timeline.find_clip(1).unwrap().1.timeline_start, 0);
+
+        history.execute(&mut timeline, RemoveClip::new(1)).unwrap();
+        assert!(timeline.find_clip(2).is_none());
+        history.undo(&mut timeline).unwrap();
+        assert_eq!(timeline.linked_clips(2), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_invalid_trim() {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        let mut history = History::new();
+        history.execute(&mut timeline, AddClip::new(v1, clip(1, 0.0, 2.0))).unwrap();
+
+        let result = history.execute(&mut timeline, TrimClip::trim_out(1, 0));
+        assert_eq!(result, Err(TrackError::ClipTooShort { clip_id: 1 }));
+
+        let result = history.execute(&mut timeline, TrimClip::trim_in(1, time::from_seconds(-1.0)));
+        assert_eq!(result, Err(TrackError::SourceOutOfRange { clip_id: 1 }));
+    }
+
+    #[test]
+    fn test_linked_edit_undo_and_merge() {
+        let mut timeline = Timeline::new();
+        let (v1, a1) = (timeline.tracks[0].id, timeline.tracks[1].id);
+        let mut history = History::new();
+        history.execute(&mut timeline, AddClip::new(v1, clip(1, 0.0, 4.0))).unwrap();
+        history.execute(&mut timeline, AddClip::new(v1, clip(2, 4.0, 4.0))).unwrap();
+        history.execute(&mut timeline, AddClip::new(a1, clip(3, 0.0, 4.0))).unwrap();
+        history.execute(&mut timeline, AddClip::new(a1, clip(4, 4.0, 4.0))).unwrap();
+        history.execute(&mut timeline, TimelineEdit::link(vec![1, 3])).unwrap();
+        history.seal();
+
+        // Dragging a ripple trim: one step, moving the audio too
+        for out in [3.5, 3.0, 2.0] {
+            let edit = LinkedEdit::ripple_trim(1, TrimEdge::Out, time::from_seconds(out));
+            history.execute(&mut timeline, edit).unwrap();
+        }
+        assert_eq!(timeline.find_clip(2).unwrap().1.timeline_start, time::from_seconds(2.0));
+        assert_eq!(timeline.find_clip(4).unwrap().1.timeline_start, time::from_seconds(2.0));
+        assert_eq!(timeline.duration, time::from_seconds(6.0));
+        assert_eq!(history.undo_label(), Some("Ripple Trim"));
+
+        // Failing edits leave the tracks untouched and are not recorded
+        let result = history.execute(&mut timeline, LinkedEdit::roll(2, time::from_seconds(7.0)));
+        assert_eq!(result, Err(TrackError::NoAdjacentClip { clip_id: 2 }));
+
+        history.undo(&mut timeline).unwrap();
+        assert_eq!(timeline.find_clip(2).unwrap().1.timeline_start, time::from_seconds(4.0));
+        assert_eq!(timeline.find_clip(4).unwrap().1.timeline_start, time::from_seconds(4.0));
+        assert_eq!(timeline.duration, time::from_seconds(8.0));
+        history.redo(&mut timeline).unwrap();
+        assert_eq!(timeline.duration, time::from_seconds(6.0));
+        assert_eq!(timeline.sync_offset(3), Some(0));
+    }
+
+    #[test]
+    fn test_track_commands_round_trip() {
+        let mut timeline = Timeline::new();
+        let mut history = History::new();
+        let v1 = timeline.tracks[0].id;
+        history.execute(&mut timeline, AddClip::new(v1, clip(1, 0.0, 2.0))).unwrap();
+
+        let mut add = AddTrack::new(TrackType::Video);
+        add.apply(&mut timeline).unwrap();
+        let v2 = add.track_id().unwrap();
+        add.undo(&mut timeline).unwrap();
+        add.apply(&mut timeline).unwrap();
+        assert_eq!(timeline.tracks.last().unwrap().id, v2);
+
+        history.execute(&mut timeline, RemoveTrack::new(v1)).unwrap();
+        assert!(timeline.find_clip(1).is_none());
+        assert_eq!(history.undo_label(), Some("Remove Video Track"));
+
+        history.undo(&mut timeline).unwrap();
+        assert_eq!(timeline.tracks[0].id, v1);
+        assert!(timeline.find_clip(1).is_some());
+
+        history.execute(&mut timeline, MoveTrack::new(v1, 2)).unwrap();
+        assert_eq!(timeline.track_index(v1), Some(2));
+        history.undo(&mut timeline).unwrap();
+        assert_eq!(timeline.track_index(v1), Some(0));
+    }
+
+    #[test]
+    fn test_volume_drag_merges() {
+        let mut timeline = Timeline::new();
+        let a1 = timeline.tracks[1].id;
+        let mut history = History::new();
+
+        for volume in [0.9, 0.7, 0.5] {
+            history.execute(&mut timeline, SetTrackProperty::new(a1, TrackProperty::Volume(volume))).unwrap();
+        }
+        assert_eq!(history.undo_labels().count(), 1);
+        history.undo(&mut timeline).unwrap();
+        assert_eq!(timeline.track(a1).unwrap().volume, 1.0);
+
+        // A mute in between starts a new step
+        let height = timeline.track(a1).unwrap().height;
+        history.execute(&mut timeline, SetTrackProperty::new(a1, TrackProperty::Height(height + 10.0))).unwrap();
+        history.execute(&mut timeline, SetTrackProperty::new(a1, TrackProperty::Muted(true))).unwrap();
+        history.execute(&mut timeline, SetTrackProperty::new(a1, TrackProperty::Height(height + 20.0))).unwrap();
+        assert_eq!(history.undo_label(), Some("Resize Track"));
+        history.undo(&mut timeline).unwrap();
+        history.undo(&mut timeline).unwrap();
+        assert!(!timeline.track(a1).unwrap().muted);
+        assert_eq!(timeline.track(a1).unwrap().height, height + 10.0);
+        history.undo(&mut timeline).unwrap();
+        assert_eq!(timeline.track(a1).unwrap().height, height);
+    }
+
+    #[test]
+    fn test_timeline_edit_snapshot() {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        let mut history = History::new();
+
+        history
+            .execute(&mut timeline, TimelineEdit::new("Add Two Clips", move |tl| {
+                tl.add_clip(v1, clip(1, 0.0, 2.0))?;
+                tl.add_clip(v1, clip(2, 2.0, 2.0))
+            }))
+            .unwrap();
+        assert_eq!(timeline.duration, time::from_seconds(4.0));
+
+        timeline.set_playhead(time::from_seconds(1.0));
+        history.undo(&mut timeline).unwrap();
+        assert!(timeline.find_clip(1).is_none());
+        // Playhead is kept but clamped to the restored (empty) duration
+        assert_eq!(timeline.playhead, 0);
+
+        history.redo(&mut timeline).unwrap();
+        assert!(timeline.find_clip(2).is_some());
+        timeline.set_playhead(time::from_seconds(1.0));
+
+        // A failing edit leaves the timeline untouched
+        let result = history.execute(&mut timeline, TimelineEdit::new("Bad", move |tl| {
+            tl.remove_clip(1)?;
+            tl.add_clip(v1, clip(3, 1.0, 5.0))
+        }));
+        assert!(result.is_err());
+        assert!(timeline.find_clip(1).is_some());
+    }
+
+    #[test]
+    fn test_range_edits_report_locked_tracks() {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        let a1 = timeline.tracks[1].id;
+        timeline.add_clip(v1, clip(1, 0.0, 4.0)).unwrap();
+        timeline.add_clip(a1, clip(2, 0.0, 4.0)).unwrap();
+        timeline.track_mut(a1).unwrap().set_locked(true);
+        let mut history = History::new();
+
+        let (extract, skipped) = TimelineEdit::extract(time::from_seconds(1.0), time::from_seconds(2.0));
+        assert!(skipped.get().is_empty());
+        history.execute(&mut timeline, extract).unwrap();
+        assert_eq!(skipped.get(), vec![a1]);
+        assert_eq!(timeline.find_clip(2).unwrap().1.timeline_end, time::from_seconds(4.0));
+
+        let (insert, skipped) = TimelineEdit::insert(v1, clip(10, 0.0, 1.0));
+        history.execute(&mut timeline, insert).unwrap();
+        assert_eq!(skipped.get(), vec![a1]);
+
+        timeline.track_mut(a1).unwrap().set_locked(false);
+        let (lift, skipped) = TimelineEdit::lift(0, time::from_seconds(1.0));
+        history.execute(&mut timeline, lift).unwrap();
+        assert!(skipped.get().is_empty());
+    }
+
+    #[test]
+    fn test_remove_missing_marker_records_nothing() {
+        let mut timeline = Timeline::new();
+        let mut history = History::new();
+        let id = timeline.allocate_marker_id();
+        history.execute(&mut timeline, TimelineEdit::add_marker(Marker::new(id, 0, MarkerKind::Comment))).unwrap();
+        history.execute(&mut timeline, TimelineEdit::remove_marker(id)).unwrap();
+
+        assert_eq!(
+            history.execute(&mut timeline, TimelineEdit::remove_marker(id)),
+            Err(TrackError::MarkerNotFound { marker_id: id })
+        );
+        assert_eq!(history.undo_label(), Some("Remove Marker"));
+        history.undo(&mut timeline).unwrap();
+        assert_eq!(history.undo_label(), Some("Add Marker"));
+        assert_eq!(timeline.markers.len(), 1);
+    }
+
+    #[test]
+    fn test_caption_edits_undo() {
+        let mut timeline = Timeline::new();
+        let c1 = timeline.add_track(TrackType::Caption);
+        let mut history = History::new();
+        let cues = vec![
+            Cue::new(1, 0, time::from_seconds(2.0), "Hello"),
+            Cue::new(2, time::from_seconds(2.0), time::from_seconds(3.0), "World"),
+        ];
+        history.execute(&mut timeline, TimelineEdit::import_cues(c1, cues)).unwrap();
+        let mut edited = timeline.find_cue(2).unwrap().1.clone();
+        edited.text = "Everyone".to_string();
+        history.execute(&mut timeline, TimelineEdit::replace_cue(edited)).unwrap();
+        history.execute(&mut timeline, TimelineEdit::remove_cue(1)).unwrap();
+        assert_eq!(timeline.track(c1).unwrap().cues.len(), 1);
+
+        history.undo(&mut timeline).unwrap();
+        history.undo(&mut timeline).unwrap();
+        assert_eq!(timeline.find_cue(2).unwrap().1.text, "World");
+        history.undo(&mut timeline).unwrap();
+        assert!(timeline.track(c1).unwrap().cues.is_empty());
+        history.redo(&mut timeline).unwrap();
+        assert_eq!(timeline.duration, time::from_seconds(3.0));
+    }
+
+    #[test]
+    fn test_compound_and_nested_edit_undo() {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        timeline.add_clip(v1, clip(1, 0.0, 2.0)).unwrap();
+        timeline.add_clip(v1, clip(2, 2.0, 2.0)).unwrap();
+        let mut history = History::new();
+
+        history.execute(&mut timeline, TimelineEdit::compound(vec![1, 2], "Nest")).unwrap();
+        let sequence = *timeline.sequences.keys().next().unwrap();
+        let split = TimelineEdit::new("Split", |tl| tl.split_clip(1, time::from_seconds(1.0)).map(|_| ()));
+        history.execute(&mut timeline, TimelineEdit::in_sequence(sequence, split)).unwrap();
+        assert_eq!(history.undo_label(), Some("Split"));
+        assert_eq!(timeline.sequence(sequence).unwrap().timeline.tracks[0].clips.len(), 3);
+
+        history.undo(&mut timeline).unwrap();
+        assert_eq!(timeline.sequence(sequence).unwrap().timeline.tracks[0].clips.len(), 2);
+        history.undo(&mut timeline).unwrap();
+        assert!(timeline.sequences.is_empty());
+        assert!(timeline.find_clip(2).is_some());
+    }
+}
@@ -0,0 +1,302 @@
+//! Recorded changes between two states of a timeline.
+//!
+//! `TimelineEdit` runs an arbitrary edit once and keeps only what it changed,
+//! so the undo history grows with the size of the edits instead of the size
+//! of the timeline. Lists (tracks, clips, transitions, cues, markers) are
+//! compared by their common start and end; the run in between is kept as a
+//! [`Splice`] holding the items before and after the edit.
+
+use std::mem;
+use crate::timeline::clip::Clip;
+use crate::timeline::caption::Cue;
+use crate::timeline::marker::Marker;
+use crate::timeline::sequence::{Sequence, SequenceId};
+use crate::timeline::timeline::{IdCounters, Timeline};
+use crate::timeline::track::Track;
+use crate::timeline::transition::Transition;
+use crate::core::time::Time;
+
+/// A run of a list replaced by an edit: `before` at index `at` became `after`.
+#[derive(Debug, Clone)]
+struct Splice<T> {
+    at: usize,
+    before: Vec<T>,
+    after: Vec<T>,
+}
+
+impl<T: Clone + PartialEq> Splice<T> {
+    /// The smallest run that differs between two lists, or `None` if they are equal.
+    fn between(before: &[T], after: &[T]) -> Option<Self> {
+        let prefix = before.iter().zip(after).take_while(|(a, b)| a == b).count();
+        if prefix == before.len() && prefix == after.len() {
+            return None;
+        }
+        let suffix = before[prefix..]
+            .iter()
+            .rev()
+            .zip(after[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        Some(Self {
+            at: prefix,
+            before: before[prefix..before.len() - suffix].to_vec(),
+            after: after[prefix..after.len() - suffix].to_vec(),
+        })
+    }
+
+    /// Swap the run into `list`: the "after" run if `forward`, otherwise
+    /// the "before" run. `list` must hold the other one.
+    fn apply(&self, list: &mut Vec<T>, forward: bool) {
+        let (current, replacement) = if forward { (&self.before, &self.after) } else { (&self.after, &self.before) };
+        let end = (self.at + current.len()).min(list.len());
+        list.splice(self.at.min(end)..end, replacement.iter().cloned());
+    }
+}
+
+/// Apply an optional splice (see `Splice::apply`).
+fn apply_splice<T: Clone + PartialEq>(splice: &Option<Splice<T>>, list: &mut Vec<T>, forward: bool) {
+    if let Some(splice) = splice {
+        splice.apply(list, forward);
+    }
+}
+
+/// Pick the "after" side of a pair if `forward`, otherwise the "before" side.
+fn side<T>(pair: &(T, T), forward: bool) -> &T {
+    if forward { &pair.1 } else { &pair.0 }
+}
+
+/// A track's settings: the track without its clips, transitions and cues.
+fn settings(track: &Track) -> Track {
+    Track {
+        clips: Vec::new(),
+        transitions: Vec::new(),
+        cues: Vec::new(),
+        ..track.clone()
+    }
+}
+
+/// Changes inside a track that kept its place in the track list.
+#[derive(Debug, Clone)]
+struct TrackDiff {
+    index: usize,
+    settings: Option<(Track, Track)>,
+    clips: Option<Splice<Clip>>,
+    transitions: Option<Splice<Transition>>,
+    cues: Option<Splice<Cue>>,
+}
+
+impl TrackDiff {
+    fn between(index: usize, before: &Track, after: &Track) -> Self {
+        let (before_settings, after_settings) = (settings(before), settings(after));
+        Self {
+            index,
+            settings: (before_settings != after_settings).then_some((before_settings, after_settings)),
+            clips: Splice::between(&before.clips, &after.clips),
+            transitions: Splice::between(&before.transitions, &after.transitions),
+            cues: Splice::between(&before.cues, &after.cues),
+        }
+    }
+
+    fn apply(&self, track: &mut Track, forward: bool) {
+        if let Some(pair) = &self.settings {
+            let clips = mem::take(&mut track.clips);
+            let transitions = mem::take(&mut track.transitions);
+            let cues = mem::take(&mut track.cues);
+            *track = Track { clips, transitions, cues, ..side(pair, forward).clone() };
+        }
+        apply_splice(&self.clips, &mut track.clips, forward);
+        apply_splice(&self.transitions, &mut track.transitions, forward);
+        apply_splice(&self.cues, &mut track.cues, forward);
+    }
+}
+
+/// A change to one entry of the sequence registry.
+#[derive(Debug, Clone)]
+enum SequenceDiff {
+    /// The sequence exists on one side only: `(before, after)`
+    Replaced(Option<Box<Sequence>>, Option<Box<Sequence>>),
+    Edited {
+        id: SequenceId,
+        name: Option<(String, String)>,
+        timeline: Box<TimelineDiff>,
+    },
+}
+
+/// Everything an edit changed on a timeline and its nested sequences.
+///
+/// The playhead is not recorded; it stays where it is, clamped to the new
+/// duration.
+#[derive(Debug, Clone)]
+pub(crate) struct TimelineDiff {
+    /// Whole tracks, when tracks were added, removed or reordered
+    tracks: Option<Splice<Track>>,
+    /// Otherwise the changes inside each track
+    track_changes: Vec<TrackDiff>,
+    markers: Option<Splice<Marker>>,
+    sequences: Vec<SequenceDiff>,
+    duration: (Time, Time),
+    counters: (IdCounters, IdCounters),
+}
+
+impl TimelineDiff {
+    /// Record what changed from `before` to `after`.
+    pub(crate) fn between(before: &Timeline, after: &Timeline) -> Self {
+        let same_tracks = before.tracks.iter().map(|t| t.id).eq(after.tracks.iter().map(|t| t.id));
+        let (tracks, track_changes) = if same_tracks {
+            let changes = before.tracks
+                .iter()
+                .zip(&after.tracks)
+                .enumerate()
+                .filter(|(_, (b, a))| b != a)
+                .map(|(index, (b, a))| TrackDiff::between(index, b, a))
+                .collect();
+            (None, changes)
+        } else {
+            (Splice::between(&before.tracks, &after.tracks), Vec::new())
+        };
+
+        let mut sequences = Vec::new();
+        for (id, old) in &before.sequences {
+            match after.sequences.get(id) {
+                None => sequences.push(SequenceDiff::Replaced(Some(Box::new(old.clone())), None)),
+                Some(new) => {
+                    let timeline = Self::between(&old.timeline, &new.timeline);
+                    let name = (old.name != new.name).then(|| (old.name.clone(), new.name.clone()));
+                    if name.is_some() || !timeline.is_empty() {
+                        sequences.push(SequenceDiff::Edited { id: *id, name, timeline: Box::new(timeline) });
+                    }
+                }
+            }
+        }
+        for (id, new) in &after.sequences {
+            if !before.sequences.contains_key(id) {
+                sequences.push(SequenceDiff::Replaced(None, Some(Box::new(new.clone()))));
+            }
+        }
+
+        Self {
+            tracks,
+            track_changes,
+            markers: Splice::between(&before.markers, &after.markers),
+            sequences,
+            duration: (before.duration, after.duration),
+            counters: (before.ids, after.ids),
+        }
+    }
+
+    /// Whether the edit changed nothing.
+    fn is_empty(&self) -> bool {
+        self.tracks.is_none()
+            && self.track_changes.is_empty()
+            && self.markers.is_none()
+            && self.sequences.is_empty()
+            && self.duration.0 == self.duration.1
+            && self.counters.0 == self.counters.1
+    }
+
+    /// Turn a timeline in the "before" state into the "after" state.
+    pub(crate) fn redo(&self, timeline: &mut Timeline) {
+        self.apply(timeline, true);
+    }
+
+    /// Turn a timeline in the "after" state back into the "before" state.
+    pub(crate) fn undo(&self, timeline: &mut Timeline) {
+        self.apply(timeline, false);
+    }
+
+    fn apply(&self, timeline: &mut Timeline, forward: bool) {
+        apply_splice(&self.tracks, &mut timeline.tracks, forward);
+        for change in &self.track_changes {
+            if let Some(track) = timeline.tracks.get_mut(change.index) {
+                change.apply(track, forward);
+            }
+        }
+        apply_splice(&self.markers, &mut timeline.markers, forward);
+        for change in &self.sequences {
+            match change {
+                SequenceDiff::Replaced(before, after) => {
+                    let (current, replacement) = if forward { (before, after) } else { (after, before) };
+                    if let Some(sequence) = current {
+                        timeline.sequences.remove(&sequence.id);
+                    }
+                    if let Some(sequence) = replacement {
+                        timeline.sequences.insert(sequence.id, Sequence::clone(sequence));
+                    }
+                }
+                SequenceDiff::Edited { id, name, timeline: diff } => {
+                    if let Some(sequence) = timeline.sequences.get_mut(id) {
+                        if let Some(pair) = name {
+                            sequence.name = side(pair, forward).clone();
+                        }
+                        diff.apply(&mut sequence.timeline, forward);
+                    }
+                }
+            }
+        }
+        timeline.duration = *side(&self.duration, forward);
+        timeline.ids = *side(&self.counters, forward);
+        timeline.set_playhead(timeline.playhead);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::time;
+    use crate::timeline::track::TrackType;
+    use std::path::PathBuf;
+
+    fn clip(id: u64, start: f64) -> Clip {
+        Clip::new(id, PathBuf::from("test.mp4"), 0, time::from_seconds(1.0), time::from_seconds(start), 0)
+    }
+
+    #[test]
+    fn test_splice_keeps_only_the_changed_run() {
+        let splice = Splice::between(&[1, 2, 3, 4, 5], &[1, 2, 9, 5]).unwrap();
+        assert_eq!((splice.at, splice.before.clone(), splice.after.clone()), (2, vec![3, 4], vec![9]));
+        let mut list = vec![1, 2, 9, 5];
+        splice.apply(&mut list, false);
+        assert_eq!(list, vec![1, 2, 3, 4, 5]);
+        splice.apply(&mut list, true);
+        assert_eq!(list, vec![1, 2, 9, 5]);
+        assert!(Splice::between(&[1, 2], &[1, 2]).is_none());
+    }
+
+    #[test]
+    fn test_diff_round_trip() {
+        let mut before = Timeline::new();
+        let v1 = before.tracks[0].id;
+        for (id, start) in [(1, 0.0), (2, 1.0), (3, 2.0), (4, 3.0)] {
+            before.add_clip(v1, clip(id, start)).unwrap();
+        }
+        let kept = before.add_sequence("Kept", Timeline::new()).unwrap();
+        let removed = before.add_sequence("Removed", Timeline::new()).unwrap();
+
+        let mut after = before.clone();
+        after.extract(time::from_seconds(1.0), time::from_seconds(2.0));
+        after.sequence_mut(kept).unwrap().name = "Renamed".to_string();
+        after.sequences.remove(&removed);
+        let diff = TimelineDiff::between(&before, &after);
+        // Clip 1 is unchanged and not recorded
+        let clips = diff.track_changes[0].clips.as_ref().unwrap();
+        assert_eq!(clips.before.iter().map(|c| c.id).collect::<Vec<_>>(), vec![2, 3, 4]);
+
+        let mut timeline = after.clone();
+        diff.undo(&mut timeline);
+        assert_eq!(timeline.tracks, before.tracks);
+        assert_eq!(timeline.sequence(kept).unwrap().name, "Kept");
+        assert!(timeline.sequence(removed).is_some());
+        diff.redo(&mut timeline);
+        assert_eq!(timeline.tracks, after.tracks);
+        assert_eq!(timeline.duration, after.duration);
+        assert!(timeline.sequence(removed).is_none());
+
+        // Added tracks are recorded whole, along with the ID counters
+        let mut added = after.clone();
+        added.add_track(TrackType::Caption);
+        let diff = TimelineDiff::between(&after, &added);
+        diff.undo(&mut timeline);
+        assert_eq!(timeline.tracks, after.tracks);
+        assert_eq!(timeline.add_track(TrackType::Caption), added.tracks.last().unwrap().id);
+    }
+}
@@ -0,0 +1,715 @@
+//! Insert, overwrite, lift and extract edits, gap handling, and three-point
+//! edit solving.
+//!
+//! `Track::add_clip` rejects overlaps; the edits here make room first, either
+//! by pushing later clips right (insert) or by trimming/splitting whatever is
+//! underneath (overwrite).
+
+use std::fmt;
+use std::path::PathBuf;
+use crate::timeline::clip::{Clip, ClipId};
+use crate::timeline::timeline::Timeline;
+use crate::timeline::track::{Track, TrackError, TrackId};
+use crate::core::time::{Time, TimeRange};
+
+impl Track {
+    /// Remove everything in the timeline range `[start, end)`.
+    ///
+    /// Clips inside the range are removed, clips straddling an edge are trimmed
+    /// to it, and a clip spanning the whole range is split in two: the part after
+    /// the range gets `split_id`. Other clips do not move, so a gap is left.
+    /// Cues are cleared the same way, except that a cue spanning the whole
+    /// range is kept whole.
+    ///
+    /// Returns the removed pieces in timeline order.
+    pub fn clear_range(&mut self, start: Time, end: Time, split_id: ClipId) -> Vec<Clip> {
+        let mut removed = Vec::new();
+        if end <= start {
+            return removed;
+        }
+        self.clear_cue_range(start, end);
+
+        // Only the clips overlapping the range are taken out and put back
+        let first = self.clips.partition_point(|c| c.timeline_end <= start);
+        let last = self.clips.partition_point(|c| c.timeline_start < end).max(first);
+        let mut kept = Vec::new();
+        for mut clip in self.clips.drain(first..last).collect::<Vec<_>>() {
+            let original_id = clip.id;
+            let mut tail = clip.split_at(end, split_id);
+            let head = clip
+                .split_at(start, original_id)
+                .map(|middle| std::mem::replace(&mut clip, middle));
+            if head.is_none() {
+                // Only the end is trimmed: the surviving part keeps the ID
+                if let Some(tail) = &mut tail {
+                    tail.id = original_id;
+                }
+            }
+
+            kept.extend(head);
+            removed.push(clip);
+            kept.extend(tail);
+        }
+        self.clips.splice(first..first, kept);
+        self.drop_stray_transitions();
+        removed
+    }
+
+    /// The `(head, tail)` clip IDs if `clear_range` cut a clip spanning the
+    /// whole range in two, giving the tail `split_id`.
+    fn range_cut(&self, split_id: ClipId) -> Option<(ClipId, ClipId)> {
+        let index = self.clips.iter().position(|c| c.id == split_id)?;
+        Some((self.clips[index.checked_sub(1)?].id, split_id))
+    }
+
+    /// Move every clip starting at or after `position` by `delta`, and every
+    /// cue edge at or after it (a cue spanning `position` is stretched).
+    ///
+    /// The caller must ensure no clip straddles `position` and, for negative
+    /// `delta`, that the space being closed is empty.
+    pub fn ripple_from(&mut self, position: Time, delta: Time) {
+        let first = self.clips.partition_point(|c| c.timeline_start < position);
+        for clip in &mut self.clips[first..] {
+            clip.set_timeline_start(clip.timeline_start + delta);
+        }
+        self.ripple_cues_from(position, delta);
+    }
+
+    /// Open an empty span of `length` at `position`: split the clip under it
+    /// (the right half gets `split_id`) and ripple everything from `position`
+    /// right (see `ripple_from`).
+    ///
+    /// Returns the `(left, right)` clip IDs if a clip was split.
+    pub fn open_gap(&mut self, position: Time, length: Time, split_id: ClipId) -> Option<(ClipId, ClipId)> {
+        let index = self.clips.partition_point(|c| c.timeline_end <= position);
+        let right = self.clips.get_mut(index).and_then(|clip| clip.split_at(position, split_id));
+        let cut = right.map(|right| {
+            self.clips.insert(index + 1, right);
+            (self.clips[index].id, split_id)
+        });
+        self.ripple_from(position, length);
+        cut
+    }
+
+    /// Put `clip` into an empty span opened by `open_gap`.
+    fn place_in_gap(&mut self, clip: Clip) {
+        let index = self.clips.partition_point(|c| c.timeline_start < clip.timeline_start);
+        self.clips.insert(index, clip);
+    }
+
+    /// Check that `clip` can be insert edited onto this track.
+    fn check_insert(&self, clip: &Clip) -> Result<(), TrackError> {
+        self.check_unlocked()?;
+        self.check_holds_clips()?;
+        if clip.timeline_end <= clip.timeline_start {
+            return Err(TrackError::ClipTooShort { clip_id: clip.id });
+        }
+        Ok(())
+    }
+
+    /// Insert edit: place `clip` at its `timeline_start`, pushing later clips right.
+    ///
+    /// A clip under the insert point is split; its right half gets `split_id`.
+    /// The track is checked before anything moves, so on error it is unchanged.
+    pub fn insert(&mut self, clip: Clip, split_id: ClipId) -> Result<(), TrackError> {
+        self.check_insert(&clip)?;
+        self.open_gap(clip.timeline_start, clip.timeline_end - clip.timeline_start, split_id);
+        self.place_in_gap(clip);
+        Ok(())
+    }
+
+    /// Overwrite edit: place `clip` at its `timeline_start`, replacing whatever
+    /// is underneath (see `clear_range`).
+    pub fn overwrite(&mut self, clip: Clip, split_id: ClipId) -> Result<(), TrackError> {
+        self.check_unlocked()?;
+        self.check_holds_clips()?;
+        self.clear_range(clip.timeline_start, clip.timeline_end, split_id);
+        self.add_clip(clip)
+    }
+
+    /// Empty spans on the track, in timeline order.
+    ///
+    /// Includes the span before the first clip if it does not start at 0;
+    /// the open end after the last clip is not a gap.
+    pub fn gaps(&self) -> Vec<TimeRange> {
+        let mut gaps = Vec::new();
+        let mut cursor: Time = 0;
+        for clip in &self.clips {
+            if clip.timeline_start > cursor {
+                gaps.push(TimeRange::new(cursor, clip.timeline_start));
+            }
+            cursor = cursor.max(clip.timeline_end);
+        }
+        gaps
+    }
+
+    /// Move clips left so each starts where the previous one ends (the first at 0).
+    pub fn close_gaps(&mut self) {
+        let mut cursor: Time = 0;
+        for clip in &mut self.clips {
+            if clip.timeline_start > cursor {
+                clip.set_timeline_start(cursor);
+            }
+            cursor = clip.timeline_end;
+        }
+    }
+}
+
+impl Timeline {
+    /// Run a track edit that may need one fresh clip ID for a split.
+    ///
+    /// Fails with `TrackError::TrackLocked` before editing a locked track.
+    fn edit_track_with_split<T>(
+        &mut self,
+        track_id: TrackId,
+        edit: impl FnOnce(&mut Track, ClipId) -> Result<T, TrackError>,
+    ) -> Result<T, TrackError> {
+        let split_id = self.peek_clip_id();
+        let track = self.track_mut(track_id)
+            .ok_or(TrackError::TrackNotFound { track_id })?;
+        track.check_unlocked()?;
+        let result = edit(track, split_id)?;
+        if self.find_clip(split_id).is_some() {
+            self.reserve_clip_id(split_id);
+        }
+        self.update_duration();
+        Ok(result)
+    }
+
+    /// Insert edit: place `clip` on `track_id` at its `timeline_start` and
+    /// push everything after that point right by the clip's duration, on every
+    /// unlocked track so the tracks stay in sync.
+    ///
+    /// Clips under the insert point are split (linked halves stay linked), and
+    /// later cues and timeline markers move too. Locked tracks are left in
+    /// place; their IDs are returned so the caller can warn about lost sync.
+    pub fn insert_edit(&mut self, track_id: TrackId, clip: Clip) -> Result<Vec<TrackId>, TrackError> {
+        self.track(track_id)
+            .ok_or(TrackError::TrackNotFound { track_id })?
+            .check_insert(&clip)?;
+        self.reserve_clip_id(clip.id);
+        let position = clip.timeline_start;
+        let length = clip.timeline_end - clip.timeline_start;
+
+        let mut cuts = Vec::new();
+        let mut skipped = Vec::new();
+        for index in 0..self.tracks.len() {
+            if self.tracks[index].locked {
+                skipped.push(self.tracks[index].id);
+                continue;
+            }
+            let split_id = self.peek_clip_id();
+            if let Some(cut) = self.tracks[index].open_gap(position, length, split_id) {
+                self.reserve_clip_id(split_id);
+                cuts.push(cut);
+            }
+        }
+        self.relink_split_halves(&cuts);
+        self.ripple_markers_from(position, length);
+        if let Some(track) = self.track_mut(track_id) {
+            track.place_in_gap(clip);
+        }
+        self.update_duration();
+        Ok(skipped)
+    }
+
+    /// Overwrite edit on one track: place `clip` at its `timeline_start`,
+    /// trimming, splitting or removing whatever it covers.
+    ///
+    /// A clip split in two keeps its links on the left half; as the linked
+    /// clips on other tracks are not cut, the right half is not linked to
+    /// anything (as with `split_clip`). Returns the pieces of clips that were
+    /// overwritten.
+    pub fn overwrite_edit(&mut self, track_id: TrackId, clip: Clip) -> Result<Vec<Clip>, TrackError> {
+        self.reserve_clip_id(clip.id);
+        let (removed, cut) = self.edit_track_with_split(track_id, |track, split_id| {
+            track.check_holds_clips()?;
+            let removed = track.clear_range(clip.timeline_start, clip.timeline_end, split_id);
+            let cut = track.range_cut(split_id);
+            track.add_clip(clip)?;
+            Ok((removed, cut))
+        })?;
+        if let Some(tail) = cut.and_then(|(_, tail)| self.clip_mut(tail)) {
+            tail.link_group = None;
+        }
+        Ok(removed)
+    }
+
+    /// Lift: remove everything between `start` and `end` on all unlocked
+    /// tracks, leaving a gap.
+    ///
+    /// Clips straddling the range boundaries are split; the parts after a
+    /// range cut out of linked clips are linked to each other, not to the
+    /// parts before it. Locked tracks are left as they are and listed in the
+    /// result so the caller can warn about them.
+    pub fn lift(&mut self, start: Time, end: Time) -> RangeEdit {
+        let mut result = RangeEdit::default();
+        let mut cuts = Vec::new();
+        let track_ids: Vec<(TrackId, bool)> = self.tracks.iter().map(|t| (t.id, t.locked)).collect();
+        for (track_id, locked) in track_ids {
+            if locked {
+                result.skipped.push(track_id);
+                continue;
+            }
+            let pieces = self.edit_track_with_split(track_id, |track, split_id| {
+                let removed = track.clear_range(start, end, split_id);
+                Ok((removed, track.range_cut(split_id)))
+            });
+            let (removed, cut) = pieces.expect("unlocked track exists");
+            result.removed.extend(removed);
+            cuts.extend(cut);
+        }
+        self.relink_split_halves(&cuts);
+        result
+    }
+
+    /// Extract: remove everything between `start` and `end` on all unlocked
+    /// tracks and ripple their later clips left to close the hole.
+    ///
+    /// Timeline markers after the range move left with the clips, and markers
+    /// inside it move to `start`. Locked tracks do not move, so they fall out
+    /// of sync with the others; they are listed in the result.
+    pub fn extract(&mut self, start: Time, end: Time) -> RangeEdit {
+        let result = self.lift(start, end);
+        if end > start {
+            for track in self.tracks.iter_mut().filter(|t| !t.locked) {
+                track.ripple_from(end, start - end);
+            }
+            for marker in self.markers.iter_mut().filter(|m| start < m.position && m.position < end) {
+                marker.position = start;
+            }
+            self.ripple_markers_from(end, start - end);
+            self.update_duration();
+        }
+        result
+    }
+
+    /// Close all gaps on a track (see `Track::close_gaps`).
+    pub fn close_gaps(&mut self, track_id: TrackId) -> Result<(), TrackError> {
+        let track = self.track_mut(track_id)
+            .ok_or(TrackError::TrackNotFound { track_id })?;
+        track.check_unlocked()?;
+        track.close_gaps();
+        self.update_duration();
+        Ok(())
+    }
+
+    /// Spans within the timeline where no video track has a clip.
+    ///
+    /// These export as black frames.
+    pub fn video_gaps(&self) -> Vec<TimeRange> {
+        let mut covered: Vec<(Time, Time)> = self.video_tracks()
+            .flat_map(|t| t.clips.iter())
+            .map(|c| (c.timeline_start, c.timeline_end))
+            .collect();
+        covered.sort_unstable();
+
+        let mut gaps = Vec::new();
+        let mut cursor: Time = 0;
+        for (start, end) in covered {
+            if start > cursor {
+                gaps.push(TimeRange::new(cursor, start));
+            }
+            cursor = cursor.max(end);
+        }
+        if cursor < self.duration {
+            gaps.push(TimeRange::new(cursor, self.duration));
+        }
+        gaps
+    }
+}
+
+/// Error type for three-point edit solving
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditPointError {
+    /// Fewer than three of the four edit points were given
+    NotEnoughPoints,
+    /// All four points were given but the source and record durations differ
+    DurationMismatch { source: Time, record: Time },
+    /// The resulting range is empty or reversed
+    EmptyRange,
+}
+
+impl fmt::Display for EditPointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EditPointError::NotEnoughPoints => {
+                write!(f, "At least three of source in/out and record in/out are required")
+            }
+            EditPointError::DurationMismatch { source, record } => {
+                write!(f, "Source duration {}ns does not match record duration {}ns", source, record)
+            }
+            EditPointError::EmptyRange => write!(f, "Edit range is empty"),
+        }
+    }
+}
+
+impl std::error::Error for EditPointError {}
+
+/// What a lift or extract did.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RangeEdit {
+    pub removed: Vec<Clip>,    // Removed clip pieces, track by track
+    pub skipped: Vec<TrackId>, // Locked tracks that were left untouched
+}
+
+/// Edit points for a three-point edit; leave the unknown point as `None`.
+///
+/// Source points are in source media time, record points in timeline time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ThreePointEdit {
+    pub source_in: Option<Time>,
+    pub source_out: Option<Time>,
+    pub record_in: Option<Time>,
+    pub record_out: Option<Time>,
+}
+
+/// Fully resolved edit points (source and record ranges of equal length).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EditPoints {
+    pub source_in: Time,
+    pub source_out: Time,
+    pub record_in: Time,
+    pub record_out: Time,
+}
+
+impl ThreePointEdit {
+    /// Compute the missing edit point.
+    ///
+    /// Source and record durations are equal (no speed change). If all four
+    /// points are given they must agree.
+    pub fn solve(&self) -> Result<EditPoints, EditPointError> {
+        let (source_in, source_out, record_in, record_out) = match *self {
+            ThreePointEdit {
+                source_in: Some(si), source_out: Some(so), record_in: Some(ri), record_out: Some(ro),
+            } => {
+                if so - si != ro - ri {
+                    return Err(EditPointError::DurationMismatch { source: so - si, record: ro - ri });
+                }
+                (si, so, ri, ro)
+            }
+            ThreePointEdit { source_in: Some(si), source_out: Some(so), record_in: Some(ri), .. } => {
+                (si, so, ri, ri + (so - si))
+            }
+            ThreePointEdit { source_in: Some(si), source_out: Some(so), record_out: Some(ro), .. } => {
+                (si, so, ro - (so - si), ro)
+            }
+            ThreePointEdit { source_in: Some(si), record_in: Some(ri), record_out: Some(ro), .. } => {
+                (si, si + (ro - ri), ri, ro)
+            }
+            ThreePointEdit { source_out: Some(so), record_in: Some(ri), record_out: Some(ro), .. } => {
+                (so - (ro - ri), so, ri, ro)
+            }
+            _ => return Err(EditPointError::NotEnoughPoints),
+        };
+
+        if source_out <= source_in {
+            return Err(EditPointError::EmptyRange);
+        }
+        Ok(EditPoints { source_in, source_out, record_in, record_out })
+    }
+}
+
+impl EditPoints {
+    /// Length of the edit in nanoseconds.
+    pub fn duration(&self) -> Time {
+        self.source_out - self.source_in
+    }
+
+    /// Build the clip these edit points describe.
+    pub fn to_clip(&self, id: ClipId, source_path: PathBuf, stream_index: usize) -> Clip {
+        Clip::new(id, source_path, self.source_in, self.source_out, self.record_in, stream_index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::time;
+    use crate::timeline::marker::{Marker, MarkerKind};
+    use crate::timeline::track::TrackType;
+
+    fn clip(id: ClipId, start: f64, length: f64) -> Clip {
+        Clip::new(
+            id,
+            PathBuf::from("test.mp4"),
+            time::from_seconds(10.0),
+            time::from_seconds(10.0 + length),
+            time::from_seconds(start),
+            0,
+        )
+    }
+
+    fn layout(timeline: &Timeline, track_id: TrackId) -> Vec<(ClipId, Time, Time)> {
+        timeline.track(track_id).unwrap().clips
+            .iter()
+            .map(|c| (c.id, c.timeline_start, c.timeline_end))
+            .collect()
+    }
+
+    /// V1: clip 1 at [0, 4), clip 2 at [6, 10)
+    fn timeline() -> (Timeline, TrackId) {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        timeline.add_clip(v1, clip(1, 0.0, 4.0)).unwrap();
+        timeline.add_clip(v1, clip(2, 6.0, 4.0)).unwrap();
+        (timeline, v1)
+    }
+
+    #[test]
+    fn test_insert_splits_and_pushes_right() {
+        let (mut timeline, v1) = timeline();
+        timeline.insert_edit(v1, clip(10, 1.0, 2.0)).unwrap();
+
+        assert_eq!(
+            layout(&timeline, v1),
+            vec![(1, 0, time::from_seconds(1.0)), (10, time::from_seconds(1.0), time::from_seconds(3.0)), (11, time::from_seconds(3.0), time::from_seconds(6.0)), (2, time::from_seconds(8.0), time::from_seconds(12.0))]
+        );
+        let right = timeline.find_clip(11).unwrap().1;
+        assert_eq!(right.in_point, time::from_seconds(11.0));
+        assert_eq!(timeline.duration, time::from_seconds(12.0));
+        assert_eq!(timeline.allocate_clip_id(), 12);
+    }
+
+    #[test]
+    fn test_insert_at_edit_point_does_not_split() {
+        let (mut timeline, v1) = timeline();
+        timeline.insert_edit(v1, clip(10, 4.0, 1.0)).unwrap();
+        assert_eq!(
+            layout(&timeline, v1),
+            vec![(1, 0, time::from_seconds(4.0)), (10, time::from_seconds(4.0), time::from_seconds(5.0)), (2, time::from_seconds(7.0), time::from_seconds(11.0))]
+        );
+    }
+
+    #[test]
+    fn test_insert_ripples_every_unlocked_track() {
+        let (mut timeline, v1) = timeline();
+        let a1 = timeline.audio_tracks().next().unwrap().id;
+        timeline.add_clip(a1, clip(3, 0.0, 4.0)).unwrap();
+        timeline.link(&[1, 3]).unwrap();
+        let v2 = timeline.add_track(TrackType::Video);
+        timeline.add_clip(v2, clip(4, 2.0, 4.0)).unwrap();
+        timeline.track_mut(v2).unwrap().set_locked(true);
+        let marker = timeline.allocate_marker_id();
+        timeline.add_marker(Marker::new(marker, time::from_seconds(5.0), MarkerKind::Comment));
+
+        let skipped = timeline.insert_edit(v1, clip(10, 1.0, 2.0)).unwrap();
+        assert_eq!(skipped, vec![v2]);
+        assert_eq!(
+            layout(&timeline, a1),
+            vec![(3, 0, time::from_seconds(1.0)), (12, time::from_seconds(3.0), time::from_seconds(6.0))]
+        );
+        // The right halves are linked to each other, not to the left halves
+        let group = timeline.find_clip(11).unwrap().1.link_group;
+        assert!(group.is_some());
+        assert_eq!(timeline.find_clip(12).unwrap().1.link_group, group);
+        assert_ne!(timeline.find_clip(1).unwrap().1.link_group, group);
+        assert_eq!(timeline.markers[0].position, time::from_seconds(7.0));
+        assert_eq!(layout(&timeline, v2), vec![(4, time::from_seconds(2.0), time::from_seconds(6.0))]);
+
+        // A rejected insert moves nothing
+        let mut empty = clip(20, 1.0, 1.0);
+        empty.timeline_end = empty.timeline_start;
+        assert_eq!(timeline.insert_edit(v1, empty), Err(TrackError::ClipTooShort { clip_id: 20 }));
+        assert_eq!(timeline.insert_edit(v2, clip(20, 0.0, 1.0)), Err(TrackError::TrackLocked { track_id: v2 }));
+        assert_eq!(timeline.find_clip(2).unwrap().1.timeline_start, time::from_seconds(8.0));
+    }
+
+    #[test]
+    fn test_overwrite_trims_covered_clips() {
+        let (mut timeline, v1) = timeline();
+        let removed = timeline.overwrite_edit(v1, clip(10, 3.0, 4.0)).unwrap();
+
+        assert_eq!(
+            layout(&timeline, v1),
+            vec![(1, 0, time::from_seconds(3.0)), (10, time::from_seconds(3.0), time::from_seconds(7.0)), (2, time::from_seconds(7.0), time::from_seconds(10.0))]
+        );
+        // Clip 2 lost its first second of source
+        assert_eq!(timeline.find_clip(2).unwrap().1.in_point, time::from_seconds(11.0));
+        assert_eq!(timeline.find_clip(1).unwrap().1.out_point, time::from_seconds(13.0));
+        assert_eq!(removed.len(), 2);
+        assert_eq!(removed[0].timeline_start, time::from_seconds(3.0));
+        assert_eq!(removed[1].timeline_end, time::from_seconds(7.0));
+    }
+
+    #[test]
+    fn test_overwrite_inside_clip_splits() {
+        let (mut timeline, v1) = timeline();
+        timeline.overwrite_edit(v1, clip(10, 1.0, 1.0)).unwrap();
+        assert_eq!(
+            layout(&timeline, v1),
+            vec![(1, 0, time::from_seconds(1.0)), (10, time::from_seconds(1.0), time::from_seconds(2.0)), (11, time::from_seconds(2.0), time::from_seconds(4.0)), (2, time::from_seconds(6.0), time::from_seconds(10.0))]
+        );
+        assert_eq!(timeline.find_clip(11).unwrap().1.in_point, time::from_seconds(12.0));
+    }
+
+    #[test]
+    fn test_overwrite_replaces_whole_clips() {
+        let (mut timeline, v1) = timeline();
+        let removed = timeline.overwrite_edit(v1, clip(10, 0.0, 12.0)).unwrap();
+        assert_eq!(layout(&timeline, v1), vec![(10, 0, time::from_seconds(12.0))]);
+        assert_eq!(removed.iter().map(|c| c.id).collect::<Vec<_>>(), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_edit_on_missing_track() {
+        let (mut timeline, _) = timeline();
+        assert_eq!(
+            timeline.insert_edit(99, clip(10, 0.0, 1.0)),
+            Err(TrackError::TrackNotFound { track_id: 99 })
+        );
+        let mut track = Track::new(5, TrackType::Audio);
+        track.insert(clip(1, 0.0, 1.0), 2).unwrap();
+        assert_eq!(track.clips.len(), 1);
+    }
+
+    #[test]
+    fn test_gaps_and_close_gaps() {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        timeline.add_clip(v1, clip(1, 1.0, 4.0)).unwrap();
+        timeline.add_clip(v1, clip(2, 6.0, 4.0)).unwrap();
+        timeline.add_clip(v1, clip(3, 12.0, 1.0)).unwrap();
+
+        let gaps = timeline.track(v1).unwrap().gaps();
+        assert_eq!(gaps, vec![TimeRange::new(0, time::from_seconds(1.0)), TimeRange::new(time::from_seconds(5.0), time::from_seconds(6.0)), TimeRange::new(time::from_seconds(10.0), time::from_seconds(12.0))]);
+
+        timeline.close_gaps(v1).unwrap();
+        assert_eq!(
+            layout(&timeline, v1),
+            vec![(1, 0, time::from_seconds(4.0)), (2, time::from_seconds(4.0), time::from_seconds(8.0)), (3, time::from_seconds(8.0), time::from_seconds(9.0))]
+        );
+        assert!(timeline.track(v1).unwrap().gaps().is_empty());
+        assert_eq!(timeline.duration, time::from_seconds(9.0));
+    }
+
+    #[test]
+    fn test_video_gaps() {
+        let (mut timeline, v1) = timeline();
+        let v2 = timeline.add_track(TrackType::Video);
+        let a1 = timeline.audio_tracks().next().unwrap().id;
+        timeline.add_clip(v2, clip(3, 3.0, 2.0)).unwrap();
+        timeline.add_clip(a1, clip(4, 0.0, 14.0)).unwrap();
+
+        // V1 covers [0,4) and [6,10), V2 covers [3,5): gaps [5,6) and the tail [10,14)
+        assert_eq!(timeline.video_gaps(), vec![TimeRange::new(time::from_seconds(5.0), time::from_seconds(6.0)), TimeRange::new(time::from_seconds(10.0), time::from_seconds(14.0))]);
+        assert_eq!(timeline.track(v1).unwrap().gaps(), vec![TimeRange::new(time::from_seconds(4.0), time::from_seconds(6.0))]);
+    }
+
+    #[test]
+    fn test_lift_leaves_gap() {
+        let (mut timeline, v1) = timeline();
+        let a1 = timeline.audio_tracks().next().unwrap().id;
+        timeline.add_clip(a1, clip(3, 0.0, 10.0)).unwrap();
+
+        let lifted = timeline.lift(time::from_seconds(2.0), time::from_seconds(7.0));
+        assert_eq!(lifted.removed.len(), 3);
+        assert!(lifted.skipped.is_empty());
+        assert_eq!(
+            layout(&timeline, v1),
+            vec![(1, 0, time::from_seconds(2.0)), (2, time::from_seconds(7.0), time::from_seconds(10.0))]
+        );
+        // The audio clip spanned the range and was split
+        assert_eq!(layout(&timeline, a1), vec![(3, 0, time::from_seconds(2.0)), (4, time::from_seconds(7.0), time::from_seconds(10.0))]);
+        assert_eq!(timeline.find_clip(4).unwrap().1.in_point, time::from_seconds(17.0));
+        assert_eq!(timeline.duration, time::from_seconds(10.0));
+    }
+
+    #[test]
+    fn test_lift_and_extract_through_linked_pair() {
+        for extract in [false, true] {
+            let mut timeline = Timeline::new();
+            let v1 = timeline.tracks[0].id;
+            let a1 = timeline.tracks[1].id;
+            timeline.add_clip(v1, clip(1, 0.0, 10.0)).unwrap();
+            timeline.add_clip(a1, clip(2, 0.0, 10.0)).unwrap();
+            timeline.link(&[1, 2]).unwrap();
+
+            let (start, end) = (time::from_seconds(2.0), time::from_seconds(7.0));
+            if extract {
+                timeline.extract(start, end);
+            } else {
+                timeline.lift(start, end);
+            }
+            // The tails are linked to each other, not to the heads
+            assert_eq!(timeline.linked_clips(1), vec![1, 2]);
+            assert_eq!(timeline.linked_clips(3), vec![3, 4]);
+            for id in 1..=4 {
+                assert_eq!(timeline.sync_offset(id), Some(0));
+            }
+
+            // Deleting a head leaves the tails alone
+            timeline.remove_linked(1).unwrap();
+            assert!(timeline.find_clip(3).is_some() && timeline.find_clip(4).is_some());
+        }
+    }
+
+    #[test]
+    fn test_extract_ripples_left() {
+        let (mut timeline, v1) = timeline();
+        let a1 = timeline.audio_tracks().next().unwrap().id;
+        timeline.add_clip(a1, clip(3, 0.0, 10.0)).unwrap();
+
+        for position in [1.0, 3.0, 8.0] {
+            let marker = timeline.allocate_marker_id();
+            timeline.add_marker(Marker::new(marker, time::from_seconds(position), MarkerKind::Comment));
+        }
+
+        timeline.extract(time::from_seconds(2.0), time::from_seconds(7.0));
+        assert_eq!(
+            layout(&timeline, v1),
+            vec![(1, 0, time::from_seconds(2.0)), (2, time::from_seconds(2.0), time::from_seconds(5.0))]
+        );
+        let markers: Vec<Time> = timeline.markers.iter().map(|m| m.position).collect();
+        assert_eq!(markers, vec![time::from_seconds(1.0), time::from_seconds(2.0), time::from_seconds(3.0)]);
+        assert_eq!(layout(&timeline, a1), vec![(3, 0, time::from_seconds(2.0)), (4, time::from_seconds(2.0), time::from_seconds(5.0))]);
+        assert_eq!(timeline.duration, time::from_seconds(5.0));
+    }
+
+    #[test]
+    fn test_three_point_edit() {
+        let points = ThreePointEdit {
+            source_in: Some(time::from_seconds(5.0)),
+            source_out: Some(time::from_seconds(8.0)),
+            record_in: Some(time::from_seconds(20.0)),
+            record_out: None,
+        };
+        let solved = points.solve().unwrap();
+        assert_eq!(solved.record_out, time::from_seconds(23.0));
+        assert_eq!(solved.duration(), time::from_seconds(3.0));
+
+        let backtimed = ThreePointEdit {
+            source_in: None,
+            source_out: Some(time::from_seconds(8.0)),
+            record_in: Some(time::from_seconds(20.0)),
+            record_out: Some(time::from_seconds(22.0)),
+        };
+        assert_eq!(backtimed.solve().unwrap().source_in, time::from_seconds(6.0));
+
+        let record_in = ThreePointEdit { record_in: None, record_out: Some(time::from_seconds(22.0)), ..points };
+        assert_eq!(record_in.solve().unwrap().record_in, time::from_seconds(19.0));
+
+        let source_out = ThreePointEdit { source_out: None, record_out: Some(time::from_seconds(22.0)), ..points };
+        assert_eq!(source_out.solve().unwrap().source_out, time::from_seconds(7.0));
+
+        let clip = solved.to_clip(1, PathBuf::from("a.mp4"), 0);
+        assert_eq!(clip.timeline_end, time::from_seconds(23.0));
+    }
+
+    #[test]
+    fn test_three_point_edit_errors() {
+        let two = ThreePointEdit { source_in: Some(0), record_in: Some(0), ..Default::default() };
+        assert_eq!(two.solve(), Err(EditPointError::NotEnoughPoints));
+
+        let four = ThreePointEdit {
+            source_in: Some(0),
+            source_out: Some(time::from_seconds(2.0)),
+            record_in: Some(0),
+            record_out: Some(time::from_seconds(3.0)),
+        };
+        assert_eq!(four.solve(), Err(EditPointError::DurationMismatch { source: time::from_seconds(2.0), record: time::from_seconds(3.0) }));
+
+        let reversed = ThreePointEdit { source_in: Some(time::from_seconds(2.0)), source_out: Some(time::from_seconds(1.0)), record_in: Some(0), ..Default::default() };
+        assert_eq!(reversed.solve(), Err(EditPointError::EmptyRange));
+    }
+}
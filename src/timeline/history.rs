@@ -0,0 +1,538 @@
+//! Undo/redo history for timeline edits.
+//!
+//! Every timeline mutation is expressed as a reversible [`Command`]. The
+//! [`History`] applies commands, records them as undo steps and replays them
+//! for redo. Consecutive drag updates merge into one step, and a transaction
+//! groups several commands into a single step.
+
+use std::any::Any;
+use std::fmt;
+use crate::timeline::timeline::Timeline;
+use crate::timeline::track::TrackError;
+
+/// A reversible edit on a timeline.
+pub trait Command: fmt::Debug + Send {
+    /// Perform the edit. Called again on redo.
+    ///
+    /// On error the timeline must be left unchanged.
+    fn apply(&mut self, timeline: &mut Timeline) -> Result<(), TrackError>;
+
+    /// Revert a previously applied edit.
+    fn undo(&mut self, timeline: &mut Timeline) -> Result<(), TrackError>;
+
+    /// Human-readable description for the history panel (e.g. "Move Clip").
+    fn label(&self) -> String;
+
+    /// Absorb a command that was applied right after this one.
+    ///
+    /// Returns `true` if `next` was merged, in which case undoing `self`
+    /// must also revert `next`. Used to collapse drag updates into one step.
+    fn merge(&mut self, _next: &dyn Command) -> bool {
+        false
+    }
+
+    /// Downcast support for `merge`.
+    fn as_any(&self) -> &dyn Any;
+}
+
+/// One undo step: one or more commands undone and redone together.
+#[derive(Debug)]
+struct Step {
+    label: String,
+    commands: Vec<Box<dyn Command>>,
+}
+
+impl Step {
+    /// Undo every command, last first.
+    ///
+    /// If one fails, the commands already undone are applied again so the
+    /// step is either fully undone or not at all.
+    fn undo(&mut self, timeline: &mut Timeline) -> Result<(), TrackError> {
+        for index in (0..self.commands.len()).rev() {
+            if let Err(error) = self.commands[index].undo(timeline) {
+                for command in &mut self.commands[index + 1..] {
+                    let _ = command.apply(timeline);
+                }
+                return Err(error);
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply every command again, first first.
+    ///
+    /// If one fails, the commands already applied are undone again.
+    fn redo(&mut self, timeline: &mut Timeline) -> Result<(), TrackError> {
+        for index in 0..self.commands.len() {
+            if let Err(error) = self.commands[index].apply(timeline) {
+                for command in self.commands[..index].iter_mut().rev() {
+                    let _ = command.undo(timeline);
+                }
+                return Err(error);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Undo/redo history of timeline commands.
+///
+/// Keeps every undo step unless a limit is set with `with_limit`.
+#[derive(Debug, Default)]
+pub struct History {
+    limit: Option<usize>,    // Most undo steps kept; `None` keeps all
+    undo_stack: Vec<Step>,
+    redo_stack: Vec<Step>,
+    /// Open transaction collecting commands into one step
+    transaction: Option<Step>,
+    /// Whether the last step may absorb the next command via `Command::merge`
+    merge_open: bool,
+    /// Bumped by every change to the timeline made through the history
+    revision: u64,
+}
+
+impl History {
+    /// Create an empty history.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep at most `limit` undo steps (at least one); the oldest are
+    /// dropped first.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit.max(1));
+        self.trim();
+        self
+    }
+
+    /// Record a finished step, dropping the oldest beyond the limit, if any.
+    fn push_undo(&mut self, step: Step) {
+        self.undo_stack.push(step);
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        if let Some(limit) = self.limit {
+            let excess = self.undo_stack.len().saturating_sub(limit);
+            self.undo_stack.drain(..excess);
+        }
+    }
+
+    /// Apply a command and record it as an undo step.
+    ///
+    /// If the command fails, nothing is recorded and the error is returned.
+    /// Clears the redo stack. Inside a transaction the command becomes part
+    /// of the transaction's step instead.
+    pub fn execute<C: Command + 'static>(
+        &mut self,
+        timeline: &mut Timeline,
+        mut command: C,
+    ) -> Result<(), TrackError> {
+        command.apply(timeline)?;
+        self.revision += 1;
+        self.redo_stack.clear();
+
+        if let Some(transaction) = &mut self.transaction {
+            transaction.commands.push(Box::new(command));
+            return Ok(());
+        }
+
+        if self.merge_open {
+            if let Some(last) = self.undo_stack.last_mut() {
+                if let [previous] = last.commands.as_mut_slice() {
+                    if previous.merge(&command) {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+
+        self.push_undo(Step {
+            label: command.label(),
+            commands: vec![Box::new(command)],
+        });
+        self.merge_open = true;
+        Ok(())
+    }
+
+    /// Stop the last step from absorbing further commands.
+    ///
+    /// Call when a drag ends so the next drag becomes its own undo step.
+    pub fn seal(&mut self) {
+        self.merge_open = false;
+    }
+
+    /// Start grouping commands into a single undo step.
+    ///
+    /// Nested calls are folded into the outer transaction.
+    pub fn begin_transaction(&mut self, label: impl Into<String>) {
+        if self.transaction.is_none() {
+            self.transaction = Some(Step {
+                label: label.into(),
+                commands: Vec::new(),
+            });
+        }
+    }
+
+    /// Finish the open transaction and record it as one undo step.
+    ///
+    /// Empty transactions are discarded.
+    pub fn commit_transaction(&mut self) {
+        if let Some(step) = self.transaction.take() {
+            if !step.commands.is_empty() {
+                self.push_undo(step);
+                self.merge_open = false;
+            }
+        }
+    }
+
+    /// Abort the open transaction, reverting every command applied in it.
+    pub fn rollback_transaction(&mut self, timeline: &mut Timeline) -> Result<(), TrackError> {
+        match self.transaction.take() {
+            Some(mut step) => {
+                self.revision += 1;
+                step.undo(timeline)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Whether a transaction is open.
+    pub fn in_transaction(&self) -> bool {
+        self.transaction.is_some()
+    }
+
+    /// Undo the most recent step.
+    ///
+    /// Returns the label of the undone step, or `None` if there was nothing to undo.
+    /// An open transaction is committed first. If the step fails to undo, it
+    /// stays on the undo stack, the timeline is left as it was and the error
+    /// is returned.
+    pub fn undo(&mut self, timeline: &mut Timeline) -> Result<Option<String>, TrackError> {
+        self.commit_transaction();
+        self.merge_open = false;
+        let Some(mut step) = self.undo_stack.pop() else {
+            return Ok(None);
+        };
+        if let Err(error) = step.undo(timeline) {
+            self.undo_stack.push(step);
+            return Err(error);
+        }
+        self.revision += 1;
+        let label = step.label.clone();
+        self.redo_stack.push(step);
+        Ok(Some(label))
+    }
+
+    /// Redo the most recently undone step.
+    ///
+    /// Returns the label of the redone step, or `None` if there was nothing to redo.
+    /// If the step fails to redo, it stays on the redo stack and the timeline
+    /// is left as it was.
+    pub fn redo(&mut self, timeline: &mut Timeline) -> Result<Option<String>, TrackError> {
+        self.merge_open = false;
+        let Some(mut step) = self.redo_stack.pop() else {
+            return Ok(None);
+        };
+        if let Err(error) = step.redo(timeline) {
+            self.redo_stack.push(step);
+            return Err(error);
+        }
+        self.revision += 1;
+        let label = step.label.clone();
+        self.push_undo(step);
+        Ok(Some(label))
+    }
+
+    /// Counter that changes whenever an execute, undo, redo or rollback
+    /// changes the timeline.
+    ///
+    /// Compare it with an earlier value to tell whether the timeline was
+    /// edited since (e.g. since the last save). `clear` keeps it.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Check if there is a step to undo.
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    /// Check if there is a step to redo.
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Label of the step that `undo` would revert.
+    pub fn undo_label(&self) -> Option<&str> {
+        self.undo_stack.last().map(|s| s.label.as_str())
+    }
+
+    /// Label of the step that `redo` would reapply.
+    pub fn redo_label(&self) -> Option<&str> {
+        self.redo_stack.last().map(|s| s.label.as_str())
+    }
+
+    /// Labels of all undo steps, oldest first (for a history panel).
+    pub fn undo_labels(&self) -> impl Iterator<Item = &str> {
+        self.undo_stack.iter().map(|s| s.label.as_str())
+    }
+
+    /// Labels of all redo steps, next redo first.
+    pub fn redo_labels(&self) -> impl Iterator<Item = &str> {
+        self.redo_stack.iter().rev().map(|s| s.label.as_str())
+    }
+
+    /// Drop all recorded steps.
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.transaction = None;
+        self.merge_open = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::time;
+    use crate::timeline::clip::Clip;
+    use crate::timeline::commands::{AddClip, MoveClip, RemoveClip};
+    use std::path::PathBuf;
+
+    fn clip(id: u64, start: f64) -> Clip {
+        Clip::new(
+            id,
+            PathBuf::from("test.mp4"),
+            time::from_seconds(0.0),
+            time::from_seconds(2.0),
+            time::from_seconds(start),
+            0,
+        )
+    }
+
+    fn clip_start(timeline: &Timeline, id: u64) -> Option<i64> {
+        timeline.find_clip(id).map(|(_, c)| c.timeline_start)
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        let mut history = History::new();
+
+        history.execute(&mut timeline, AddClip::new(v1, clip(1, 0.0))).unwrap();
+        history.execute(&mut timeline, AddClip::new(v1, clip(2, 5.0))).unwrap();
+        assert_eq!(timeline.duration, time::from_seconds(7.0));
+
+        assert_eq!(history.undo(&mut timeline).unwrap(), Some("Add Clip".to_string()));
+        assert!(timeline.find_clip(2).is_none());
+        assert_eq!(timeline.duration, time::from_seconds(2.0));
+
+        assert_eq!(history.redo(&mut timeline).unwrap(), Some("Add Clip".to_string()));
+        assert!(timeline.find_clip(2).is_some());
+
+        history.undo(&mut timeline).unwrap();
+        history.undo(&mut timeline).unwrap();
+        assert!(timeline.find_clip(1).is_none());
+        assert_eq!(history.undo(&mut timeline).unwrap(), None);
+        assert!(history.can_redo());
+    }
+
+    #[test]
+    fn test_new_command_clears_redo() {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        let mut history = History::new();
+
+        history.execute(&mut timeline, AddClip::new(v1, clip(1, 0.0))).unwrap();
+        history.undo(&mut timeline).unwrap();
+        assert!(history.can_redo());
+
+        history.execute(&mut timeline, AddClip::new(v1, clip(2, 0.0))).unwrap();
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn test_failed_command_not_recorded() {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        let mut history = History::new();
+
+        history.execute(&mut timeline, AddClip::new(v1, clip(1, 0.0))).unwrap();
+        history.seal();
+        // Overlaps clip 1
+        let revision = history.revision();
+        assert!(history.execute(&mut timeline, AddClip::new(v1, clip(2, 1.0))).is_err());
+        assert_eq!(history.undo_labels().count(), 1);
+        assert_eq!(history.revision(), revision);
+
+        history.undo(&mut timeline).unwrap();
+        assert_ne!(history.revision(), revision);
+    }
+
+    #[test]
+    fn test_drag_merges_into_one_step() {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        let mut history = History::new();
+        history.execute(&mut timeline, AddClip::new(v1, clip(1, 0.0))).unwrap();
+        history.seal();
+
+        for step in 1..=10 {
+            let start = time::from_seconds(step as f64 * 0.5);
+            history.execute(&mut timeline, MoveClip::new(1, start)).unwrap();
+        }
+        history.seal();
+        assert_eq!(clip_start(&timeline, 1), Some(time::from_seconds(5.0)));
+        assert_eq!(history.undo_labels().collect::<Vec<_>>(), vec!["Add Clip", "Move Clip"]);
+
+        // A second drag is a separate step
+        history.execute(&mut timeline, MoveClip::new(1, time::from_seconds(8.0))).unwrap();
+        assert_eq!(history.undo_labels().count(), 3);
+
+        history.undo(&mut timeline).unwrap();
+        assert_eq!(clip_start(&timeline, 1), Some(time::from_seconds(5.0)));
+        history.undo(&mut timeline).unwrap();
+        assert_eq!(clip_start(&timeline, 1), Some(0));
+    }
+
+    #[test]
+    fn test_unlimited_by_default() {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        let mut history = History::new();
+
+        for id in 1..=600 {
+            history.execute(&mut timeline, AddClip::new(v1, clip(id, id as f64 * 2.0))).unwrap();
+            history.seal();
+        }
+        assert_eq!(history.undo_labels().count(), 600);
+        while history.undo(&mut timeline).unwrap().is_some() {}
+        assert!(timeline.find_clip(1).is_none());
+    }
+
+    #[test]
+    fn test_limit_drops_oldest_steps() {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        let mut history = History::new().with_limit(3);
+
+        for id in 1..=5 {
+            history.execute(&mut timeline, AddClip::new(v1, clip(id, id as f64 * 2.0))).unwrap();
+            history.seal();
+        }
+        assert_eq!(history.undo_labels().count(), 3);
+        while history.undo(&mut timeline).unwrap().is_some() {}
+        // Clips 1 and 2 were added by steps that are no longer kept
+        assert!(timeline.find_clip(2).is_some());
+        assert!(timeline.find_clip(3).is_none());
+    }
+
+    #[test]
+    fn test_transaction_is_one_step() {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        let a1 = timeline.tracks[1].id;
+        let mut history = History::new();
+
+        history.begin_transaction("Add Linked Clips");
+        history.execute(&mut timeline, AddClip::new(v1, clip(1, 0.0))).unwrap();
+        history.execute(&mut timeline, AddClip::new(a1, clip(2, 0.0))).unwrap();
+        history.commit_transaction();
+
+        assert_eq!(history.undo_label(), Some("Add Linked Clips"));
+        history.undo(&mut timeline).unwrap();
+        assert!(timeline.find_clip(1).is_none());
+        assert!(timeline.find_clip(2).is_none());
+
+        history.redo(&mut timeline).unwrap();
+        assert!(timeline.find_clip(1).is_some());
+        assert!(timeline.find_clip(2).is_some());
+    }
+
+    /// Applies once, then fails to undo or to apply again.
+    #[derive(Debug)]
+    struct Fails {
+        undo_fails: bool,
+        applied: bool,
+    }
+
+    impl Command for Fails {
+        fn apply(&mut self, _timeline: &mut Timeline) -> Result<(), TrackError> {
+            if self.applied && !self.undo_fails {
+                return Err(TrackError::NoClips);
+            }
+            self.applied = true;
+            Ok(())
+        }
+
+        fn undo(&mut self, _timeline: &mut Timeline) -> Result<(), TrackError> {
+            if self.undo_fails {
+                return Err(TrackError::NoClips);
+            }
+            Ok(())
+        }
+
+        fn label(&self) -> String {
+            "Fails".to_string()
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    #[test]
+    fn test_failed_undo_and_redo_keep_step() {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        let mut history = History::new();
+
+        // Undo reverts clip 3 before reaching the failing command
+        history.begin_transaction("Broken Undo");
+        history.execute(&mut timeline, AddClip::new(v1, clip(1, 0.0))).unwrap();
+        history.execute(&mut timeline, Fails { undo_fails: true, applied: false }).unwrap();
+        history.execute(&mut timeline, AddClip::new(v1, clip(3, 5.0))).unwrap();
+        history.commit_transaction();
+        let revision = history.revision();
+        assert_eq!(history.undo(&mut timeline), Err(TrackError::NoClips));
+        assert!(timeline.find_clip(1).is_some());
+        assert!(timeline.find_clip(3).is_some());
+        assert_eq!(history.revision(), revision);
+        assert_eq!(history.undo_label(), Some("Broken Undo"));
+        assert!(!history.can_redo());
+
+        // Redo reapplies clip 4 before reaching the failing command
+        let mut history = History::new();
+        history.begin_transaction("Broken Redo");
+        history.execute(&mut timeline, AddClip::new(v1, clip(4, 10.0))).unwrap();
+        history.execute(&mut timeline, Fails { undo_fails: false, applied: false }).unwrap();
+        history.commit_transaction();
+        history.undo(&mut timeline).unwrap();
+        assert!(timeline.find_clip(4).is_none());
+        let revision = history.revision();
+        assert_eq!(history.redo(&mut timeline), Err(TrackError::NoClips));
+        assert!(timeline.find_clip(4).is_none());
+        assert_eq!(history.revision(), revision);
+        assert_eq!(history.redo_label(), Some("Broken Redo"));
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn test_rollback_transaction() {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        let mut history = History::new();
+        history.execute(&mut timeline, AddClip::new(v1, clip(1, 0.0))).unwrap();
+
+        history.begin_transaction("Replace");
+        history.execute(&mut timeline, RemoveClip::new(1)).unwrap();
+        history.execute(&mut timeline, AddClip::new(v1, clip(2, 0.0))).unwrap();
+        history.rollback_transaction(&mut timeline).unwrap();
+
+        assert!(timeline.find_clip(1).is_some());
+        assert!(timeline.find_clip(2).is_none());
+        assert_eq!(history.undo_labels().count(), 1);
+    }
+}
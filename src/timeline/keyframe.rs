@@ -0,0 +1,285 @@
+//! Keyframe animation of clip properties.
+//!
+//! A [`KeyframeTrack`] is a curve of values against the offset into the clip
+//! on the timeline (nanoseconds from `timeline_start`). Between keyframes the
+//! value holds, moves linearly or follows a cubic bezier ease; before the first
+//! and after the last keyframe it holds. Evaluation only uses the integer
+//! offsets and the stored values, so a curve gives the same value at a given
+//! `Time` in playback, export and the UI.
+//!
+//! [`ClipAnimation`] groups the curves a clip can animate. Its offsets stay
+//! put on the timeline when the clip's start is trimmed or split, and move
+//! with the clip when it is moved.
+
+use crate::core::time::Time;
+use crate::timeline::clip::ClipId;
+use crate::timeline::remap::BezierHandles;
+use crate::timeline::timeline::Timeline;
+use crate::timeline::track::TrackError;
+use serde::{Deserialize, Serialize};
+
+/// A value that can be keyframed: anything that can be blended between two values.
+pub trait Animatable: Copy + PartialEq {
+    /// The value `progress` of the way from `from` to `to`.
+    ///
+    /// `progress` is 0.0-1.0 for linear segments, but may overshoot either end
+    /// along a bezier ease.
+    fn lerp(from: Self, to: Self, progress: f64) -> Self;
+}
+
+impl Animatable for f32 {
+    fn lerp(from: Self, to: Self, progress: f64) -> Self {
+        (from as f64 + (to as f64 - from as f64) * progress) as f32
+    }
+}
+
+impl Animatable for (f32, f32) {
+    fn lerp(from: Self, to: Self, progress: f64) -> Self {
+        (f32::lerp(from.0, to.0, progress), f32::lerp(from.1, to.1, progress))
+    }
+}
+
+/// How the value moves from one keyframe to the next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Interpolation {
+    /// Keep this keyframe's value until the next keyframe
+    Hold,
+    Linear,
+    /// Cubic bezier ease with explicit tangent handles
+    Bezier(BezierHandles),
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Interpolation {
+    /// Eased progress at segment progress `progress` (0.0-1.0).
+    fn ease(&self, progress: f64) -> f64 {
+        match self {
+            Interpolation::Hold => 0.0,
+            Interpolation::Linear => progress,
+            Interpolation::Bezier(handles) => handles.ease(progress),
+            Interpolation::EaseIn => BezierHandles::EASE_IN.ease(progress),
+            Interpolation::EaseOut => BezierHandles::EASE_OUT.ease(progress),
+            Interpolation::EaseInOut => BezierHandles::EASE_IN_OUT.ease(progress),
+        }
+    }
+}
+
+/// One point on a keyframe curve.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Keyframe<T> {
+    pub offset: Time,                 // Timeline offset from the clip's start (nanoseconds)
+    pub value: T,
+    pub interpolation: Interpolation, // Towards the next keyframe
+}
+
+impl<T: Animatable> Keyframe<T> {
+    pub fn new(offset: Time, value: T, interpolation: Interpolation) -> Self {
+        Self { offset, value, interpolation }
+    }
+
+    /// A keyframe with linear interpolation to the next one.
+    pub fn linear(offset: Time, value: T) -> Self {
+        Self::new(offset, value, Interpolation::Linear)
+    }
+
+    /// A keyframe whose value holds until the next one.
+    pub fn hold(offset: Time, value: T) -> Self {
+        Self::new(offset, value, Interpolation::Hold)
+    }
+}
+
+/// Keyframed curve of one clip property. An empty track is not animated.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyframeTrack<T> {
+    keyframes: Vec<Keyframe<T>>,
+}
+
+impl<T> Default for KeyframeTrack<T> {
+    fn default() -> Self {
+        Self { keyframes: Vec::new() }
+    }
+}
+
+impl<T: Animatable> KeyframeTrack<T> {
+    /// Create a curve from keyframes (sorted by offset; later duplicates win).
+    pub fn new(mut keyframes: Vec<Keyframe<T>>) -> Self {
+        keyframes.sort_by_key(|k| k.offset);
+        keyframes.reverse();
+        keyframes.dedup_by_key(|k| k.offset);
+        keyframes.reverse();
+        Self { keyframes }
+    }
+
+    /// Keyframes in offset order.
+    pub fn keyframes(&self) -> &[Keyframe<T>] {
+        &self.keyframes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keyframes.is_empty()
+    }
+
+    /// Add a keyframe, replacing any keyframe at the same offset.
+    pub fn set(&mut self, keyframe: Keyframe<T>) {
+        let index = self.keyframes.partition_point(|k| k.offset < keyframe.offset);
+        match self.keyframes.get_mut(index) {
+            Some(existing) if existing.offset == keyframe.offset => *existing = keyframe,
+            _ => self.keyframes.insert(index, keyframe),
+        }
+    }
+
+    /// Remove and return the keyframe at `offset`, if there is one.
+    pub fn remove(&mut self, offset: Time) -> Option<Keyframe<T>> {
+        let index = self.keyframes.iter().position(|k| k.offset == offset)?;
+        Some(self.keyframes.remove(index))
+    }
+
+    /// Move every keyframe by `delta` on the timeline.
+    pub fn shift(&mut self, delta: Time) {
+        for keyframe in &mut self.keyframes {
+            keyframe.offset += delta;
+        }
+    }
+
+    /// Value at `offset` into the clip, or `None` if the track is empty.
+    pub fn value_at(&self, offset: Time) -> Option<T> {
+        let next = self.keyframes.partition_point(|k| k.offset <= offset);
+        if next == 0 {
+            return self.keyframes.first().map(|k| k.value);
+        }
+        let from = &self.keyframes[next - 1];
+        let Some(to) = self.keyframes.get(next) else {
+            return Some(from.value);
+        };
+
+        let progress = (offset - from.offset) as f64 / (to.offset - from.offset) as f64;
+        Some(T::lerp(from.value, to.value, from.interpolation.ease(progress)))
+    }
+}
+
+/// Keyframed properties of a clip.
+///
+/// Video clips use position, scale, opacity and rotation, which override the
+/// clip's static `VisualProperties` while keyframed; audio clips use volume
+/// and pan. Unanimated audio properties leave the sound unchanged.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ClipAnimation {
+    pub position: KeyframeTrack<(f32, f32)>, // Anchor position, (0.5, 0.5) = centred
+    pub scale: KeyframeTrack<(f32, f32)>,    // 1.0 = original size
+    pub opacity: KeyframeTrack<f32>,         // 0.0 to 1.0
+    pub rotation: KeyframeTrack<f32>,        // Degrees clockwise
+    pub volume: KeyframeTrack<f32>,          // Gain on top of the track volume, 1.0 = unchanged
+    pub pan: KeyframeTrack<f32>,             // -1.0 = left, 0.0 = centre, 1.0 = right
+}
+
+impl ClipAnimation {
+    /// Whether no property is animated.
+    pub fn is_empty(&self) -> bool {
+        self.position.is_empty()
+            && self.scale.is_empty()
+            && self.opacity.is_empty()
+            && self.rotation.is_empty()
+            && self.volume.is_empty()
+            && self.pan.is_empty()
+    }
+
+    /// Move every keyframe by `delta` on the timeline.
+    pub fn shift(&mut self, delta: Time) {
+        self.position.shift(delta);
+        self.scale.shift(delta);
+        self.opacity.shift(delta);
+        self.rotation.shift(delta);
+        self.volume.shift(delta);
+        self.pan.shift(delta);
+    }
+
+    /// Volume at `offset`; never negative.
+    pub fn volume_at(&self, offset: Time) -> f32 {
+        self.volume.value_at(offset).unwrap_or(1.0).max(0.0)
+    }
+
+    /// Pan at `offset`, clamped to -1.0-1.0.
+    pub fn pan_at(&self, offset: Time) -> f32 {
+        self.pan.value_at(offset).unwrap_or(0.0).clamp(-1.0, 1.0)
+    }
+}
+
+impl Timeline {
+    /// Replace the keyframed properties of a clip.
+    ///
+    /// Only `clip_id` is changed: linked clips animate independently (e.g. the
+    /// opacity of the video and the volume of its audio).
+    pub fn set_clip_animation(&mut self, clip_id: ClipId, animation: ClipAnimation) -> Result<(), TrackError> {
+        let clip = self.editable_clip_mut(clip_id)?;
+        clip.animation = animation;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::time;
+
+    /// Fade in over 1s, hold, then ease out from 3s to 4s.
+    fn fade() -> KeyframeTrack<f32> {
+        KeyframeTrack::new(vec![
+            Keyframe::linear(0, 0.0),
+            Keyframe::hold(time::from_seconds(1.0), 1.0),
+            Keyframe::new(time::from_seconds(3.0), 1.0, Interpolation::EaseInOut),
+            Keyframe::linear(time::from_seconds(4.0), 0.0),
+        ])
+    }
+
+    #[test]
+    fn test_value_at() {
+        let track = fade();
+        assert_eq!(track.value_at(time::from_seconds(-1.0)), Some(0.0));
+        assert_eq!(track.value_at(time::from_seconds(0.25)), Some(0.25));
+        assert_eq!(track.value_at(time::from_seconds(2.0)), Some(1.0));
+        // Symmetric ease: halfway in time is halfway in value
+        assert!((track.value_at(time::from_seconds(3.5)).unwrap() - 0.5).abs() < 1e-6);
+        assert!(track.value_at(time::from_seconds(3.25)).unwrap() > 0.75);
+        assert_eq!(track.value_at(time::from_seconds(9.0)), Some(0.0));
+        assert_eq!(KeyframeTrack::<f32>::default().value_at(0), None);
+    }
+
+    #[test]
+    fn test_hold_steps() {
+        let track = KeyframeTrack::new(vec![Keyframe::hold(0, 2.0), Keyframe::hold(time::from_seconds(1.0), 5.0)]);
+        assert_eq!(track.value_at(time::from_seconds(1.0) - 1), Some(2.0));
+        assert_eq!(track.value_at(time::from_seconds(1.0)), Some(5.0));
+    }
+
+    #[test]
+    fn test_set_and_remove() {
+        let mut track = fade();
+        track.set(Keyframe::linear(time::from_seconds(1.0), 0.5));
+        track.set(Keyframe::linear(time::from_seconds(2.0), 0.75));
+        assert_eq!(track.keyframes().len(), 5);
+        assert_eq!(track.value_at(time::from_seconds(1.0)), Some(0.5));
+        assert_eq!(track.remove(time::from_seconds(2.0)).map(|k| k.value), Some(0.75));
+        assert_eq!(track.remove(time::from_seconds(2.0)), None);
+    }
+
+    #[test]
+    fn test_pairs_and_defaults() {
+        let mut animation = ClipAnimation::default();
+        assert!(animation.is_empty());
+        assert_eq!(animation.volume_at(time::from_seconds(1.0)), 1.0);
+
+        animation.position = KeyframeTrack::new(vec![
+            Keyframe::linear(0, (0.0, 0.0)),
+            Keyframe::linear(time::from_seconds(2.0), (1.0, 0.5)),
+        ]);
+        animation.pan = KeyframeTrack::new(vec![Keyframe::hold(0, 3.0)]);
+        assert_eq!(animation.position.value_at(time::from_seconds(1.0)), Some((0.5, 0.25)));
+        assert_eq!(animation.pan_at(0), 1.0);
+
+        // Shifting keeps the curve's shape
+        animation.shift(time::from_seconds(1.0));
+        assert_eq!(animation.position.value_at(time::from_seconds(2.0)), Some((0.5, 0.25)));
+    }
+}
@@ -0,0 +1,421 @@
+//! Linked clips: groups of clips (typically a video clip and its audio) that
+//! move, trim, split and delete together.
+//!
+//! Each linked clip remembers its `sync_origin` (`in_point - timeline_start`
+//! when it was linked). If members drift apart in source time, e.g. after
+//! being moved while unlinked, `sync_offset` reports how far.
+
+use crate::timeline::clip::{Clip, ClipId, LinkGroupId};
+use crate::timeline::timeline::Timeline;
+use crate::timeline::track::{Track, TrackError, TrackId};
+use crate::timeline::trim::{check_source_range, TrimEdge};
+use crate::core::time::Time;
+
+impl Timeline {
+    pub(crate) fn clip_mut(&mut self, clip_id: ClipId) -> Option<&mut Clip> {
+        self.tracks
+            .iter_mut()
+            .flat_map(|t| t.clips.iter_mut())
+            .find(|c| c.id == clip_id)
+    }
+
+    /// A clip for modification, refusing clips on locked tracks.
+    pub(crate) fn editable_clip_mut(&mut self, clip_id: ClipId) -> Result<&mut Clip, TrackError> {
+        self.check_clip_editable(clip_id)?;
+        self.clip_mut(clip_id).ok_or(TrackError::ClipNotFound { clip_id })
+    }
+
+    /// Fail with `TrackError::ClipNotFound` if the clip does not exist, or
+    /// `TrackError::TrackLocked` if it is on a locked track.
+    pub(crate) fn check_clip_editable(&self, clip_id: ClipId) -> Result<(), TrackError> {
+        self.find_clip(clip_id)
+            .ok_or(TrackError::ClipNotFound { clip_id })?
+            .0
+            .check_unlocked()
+    }
+
+    /// Members of a link group with their track IDs, in track order.
+    fn group_members(&self, group: LinkGroupId) -> Vec<(TrackId, Clip)> {
+        self.tracks
+            .iter()
+            .flat_map(|t| t.clips.iter().map(move |c| (t.id, c)))
+            .filter(|(_, c)| c.link_group == Some(group))
+            .map(|(track_id, c)| (track_id, c.clone()))
+            .collect()
+    }
+
+    /// A clip and every clip linked to it, with their track IDs.
+    pub(crate) fn linked_with_tracks(&self, clip_id: ClipId) -> Result<Vec<(TrackId, Clip)>, TrackError> {
+        let (track, clip) = self.find_clip(clip_id)
+            .ok_or(TrackError::ClipNotFound { clip_id })?;
+        Ok(match clip.link_group {
+            Some(group) => self.group_members(group),
+            None => vec![(track.id, clip.clone())],
+        })
+    }
+
+    /// Replace clips with updated copies (matched by ID), possibly on other tracks.
+    ///
+    /// All updates are validated together; on any failure the tracks involved
+    /// are put back as they were and the timeline is unchanged. Clips may not
+    /// leave or join a locked track.
+    pub(crate) fn replace_clips(&mut self, updated: Vec<(TrackId, Clip)>) -> Result<(), TrackError> {
+        let mut track_ids = Vec::with_capacity(updated.len() * 2);
+        for (track_id, clip) in &updated {
+            let track_id = *track_id;
+            self.track(track_id)
+                .ok_or(TrackError::TrackNotFound { track_id })?
+                .check_unlocked()?;
+            self.check_clip_editable(clip.id)?;
+            track_ids.push(track_id);
+            track_ids.extend(self.find_clip(clip.id).map(|(track, _)| track.id));
+        }
+        track_ids.sort_unstable();
+        track_ids.dedup();
+        let originals: Vec<Track> = track_ids.iter().filter_map(|&id| self.track(id).cloned()).collect();
+
+        let result = self.swap_clips(updated);
+        if result.is_err() {
+            for original in originals {
+                if let Some(track) = self.track_mut(original.id) {
+                    *track = original;
+                }
+            }
+            self.update_duration();
+        }
+        result
+    }
+
+    /// Take out every clip being replaced, then add the updated copies.
+    ///
+    /// The transitions into the clips go with them to their new tracks (or
+    /// are dropped on a track of the other type).
+    fn swap_clips(&mut self, updated: Vec<(TrackId, Clip)>) -> Result<(), TrackError> {
+        let mut carried = Vec::new();
+        for track in &mut self.tracks {
+            let (moving, staying) = std::mem::take(&mut track.transitions)
+                .into_iter()
+                .partition(|t| updated.iter().any(|(_, c)| c.id == t.clip_id));
+            track.transitions = staying;
+            carried.extend(moving);
+        }
+        for (_, clip) in &updated {
+            self.remove_clip(clip.id)?;
+        }
+        for (track_id, clip) in updated {
+            self.add_clip(track_id, clip)?;
+        }
+        for transition in carried {
+            let track = self.tracks
+                .iter_mut()
+                .find(|t| t.clips.iter().any(|c| c.id == transition.clip_id));
+            if let Some(track) = track.filter(|t| t.track_type == transition.kind.track_type()) {
+                track.transitions.push(transition);
+            }
+        }
+        Ok(())
+    }
+
+    /// Link clips into a new group and return its ID.
+    ///
+    /// The clips' current source/timeline alignment becomes the reference for
+    /// `sync_offset`. Clips already in another group are moved to the new one.
+    pub fn link(&mut self, clip_ids: &[ClipId]) -> Result<LinkGroupId, TrackError> {
+        for &clip_id in clip_ids {
+            self.check_clip_editable(clip_id)?;
+        }
+        let group = self.allocate_link_group();
+        for &clip_id in clip_ids {
+            if let Some(clip) = self.clip_mut(clip_id) {
+                clip.link_group = Some(group);
+                clip.sync_origin = clip.in_point - clip.timeline_start;
+            }
+        }
+        Ok(group)
+    }
+
+    /// Remove a clip from its link group so it can be edited on its own.
+    ///
+    /// Returns the group it left (for `relink`), or `None` if it was not linked.
+    /// The clip keeps its sync reference, so relinking after moving it shows the drift.
+    pub fn unlink(&mut self, clip_id: ClipId) -> Result<Option<LinkGroupId>, TrackError> {
+        let clip = self.editable_clip_mut(clip_id)?;
+        Ok(clip.link_group.take())
+    }
+
+    /// Put a clip back into a link group, keeping its original sync reference.
+    ///
+    /// Fails with `TrackError::LinkGroupNotFound` if no clip is left in the group.
+    pub fn relink(&mut self, clip_id: ClipId, group: LinkGroupId) -> Result<(), TrackError> {
+        let in_use = self.tracks.iter().flat_map(|t| &t.clips).any(|c| c.link_group == Some(group));
+        if !self.is_link_group(group) || !in_use {
+            return Err(TrackError::LinkGroupNotFound { group });
+        }
+        let clip = self.editable_clip_mut(clip_id)?;
+        clip.link_group = Some(group);
+        Ok(())
+    }
+
+    /// IDs of a clip and every clip linked to it, in track order.
+    ///
+    /// Returns just `[clip_id]` for an unlinked clip, and nothing if the clip does not exist.
+    pub fn linked_clips(&self, clip_id: ClipId) -> Vec<ClipId> {
+        self.linked_with_tracks(clip_id)
+            .map(|members| members.into_iter().map(|(_, c)| c.id).collect())
+            .unwrap_or_default()
+    }
+
+    /// How far a linked clip has drifted out of sync with its group, in source time.
+    ///
+    /// Measured against the first member of the group in track order (normally
+    /// the video clip). Positive means this clip plays later source material than
+    /// it should. Returns `None` for unlinked clips.
+    pub fn sync_offset(&self, clip_id: ClipId) -> Option<Time> {
+        let (_, clip) = self.find_clip(clip_id)?;
+        let group = clip.link_group?;
+        let reference = self.tracks
+            .iter()
+            .flat_map(|t| t.clips.iter())
+            .find(|c| c.link_group == Some(group))?;
+        let drift = |c: &Clip| (c.in_point - c.timeline_start) - c.sync_origin;
+        Some(drift(clip) - drift(reference))
+    }
+
+    /// Move a clip, and every clip linked to it by the same amount.
+    ///
+    /// Only `clip_id` changes track (to `track_id`); linked clips stay on theirs.
+    pub fn move_linked(&mut self, clip_id: ClipId, track_id: TrackId, new_start: Time) -> Result<(), TrackError> {
+        let members = self.linked_with_tracks(clip_id)?;
+        let start = members.iter()
+            .find(|(_, c)| c.id == clip_id)
+            .map(|(_, c)| c.timeline_start)
+            .ok_or(TrackError::ClipNotFound { clip_id })?;
+        let delta = new_start - start;
+
+        let updated = members
+            .into_iter()
+            .map(|(member_track, mut clip)| {
+                let target = if clip.id == clip_id { track_id } else { member_track };
+                clip.move_to(clip.timeline_start + delta);
+                (target, clip)
+            })
+            .collect();
+        self.replace_clips(updated)
+    }
+
+    /// Trim the in or out point of a clip and every clip linked to it.
+    ///
+    /// `source_point` is the new in/out point of `clip_id`; the clip keeps its
+    /// other timeline edge. Linked clips have the same timeline edge moved by
+    /// the same amount, whatever their speed.
+    pub fn trim_linked(&mut self, clip_id: ClipId, edge: TrimEdge, source_point: Time) -> Result<(), TrackError> {
+        let members = self.linked_with_tracks(clip_id)?;
+        let (_, clip) = members.iter()
+            .find(|(_, c)| c.id == clip_id)
+            .ok_or(TrackError::ClipNotFound { clip_id })?;
+        if clip.time_remap.is_some() {
+            return Err(TrackError::TimeRemapped { clip_id });
+        }
+
+        let mut trimmed = clip.clone();
+        match edge {
+            TrimEdge::In => trimmed.in_point = source_point,
+            TrimEdge::Out => trimmed.out_point = source_point,
+        }
+        check_source_range(clip, trimmed.in_point, trimmed.out_point)?;
+        let delta = match edge {
+            TrimEdge::In => clip.timeline_end - trimmed.retimed_duration() - clip.timeline_start,
+            TrimEdge::Out => clip.timeline_start + trimmed.retimed_duration() - clip.timeline_end,
+        };
+
+        let mut updated = Vec::with_capacity(members.len());
+        for (track_id, mut clip) in members {
+            let (start, end) = match edge {
+                TrimEdge::In => (clip.timeline_start + delta, clip.timeline_end),
+                TrimEdge::Out => (clip.timeline_start, clip.timeline_end + delta),
+            };
+            if end <= start {
+                return Err(TrackError::ClipTooShort { clip_id: clip.id });
+            }
+            if clip.id == clip_id {
+                (clip.in_point, clip.out_point) = (trimmed.in_point, trimmed.out_point);
+                (clip.timeline_start, clip.timeline_end) = (start, end);
+            } else {
+                let (in_point, out_point) = clip.source_range_for(start, end);
+                check_source_range(&clip, in_point, out_point)?;
+                clip.set_timeline_range(start, end);
+            }
+            updated.push((track_id, clip));
+        }
+        self.replace_clips(updated)
+    }
+
+    /// Split a clip and every linked clip that spans `position`.
+    ///
+    /// The right halves are linked together in a new group. Returns
+    /// `(left, right)` clip ID pairs for each cut.
+    pub fn split_linked(&mut self, clip_id: ClipId, position: Time) -> Result<Vec<(ClipId, ClipId)>, TrackError> {
+        let members = self.linked_with_tracks(clip_id)?;
+        let clip = &members.iter()
+            .find(|(_, c)| c.id == clip_id)
+            .ok_or(TrackError::ClipNotFound { clip_id })?
+            .1;
+        if position <= clip.timeline_start || position >= clip.timeline_end {
+            return Err(TrackError::InvalidSplit { clip_id, position });
+        }
+        let spanning = || members
+            .iter()
+            .filter(|(_, c)| c.timeline_start < position && position < c.timeline_end);
+        for (_, c) in spanning() {
+            self.check_clip_editable(c.id)?;
+        }
+
+        let cuts = spanning()
+            .map(|(_, c)| Ok((c.id, self.split_clip(c.id, position)?)))
+            .collect::<Result<Vec<(ClipId, ClipId)>, TrackError>>()?;
+        self.relink_split_halves(&cuts);
+        Ok(cuts)
+    }
+
+    /// Remove a clip and every clip linked to it.
+    ///
+    /// Returns the removed clips with the IDs of the tracks they were on.
+    /// Nothing is removed if any of them is on a locked track.
+    pub fn remove_linked(&mut self, clip_id: ClipId) -> Result<Vec<(TrackId, Clip)>, TrackError> {
+        let members = self.linked_with_tracks(clip_id)?;
+        for (_, clip) in &members {
+            self.check_clip_editable(clip.id)?;
+        }
+        members
+            .into_iter()
+            .map(|(_, clip)| self.remove_clip(clip.id))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::time;
+    use std::path::PathBuf;
+
+    /// Interview video (clip 1) and audio (clip 2) linked at 0s, plus an
+    /// unrelated audio clip 3 at 20s.
+    fn interview() -> (Timeline, TrackId, TrackId, LinkGroupId) {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        let a1 = timeline.tracks[1].id;
+        let path = PathBuf::from("interview.mp4");
+        timeline.add_clip(v1, Clip::new(1, path.clone(), time::from_seconds(5.0), time::from_seconds(15.0), 0, 0)).unwrap();
+        timeline.add_clip(a1, Clip::new(2, path, time::from_seconds(5.0), time::from_seconds(15.0), 0, 1)).unwrap();
+        timeline.add_clip(a1, Clip::new(3, PathBuf::from("music.wav"), 0, time::from_seconds(5.0), time::from_seconds(20.0), 0)).unwrap();
+        let group = timeline.link(&[1, 2]).unwrap();
+        (timeline, v1, a1, group)
+    }
+
+    #[test]
+    fn test_link_and_unlink() {
+        let (mut timeline, _, _, group) = interview();
+        assert_eq!(timeline.linked_clips(2), vec![1, 2]);
+        assert_eq!(timeline.linked_clips(3), vec![3]);
+        assert!(timeline.linked_clips(99).is_empty());
+
+        assert_eq!(timeline.unlink(2), Ok(Some(group)));
+        assert_eq!(timeline.linked_clips(1), vec![1]);
+        timeline.relink(2, group).unwrap();
+        assert_eq!(timeline.linked_clips(1), vec![1, 2]);
+
+        assert_eq!(timeline.relink(2, 42), Err(TrackError::LinkGroupNotFound { group: 42 }));
+        assert_eq!(timeline.link(&[1, 99]), Err(TrackError::ClipNotFound { clip_id: 99 }));
+
+        // A group whose every member left is gone
+        timeline.unlink(1).unwrap();
+        timeline.unlink(2).unwrap();
+        assert_eq!(timeline.relink(2, group), Err(TrackError::LinkGroupNotFound { group }));
+    }
+
+    #[test]
+    fn test_move_linked() {
+        let (mut timeline, v1, _, _) = interview();
+        let v2 = timeline.add_track(crate::timeline::TrackType::Video);
+
+        timeline.move_linked(1, v2, time::from_seconds(3.0)).unwrap();
+        let (track, video) = timeline.find_clip(1).unwrap();
+        assert_eq!(track.id, v2);
+        assert_eq!(video.timeline_start, time::from_seconds(3.0));
+        assert_eq!(timeline.find_clip(2).unwrap().1.timeline_start, time::from_seconds(3.0));
+        assert_eq!(timeline.sync_offset(2), Some(0));
+
+        // Audio would overlap clip 3: nothing moves
+        assert_eq!(timeline.move_linked(1, v1, time::from_seconds(15.0)), Err(TrackError::Overlap { clip_id: 2 }));
+        assert_eq!(timeline.find_clip(1).unwrap().1.timeline_start, time::from_seconds(3.0));
+        assert_eq!(timeline.find_clip(1).unwrap().0.id, v2);
+    }
+
+    #[test]
+    fn test_trim_linked() {
+        let (mut timeline, _, _, _) = interview();
+        timeline.trim_linked(2, TrimEdge::In, time::from_seconds(7.0)).unwrap();
+        for id in [1, 2] {
+            let clip = timeline.find_clip(id).unwrap().1;
+            assert_eq!(clip.in_point, time::from_seconds(7.0));
+            assert_eq!(clip.timeline_start, time::from_seconds(2.0));
+        }
+        timeline.trim_linked(1, TrimEdge::Out, time::from_seconds(10.0)).unwrap();
+        assert_eq!(timeline.find_clip(2).unwrap().1.timeline_end, time::from_seconds(5.0));
+        assert_eq!(timeline.sync_offset(2), Some(0));
+
+        assert_eq!(
+            timeline.trim_linked(1, TrimEdge::In, time::from_seconds(-1.0)),
+            Err(TrackError::SourceOutOfRange { clip_id: 1 })
+        );
+    }
+
+    #[test]
+    fn test_split_linked_regroups_right_halves() {
+        let (mut timeline, _, _, group) = interview();
+        let cuts = timeline.split_linked(1, time::from_seconds(4.0)).unwrap();
+        assert_eq!(cuts, vec![(1, 4), (2, 5)]);
+        assert_eq!(timeline.linked_clips(1), vec![1, 2]);
+        assert_eq!(timeline.linked_clips(4), vec![4, 5]);
+        assert_ne!(timeline.find_clip(4).unwrap().1.link_group, Some(group));
+        assert_eq!(timeline.sync_offset(5), Some(0));
+
+        assert_eq!(
+            timeline.split_linked(1, time::from_seconds(4.0)),
+            Err(TrackError::InvalidSplit { clip_id: 1, position: time::from_seconds(4.0) })
+        );
+    }
+
+    #[test]
+    fn test_split_all_keeps_links() {
+        let (mut timeline, _, _, _) = interview();
+        timeline.set_playhead(time::from_seconds(4.0));
+        timeline.split_all_at_playhead().unwrap();
+        assert_eq!(timeline.linked_clips(4), vec![4, 5]);
+    }
+
+    #[test]
+    fn test_remove_linked() {
+        let (mut timeline, _, a1, _) = interview();
+        let removed = timeline.remove_linked(2).unwrap();
+        assert_eq!(removed.len(), 2);
+        assert_eq!(removed[1].0, a1);
+        assert!(timeline.find_clip(1).is_none());
+        assert!(timeline.find_clip(3).is_some());
+    }
+
+    #[test]
+    fn test_sync_offset_after_unlinked_move() {
+        let (mut timeline, _, a1, group) = interview();
+        timeline.unlink(2).unwrap();
+        timeline.move_linked(2, a1, time::from_seconds(0.5)).unwrap();
+        assert_eq!(timeline.find_clip(1).unwrap().1.timeline_start, 0);
+
+        timeline.relink(2, group).unwrap();
+        assert_eq!(timeline.sync_offset(1), Some(0));
+        // Audio now plays 0.5s earlier source material than the video at the same time
+        assert_eq!(timeline.sync_offset(2), Some(time::from_seconds(-0.5)));
+        assert_eq!(timeline.sync_offset(3), None);
+    }
+}
@@ -0,0 +1,341 @@
+//! Timeline and clip markers: named points or ranges used for navigation,
+//! review notes and chapter points.
+//!
+//! Timeline markers sit at a fixed timeline position. Clip markers are stored
+//! on the clip in source time, so they follow the clip when it is moved and
+//! disappear from view when trimmed out of the clip's range.
+
+use crate::timeline::clip::ClipId;
+use crate::timeline::timeline::Timeline;
+use crate::timeline::track::TrackError;
+use crate::core::time::{Time, TimeRange};
+use serde::{Deserialize, Serialize};
+
+/// Unique identifier for a marker (timeline and clip markers share one ID space)
+pub type MarkerId = u64;
+
+/// What a marker is for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MarkerKind {
+    /// Chapter point, written to exported files as chapter metadata
+    Chapter,
+    /// Something still to be done at this point
+    Todo,
+    /// Review note
+    Comment,
+}
+
+/// Marker colour from the editor's fixed palette.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MarkerColor {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Cyan,
+    Blue,
+    Purple,
+}
+
+impl MarkerColor {
+    /// RGB value used to draw the marker.
+    pub fn rgb(&self) -> [u8; 3] {
+        match self {
+            MarkerColor::Red => [230, 70, 70],
+            MarkerColor::Orange => [240, 150, 50],
+            MarkerColor::Yellow => [235, 210, 60],
+            MarkerColor::Green => [90, 200, 90],
+            MarkerColor::Cyan => [70, 200, 210],
+            MarkerColor::Blue => [80, 130, 240],
+            MarkerColor::Purple => [170, 100, 220],
+        }
+    }
+}
+
+impl MarkerKind {
+    /// Colour a new marker of this kind gets by default.
+    pub fn default_color(&self) -> MarkerColor {
+        match self {
+            MarkerKind::Chapter => MarkerColor::Purple,
+            MarkerKind::Todo => MarkerColor::Orange,
+            MarkerKind::Comment => MarkerColor::Blue,
+        }
+    }
+}
+
+/// A named point, or range if it has a duration, on the timeline or a clip.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Marker {
+    pub id: MarkerId,
+    pub position: Time,        // Timeline time, or source time for clip markers
+    pub duration: Option<Time>, // Length of a range marker; `None` for a point
+    pub kind: MarkerKind,
+    pub name: String,
+    pub note: String,
+    pub color: MarkerColor,
+}
+
+impl Marker {
+    /// Create a point marker with an empty name and note and the kind's default colour.
+    pub fn new(id: MarkerId, position: Time, kind: MarkerKind) -> Self {
+        Self {
+            id,
+            position,
+            duration: None,
+            kind,
+            name: String::new(),
+            note: String::new(),
+            color: kind.default_color(),
+        }
+    }
+
+    /// Set the marker name (builder style).
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Set the marker note (builder style).
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.note = note.into();
+        self
+    }
+
+    /// Set the marker colour (builder style).
+    pub fn with_color(mut self, color: MarkerColor) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Make this a range marker of `duration` (builder style).
+    pub fn with_duration(mut self, duration: Time) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    /// Time span covered by the marker (empty for a point marker).
+    pub fn range(&self) -> TimeRange {
+        TimeRange::from_duration(self.position, self.duration.unwrap_or(0))
+    }
+}
+
+/// Where a marker is attached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkerOwner {
+    Timeline,
+    Clip(ClipId),
+}
+
+/// A marker resolved to timeline time, as returned by the navigation queries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlacedMarker {
+    pub owner: MarkerOwner,
+    /// The marker with `position` converted to timeline time
+    pub marker: Marker,
+}
+
+impl Timeline {
+    /// Allocate a marker ID not used by any marker added to this timeline so far.
+    pub fn allocate_marker_id(&mut self) -> MarkerId {
+        self.ids.marker.allocate()
+    }
+
+    /// Mark a marker ID as used.
+    pub(crate) fn reserve_marker_id(&mut self, id: MarkerId) {
+        self.ids.marker.reserve(id);
+    }
+
+    /// Add a timeline marker, keeping markers sorted by position.
+    pub fn add_marker(&mut self, marker: Marker) {
+        self.reserve_marker_id(marker.id);
+        let index = self.markers.partition_point(|m| m.position <= marker.position);
+        self.markers.insert(index, marker);
+    }
+
+    /// Move every timeline marker at or after `position` by `delta`, keeping
+    /// them sorted. Clip markers move with their clips.
+    pub(crate) fn ripple_markers_from(&mut self, position: Time, delta: Time) {
+        for marker in self.markers.iter_mut().filter(|m| m.position >= position) {
+            marker.position += delta;
+        }
+        self.markers.sort_by_key(|m| m.position);
+    }
+
+    /// Remove a timeline or clip marker.
+    ///
+    /// Returns the removed marker if found.
+    pub fn remove_marker(&mut self, id: MarkerId) -> Option<Marker> {
+        if let Some(index) = self.markers.iter().position(|m| m.id == id) {
+            return Some(self.markers.remove(index));
+        }
+        self.tracks
+            .iter_mut()
+            .flat_map(|t| t.clips.iter_mut())
+            .find_map(|clip| {
+                let index = clip.markers.iter().position(|m| m.id == id)?;
+                Some(clip.markers.remove(index))
+            })
+    }
+
+    /// Add a marker to a clip. The marker's `position` is in the clip's source time.
+    pub fn add_clip_marker(&mut self, clip_id: ClipId, marker: Marker) -> Result<(), TrackError> {
+        let clip = self.tracks
+            .iter_mut()
+            .flat_map(|t| t.clips.iter_mut())
+            .find(|c| c.id == clip_id)
+            .ok_or(TrackError::ClipNotFound { clip_id })?;
+        let index = clip.markers.partition_point(|m| m.position <= marker.position);
+        let id = marker.id;
+        clip.markers.insert(index, marker);
+        self.reserve_marker_id(id);
+        Ok(())
+    }
+
+    /// Every timeline marker, and every clip marker inside its clip's current
+    /// source range, in timeline time and sorted by position.
+    pub fn all_markers(&self) -> Vec<PlacedMarker> {
+        let clip_markers = self.tracks
+            .iter()
+            .flat_map(|t| t.clips.iter())
+            .flat_map(|clip| {
+                clip.markers.iter().filter_map(move |m| {
+                    if m.position >= clip.out_point {
+                        return None;
+                    }
+                    let mut marker = m.clone();
+                    marker.position = clip.source_to_timeline(m.position)?;
+                    Some(PlacedMarker { owner: MarkerOwner::Clip(clip.id), marker })
+                })
+            });
+        let mut markers: Vec<PlacedMarker> = self.markers
+            .iter()
+            .map(|m| PlacedMarker { owner: MarkerOwner::Timeline, marker: m.clone() })
+            .chain(clip_markers)
+            .collect();
+        markers.sort_by_key(|p| (p.marker.position, p.marker.id));
+        markers
+    }
+
+    /// First marker strictly after the playhead.
+    pub fn next_marker(&self) -> Option<PlacedMarker> {
+        self.all_markers()
+            .into_iter()
+            .find(|p| p.marker.position > self.playhead)
+    }
+
+    /// Last marker strictly before the playhead.
+    pub fn previous_marker(&self) -> Option<PlacedMarker> {
+        self.all_markers()
+            .into_iter()
+            .rev()
+            .find(|p| p.marker.position < self.playhead)
+    }
+
+    /// Markers that start in `[start, end)` or whose range overlaps it.
+    pub fn markers_in(&self, start: Time, end: Time) -> Vec<PlacedMarker> {
+        let range = TimeRange::new(start, end);
+        self.all_markers()
+            .into_iter()
+            .filter(|p| range.contains(p.marker.position) || range.overlaps(&p.marker.range()))
+            .collect()
+    }
+
+    /// Chapter markers as `(range, title)`, in order.
+    ///
+    /// Each chapter runs to the next chapter marker (or its own end, if it is a
+    /// range marker), and the last one to the end of the timeline.
+    pub fn chapters(&self) -> Vec<(TimeRange, String)> {
+        let starts: Vec<Marker> = self.all_markers()
+            .into_iter()
+            .map(|p| p.marker)
+            .filter(|m| m.kind == MarkerKind::Chapter && m.position < self.duration)
+            .collect();
+        starts
+            .iter()
+            .enumerate()
+            .map(|(i, m)| {
+                let next = starts.get(i + 1).map_or(self.duration, |n| n.position);
+                let end = m.duration.map_or(next, |d| (m.position + d).min(next));
+                (TimeRange::new(m.position, end), m.name.clone())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::time;
+    use crate::timeline::clip::Clip;
+    use std::path::PathBuf;
+
+    /// 10s clip 1 placed at 2s using source [5s, 15s], with a clip marker at source 8s
+    /// (timeline 5s), and timeline markers at 1s and 9s.
+    fn marked() -> Timeline {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        timeline.add_clip(v1, Clip::new(1, PathBuf::from("a.mp4"), time::from_seconds(5.0), time::from_seconds(15.0), time::from_seconds(2.0), 0)).unwrap();
+        timeline.add_clip_marker(1, Marker::new(10, time::from_seconds(8.0), MarkerKind::Comment)).unwrap();
+        timeline.add_marker(Marker::new(12, time::from_seconds(9.0), MarkerKind::Todo));
+        timeline.add_marker(Marker::new(11, time::from_seconds(1.0), MarkerKind::Chapter).with_name("Intro"));
+        timeline
+    }
+
+    fn positions(markers: &[PlacedMarker]) -> Vec<Time> {
+        markers.iter().map(|p| p.marker.position).collect()
+    }
+
+    #[test]
+    fn test_navigation() {
+        let mut timeline = marked();
+        assert_eq!(positions(&timeline.all_markers()), vec![time::from_seconds(1.0), time::from_seconds(5.0), time::from_seconds(9.0)]);
+
+        timeline.set_playhead(time::from_seconds(5.0));
+        assert_eq!(timeline.next_marker().unwrap().marker.id, 12);
+        let previous = timeline.previous_marker().unwrap();
+        assert_eq!(previous.marker.id, 11);
+        assert_eq!(previous.owner, MarkerOwner::Timeline);
+
+        timeline.set_playhead(time::from_seconds(9.0));
+        assert!(timeline.next_marker().is_none());
+        assert_eq!(timeline.allocate_marker_id(), 13);
+    }
+
+    #[test]
+    fn test_markers_in_range() {
+        let mut timeline = marked();
+        timeline.add_marker(Marker::new(20, 0, MarkerKind::Comment).with_duration(time::from_seconds(4.5)));
+        let ids: Vec<MarkerId> = timeline.markers_in(time::from_seconds(4.0), time::from_seconds(9.0)).iter().map(|p| p.marker.id).collect();
+        assert_eq!(ids, vec![20, 10]);
+    }
+
+    #[test]
+    fn test_clip_markers_follow_clip() {
+        let mut timeline = marked();
+        timeline.move_linked(1, timeline.tracks[0].id, time::from_seconds(4.0)).unwrap();
+        let placed = timeline.markers_in(time::from_seconds(7.0), time::from_seconds(7.5));
+        assert_eq!(placed.len(), 1);
+        assert_eq!(placed[0].owner, MarkerOwner::Clip(1));
+
+        // Split: the marker goes with the half that contains it
+        let right = timeline.split_clip(1, time::from_seconds(5.0)).unwrap();
+        assert!(timeline.find_clip(1).unwrap().1.markers.is_empty());
+        assert_eq!(timeline.find_clip(right).unwrap().1.markers.len(), 1);
+
+        assert_eq!(timeline.remove_marker(10).map(|m| m.id), Some(10));
+        assert_eq!(timeline.all_markers().len(), 2);
+    }
+
+    #[test]
+    fn test_chapters() {
+        let mut timeline = marked();
+        timeline.add_marker(Marker::new(20, time::from_seconds(6.0), MarkerKind::Chapter).with_name("Interview"));
+        assert_eq!(
+            timeline.chapters(),
+            vec![
+                (TimeRange::new(time::from_seconds(1.0), time::from_seconds(6.0)), "Intro".to_string()),
+                (TimeRange::new(time::from_seconds(6.0), time::from_seconds(12.0)), "Interview".to_string()),
+            ]
+        );
+    }
+}
@@ -1,9 +1,35 @@
 pub mod clip;
+pub mod speed;
+pub mod remap;
+pub mod keyframe;
+pub mod visual;
 pub mod track;
+pub mod trim;
+pub mod edit;
+pub mod link;
+pub mod marker;
+pub mod caption;
+pub mod transition;
+pub mod sequence;
+pub mod history;
+pub mod diff;
+pub mod commands;
+pub mod validate;
 #[allow(clippy::module_inception)]
 pub mod timeline;
 
-pub use clip::{Clip, ClipId};
+pub use clip::{Clip, ClipId, ClipSource, LinkGroupId};
+pub use speed::Speed;
+pub use remap::{TimeRemap, RemapKeyframe, RemapInterpolation, BezierHandles};
+pub use keyframe::{Animatable, ClipAnimation, Interpolation, Keyframe, KeyframeTrack};
+pub use visual::{Crop, VisualProperties};
 pub use track::{Track, TrackType, TrackId, TrackError};
+pub use trim::TrimEdge;
+pub use marker::{Marker, MarkerId, MarkerKind, MarkerColor, MarkerOwner, PlacedMarker};
+pub use caption::{Cue, CueId, CueAlign, CuePosition, CueStyle};
+pub use transition::{Transition, TransitionId, TransitionKind, TransitionAlignment, TransitionWindow, FadeCurve};
+pub use sequence::{Sequence, SequenceId};
 pub use timeline::Timeline;
-
+pub use history::{History, Command};
+pub use edit::{ThreePointEdit, EditPoints, EditPointError, RangeEdit};
+pub use validate::{Diagnostic, MediaDurations, TimelineIssue};
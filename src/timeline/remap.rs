@@ -0,0 +1,429 @@
+//! Time remapping: variable speed ramps on a clip.
+//!
+//! A [`TimeRemap`] is a keyframed curve of source time against the offset
+//! into the clip on the timeline. Between keyframes the source time is
+//! interpolated linearly or along a cubic bezier ease; before the first and
+//! after the last keyframe it holds. When set on a clip it replaces the
+//! clip's constant `speed` for `Clip::timeline_to_source`.
+
+use crate::core::time::Time;
+use crate::timeline::clip::ClipId;
+use crate::timeline::timeline::Timeline;
+use crate::timeline::track::TrackError;
+use serde::{Deserialize, Serialize};
+
+/// Cubic bezier ease between two keyframes, like CSS `cubic-bezier(x1, y1, x2, y2)`.
+///
+/// Coordinates are in thousandths of the segment: x is the fraction of the
+/// segment's timeline length, y the fraction of its source change. x values
+/// are clamped to `0..=1000` so the curve stays a function of time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BezierHandles {
+    pub x1: i32,
+    pub y1: i32,
+    pub x2: i32,
+    pub y2: i32,
+}
+
+impl BezierHandles {
+    pub const EASE_IN: BezierHandles = BezierHandles { x1: 420, y1: 0, x2: 1000, y2: 1000 };
+    pub const EASE_OUT: BezierHandles = BezierHandles { x1: 0, y1: 0, x2: 580, y2: 1000 };
+    pub const EASE_IN_OUT: BezierHandles = BezierHandles { x1: 420, y1: 0, x2: 580, y2: 1000 };
+
+    pub fn new(x1: i32, y1: i32, x2: i32, y2: i32) -> Self {
+        Self { x1: x1.clamp(0, 1000), y1, x2: x2.clamp(0, 1000), y2 }
+    }
+
+    fn points(&self) -> [(f64, f64); 4] {
+        [
+            (0.0, 0.0),
+            (self.x1 as f64 / 1000.0, self.y1 as f64 / 1000.0),
+            (self.x2 as f64 / 1000.0, self.y2 as f64 / 1000.0),
+            (1.0, 1.0),
+        ]
+    }
+
+    /// Eased progress (y) at segment progress `x` (both 0.0-1.0).
+    pub(crate) fn ease(&self, x: f64) -> f64 {
+        let points = self.points();
+        cubic(&points, solve_x(&points, x)).1
+    }
+
+    /// The part of this ease between segment progress `x0` and `x1`, rescaled
+    /// to its own unit box. `None` if the part has no source change.
+    fn sub_range(&self, x0: f64, x1: f64) -> Option<BezierHandles> {
+        let points = self.points();
+        let s0 = solve_x(&points, x0);
+        let s1 = solve_x(&points, x1);
+        // Cut off everything after s1, then everything before s0 within what is left
+        let (head, _) = split(&points, s1);
+        let (_, part) = split(&head, if s1 > 0.0 { s0 / s1 } else { 0.0 });
+
+        let (start, end) = (part[0], part[3]);
+        let (width, height) = (end.0 - start.0, end.1 - start.1);
+        if width <= 0.0 || height.abs() < 1e-9 {
+            return None;
+        }
+        let scale = |p: (f64, f64)| {
+            (
+                ((p.0 - start.0) / width * 1000.0).round() as i32,
+                ((p.1 - start.1) / height * 1000.0).round() as i32,
+            )
+        };
+        let (ax, ay) = scale(part[1]);
+        let (bx, by) = scale(part[2]);
+        Some(BezierHandles::new(ax, ay, bx, by))
+    }
+}
+
+/// Point on a cubic bezier at parameter `s`.
+fn cubic(points: &[(f64, f64); 4], s: f64) -> (f64, f64) {
+    let t = 1.0 - s;
+    let (a, b, c, d) = (t * t * t, 3.0 * t * t * s, 3.0 * t * s * s, s * s * s);
+    (
+        a * points[0].0 + b * points[1].0 + c * points[2].0 + d * points[3].0,
+        a * points[0].1 + b * points[1].1 + c * points[2].1 + d * points[3].1,
+    )
+}
+
+/// Parameter at which the (monotone in x) curve reaches `x`, by bisection.
+fn solve_x(points: &[(f64, f64); 4], x: f64) -> f64 {
+    let (mut low, mut high) = (0.0, 1.0);
+    for _ in 0..48 {
+        let mid = (low + high) / 2.0;
+        if cubic(points, mid).0 < x {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    (low + high) / 2.0
+}
+
+/// Split a cubic bezier at parameter `s` (de Casteljau).
+#[allow(clippy::type_complexity)]
+fn split(points: &[(f64, f64); 4], s: f64) -> ([(f64, f64); 4], [(f64, f64); 4]) {
+    let lerp = |a: (f64, f64), b: (f64, f64)| (a.0 + (b.0 - a.0) * s, a.1 + (b.1 - a.1) * s);
+    let p01 = lerp(points[0], points[1]);
+    let p12 = lerp(points[1], points[2]);
+    let p23 = lerp(points[2], points[3]);
+    let p012 = lerp(p01, p12);
+    let p123 = lerp(p12, p23);
+    let mid = lerp(p012, p123);
+    ([points[0], p01, p012, mid], [mid, p123, p23, points[3]])
+}
+
+/// How source time moves from one keyframe to the next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RemapInterpolation {
+    Linear,
+    Bezier(BezierHandles),
+}
+
+/// One point on a time-remap curve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RemapKeyframe {
+    pub offset: Time,       // Timeline offset from the clip's start (nanoseconds)
+    pub source_time: Time,  // Source time shown at that offset (nanoseconds)
+    pub interpolation: RemapInterpolation, // Towards the next keyframe
+}
+
+impl RemapKeyframe {
+    /// A keyframe with linear interpolation to the next one.
+    pub fn linear(offset: Time, source_time: Time) -> Self {
+        Self { offset, source_time, interpolation: RemapInterpolation::Linear }
+    }
+
+    /// A keyframe with a bezier ease to the next one.
+    pub fn bezier(offset: Time, source_time: Time, handles: BezierHandles) -> Self {
+        Self { offset, source_time, interpolation: RemapInterpolation::Bezier(handles) }
+    }
+}
+
+/// Keyframed source-time curve for a clip (a speed ramp).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TimeRemap {
+    keyframes: Vec<RemapKeyframe>,
+    /// Blend the two nearest source frames when the curve lands between them
+    pub frame_blend: bool,
+}
+
+impl TimeRemap {
+    /// Create a curve from keyframes (sorted by offset; later duplicates win).
+    ///
+    /// # Panics
+    /// Panics if `keyframes` is empty.
+    pub fn new(mut keyframes: Vec<RemapKeyframe>) -> Self {
+        assert!(!keyframes.is_empty(), "TimeRemap needs at least one keyframe");
+        keyframes.sort_by_key(|k| k.offset);
+        keyframes.reverse();
+        keyframes.dedup_by_key(|k| k.offset);
+        keyframes.reverse();
+        Self { keyframes, frame_blend: false }
+    }
+
+    /// Turn frame blending on or off (builder style).
+    pub fn with_frame_blend(mut self, frame_blend: bool) -> Self {
+        self.frame_blend = frame_blend;
+        self
+    }
+
+    /// Keyframes in offset order.
+    pub fn keyframes(&self) -> &[RemapKeyframe] {
+        &self.keyframes
+    }
+
+    /// Move every keyframe's source time by `delta` (slip).
+    pub fn shift_source(&mut self, delta: Time) {
+        for keyframe in &mut self.keyframes {
+            keyframe.source_time += delta;
+        }
+    }
+
+    /// Source time at `offset` into the clip.
+    pub fn source_at(&self, offset: Time) -> Time {
+        let next = self.keyframes.partition_point(|k| k.offset <= offset);
+        if next == 0 {
+            return self.keyframes[0].source_time;
+        }
+        let from = &self.keyframes[next - 1];
+        let Some(to) = self.keyframes.get(next) else {
+            return from.source_time;
+        };
+
+        let progress = (offset - from.offset) as f64 / (to.offset - from.offset) as f64;
+        let eased = match from.interpolation {
+            RemapInterpolation::Linear => progress,
+            RemapInterpolation::Bezier(handles) => handles.ease(progress),
+        };
+        from.source_time + ((to.source_time - from.source_time) as f64 * eased).round() as Time
+    }
+
+    /// First offset in `[0, length]` at which `source_time` is shown.
+    ///
+    /// Each segment is assumed to move monotonically between its keyframes.
+    pub fn offset_of(&self, source_time: Time, length: Time) -> Option<Time> {
+        let first = &self.keyframes[0];
+        if first.offset > 0 && source_time == first.source_time {
+            return Some(0);
+        }
+        for pair in self.keyframes.windows(2) {
+            let (from, to) = (&pair[0], &pair[1]);
+            let (low, high) = (from.source_time.min(to.source_time), from.source_time.max(to.source_time));
+            if from.offset > length || source_time < low || source_time > high {
+                continue;
+            }
+            // Bisect on the segment: source time moves from `from` towards `to`
+            let rising = to.source_time >= from.source_time;
+            let (mut a, mut b) = (from.offset, to.offset);
+            while b - a > 1 {
+                let mid = a + (b - a) / 2;
+                if (self.source_at(mid) < source_time) == rising {
+                    a = mid;
+                } else {
+                    b = mid;
+                }
+            }
+            let offset = if self.source_at(a) == source_time { a } else { b };
+            return (offset <= length).then_some(offset);
+        }
+        let last = self.keyframes.last().expect("curve is non-empty");
+        (source_time == last.source_time && last.offset <= length).then_some(last.offset.max(0))
+    }
+
+    /// Lowest and highest source time shown over `[0, length]`.
+    pub fn source_bounds(&self, length: Time) -> (Time, Time) {
+        let mut low = self.source_at(0).min(self.source_at(length));
+        let mut high = self.source_at(0).max(self.source_at(length));
+        for (i, keyframe) in self.keyframes.iter().enumerate() {
+            if keyframe.offset < 0 || keyframe.offset > length {
+                continue;
+            }
+            low = low.min(keyframe.source_time);
+            high = high.max(keyframe.source_time);
+            // Bezier handles may overshoot the keyframe values
+            if let (RemapInterpolation::Bezier(_), Some(next)) = (keyframe.interpolation, self.keyframes.get(i + 1)) {
+                for step in 1..16 {
+                    let offset = keyframe.offset + (next.offset - keyframe.offset) * step / 16;
+                    if offset <= length {
+                        let source = self.source_at(offset);
+                        low = low.min(source);
+                        high = high.max(source);
+                    }
+                }
+            }
+        }
+        (low, high)
+    }
+
+    /// The curve between offsets `start` and `end`, rebased so `start` becomes 0.
+    ///
+    /// Keyframes are added at both cut points; a bezier segment that is cut
+    /// keeps its shape on each side.
+    pub fn trimmed(&self, start: Time, end: Time) -> TimeRemap {
+        let mut keyframes = vec![self.keyframe_at(start)];
+        keyframes.extend(
+            self.keyframes
+                .iter()
+                .filter(|k| k.offset > start && k.offset < end)
+                .copied(),
+        );
+        if end > start {
+            let mut last = self.keyframe_at(end);
+            last.interpolation = RemapInterpolation::Linear;
+            keyframes.push(last);
+        }
+
+        // Reshape the cut segments so the ease is unchanged
+        for i in 0..keyframes.len().saturating_sub(1) {
+            let (from, to) = (keyframes[i].offset, keyframes[i + 1].offset);
+            keyframes[i].interpolation = self.segment_between(from, to);
+        }
+        for keyframe in &mut keyframes {
+            keyframe.offset -= start;
+        }
+        TimeRemap { keyframes, frame_blend: self.frame_blend }
+    }
+
+    /// The curve moved by `offset_delta` on the timeline and `source_delta` in source.
+    fn moved(&self, offset_delta: Time, source_delta: Time) -> TimeRemap {
+        let keyframes = self.keyframes
+            .iter()
+            .map(|k| RemapKeyframe { offset: k.offset + offset_delta, source_time: k.source_time + source_delta, ..*k })
+            .collect();
+        TimeRemap { keyframes, frame_blend: self.frame_blend }
+    }
+
+    /// A keyframe at `offset` on the curve.
+    fn keyframe_at(&self, offset: Time) -> RemapKeyframe {
+        RemapKeyframe::linear(offset, self.source_at(offset))
+    }
+
+    /// Interpolation reproducing the curve between `from` and `to`, which lie
+    /// within one original segment.
+    fn segment_between(&self, from: Time, to: Time) -> RemapInterpolation {
+        let next = self.keyframes.partition_point(|k| k.offset <= from);
+        let segment_start = next.checked_sub(1).map(|i| &self.keyframes[i]);
+        let (Some(segment_start), Some(segment_end)) = (segment_start, self.keyframes.get(next)) else {
+            return RemapInterpolation::Linear;
+        };
+        let RemapInterpolation::Bezier(handles) = segment_start.interpolation else {
+            return RemapInterpolation::Linear;
+        };
+        if from == segment_start.offset && to == segment_end.offset {
+            return segment_start.interpolation;
+        }
+        let length = (segment_end.offset - segment_start.offset) as f64;
+        let x0 = (from - segment_start.offset) as f64 / length;
+        let x1 = (to - segment_start.offset) as f64 / length;
+        handles
+            .sub_range(x0, x1)
+            .map_or(RemapInterpolation::Linear, RemapInterpolation::Bezier)
+    }
+}
+
+impl Timeline {
+    /// Set or remove (`None`) the time-remap curve of a clip and every clip linked to it.
+    ///
+    /// `remap` is relative to `clip_id`. Linked clips get the same curve, moved
+    /// to their own timeline start and source offset so they stay in sync.
+    /// Timeline ranges are kept while a curve is set; removing it returns each
+    /// clip to normal speed over the source range the curve covered.
+    pub fn set_clip_time_remap(&mut self, clip_id: ClipId, remap: Option<TimeRemap>) -> Result<(), TrackError> {
+        let members = self.linked_with_tracks(clip_id)?;
+        let (_, clip) = members.iter()
+            .find(|(_, c)| c.id == clip_id)
+            .expect("clip is a member of its own group");
+        let (start, origin) = (clip.timeline_start, clip.in_point - clip.timeline_start);
+
+        let updated = members
+            .into_iter()
+            .map(|(track_id, mut member)| {
+                let source_delta = member.in_point - member.timeline_start - origin;
+                let remap = remap.as_ref().map(|r| r.moved(start - member.timeline_start, source_delta));
+                member.set_time_remap(remap);
+                (track_id, member)
+            })
+            .collect();
+        self.replace_clips(updated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::time;
+
+    /// Normal speed for 2s, then a ramp that covers 4s of source in the next 2s.
+    fn ramp() -> TimeRemap {
+        TimeRemap::new(vec![
+            RemapKeyframe::linear(0, time::from_seconds(10.0)),
+            RemapKeyframe::linear(time::from_seconds(2.0), time::from_seconds(12.0)),
+            RemapKeyframe::bezier(time::from_seconds(4.0), time::from_seconds(16.0), BezierHandles::EASE_IN_OUT),
+            RemapKeyframe::linear(time::from_seconds(6.0), time::from_seconds(17.0)),
+        ])
+    }
+
+    #[test]
+    fn test_source_at() {
+        let remap = ramp();
+        assert_eq!(remap.source_at(time::from_seconds(-1.0)), time::from_seconds(10.0));
+        assert_eq!(remap.source_at(time::from_seconds(1.0)), time::from_seconds(11.0));
+        assert_eq!(remap.source_at(time::from_seconds(3.0)), time::from_seconds(14.0));
+        // Symmetric ease: halfway in time is halfway in source
+        assert_eq!(remap.source_at(time::from_seconds(5.0)), time::from_seconds(16.5));
+        assert!(remap.source_at(time::from_seconds(4.5)) < time::from_seconds(16.25));
+        assert_eq!(remap.source_at(time::from_seconds(9.0)), time::from_seconds(17.0));
+    }
+
+    #[test]
+    fn test_offset_of_and_bounds() {
+        let remap = ramp();
+        assert_eq!(remap.offset_of(time::from_seconds(14.0), time::from_seconds(6.0)), Some(time::from_seconds(3.0)));
+        assert_eq!(remap.offset_of(time::from_seconds(16.5), time::from_seconds(6.0)), Some(time::from_seconds(5.0)));
+        assert_eq!(remap.offset_of(time::from_seconds(9.0), time::from_seconds(6.0)), None);
+        assert_eq!(remap.source_bounds(time::from_seconds(3.0)), (time::from_seconds(10.0), time::from_seconds(14.0)));
+    }
+
+    #[test]
+    fn test_trimmed_keeps_shape() {
+        let remap = ramp();
+        let tail = remap.trimmed(time::from_seconds(4.5), time::from_seconds(6.0));
+        assert_eq!(tail.keyframes()[0].offset, 0);
+        for offset in [0.0, 0.2, 0.5, 0.9, 1.5] {
+            let expected = remap.source_at(time::from_seconds(4.5 + offset));
+            let actual = tail.source_at(time::from_seconds(offset));
+            assert!((expected - actual).abs() < time::from_millis(5), "at {}", offset);
+        }
+
+        let head = remap.trimmed(0, time::from_seconds(3.0));
+        assert_eq!(head.keyframes().len(), 3);
+        assert_eq!(head.source_at(time::from_seconds(3.0)), time::from_seconds(14.0));
+    }
+
+    #[test]
+    fn test_set_clip_time_remap_linked() {
+        use crate::timeline::clip::Clip;
+        use crate::timeline::track::TrackType;
+
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        let a1 = timeline.add_track(TrackType::Audio);
+        let path = std::path::PathBuf::from("a.mp4");
+        timeline.add_clip(v1, Clip::new(1, path.clone(), time::from_seconds(10.0), time::from_seconds(16.0), 0, 0)).unwrap();
+        // Audio starts a second later on the timeline, in sync with the video
+        timeline.add_clip(a1, Clip::new(2, path, time::from_seconds(11.0), time::from_seconds(16.0), time::from_seconds(1.0), 1)).unwrap();
+        timeline.link(&[1, 2]).unwrap();
+
+        timeline.set_clip_time_remap(1, Some(ramp())).unwrap();
+        let audio = timeline.find_clip(2).unwrap().1;
+        assert_eq!(audio.timeline_to_source(time::from_seconds(3.0)), Some(time::from_seconds(14.0)));
+        assert_eq!(audio.in_point, time::from_seconds(11.0));
+
+        // Removing the curve plays the covered source range at normal speed
+        timeline.set_clip_time_remap(1, None).unwrap();
+        let video = timeline.find_clip(1).unwrap().1;
+        assert_eq!((video.in_point, video.out_point), (time::from_seconds(10.0), time::from_seconds(17.0)));
+        assert_eq!(video.timeline_end, time::from_seconds(7.0));
+    }
+}
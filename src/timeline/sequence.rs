@@ -0,0 +1,383 @@
+//! Nested sequences and compound clips.
+//!
+//! A compound clip plays another timeline (a sequence) as if it were a single
+//! media clip: its source time is time on the nested timeline. Sequences are
+//! kept in one flat registry on the root timeline (`Timeline::sequences`) and
+//! referenced by ID, so one sequence can be used by several clips and by other
+//! sequences at any depth. Edits that would make a sequence contain itself are
+//! refused. Nested timelines take their IDs from the root's allocators, so
+//! clip, track and other IDs are unique across all sequences.
+
+use std::collections::{BTreeMap, BTreeSet};
+use crate::timeline::clip::{Clip, ClipId, ClipSource};
+use crate::timeline::timeline::Timeline;
+use crate::timeline::track::{Track, TrackError, TrackType};
+
+/// Unique identifier for a sequence in the root timeline's registry
+pub type SequenceId = u64;
+
+/// A named timeline that compound clips can play.
+#[derive(Debug, Clone)]
+pub struct Sequence {
+    pub id: SequenceId,
+    pub name: String,
+    pub timeline: Timeline,
+}
+
+impl Timeline {
+    /// Allocate a sequence ID not used by any sequence added so far.
+    pub fn allocate_sequence_id(&mut self) -> SequenceId {
+        self.ids.sequence.allocate()
+    }
+
+    /// Mark a sequence ID as used.
+    pub(crate) fn reserve_sequence_id(&mut self, id: SequenceId) {
+        self.ids.sequence.reserve(id);
+    }
+
+    /// Look up a sequence in this (root) timeline's registry.
+    pub fn sequence(&self, id: SequenceId) -> Option<&Sequence> {
+        self.sequences.get(&id)
+    }
+
+    /// Mutable access to a sequence, for view state such as its playhead.
+    ///
+    /// Edit its clips through `edit_sequence`, which checks for cycles.
+    pub fn sequence_mut(&mut self, id: SequenceId) -> Option<&mut Sequence> {
+        self.sequences.get_mut(&id)
+    }
+
+    /// Register `timeline` as a new sequence and return its ID.
+    ///
+    /// Sequences must be registered on the root timeline; the `sequences` of
+    /// `timeline` itself are discarded. The IDs `timeline` has allocated are
+    /// reserved on the root. Fails with `TrackError::SequenceCycle` if it
+    /// contains a compound clip that would play the new sequence.
+    pub fn add_sequence(&mut self, name: impl Into<String>, mut timeline: Timeline) -> Result<SequenceId, TrackError> {
+        let id = self.allocate_sequence_id();
+        timeline.sequences.clear();
+        self.ids.include(timeline.ids);
+        self.sequences.insert(id, Sequence { id, name: name.into(), timeline });
+        if self.sequence_reaches(id, id) {
+            self.sequences.remove(&id);
+            return Err(TrackError::SequenceCycle { sequence: id });
+        }
+        Ok(id)
+    }
+
+    /// Edit the timeline of a sequence ("open" a compound clip).
+    ///
+    /// `edit` runs on the nested timeline with this timeline's registry and
+    /// ID allocators lent to it, so it can use and create sequences like the
+    /// root can and its new IDs are unique across sequences. If `edit`
+    /// fails, or leaves the sequence containing itself
+    /// (`TrackError::SequenceCycle`), the sequence is restored. On success every
+    /// compound clip using the sequence gets its new length as source duration.
+    pub fn edit_sequence<R, F>(&mut self, id: SequenceId, edit: F) -> Result<R, TrackError>
+    where
+        F: FnOnce(&mut Timeline) -> Result<R, TrackError>,
+    {
+        let mut sequence = self.sequences.remove(&id)
+            .ok_or(TrackError::SequenceNotFound { sequence: id })?;
+        let before = sequence.timeline.clone();
+        let first_new_id = self.ids.sequence.peek();
+
+        sequence.timeline.sequences = std::mem::take(&mut self.sequences);
+        sequence.timeline.ids = self.ids;
+        let result = edit(&mut sequence.timeline);
+        sequence.timeline.update_duration();
+        self.sequences = std::mem::take(&mut sequence.timeline.sequences);
+        self.ids = sequence.timeline.ids;
+        self.sequences.insert(id, sequence);
+
+        let result = result.and_then(|value| {
+            if self.sequence_reaches(id, id) {
+                Err(TrackError::SequenceCycle { sequence: id })
+            } else {
+                Ok(value)
+            }
+        });
+        match result {
+            Ok(_) => self.refresh_sequence_duration(id),
+            Err(_) => {
+                self.sequences.get_mut(&id).expect("sequence was put back").timeline = before;
+                self.sequences.retain(|&other, _| other < first_new_id);
+            }
+        }
+        result
+    }
+
+    /// Collapse clips into a compound clip and return the new sequence's ID.
+    ///
+    /// The clips, with the clips linked to them, move to a new sequence with
+    /// the same track layout, keeping their relative positions, with the
+    /// earliest clip at time 0. Transitions into them move along. A compound
+    /// clip covering their combined range takes their place on the lowest track
+    /// of each type they came from; when there are both video and audio clips,
+    /// the video and audio compound clips are linked. Fails with
+    /// `TrackError::TrackLocked` if a clip is on a locked track, or
+    /// `TrackError::Overlap` if another clip on one of those tracks is in the
+    /// way; on failure the timeline is unchanged.
+    pub fn compound_clips(&mut self, clip_ids: &[ClipId], name: impl Into<String>) -> Result<SequenceId, TrackError> {
+        if clip_ids.is_empty() {
+            return Err(TrackError::NoClips);
+        }
+        // Linked clips come along, so no link group spans the sequence boundary
+        let mut clip_ids = clip_ids.to_vec();
+        for index in 0..clip_ids.len() {
+            for partner in self.linked_clips(clip_ids[index]) {
+                if !clip_ids.contains(&partner) {
+                    clip_ids.push(partner);
+                }
+            }
+        }
+        let mut track_indices = BTreeSet::new();
+        for &clip_id in &clip_ids {
+            let index = self.tracks
+                .iter()
+                .position(|t| t.clips.iter().any(|c| c.id == clip_id))
+                .ok_or(TrackError::ClipNotFound { clip_id })?;
+            self.tracks[index].check_unlocked()?;
+            track_indices.insert(index);
+        }
+        let selected = || {
+            self.tracks
+                .iter()
+                .flat_map(|t| &t.clips)
+                .filter(|c| clip_ids.contains(&c.id))
+        };
+        let start = selected().map(|c| c.timeline_start).min().ok_or(TrackError::NoClips)?;
+        let end = selected().map(|c| c.timeline_end).max().ok_or(TrackError::NoClips)?;
+
+        // One compound clip per track type, on the lowest track of that type
+        let targets: Vec<usize> = [TrackType::Video, TrackType::Audio]
+            .into_iter()
+            .filter_map(|track_type| {
+                track_indices.iter().copied().find(|&i| self.tracks[i].track_type == track_type)
+            })
+            .collect();
+        for &index in &targets {
+            let in_the_way = self.tracks[index].clips
+                .iter()
+                .find(|c| !clip_ids.contains(&c.id) && c.timeline_start < end && start < c.timeline_end);
+            if let Some(other) = in_the_way {
+                return Err(TrackError::Overlap { clip_id: other.id });
+            }
+        }
+
+        // Build the sequence from copies, starting at 0, before touching the
+        // tracks; its track IDs come from this timeline
+        let mut inner = Timeline::empty();
+        let mut edited = BTreeMap::new();
+        for &index in &track_indices {
+            let mut track = self.tracks[index].clone();
+            let mut nested = Track::new(self.ids.track.allocate(), track.track_type);
+            let (moved, kept) = std::mem::take(&mut track.clips)
+                .into_iter()
+                .partition(|c| clip_ids.contains(&c.id));
+            track.clips = kept;
+            (nested.transitions, track.transitions) = std::mem::take(&mut track.transitions)
+                .into_iter()
+                .partition(|t| clip_ids.contains(&t.clip_id));
+            for mut clip in moved {
+                clip.move_to(clip.timeline_start - start);
+                nested.add_clip(clip)?;
+            }
+            inner.tracks.push(nested);
+            edited.insert(index, track);
+        }
+        inner.update_duration();
+        let sequence = self.add_sequence(name, inner)?;
+
+        let group = (targets.len() > 1).then(|| self.allocate_link_group());
+        for &index in &targets {
+            let mut clip = Clip::new(self.allocate_clip_id(), ClipSource::Sequence(sequence), 0, end - start, start, 0)
+                .with_source_duration(end - start);
+            clip.link_group = group;
+            let added = edited.get_mut(&index).map_or(Ok(()), |track: &mut Track| track.add_clip(clip));
+            if let Err(err) = added {
+                self.sequences.remove(&sequence);
+                return Err(err);
+            }
+        }
+        for (index, track) in edited {
+            self.tracks[index] = track;
+        }
+        self.update_duration();
+        Ok(sequence)
+    }
+
+    /// IDs of the clips on this timeline that play sequence `id`.
+    pub fn sequence_clips(&self, id: SequenceId) -> Vec<ClipId> {
+        self.tracks
+            .iter()
+            .flat_map(|t| &t.clips)
+            .filter(|c| c.sequence_id() == Some(id))
+            .map(|c| c.id)
+            .collect()
+    }
+
+    /// A sequence in the registry that contains itself, if any.
+    ///
+    /// Edits refuse to create cycles, so this only finds them in timelines
+    /// built by other means (e.g. loaded from disk). Renderers check it first.
+    pub fn find_sequence_cycle(&self) -> Option<SequenceId> {
+        self.sequences.keys().copied().find(|&id| self.sequence_reaches(id, id))
+    }
+
+    /// Whether sequence `from` plays `target`, directly or through other sequences.
+    fn sequence_reaches(&self, from: SequenceId, target: SequenceId) -> bool {
+        let mut visited = BTreeSet::new();
+        let mut pending = vec![from];
+        while let Some(id) = pending.pop() {
+            if !visited.insert(id) {
+                continue;
+            }
+            let Some(sequence) = self.sequences.get(&id) else {
+                continue;
+            };
+            for nested in sequence.timeline.tracks.iter().flat_map(|t| &t.clips).filter_map(|c| c.sequence_id()) {
+                if nested == target {
+                    return true;
+                }
+                pending.push(nested);
+            }
+        }
+        false
+    }
+
+    /// Set the source duration of every compound clip playing `id` to its length.
+    fn refresh_sequence_duration(&mut self, id: SequenceId) {
+        let duration = self.sequences[&id].timeline.duration;
+        let nested_tracks = self.sequences.values_mut().flat_map(|s| &mut s.timeline.tracks);
+        for clip in self.tracks.iter_mut().chain(nested_tracks).flat_map(|t| &mut t.clips) {
+            if clip.sequence_id() == Some(id) {
+                clip.source_duration = Some(duration);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::time;
+    use crate::timeline::transition::{Transition, TransitionAlignment, TransitionKind};
+    use std::path::PathBuf;
+
+    fn clip(id: ClipId, start: f64, length: f64) -> Clip {
+        Clip::new(
+            id,
+            PathBuf::from("a.mp4"),
+            0,
+            time::from_seconds(length),
+            time::from_seconds(start),
+            0,
+        )
+    }
+
+    /// V1: clips 1 [0s, 2s), 2 [2s, 5s), 3 [8s, 9s); A1: clip 4 [3s, 6s)
+    fn timeline() -> Timeline {
+        let mut timeline = Timeline::new();
+        let (v1, a1) = (timeline.tracks[0].id, timeline.tracks[1].id);
+        timeline.add_clip(v1, clip(1, 0.0, 2.0)).unwrap();
+        timeline.add_clip(v1, clip(2, 2.0, 3.0)).unwrap();
+        timeline.add_clip(v1, clip(3, 8.0, 1.0)).unwrap();
+        timeline.add_clip(a1, clip(4, 3.0, 3.0)).unwrap();
+        timeline
+    }
+
+    #[test]
+    fn test_compound_clips() {
+        let mut timeline = timeline();
+        let id = timeline.compound_clips(&[2, 4], "Nest").unwrap();
+
+        // Compound clips cover [2s, 6s) on V1 and A1 and are linked
+        let compounds = timeline.sequence_clips(id);
+        assert_eq!(compounds.len(), 2);
+        let (_, video) = timeline.find_clip(compounds[0]).unwrap();
+        assert_eq!((video.timeline_start, video.timeline_end), (time::from_seconds(2.0), time::from_seconds(6.0)));
+        assert_eq!(timeline.linked_clips(compounds[0]), compounds);
+        assert!(timeline.find_clip(2).is_none());
+
+        // Inside, the clips keep their relative positions from 0
+        let inner = &timeline.sequence(id).unwrap().timeline;
+        assert_eq!(inner.find_clip(2).unwrap().1.timeline_start, 0);
+        assert_eq!(inner.find_clip(4).unwrap().1.timeline_start, time::from_seconds(1.0));
+        assert_eq!(inner.duration, time::from_seconds(4.0));
+
+        // Clip 4 now lives in the sequence
+        assert_eq!(timeline.compound_clips(&[3, 4], "Bad"), Err(TrackError::ClipNotFound { clip_id: 4 }));
+
+        // Clip 2 on V1 is in the way of [0s, 9s)
+        let mut timeline = self::timeline();
+        assert_eq!(timeline.compound_clips(&[1, 3], "Bad"), Err(TrackError::Overlap { clip_id: 2 }));
+        assert!(timeline.find_clip(1).is_some());
+        assert_eq!(timeline.compound_clips(&[], "Bad"), Err(TrackError::NoClips));
+    }
+
+    #[test]
+    fn test_compound_takes_links_transitions_and_root_ids() {
+        let mut timeline = timeline();
+        timeline.link(&[2, 4]).unwrap();
+        let id = timeline.allocate_transition_id();
+        let dissolve = Transition::new(
+            id,
+            2,
+            TransitionKind::CrossDissolve,
+            TransitionAlignment::CenterOnCut,
+            time::from_seconds(0.5),
+        );
+        timeline.tracks[0].transitions.push(dissolve);
+        let sequence = timeline.compound_clips(&[2], "Nest").unwrap();
+
+        // Clip 4 is linked to clip 2, so it moves too and stays linked
+        let inner = &timeline.sequence(sequence).unwrap().timeline;
+        assert_eq!(inner.linked_clips(2), vec![2, 4]);
+        assert!(timeline.find_clip(4).is_none());
+        assert!(timeline.tracks[0].transitions.is_empty());
+        assert_eq!(inner.tracks[0].transitions[0].id, id);
+
+        // Nested IDs come from the root's allocators
+        let inner_tracks: Vec<_> = inner.tracks.iter().map(|t| t.id).collect();
+        assert!(timeline.tracks.iter().all(|t| !inner_tracks.contains(&t.id)));
+        let nested_clip = timeline.edit_sequence(sequence, |tl| Ok(tl.allocate_clip_id())).unwrap();
+        assert!(timeline.allocate_clip_id() > nested_clip);
+    }
+
+    #[test]
+    fn test_edit_sequence_refuses_cycles() {
+        let mut timeline = timeline();
+        let inner = timeline.compound_clips(&[1], "Inner").unwrap();
+        let inner_clips = timeline.sequence_clips(inner);
+        let outer = timeline.compound_clips(&inner_clips, "Outer").unwrap();
+        assert_eq!(timeline.sequence_clips(outer).len(), 1);
+
+        // Placing the outer sequence inside the inner one would loop forever
+        let result = timeline.edit_sequence(inner, |tl| {
+            let track = tl.tracks[0].id;
+            let id = tl.allocate_clip_id();
+            let outer_clip = Clip::new(
+                id,
+                ClipSource::Sequence(outer),
+                0,
+                time::from_seconds(2.0),
+                time::from_seconds(5.0),
+                0,
+            );
+            tl.add_clip(track, outer_clip)
+        });
+        assert_eq!(result, Err(TrackError::SequenceCycle { sequence: inner }));
+        assert_eq!(timeline.sequence(inner).unwrap().timeline.duration, time::from_seconds(2.0));
+        assert_eq!(timeline.find_sequence_cycle(), None);
+
+        // A normal edit updates the compound clip's source bounds
+        timeline.edit_sequence(inner, |tl| {
+            let track = tl.tracks[0].id;
+            tl.add_clip(track, clip(9, 2.0, 1.0))
+        }).unwrap();
+        let outer_timeline = &timeline.sequence(outer).unwrap().timeline;
+        let compound = &outer_timeline.tracks[0].clips[0];
+        assert_eq!(compound.source_duration, Some(time::from_seconds(3.0)));
+    }
+}
@@ -0,0 +1,163 @@
+//! Clip playback speed.
+//!
+//! Speeds are exact rationals like [`FrameRate`](crate::core::time::FrameRate),
+//! so a 50% clip maps every timeline nanosecond to the same source time in
+//! the UI, playback and export.
+
+use std::fmt;
+use crate::timeline::clip::ClipId;
+use crate::timeline::timeline::Timeline;
+use crate::timeline::track::TrackError;
+use crate::core::time::{gcd, Time};
+use serde::{Deserialize, Serialize};
+
+/// Playback speed of a clip as `num / den` source seconds per timeline second.
+///
+/// Negative speeds play the source in reverse; zero holds a single frame
+/// (freeze frame).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Speed {
+    pub num: i32,
+    pub den: u32,
+}
+
+impl Speed {
+    pub const NORMAL: Speed = Speed { num: 1, den: 1 };
+    pub const REVERSE: Speed = Speed { num: -1, den: 1 };
+    pub const FREEZE: Speed = Speed { num: 0, den: 1 };
+
+    /// Create a speed of `num / den`, reduced to lowest terms.
+    ///
+    /// # Panics
+    /// Panics if `den` is zero.
+    pub fn new(num: i32, den: u32) -> Self {
+        assert!(den > 0, "Speed den must be > 0");
+        let divisor = gcd(num.unsigned_abs(), den);
+        Self { num: num / divisor as i32, den: den / divisor }
+    }
+
+    /// Speed as a percentage (e.g. `percent(50)` for half speed, `-100` for reverse).
+    pub fn percent(percent: i32) -> Self {
+        Self::new(percent, 100)
+    }
+
+    /// Speed as a float (for display only).
+    pub fn as_f64(&self) -> f64 {
+        self.num as f64 / self.den as f64
+    }
+
+    /// Whether the clip holds one frame.
+    pub fn is_freeze(&self) -> bool {
+        self.num == 0
+    }
+
+    /// Whether the clip plays backwards.
+    pub fn is_reverse(&self) -> bool {
+        self.num < 0
+    }
+
+    /// The same speed in the opposite direction.
+    pub fn reversed(&self) -> Self {
+        Self { num: -self.num, den: self.den }
+    }
+
+    /// Signed source time that plays during `timeline` time (negative in reverse).
+    pub fn to_source(&self, timeline: Time) -> Time {
+        div_round(timeline as i128 * self.num as i128, self.den as i128)
+    }
+
+    /// Timeline time needed to play `source` time, regardless of direction.
+    ///
+    /// # Panics
+    /// Panics for a freeze frame, whose length is independent of its source.
+    pub fn to_timeline(&self, source: Time) -> Time {
+        assert!(!self.is_freeze(), "Freeze frames have no source-derived length");
+        div_round(source as i128 * self.den as i128, self.num.unsigned_abs() as i128)
+    }
+}
+
+impl Default for Speed {
+    fn default() -> Self {
+        Self::NORMAL
+    }
+}
+
+impl fmt::Display for Speed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_freeze() {
+            write!(f, "Freeze")
+        } else {
+            write!(f, "{}%", div_round(self.num as i128 * 100, self.den as i128))
+        }
+    }
+}
+
+impl Timeline {
+    /// Change the speed of a clip and every clip linked to it.
+    ///
+    /// Each clip keeps its start and source range, so its timeline length
+    /// changes (see `Clip::set_speed`). Returns `Err(TrackError::Overlap)`, with
+    /// nothing changed, if a lengthened clip would run into the next clip.
+    pub fn set_clip_speed(&mut self, clip_id: ClipId, speed: Speed) -> Result<(), TrackError> {
+        let updated = self.linked_with_tracks(clip_id)?
+            .into_iter()
+            .map(|(track_id, mut clip)| {
+                clip.set_speed(speed);
+                (track_id, clip)
+            })
+            .collect();
+        self.replace_clips(updated)
+    }
+}
+
+/// `a / b` rounded to the nearest integer, halves away from zero (`b > 0`).
+fn div_round(a: i128, b: i128) -> i64 {
+    let half = b / 2;
+    let rounded = if a >= 0 { (a + half) / b } else { (a - half) / b };
+    rounded as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::time;
+    use crate::timeline::clip::Clip;
+
+    #[test]
+    fn test_speed_conversions() {
+        let half = Speed::percent(50);
+        assert_eq!(half, Speed::new(1, 2));
+        assert_eq!(half.to_source(time::from_seconds(4.0)), time::from_seconds(2.0));
+        assert_eq!(half.to_timeline(time::from_seconds(2.0)), time::from_seconds(4.0));
+
+        let reverse = Speed::new(-3, 1);
+        assert!(reverse.is_reverse());
+        assert_eq!(reverse.to_source(time::from_seconds(1.0)), time::from_seconds(-3.0));
+        assert_eq!(reverse.to_timeline(time::from_seconds(3.0)), time::from_seconds(1.0));
+
+        assert_eq!(Speed::FREEZE.to_source(time::from_seconds(5.0)), 0);
+        assert_eq!(Speed::new(1, 3).to_source(2), 1);
+        assert_eq!(format!("{}", Speed::new(-2, 3)), "-67%");
+    }
+
+    #[test]
+    fn test_set_clip_speed() {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        let path = std::path::PathBuf::from("a.mp4");
+        let clip = |id, start| Clip::new(id, path.clone(), 0, time::from_seconds(2.0), start, 0);
+        timeline.add_clip(v1, clip(1, 0)).unwrap();
+        timeline.add_clip(v1, clip(2, time::from_seconds(5.0))).unwrap();
+
+        timeline.set_clip_speed(1, Speed::new(4, 5)).unwrap();
+        assert_eq!(timeline.find_clip(1).unwrap().1.timeline_end, time::from_seconds(2.5));
+
+        // Reverse at half speed: clip 2 now runs to 9s
+        timeline.set_clip_speed(2, Speed::percent(-50)).unwrap();
+        assert_eq!(timeline.duration, time::from_seconds(9.0));
+
+        // A third of the speed would run clip 1 to 6s, into clip 2
+        assert_eq!(timeline.set_clip_speed(1, Speed::new(1, 3)), Err(TrackError::Overlap { clip_id: 1 }));
+        assert_eq!(timeline.find_clip(1).unwrap().1.speed, Speed::new(4, 5));
+    }
+}
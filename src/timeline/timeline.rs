@@ -1,75 +1,495 @@
-//! Timeline data structure managing video and audio tracks.
+//! Timeline data structure managing an ordered set of video and audio tracks.
 //! Per SPEC.md: Timeline → Tracks → Clips hierarchy.
 
-use crate::timeline::track::{Track, TrackType, TrackError};
-use crate::timeline::clip::{Clip, ClipId};
+use crate::timeline::track::{Track, TrackType, TrackId, TrackError};
+use crate::timeline::clip::{Clip, ClipId, LinkGroupId};
+use crate::timeline::marker::{Marker, MarkerId};
+use crate::timeline::caption::CueId;
+use crate::timeline::sequence::{Sequence, SequenceId};
+use crate::timeline::transition::TransitionId;
 use crate::core::time::Time;
+use std::collections::BTreeMap;
 
 /// Main timeline structure.
 /// 
 /// Per SPEC.md: Timeline → Tracks → Clips hierarchy.
-/// Contains one video track and one audio track.
+/// Contains any number of video and audio tracks in display order.
 /// All time values are in nanoseconds.
+///
+/// # Track order
+/// Tracks of the same type are stacked bottom-up: a video track that comes
+/// later in `tracks` is composited on top of earlier video tracks (V1 is the
+/// bottom layer). Audio tracks are listed A1, A2, ... and mixed together.
 #[derive(Debug, Clone)]
 pub struct Timeline {
-    pub video_track: Track,
-    pub audio_track: Track,
+    pub tracks: Vec<Track>,   // Ordered bottom-up within each track type
     pub duration: Time,       // Total timeline duration in nanoseconds
     pub playhead: Time,       // Current playhead position in nanoseconds
+    pub markers: Vec<Marker>, // Timeline markers, sorted by position
+    pub sequences: BTreeMap<SequenceId, Sequence>, // Nested sequences used by compound clips (root timeline only)
+    pub(crate) ids: IdCounters, // Next free ID of each kind
+}
+
+/// IDs from here up are refused by `IdAllocator::reserve`, so an allocator
+/// always has 2^63 IDs left to hand out.
+pub(crate) const ID_LIMIT: u64 = 1 << 63;
+
+/// Hands out increasing IDs, starting at 1. ID 0 is never allocated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct IdAllocator<T> {
+    next: T,
+}
+
+impl IdAllocator<u64> {
+    /// An allocator whose first ID is `first`.
+    pub(crate) fn starting_at(first: u64) -> Self {
+        Self { next: first }
+    }
+
+    /// Allocate a new ID.
+    pub(crate) fn allocate(&mut self) -> u64 {
+        let id = self.next;
+        self.next = id + 1;
+        id
+    }
+
+    /// The ID `allocate` would return next, without allocating it.
+    pub(crate) fn peek(&self) -> u64 {
+        self.next
+    }
+
+    /// Mark `id` as used, so it is never allocated again. Returns `false`,
+    /// and marks nothing, if `id` is not below `ID_LIMIT`.
+    pub(crate) fn reserve(&mut self, id: u64) -> bool {
+        match id.checked_add(1).filter(|&next| next <= ID_LIMIT) {
+            Some(next) => {
+                self.next = self.next.max(next);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether `id` has been allocated or reserved.
+    pub(crate) fn contains(&self, id: u64) -> bool {
+        id >= 1 && id < self.next
+    }
+
+    /// Mark every ID `other` has handed out as used.
+    pub(crate) fn include(&mut self, other: Self) {
+        self.next = self.next.max(other.next);
+    }
+}
+
+impl Default for IdAllocator<u64> {
+    fn default() -> Self {
+        Self::starting_at(1)
+    }
+}
+
+/// A timeline's ID allocators, one per kind of ID, saved and restored by undo.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct IdCounters {
+    pub(crate) track: IdAllocator<TrackId>,
+    pub(crate) clip: IdAllocator<ClipId>,
+    pub(crate) link_group: IdAllocator<LinkGroupId>,
+    pub(crate) marker: IdAllocator<MarkerId>,
+    pub(crate) sequence: IdAllocator<SequenceId>,
+    pub(crate) transition: IdAllocator<TransitionId>,
+    pub(crate) cue: IdAllocator<CueId>,
+}
+
+impl IdCounters {
+    /// Mark every ID `other` has handed out as used (see `IdAllocator::include`).
+    pub(crate) fn include(&mut self, other: IdCounters) {
+        self.track.include(other.track);
+        self.clip.include(other.clip);
+        self.link_group.include(other.link_group);
+        self.marker.include(other.marker);
+        self.sequence.include(other.sequence);
+        self.transition.include(other.transition);
+        self.cue.include(other.cue);
+    }
 }
 
 impl Timeline {
-    /// Create a new timeline with empty video and audio tracks.
+    /// Create a new timeline with one empty video track and one empty audio track.
     pub fn new() -> Self {
         let video_track = Track::new(1, TrackType::Video);
         let audio_track = Track::new(2, TrackType::Audio);
 
         Self {
-            video_track,
-            audio_track,
+            tracks: vec![video_track, audio_track],
             duration: 0,
             playhead: 0,
+            markers: Vec::new(),
+            sequences: BTreeMap::new(),
+            ids: IdCounters { track: IdAllocator::starting_at(3), ..IdCounters::default() },
+        }
+    }
+
+    /// Create a timeline with no tracks.
+    pub fn empty() -> Self {
+        Self {
+            tracks: Vec::new(),
+            duration: 0,
+            playhead: 0,
+            markers: Vec::new(),
+            sequences: BTreeMap::new(),
+            ids: IdCounters::default(),
+        }
+    }
+
+    /// Append a new empty track and return its ID.
+    /// 
+    /// A new video track becomes the top-most video layer.
+    pub fn add_track(&mut self, track_type: TrackType) -> TrackId {
+        self.insert_track(self.tracks.len(), track_type)
+    }
+
+    /// Insert a new empty track at `index` in the track list and return its ID.
+    /// 
+    /// `index` is clamped to the number of tracks.
+    pub fn insert_track(&mut self, index: usize, track_type: TrackType) -> TrackId {
+        let id = self.ids.track.allocate();
+        let index = index.min(self.tracks.len());
+        self.tracks.insert(index, Track::new(id, track_type));
+        id
+    }
+
+    /// Add an existing track (e.g. one previously removed) at `index`.
+    /// 
+    /// Returns `Err(TrackError::DuplicateTrack)` if a track with the same ID exists.
+    pub fn insert_existing_track(&mut self, index: usize, track: Track) -> Result<(), TrackError> {
+        if self.track(track.id).is_some() {
+            return Err(TrackError::DuplicateTrack { track_id: track.id });
+        }
+        self.ids.track.reserve(track.id);
+        for clip in &track.clips {
+            self.reserve_clip_id(clip.id);
+            if let Some(group) = clip.link_group {
+                self.reserve_link_group(group);
+            }
+            for marker in &clip.markers {
+                self.reserve_marker_id(marker.id);
+            }
+        }
+        for transition in &track.transitions {
+            self.reserve_transition_id(transition.id);
+        }
+        for cue in &track.cues {
+            self.reserve_cue_id(cue.id);
+        }
+        let index = index.min(self.tracks.len());
+        self.tracks.insert(index, track);
+        self.update_duration();
+        Ok(())
+    }
+
+    /// Remove a track and all its clips.
+    /// 
+    /// Returns the removed track if found, `None` otherwise.
+    /// Updates timeline duration automatically.
+    pub fn remove_track(&mut self, track_id: TrackId) -> Option<Track> {
+        let index = self.track_index(track_id)?;
+        let track = self.tracks.remove(index);
+        self.update_duration();
+        Some(track)
+    }
+
+    /// Move a track to a new position in the track list.
+    /// 
+    /// `new_index` is clamped to the last position.
+    pub fn move_track(&mut self, track_id: TrackId, new_index: usize) -> Result<(), TrackError> {
+        let index = self.track_index(track_id)
+            .ok_or(TrackError::TrackNotFound { track_id })?;
+        let track = self.tracks.remove(index);
+        let new_index = new_index.min(self.tracks.len());
+        self.tracks.insert(new_index, track);
+        Ok(())
+    }
+
+    /// Position of a track in the track list.
+    pub fn track_index(&self, track_id: TrackId) -> Option<usize> {
+        self.tracks.iter().position(|t| t.id == track_id)
+    }
+
+    /// Look up a track by ID.
+    pub fn track(&self, track_id: TrackId) -> Option<&Track> {
+        self.tracks.iter().find(|t| t.id == track_id)
+    }
+
+    /// Look up a track by ID for modification.
+    /// 
+    /// Call `update_duration` after changing clips through this reference.
+    pub fn track_mut(&mut self, track_id: TrackId) -> Option<&mut Track> {
+        self.tracks.iter_mut().find(|t| t.id == track_id)
+    }
+
+    /// All tracks of a type, bottom-up.
+    pub fn tracks_of_type(&self, track_type: TrackType) -> impl DoubleEndedIterator<Item = &Track> {
+        self.tracks.iter().filter(move |t| t.track_type == track_type)
+    }
+
+    /// All video tracks, bottom-up (V1 first).
+    pub fn video_tracks(&self) -> impl DoubleEndedIterator<Item = &Track> {
+        self.tracks_of_type(TrackType::Video)
+    }
+
+    /// All audio tracks (A1 first).
+    pub fn audio_tracks(&self) -> impl DoubleEndedIterator<Item = &Track> {
+        self.tracks_of_type(TrackType::Audio)
+    }
+
+    /// Enabled video tracks, bottom-up: the layers playback and export composite.
+    pub fn visible_tracks(&self) -> impl DoubleEndedIterator<Item = &Track> {
+        self.video_tracks().filter(|t| t.enabled)
+    }
+
+    /// Audio tracks that are heard, A1 first: the tracks playback and export mix.
+    /// 
+    /// Muted tracks are never heard, even when soloed. While any audio track is
+    /// soloed, only the soloed ones are heard.
+    pub fn audible_tracks(&self) -> impl DoubleEndedIterator<Item = &Track> {
+        let any_solo = self.audio_tracks().any(|t| t.solo);
+        self.audio_tracks().filter(move |t| !t.muted && (t.solo || !any_solo))
+    }
+
+    /// Display name of a track: its `name`, or "V1"/"A2"/"C1"-style from its
+    /// position among the tracks of its type if it has none.
+    pub fn track_name(&self, track_id: TrackId) -> Option<String> {
+        let track = self.track(track_id)?;
+        if !track.name.is_empty() {
+            return Some(track.name.clone());
+        }
+        let number = self.tracks_of_type(track.track_type)
+            .position(|t| t.id == track_id)
+            .expect("track is of its own type")
+            + 1;
+        let prefix = match track.track_type {
+            TrackType::Video => "V",
+            TrackType::Audio => "A",
+            TrackType::Caption => "C",
+        };
+        Some(format!("{}{}", prefix, number))
+    }
+
+    /// Find a clip on any track.
+    /// 
+    /// Returns the track containing the clip and the clip itself.
+    pub fn find_clip(&self, clip_id: ClipId) -> Option<(&Track, &Clip)> {
+        self.tracks.iter().find_map(|track| {
+            track.clips.iter().find(|c| c.id == clip_id).map(|clip| (track, clip))
+        })
+    }
+
+    /// Add a clip to a track with overlap validation.
+    /// 
+    /// Returns `Ok(())` if successful, `Err(TrackError)` if the track does not
+    /// exist or the clip overlaps with existing clips. Updates timeline duration automatically.
+    pub fn add_clip(&mut self, track_id: TrackId, clip: Clip) -> Result<(), TrackError> {
+        let clip_id = clip.id;
+        let link_group = clip.link_group;
+        let last_marker = clip.markers.iter().map(|m| m.id).max();
+        self.track_mut(track_id)
+            .ok_or(TrackError::TrackNotFound { track_id })?
+            .add_clip(clip)?;
+        self.reserve_clip_id(clip_id);
+        if let Some(group) = link_group {
+            self.reserve_link_group(group);
+        }
+        if let Some(id) = last_marker {
+            self.reserve_marker_id(id);
+        }
+        self.update_duration();
+        Ok(())
+    }
+
+    /// Allocate a clip ID not used by any clip added to this timeline so far.
+    pub fn allocate_clip_id(&mut self) -> ClipId {
+        self.ids.clip.allocate()
+    }
+
+    /// The ID `allocate_clip_id` would return next, without allocating it.
+    pub(crate) fn peek_clip_id(&self) -> ClipId {
+        self.ids.clip.peek()
+    }
+
+    /// Mark a clip ID as used (after adding clips through `track_mut`).
+    pub(crate) fn reserve_clip_id(&mut self, clip_id: ClipId) {
+        self.ids.clip.reserve(clip_id);
+    }
+
+    /// Allocate a new link group ID.
+    pub fn allocate_link_group(&mut self) -> LinkGroupId {
+        self.ids.link_group.allocate()
+    }
+
+    /// Whether a link group ID has been allocated on this timeline.
+    pub fn is_link_group(&self, group: LinkGroupId) -> bool {
+        self.ids.link_group.contains(group)
+    }
+
+    fn reserve_link_group(&mut self, group: LinkGroupId) {
+        self.ids.link_group.reserve(group);
+    }
+
+    /// Split a single clip at a timeline position, ignoring links.
+    /// 
+    /// The left half keeps `clip_id` (and its link group); the right half gets a
+    /// freshly allocated ID, which is returned, and is not linked to anything.
+    /// Use `split_linked` to cut a whole link group.
+    /// Fails with `TrackError::ClipNotFound` or `TrackError::InvalidSplit` if the
+    /// position is not strictly inside the clip.
+    pub fn split_clip(&mut self, clip_id: ClipId, position: Time) -> Result<ClipId, TrackError> {
+        let track_id = self.find_clip(clip_id)
+            .map(|(track, _)| track.id)
+            .ok_or(TrackError::ClipNotFound { clip_id })?;
+        let new_id = self.ids.clip.peek();
+        let track = self.track_mut(track_id).expect("track holding the clip exists");
+        track.split_clip(clip_id, position, new_id)?;
+        if let Some(right) = track.clips.iter_mut().find(|c| c.id == new_id) {
+            right.link_group = None;
         }
+        self.ids.clip.reserve(new_id);
+        Ok(new_id)
     }
 
-    /// Add a clip to the video track with overlap validation.
+    /// After splitting, link right halves whose left halves were linked together
+    /// into a fresh group per original group.
+    pub(crate) fn relink_split_halves(&mut self, cuts: &[(ClipId, ClipId)]) {
+        let mut new_groups: Vec<(LinkGroupId, LinkGroupId)> = Vec::new();
+        for &(left, right) in cuts {
+            let Some(group) = self.find_clip(left).and_then(|(_, c)| c.link_group) else {
+                continue;
+            };
+            let new_group = match new_groups.iter().find(|(old, _)| *old == group) {
+                Some(&(_, new_group)) => new_group,
+                None => {
+                    let new_group = self.allocate_link_group();
+                    new_groups.push((group, new_group));
+                    new_group
+                }
+            };
+            if let Some(clip) = self.tracks
+                .iter_mut()
+                .flat_map(|t| t.clips.iter_mut())
+                .find(|c| c.id == right)
+            {
+                clip.link_group = Some(new_group);
+            }
+        }
+    }
+
+    /// IDs of all clips a split at `position` would cut, i.e. clips that contain
+    /// `position` strictly inside them (not at an edit point).
+    pub fn clips_spanning(&self, position: Time) -> Vec<ClipId> {
+        self.tracks
+            .iter()
+            .filter_map(|t| t.clip_at(position))
+            .filter(|c| c.timeline_start < position && position < c.timeline_end)
+            .map(|c| c.id)
+            .collect()
+    }
+
+    /// Split every clip on every unlocked track at `position`, cutting linked
+    /// video and audio together.
+    /// 
+    /// Returns `(left, right)` clip ID pairs for each cut made. Fails with
+    /// `TrackError::TrackLocked`, cutting nothing, if a clip on an unlocked
+    /// track is linked to one under `position` on a locked track.
+    pub fn split_all_at(&mut self, position: Time) -> Result<Vec<(ClipId, ClipId)>, TrackError> {
+        let spanning: Vec<ClipId> = self.clips_spanning(position)
+            .into_iter()
+            .filter(|&clip_id| self.check_clip_editable(clip_id).is_ok())
+            .collect();
+        for &clip_id in &spanning {
+            for (_, partner) in self.linked_with_tracks(clip_id)? {
+                if partner.timeline_start < position && position < partner.timeline_end {
+                    self.check_clip_editable(partner.id)?;
+                }
+            }
+        }
+        let cuts: Vec<(ClipId, ClipId)> = spanning
+            .into_iter()
+            .map(|clip_id| {
+                let new_id = self.split_clip(clip_id, position)
+                    .expect("spanning clip can be split");
+                (clip_id, new_id)
+            })
+            .collect();
+        self.relink_split_halves(&cuts);
+        Ok(cuts)
+    }
+
+    /// Split every clip on every track at the playhead (see `split_all_at`).
+    pub fn split_all_at_playhead(&mut self) -> Result<Vec<(ClipId, ClipId)>, TrackError> {
+        self.split_all_at(self.playhead)
+    }
+
+    /// Remove a clip from whichever track holds it.
+    /// 
+    /// Returns the ID of the track it was on and the removed clip. Fails with
+    /// `TrackError::ClipNotFound`, or `TrackError::TrackLocked` if the clip is
+    /// on a locked track. Updates timeline duration automatically.
+    pub fn remove_clip(&mut self, clip_id: ClipId) -> Result<(TrackId, Clip), TrackError> {
+        let track = self.tracks
+            .iter_mut()
+            .find(|t| t.clips.iter().any(|c| c.id == clip_id))
+            .ok_or(TrackError::ClipNotFound { clip_id })?;
+        track.check_unlocked()?;
+        let clip = track.remove_clip(clip_id).expect("track holds the clip");
+        let track_id = track.id;
+        self.update_duration();
+        Ok((track_id, clip))
+    }
+
+    /// Add a clip to the first video track (V1) with overlap validation.
     /// 
+    /// A video track is created if the timeline has none.
     /// Returns `Ok(())` if successful, `Err(TrackError)` if the clip overlaps
     /// with existing clips. Updates timeline duration automatically.
     pub fn add_video_clip(&mut self, clip: Clip) -> Result<(), TrackError> {
-        self.video_track.add_clip(clip)?;
-        self.update_duration();
-        Ok(())
+        let track_id = self.first_track_or_add(TrackType::Video);
+        self.add_clip(track_id, clip)
     }
 
-    /// Add a clip to the audio track with overlap validation.
+    /// Add a clip to the first audio track (A1) with overlap validation.
     /// 
+    /// An audio track is created if the timeline has none.
     /// Returns `Ok(())` if successful, `Err(TrackError)` if the clip overlaps
     /// with existing clips. Updates timeline duration automatically.
     pub fn add_audio_clip(&mut self, clip: Clip) -> Result<(), TrackError> {
-        self.audio_track.add_clip(clip)?;
-        self.update_duration();
-        Ok(())
+        let track_id = self.first_track_or_add(TrackType::Audio);
+        self.add_clip(track_id, clip)
     }
 
-    /// Remove a clip from the video track.
+    fn first_track_or_add(&mut self, track_type: TrackType) -> TrackId {
+        let existing = self.tracks_of_type(track_type).next().map(|t| t.id);
+        existing.unwrap_or_else(|| self.add_track(track_type))
+    }
+
+    /// Remove a clip from any unlocked video track.
     /// 
     /// Returns the removed clip if found, `None` otherwise.
     /// Updates timeline duration automatically.
     pub fn remove_video_clip(&mut self, clip_id: ClipId) -> Option<Clip> {
-        let result = self.video_track.remove_clip(clip_id);
-        if result.is_some() {
-            self.update_duration();
-        }
-        result
+        self.remove_clip_of_type(TrackType::Video, clip_id)
     }
 
-    /// Remove a clip from the audio track.
+    /// Remove a clip from any unlocked audio track.
     /// 
     /// Returns the removed clip if found, `None` otherwise.
     /// Updates timeline duration automatically.
     pub fn remove_audio_clip(&mut self, clip_id: ClipId) -> Option<Clip> {
-        let result = self.audio_track.remove_clip(clip_id);
+        self.remove_clip_of_type(TrackType::Audio, clip_id)
+    }
+
+    fn remove_clip_of_type(&mut self, track_type: TrackType, clip_id: ClipId) -> Option<Clip> {
+        let result = self.tracks
+            .iter_mut()
+            .filter(|t| t.track_type == track_type && !t.locked)
+            .find_map(|t| t.remove_clip(clip_id));
         if result.is_some() {
             self.update_duration();
         }
@@ -78,12 +498,14 @@ impl Timeline {
 
     /// Update the timeline duration based on track durations.
     /// 
-    /// Duration is the maximum of video and audio track durations.
-    fn update_duration(&mut self) {
-        let video_duration = self.video_track.duration();
-        let audio_duration = self.audio_track.duration();
-        
-        self.duration = video_duration.max(audio_duration);
+    /// Duration is the maximum duration over all tracks. Called automatically by
+    /// timeline methods; call it after editing clips through `track_mut`.
+    pub fn update_duration(&mut self) {
+        self.duration = self.tracks
+            .iter()
+            .map(|t| t.duration())
+            .max()
+            .unwrap_or(0);
     }
 
     /// Set the playhead position.
@@ -93,23 +515,40 @@ impl Timeline {
         self.playhead = position.max(0).min(self.duration);
     }
 
-    /// Get the video clip at the current playhead position.
+    /// Get all video clips at a timeline position, top-most layer first.
+    pub fn video_clips_at(&self, position: Time) -> Vec<&Clip> {
+        self.video_tracks()
+            .rev()
+            .filter_map(|t| t.clip_at(position))
+            .collect()
+    }
+
+    /// Get all audio clips at a timeline position, in track order (A1 first).
+    pub fn audio_clips_at(&self, position: Time) -> Vec<&Clip> {
+        self.audio_tracks()
+            .filter_map(|t| t.clip_at(position))
+            .collect()
+    }
+
+    /// Get the top-most video clip at the current playhead position.
     pub fn video_clip_at_playhead(&self) -> Option<&Clip> {
-        self.video_track.clip_at(self.playhead)
+        self.video_clips_at(self.playhead).into_iter().next()
     }
 
-    /// Get the audio clip at the current playhead position.
+    /// Get the audio clip at the current playhead position on the first audio
+    /// track that has one.
     pub fn audio_clip_at_playhead(&self) -> Option<&Clip> {
-        self.audio_track.clip_at(self.playhead)
+        self.audio_clips_at(self.playhead).into_iter().next()
     }
 
-    /// Get all clips (video and audio) that overlap with a time range.
+    /// Get all clips (video and audio) that overlap with a time range, across all tracks.
     /// 
-    /// Returns a tuple of (video_clips, audio_clips).
+    /// Returns a tuple of (video_clips, audio_clips). Video clips are ordered
+    /// bottom-up by track, then by time.
     pub fn clips_in_range(&self, start: Time, end: Time) -> (Vec<&Clip>, Vec<&Clip>) {
         (
-            self.video_track.clips_in_range(start, end),
-            self.audio_track.clips_in_range(start, end),
+            self.video_tracks().flat_map(|t| t.clips_in_range(start, end)).collect(),
+            self.audio_tracks().flat_map(|t| t.clips_in_range(start, end)).collect(),
         )
     }
 }
@@ -146,7 +585,7 @@ mod tests {
         );
 
         timeline.add_video_clip(clip).unwrap();
-        assert_eq!(timeline.video_track.clips.len(), 1);
+        assert_eq!(timeline.video_tracks().next().unwrap().clips.len(), 1);
         assert!(timeline.duration > 0);
     }
 
@@ -203,6 +642,63 @@ mod tests {
         assert_eq!(timeline.duration, time::from_seconds(8.0));
     }
 
+    #[test]
+    fn test_split_all_at_playhead() {
+        let mut timeline = Timeline::new();
+        let video = Clip::new(10, std::path::PathBuf::from("v.mp4"), 0, time::from_seconds(4.0), 0, 0);
+        let audio = Clip::new(11, std::path::PathBuf::from("v.mp4"), 0, time::from_seconds(4.0), 0, 1);
+        // Ends exactly at the playhead: not cut
+        let short = Clip::new(12, std::path::PathBuf::from("b.mp4"), 0, time::from_seconds(2.0), 0, 0);
+        timeline.add_video_clip(video).unwrap();
+        timeline.add_audio_clip(audio).unwrap();
+        let v2 = timeline.add_track(TrackType::Video);
+        timeline.add_clip(v2, short).unwrap();
+
+        timeline.set_playhead(time::from_seconds(2.0));
+        let cuts = timeline.split_all_at_playhead().unwrap();
+        assert_eq!(cuts, vec![(10, 13), (11, 14)]);
+
+        let (track, right) = timeline.find_clip(14).unwrap();
+        assert_eq!(track.track_type, TrackType::Audio);
+        assert_eq!(right.in_point, time::from_seconds(2.0));
+        assert_eq!(right.timeline_start, time::from_seconds(2.0));
+        assert_eq!(timeline.find_clip(10).unwrap().1.timeline_end, time::from_seconds(2.0));
+        assert_eq!(timeline.duration, time::from_seconds(4.0));
+    }
+
+    #[test]
+    fn test_split_clip_ids_are_fresh() {
+        let mut timeline = Timeline::new();
+        let clip = Clip::new(5, std::path::PathBuf::from("v.mp4"), 0, time::from_seconds(4.0), 0, 0);
+        timeline.add_video_clip(clip).unwrap();
+
+        assert_eq!(timeline.split_clip(5, time::from_seconds(1.0)), Ok(6));
+        assert_eq!(timeline.split_clip(6, time::from_seconds(2.0)), Ok(7));
+        assert_eq!(timeline.allocate_clip_id(), 8);
+        assert_eq!(timeline.split_clip(99, time::from_seconds(1.0)), Err(TrackError::ClipNotFound { clip_id: 99 }));
+        assert!(matches!(timeline.split_clip(5, time::from_seconds(1.0)), Err(TrackError::InvalidSplit { .. })));
+        assert_eq!(timeline.video_tracks().next().unwrap().clips.len(), 3);
+    }
+
+    #[test]
+    fn test_id_allocator() {
+        let mut ids = IdAllocator::<u64>::default();
+        assert!(!ids.contains(0) && !ids.contains(1));
+        assert_eq!(ids.allocate(), 1);
+        ids.reserve(5);
+        ids.reserve(3);
+        assert_eq!(ids.peek(), 6);
+        assert!(ids.contains(5) && !ids.contains(6));
+        assert_eq!(ids.allocate(), 6);
+
+        // IDs that would leave nothing to allocate are refused
+        assert!(!ids.reserve(u64::MAX));
+        assert!(!ids.reserve(ID_LIMIT));
+        assert_eq!(ids.peek(), 7);
+        assert!(ids.reserve(ID_LIMIT - 1));
+        assert_eq!(ids.allocate(), ID_LIMIT);
+    }
+
     #[test]
     fn test_overlap_validation() {
         let mut timeline = Timeline::new();
@@ -232,7 +728,7 @@ mod tests {
         assert!(timeline.add_video_clip(clip2).is_err());
         
         // Video track should still have only 1 clip
-        assert_eq!(timeline.video_track.clips.len(), 1);
+        assert_eq!(timeline.video_tracks().next().unwrap().clips.len(), 1);
     }
 
     #[test]
@@ -284,12 +780,12 @@ mod tests {
         );
 
         timeline.add_video_clip(clip).unwrap();
-        assert_eq!(timeline.video_track.clips.len(), 1);
+        assert_eq!(timeline.video_tracks().next().unwrap().clips.len(), 1);
         assert!(timeline.duration > 0);
         
         let removed = timeline.remove_video_clip(1);
         assert!(removed.is_some());
-        assert_eq!(timeline.video_track.clips.len(), 0);
+        assert_eq!(timeline.video_tracks().next().unwrap().clips.len(), 0);
         assert_eq!(timeline.duration, 0);
     }
 
@@ -301,5 +797,161 @@ mod tests {
         // Playhead should be clamped to 0
         assert_eq!(timeline.playhead, 0);
     }
-}
 
+    #[test]
+    fn test_add_remove_tracks() {
+        let mut timeline = Timeline::new();
+        let v2 = timeline.add_track(TrackType::Video);
+        let a2 = timeline.add_track(TrackType::Audio);
+
+        assert_eq!(timeline.tracks.len(), 4);
+        assert_eq!(timeline.video_tracks().count(), 2);
+        assert_eq!(timeline.audio_tracks().count(), 2);
+        assert_eq!(timeline.track(v2).unwrap().track_type, TrackType::Video);
+
+        let clip = Clip::new(
+            1,
+            std::path::PathBuf::from("music.wav"),
+            time::from_seconds(0.0),
+            time::from_seconds(20.0),
+            time::from_seconds(0.0),
+            0,
+        );
+        timeline.add_clip(a2, clip).unwrap();
+        assert_eq!(timeline.duration, time::from_seconds(20.0));
+
+        // Removing the track removes its clips from the duration
+        let removed = timeline.remove_track(a2).unwrap();
+        assert_eq!(removed.clips.len(), 1);
+        assert_eq!(timeline.duration, 0);
+        assert!(timeline.track(a2).is_none());
+
+        // Adding to a missing track is an error
+        let clip = Clip::new(2, std::path::PathBuf::from("a.mp4"), 0, time::from_seconds(1.0), 0, 0);
+        assert_eq!(
+            timeline.add_clip(a2, clip),
+            Err(TrackError::TrackNotFound { track_id: a2 })
+        );
+    }
+
+    #[test]
+    fn test_reorder_tracks() {
+        let mut timeline = Timeline::empty();
+        let v1 = timeline.add_track(TrackType::Video);
+        let v2 = timeline.add_track(TrackType::Video);
+        let v3 = timeline.add_track(TrackType::Video);
+
+        timeline.move_track(v3, 0).unwrap();
+        let order: Vec<TrackId> = timeline.tracks.iter().map(|t| t.id).collect();
+        assert_eq!(order, vec![v3, v1, v2]);
+
+        // Index is clamped to the end
+        timeline.move_track(v3, 100).unwrap();
+        assert_eq!(timeline.track_index(v3), Some(2));
+
+        assert!(timeline.move_track(99, 0).is_err());
+    }
+
+    #[test]
+    fn test_video_clips_at_top_to_bottom() {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        let v2 = timeline.add_track(TrackType::Video);
+
+        let base = Clip::new(1, std::path::PathBuf::from("a.mp4"), 0, time::from_seconds(10.0), 0, 0);
+        let overlay = Clip::new(
+            2,
+            std::path::PathBuf::from("b.mp4"),
+            0,
+            time::from_seconds(2.0),
+            time::from_seconds(3.0),
+            0,
+        );
+        timeline.add_clip(v1, base).unwrap();
+        timeline.add_clip(v2, overlay).unwrap();
+
+        let ids: Vec<ClipId> = timeline
+            .video_clips_at(time::from_seconds(4.0))
+            .iter()
+            .map(|c| c.id)
+            .collect();
+        assert_eq!(ids, vec![2, 1]);
+
+        timeline.set_playhead(time::from_seconds(4.0));
+        assert_eq!(timeline.video_clip_at_playhead().unwrap().id, 2);
+        timeline.set_playhead(time::from_seconds(8.0));
+        assert_eq!(timeline.video_clip_at_playhead().unwrap().id, 1);
+
+        let (video, audio) = timeline.clips_in_range(0, time::from_seconds(10.0));
+        assert_eq!(video.len(), 2);
+        assert!(audio.is_empty());
+
+        let (track, clip) = timeline.find_clip(2).unwrap();
+        assert_eq!(track.id, v2);
+        assert_eq!(clip.id, 2);
+        assert_eq!(timeline.remove_clip(2).map(|(t, c)| (t, c.id)), Ok((v2, 2)));
+    }
+
+    #[test]
+    fn test_audible_and_visible_tracks() {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        let a1 = timeline.tracks[1].id;
+        let a2 = timeline.add_track(TrackType::Audio);
+        let a3 = timeline.add_track(TrackType::Audio);
+        let audible = |timeline: &Timeline| timeline.audible_tracks().map(|t| t.id).collect::<Vec<_>>();
+        assert_eq!(audible(&timeline), vec![a1, a2, a3]);
+
+        // Soloing silences the other tracks; a muted track stays silent when soloed
+        timeline.track_mut(a2).unwrap().set_solo(true);
+        timeline.track_mut(a3).unwrap().set_solo(true);
+        timeline.track_mut(a3).unwrap().set_muted(true);
+        assert_eq!(audible(&timeline), vec![a2]);
+        timeline.track_mut(a2).unwrap().set_solo(false);
+        assert!(audible(&timeline).is_empty());
+
+        timeline.track_mut(v1).unwrap().set_enabled(false);
+        assert_eq!(timeline.visible_tracks().count(), 0);
+    }
+
+    #[test]
+    fn test_track_name() {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        let a2 = timeline.add_track(TrackType::Audio);
+        assert_eq!(timeline.track_name(v1).as_deref(), Some("V1"));
+        assert_eq!(timeline.track_name(a2).as_deref(), Some("A2"));
+        timeline.track_mut(a2).unwrap().name = "Music".to_string();
+        assert_eq!(timeline.track_name(a2).as_deref(), Some("Music"));
+        assert_eq!(timeline.track_name(99), None);
+    }
+
+    #[test]
+    fn test_locked_track_edits() {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        let v2 = timeline.add_track(TrackType::Video);
+        timeline.add_clip(v1, Clip::new(1, std::path::PathBuf::from("a.mp4"), 0, time::from_seconds(4.0), 0, 0)).unwrap();
+        timeline.add_clip(v2, Clip::new(2, std::path::PathBuf::from("b.mp4"), 0, time::from_seconds(4.0), 0, 0)).unwrap();
+        timeline.track_mut(v2).unwrap().set_locked(true);
+
+        let locked = TrackError::TrackLocked { track_id: v2 };
+        assert_eq!(timeline.remove_clip(2).map(|_| ()), Err(locked.clone()));
+        assert_eq!(timeline.move_linked(1, v2, time::from_seconds(5.0)), Err(locked.clone()));
+        assert_eq!(timeline.split_clip(2, time::from_seconds(1.0)), Err(locked));
+
+        // Whole-timeline edits leave locked tracks alone
+        assert_eq!(timeline.split_all_at(time::from_seconds(2.0)).unwrap().len(), 1);
+        assert_eq!(timeline.extract(0, time::from_seconds(1.0)).skipped, vec![v2]);
+        assert_eq!(timeline.track(v1).unwrap().clips[0].timeline_end, time::from_seconds(1.0));
+        assert_eq!(timeline.track(v2).unwrap().clips[0].timeline_end, time::from_seconds(4.0));
+
+        // ...but refuse to cut a clip away from a locked linked partner
+        timeline.track_mut(v2).unwrap().set_locked(false);
+        timeline.link(&[1, 2]).unwrap();
+        timeline.track_mut(v2).unwrap().set_locked(true);
+        let before = timeline.clone();
+        assert_eq!(timeline.split_all_at(time::from_seconds(0.5)), Err(TrackError::TrackLocked { track_id: v2 }));
+        assert_eq!(timeline.tracks, before.tracks);
+    }
+}
@@ -1,15 +1,63 @@
 //! Track data structure for managing clips on video/audio tracks.
-//! Per SPEC.md: Track types are Video and Audio.
+//! Per SPEC.md: Track types are Video and Audio; caption tracks hold cues
+//! instead of clips (see `timeline::caption`).
 
+use crate::timeline::caption::Cue;
 use crate::timeline::clip::Clip;
+use crate::timeline::transition::Transition;
 use crate::core::time::Time;
 use std::fmt;
+use serde::{Deserialize, Serialize};
 
 /// Error type for track operations
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TrackError {
     /// Clip overlaps with existing clips on the track
     Overlap { clip_id: crate::timeline::clip::ClipId },
+    /// No track with this ID exists on the timeline
+    TrackNotFound { track_id: TrackId },
+    /// A track with this ID already exists on the timeline
+    DuplicateTrack { track_id: TrackId },
+    /// No clip with this ID exists on the timeline
+    ClipNotFound { clip_id: crate::timeline::clip::ClipId },
+    /// Edit would need source media before its start or past its end
+    SourceOutOfRange { clip_id: crate::timeline::clip::ClipId },
+    /// Edit would leave the clip with zero or negative duration
+    ClipTooShort { clip_id: crate::timeline::clip::ClipId },
+    /// Clip has no adjacent clip to roll the edit point with
+    NoAdjacentClip { clip_id: crate::timeline::clip::ClipId },
+    /// Link group was never allocated on this timeline
+    LinkGroupNotFound { group: crate::timeline::clip::LinkGroupId },
+    /// Split position is not strictly inside the clip
+    InvalidSplit { clip_id: crate::timeline::clip::ClipId, position: Time },
+    /// Source-point trim of a clip whose source range follows a time-remap curve
+    TimeRemapped { clip_id: crate::timeline::clip::ClipId },
+    /// No sequence with this ID exists on the root timeline
+    SequenceNotFound { sequence: crate::timeline::sequence::SequenceId },
+    /// Edit would make a sequence contain itself (directly or through other sequences)
+    SequenceCycle { sequence: crate::timeline::sequence::SequenceId },
+    /// Edit needs at least one clip
+    NoClips,
+    /// Clip does not start at a cut with an adjacent clip before it
+    NoEditPoint { clip_id: crate::timeline::clip::ClipId },
+    /// Transition kind is for the other track type (e.g. a crossfade on a video track)
+    TransitionMismatch { track_id: TrackId },
+    /// Transition would need more media beyond a clip's in or out point than it has
+    InsufficientHandles { clip_id: crate::timeline::clip::ClipId },
+    /// Transition would have zero or negative duration
+    EmptyTransition { clip_id: crate::timeline::clip::ClipId },
+    /// No transition with this ID exists on the timeline
+    TransitionNotFound { transition: crate::timeline::transition::TransitionId },
+    /// Edit would change a locked track
+    TrackLocked { track_id: TrackId },
+    /// Clip on a caption track, or cue on a video or audio track
+    TrackTypeMismatch { track_id: TrackId },
+    /// No cue with this ID exists on the timeline
+    CueNotFound { cue_id: crate::timeline::caption::CueId },
+    /// Cue would end at or before its start
+    EmptyCue { cue_id: crate::timeline::caption::CueId },
+    /// No timeline or clip marker with this ID exists on the timeline
+    MarkerNotFound { marker_id: crate::timeline::marker::MarkerId },
 }
 
 impl fmt::Display for TrackError {
@@ -18,6 +66,72 @@ impl fmt::Display for TrackError {
             TrackError::Overlap { clip_id } => {
                 write!(f, "Clip {} overlaps with existing clips on the track", clip_id)
             }
+            TrackError::TrackNotFound { track_id } => {
+                write!(f, "Track {} not found", track_id)
+            }
+            TrackError::DuplicateTrack { track_id } => {
+                write!(f, "Track {} already exists", track_id)
+            }
+            TrackError::ClipNotFound { clip_id } => {
+                write!(f, "Clip {} not found", clip_id)
+            }
+            TrackError::SourceOutOfRange { clip_id } => {
+                write!(f, "Clip {} would extend beyond its source media", clip_id)
+            }
+            TrackError::ClipTooShort { clip_id } => {
+                write!(f, "Edit would leave clip {} with no duration", clip_id)
+            }
+            TrackError::NoAdjacentClip { clip_id } => {
+                write!(f, "Clip {} has no adjacent clip after it", clip_id)
+            }
+            TrackError::LinkGroupNotFound { group } => {
+                write!(f, "Link group {} not found", group)
+            }
+            TrackError::InvalidSplit { clip_id, position } => {
+                write!(f, "Cannot split clip {} at {}ns: position is not inside the clip", clip_id, position)
+            }
+            TrackError::TimeRemapped { clip_id } => {
+                write!(f, "Clip {} is time-remapped: trim it by its timeline edges instead", clip_id)
+            }
+            TrackError::SequenceNotFound { sequence } => {
+                write!(f, "Sequence {} not found", sequence)
+            }
+            TrackError::SequenceCycle { sequence } => {
+                write!(f, "Sequence {} would contain itself", sequence)
+            }
+            TrackError::NoClips => {
+                write!(f, "No clips given")
+            }
+            TrackError::NoEditPoint { clip_id } => {
+                write!(f, "Clip {} has no adjacent clip before it", clip_id)
+            }
+            TrackError::TransitionMismatch { track_id } => {
+                write!(f, "Transition does not suit the type of track {}", track_id)
+            }
+            TrackError::InsufficientHandles { clip_id } => {
+                write!(f, "Clip {} has too little media beyond its edit point for the transition", clip_id)
+            }
+            TrackError::EmptyTransition { clip_id } => {
+                write!(f, "Transition into clip {} would have no duration", clip_id)
+            }
+            TrackError::TransitionNotFound { transition } => {
+                write!(f, "Transition {} not found", transition)
+            }
+            TrackError::TrackLocked { track_id } => {
+                write!(f, "Track {} is locked", track_id)
+            }
+            TrackError::TrackTypeMismatch { track_id } => {
+                write!(f, "Track {} cannot hold this kind of item", track_id)
+            }
+            TrackError::CueNotFound { cue_id } => {
+                write!(f, "Cue {} not found", cue_id)
+            }
+            TrackError::EmptyCue { cue_id } => {
+                write!(f, "Cue {} would have no duration", cue_id)
+            }
+            TrackError::MarkerNotFound { marker_id } => {
+                write!(f, "Marker {} not found", marker_id)
+            }
         }
     }
 }
@@ -27,28 +141,64 @@ impl std::error::Error for TrackError {}
 /// Unique identifier for a track
 pub type TrackId = u64;
 
-/// Type of track (video or audio)
+/// Type of track (video, audio or caption)
 /// Per SPEC.md: Track types are Video and Audio.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum TrackType {
     Video,
     Audio,
+    /// Timed text cues (subtitles, closed captions)
+    Caption,
+}
+
+impl TrackType {
+    /// Colour a new track of this type gets by default.
+    pub fn default_color(&self) -> [u8; 3] {
+        match self {
+            TrackType::Video => [100, 150, 255],
+            TrackType::Audio => [255, 150, 100],
+            TrackType::Caption => [230, 210, 90],
+        }
+    }
 }
 
 /// A track contains clips arranged on a timeline.
 /// 
-/// Clips are stored sorted by `timeline_start` for efficient lookup.
-/// Overlapping clips are not allowed on the same track.
-#[derive(Debug, Clone)]
+/// Clips are stored sorted by `timeline_start` (then `timeline_end`), and
+/// overlapping clips are not allowed on the same track, so their ends are
+/// sorted too. That order is the track's index: point and range lookups
+/// binary search it, and edits keep it by inserting, removing and shifting
+/// clips in place rather than re-sorting. Code that edits `clips` directly
+/// must keep both orders.
+/// 
+/// A locked track refuses edits to its clips and transitions with
+/// `TrackError::TrackLocked`; its settings (mute, solo, volume, name, ...)
+/// can still be changed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Track {
     pub id: TrackId,
     pub track_type: TrackType,
-    pub clips: Vec<Clip>,  // Sorted by timeline_start
+    pub clips: Vec<Clip>,  // Sorted by timeline_start, then timeline_end; see above
+    pub name: String,      // Empty for the default "V1"/"A1"/"C1"-style label
     pub muted: bool,
+    pub solo: bool,        // Audio: while any audio track is soloed, only soloed tracks are heard
+    pub enabled: bool,     // Video and caption: disabled tracks are left out of the composite and export
+    pub locked: bool,
     pub volume: f32,       // 0.0 to 1.0
+    pub color: [u8; 3],    // RGB used to draw the track's clips
+    pub height: f32,       // Lane height in the timeline view, in points
+    pub transitions: Vec<Transition>, // At most one per incoming clip
+    pub cues: Vec<Cue>,    // Caption tracks only; sorted by start
 }
 
 impl Track {
+    /// Lane height of a new track, in points.
+    pub const DEFAULT_HEIGHT: f32 = 40.0;
+    /// Smallest lane height `set_height` allows.
+    pub const MIN_HEIGHT: f32 = 20.0;
+    /// Largest lane height `set_height` allows.
+    pub const MAX_HEIGHT: f32 = 200.0;
+
     /// Create a new track.
     /// 
     /// # Arguments
@@ -59,73 +209,133 @@ impl Track {
             id,
             track_type,
             clips: Vec::new(),
+            name: String::new(),
             muted: false,
+            solo: false,
+            enabled: true,
+            locked: false,
             volume: 1.0,
+            color: track_type.default_color(),
+            height: Self::DEFAULT_HEIGHT,
+            transitions: Vec::new(),
+            cues: Vec::new(),
+        }
+    }
+
+    /// Return `Err(TrackError::TrackLocked)` if the track is locked.
+    pub fn check_unlocked(&self) -> Result<(), TrackError> {
+        if self.locked {
+            return Err(TrackError::TrackLocked { track_id: self.id });
+        }
+        Ok(())
+    }
+
+    /// Return `Err(TrackError::TrackTypeMismatch)` if this is a caption track.
+    pub fn check_holds_clips(&self) -> Result<(), TrackError> {
+        if self.track_type == TrackType::Caption {
+            return Err(TrackError::TrackTypeMismatch { track_id: self.id });
         }
+        Ok(())
     }
 
     /// Add a clip to the track with overlap validation.
     /// 
     /// Returns `Ok(())` if successful, `Err(TrackError::Overlap)` if the clip overlaps
-    /// with existing clips. Maintains sorted order by `timeline_start`.
+    /// with existing clips, `Err(TrackError::TrackLocked)` if the track is locked,
+    /// or `Err(TrackError::TrackTypeMismatch)` on a caption track.
+    /// Maintains sorted order by `timeline_start`.
     /// 
     /// # Overlap Rules
     /// - Adjacent clips (touching at boundaries) are allowed
     /// - Overlapping clips are not allowed
     pub fn add_clip(&mut self, clip: Clip) -> Result<(), TrackError> {
-        // Check for overlaps with existing clips
-        for existing_clip in &self.clips {
-            if clip.overlaps_with(existing_clip) {
-                return Err(TrackError::Overlap { clip_id: clip.id });
-            }
+        self.check_unlocked()?;
+        self.check_holds_clips()?;
+
+        // Only clips ending after the new clip starts (a run just before the
+        // insertion point) and clips starting before it ends (a run just
+        // after) can overlap it
+        let index = self.clips
+            .partition_point(|c| (c.timeline_start, c.timeline_end) <= (clip.timeline_start, clip.timeline_end));
+        let before = self.clips[..index].iter().rev().take_while(|c| c.timeline_end > clip.timeline_start);
+        let after = self.clips[index..].iter().take_while(|c| c.timeline_start < clip.timeline_end);
+        if before.chain(after).any(|existing_clip| clip.overlaps_with(existing_clip)) {
+            return Err(TrackError::Overlap { clip_id: clip.id });
         }
 
-        self.clips.push(clip);
-        self.clips.sort_by_key(|c| c.timeline_start);
+        self.clips.insert(index, clip);
         Ok(())
     }
 
-    /// Remove a clip by ID.
+    /// Remove a clip by ID, along with the transition into it.
     /// 
-    /// Returns the removed clip if found, `None` otherwise.
+    /// Returns the removed clip if found, `None` otherwise. The lock is not
+    /// checked here; `Timeline::remove_clip` refuses clips on locked tracks.
     pub fn remove_clip(&mut self, clip_id: crate::timeline::clip::ClipId) -> Option<Clip> {
-        if let Some(pos) = self.clips.iter().position(|c| c.id == clip_id) {
-            Some(self.clips.remove(pos))
-        } else {
-            None
-        }
+        let pos = self.clips.iter().position(|c| c.id == clip_id)?;
+        self.transitions.retain(|t| t.clip_id != clip_id);
+        Some(self.clips.remove(pos))
+    }
+
+    /// Drop the transitions into clips that are no longer on this track.
+    pub(crate) fn drop_stray_transitions(&mut self) {
+        let clips = &self.clips;
+        self.transitions.retain(|t| clips.iter().any(|c| c.id == t.clip_id));
+    }
+
+    /// Split a clip at a timeline position.
+    /// 
+    /// The left half keeps `clip_id`; the right half gets `new_id` and is inserted
+    /// directly after it, so sorted order and the non-overlap invariant are preserved.
+    /// Returns `Err(TrackError::InvalidSplit)` if the position is not strictly
+    /// inside the clip (or the clip is not on this track).
+    pub fn split_clip(
+        &mut self,
+        clip_id: crate::timeline::clip::ClipId,
+        position: Time,
+        new_id: crate::timeline::clip::ClipId,
+    ) -> Result<(), TrackError> {
+        self.check_unlocked()?;
+        let index = self.clips
+            .iter()
+            .position(|c| c.id == clip_id)
+            .ok_or(TrackError::InvalidSplit { clip_id, position })?;
+        let right = self.clips[index]
+            .split_at(position, new_id)
+            .ok_or(TrackError::InvalidSplit { clip_id, position })?;
+        self.clips.insert(index + 1, right);
+        Ok(())
     }
 
     /// Find the clip at a given timeline position.
     /// 
     /// Returns the first clip that contains the position, or `None` if no clip
-    /// contains that position.
+    /// contains that position. At a cut that is the clip ending there.
+    /// O(log n).
     pub fn clip_at(&self, timeline_position: Time) -> Option<&Clip> {
-        // Since clips are sorted, we can use binary search for efficiency
-        // But for simplicity, we'll use linear search (clips list is typically small)
-        self.clips.iter().find(|clip| clip.contains(timeline_position))
+        // The first clip not ending before the position is the only candidate
+        let index = self.clips.partition_point(|clip| clip.timeline_end < timeline_position);
+        self.clips.get(index).filter(|clip| clip.contains(timeline_position))
     }
 
     /// Find all clips that overlap with a time range.
     /// 
-    /// Returns clips where `timeline_start <= end && timeline_end >= start`.
+    /// Returns clips where `timeline_start <= end && timeline_end >= start`,
+    /// in timeline order. O(log n) plus the number of clips returned.
     pub fn clips_in_range(&self, start: Time, end: Time) -> Vec<&Clip> {
-        self.clips
-            .iter()
-            .filter(|clip| {
-                // Check if clip overlaps with range
-                clip.timeline_start <= end && clip.timeline_end >= start
-            })
-            .collect()
+        let first = self.clips.partition_point(|clip| clip.timeline_end < start);
+        let last = self.clips.partition_point(|clip| clip.timeline_start <= end);
+        self.clips[first..last.max(first)].iter().collect()
     }
 
     /// Get the duration of the track in nanoseconds.
     /// 
-    /// Returns the end time of the last clip, or 0 if the track is empty.
+    /// Returns the end time of the last clip or cue, or 0 if the track is empty.
     pub fn duration(&self) -> Time {
         self.clips
             .iter()
             .map(|clip| clip.timeline_end)
+            .chain(self.cues.iter().map(|cue| cue.end))
             .max()
             .unwrap_or(0)
     }
@@ -139,6 +349,26 @@ impl Track {
     pub fn set_muted(&mut self, muted: bool) {
         self.muted = muted;
     }
+
+    /// Set solo state.
+    pub fn set_solo(&mut self, solo: bool) {
+        self.solo = solo;
+    }
+
+    /// Show or hide a video or caption track in the composite.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Lock or unlock the track for editing.
+    pub fn set_locked(&mut self, locked: bool) {
+        self.locked = locked;
+    }
+
+    /// Set the lane height (clamped to `MIN_HEIGHT`-`MAX_HEIGHT`).
+    pub fn set_height(&mut self, height: f32) {
+        self.height = height.clamp(Self::MIN_HEIGHT, Self::MAX_HEIGHT);
+    }
 }
 
 #[cfg(test)]
@@ -153,6 +383,36 @@ mod tests {
         assert_eq!(track.clips.len(), 0);
         assert_eq!(track.volume, 1.0);
         assert!(!track.muted);
+        assert!(track.enabled && !track.solo && !track.locked);
+        assert_eq!(track.color, TrackType::Video.default_color());
+    }
+
+    #[test]
+    fn test_locked_track_refuses_edits() {
+        let mut track = Track::new(1, TrackType::Video);
+        track.add_clip(Clip::new(1, PathBuf::from("a.mp4"), 0, time::from_seconds(4.0), 0, 0)).unwrap();
+        track.set_locked(true);
+
+        let locked = Err(TrackError::TrackLocked { track_id: 1 });
+        let clip = Clip::new(2, PathBuf::from("b.mp4"), 0, time::from_seconds(1.0), time::from_seconds(5.0), 0);
+        assert_eq!(track.add_clip(clip), locked);
+        assert_eq!(track.split_clip(1, time::from_seconds(1.0), 3), locked);
+        assert_eq!(track.ripple_trim_out(1, time::from_seconds(2.0)), locked);
+        assert_eq!(track.clips.len(), 1);
+        assert_eq!(track.clips[0].timeline_end, time::from_seconds(4.0));
+
+        track.set_locked(false);
+        assert!(track.split_clip(1, time::from_seconds(1.0), 3).is_ok());
+    }
+
+    #[test]
+    fn test_set_height() {
+        let mut track = Track::new(1, TrackType::Audio);
+        assert_eq!(track.height, Track::DEFAULT_HEIGHT);
+        track.set_height(1000.0);
+        assert_eq!(track.height, Track::MAX_HEIGHT);
+        track.set_height(0.0);
+        assert_eq!(track.height, Track::MIN_HEIGHT);
     }
 
     #[test]
@@ -348,6 +608,27 @@ mod tests {
         assert!(clips.iter().any(|c| c.id == 2));
     }
 
+    #[test]
+    fn test_split_clip() {
+        let mut track = Track::new(1, TrackType::Video);
+        track.add_clip(Clip::new(1, PathBuf::from("a.mp4"), 0, time::from_seconds(4.0), 0, 0)).unwrap();
+        track.add_clip(Clip::new(2, PathBuf::from("b.mp4"), 0, time::from_seconds(4.0), time::from_seconds(4.0), 0)).unwrap();
+
+        track.split_clip(1, time::from_seconds(1.0), 3).unwrap();
+        let ids: Vec<_> = track.clips.iter().map(|c| c.id).collect();
+        assert_eq!(ids, vec![1, 3, 2]);
+        assert_eq!(track.clips[1].timeline_end, time::from_seconds(4.0));
+
+        // The right half must still respect the non-overlap invariant
+        let clip = Clip::new(4, PathBuf::from("c.mp4"), 0, time::from_seconds(1.0), time::from_seconds(0.5), 0);
+        assert!(track.add_clip(clip).is_err());
+
+        assert_eq!(
+            track.split_clip(2, time::from_seconds(4.0), 5),
+            Err(TrackError::InvalidSplit { clip_id: 2, position: time::from_seconds(4.0) })
+        );
+    }
+
     #[test]
     fn test_sorted_order() {
         let mut track = Track::new(1, TrackType::Video);
@@ -366,5 +647,47 @@ mod tests {
         assert_eq!(track.clips[1].id, 3); // timeline_start = 10
         assert_eq!(track.clips[2].id, 1); // timeline_start = 20
     }
+
+    #[test]
+    fn test_indexed_lookups_match_scan() {
+        // Clips of varying length with gaps, some adjacent, added out of order
+        let mut track = Track::new(1, TrackType::Video);
+        let mut start = 0;
+        let mut clips = Vec::new();
+        for id in 1..=200u64 {
+            let length = 1 + (id * 7 % 5) as Time;
+            clips.push(Clip::new(id, PathBuf::from("a.mp4"), 0, length, start, 0));
+            start += length + (id % 3) as Time;
+        }
+        for clip in clips.iter().rev().step_by(2).chain(clips.iter().step_by(2)) {
+            track.add_clip(clip.clone()).unwrap();
+        }
+        assert!(track.clips.windows(2).all(|pair| pair[0].timeline_end <= pair[1].timeline_start));
+
+        for time in -1..start + 2 {
+            let scanned = track.clips.iter().find(|c| c.contains(time));
+            assert_eq!(track.clip_at(time).map(|c| c.id), scanned.map(|c| c.id), "at {}", time);
+
+            let end = time + 3;
+            let scanned: Vec<_> = track.clips.iter()
+                .filter(|c| c.timeline_start <= end && c.timeline_end >= time)
+                .map(|c| c.id)
+                .collect();
+            let found: Vec<_> = track.clips_in_range(time, end).iter().map(|c| c.id).collect();
+            assert_eq!(found, scanned, "in {}..{}", time, end);
+        }
+        assert!(track.clips_in_range(10, 5).is_empty());
+
+        // Overlaps are still caught against either neighbour, and after removals
+        let middle = track.clips[100].clone();
+        let overlapping = Clip::new(1000, PathBuf::from("b.mp4"), 0, 2, middle.timeline_end - 1, 0);
+        assert!(track.add_clip(overlapping).is_err());
+        let overlapping = Clip::new(1000, PathBuf::from("b.mp4"), 0, 2, middle.timeline_start - 1, 0);
+        assert!(track.add_clip(overlapping).is_err());
+        track.remove_clip(middle.id).unwrap();
+        let filler = Clip::new(1000, PathBuf::from("b.mp4"), 0, middle.duration(), middle.timeline_start, 0);
+        track.add_clip(filler).unwrap();
+        assert_eq!(track.clips[100].id, 1000);
+    }
 }
 
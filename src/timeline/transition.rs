@@ -0,0 +1,485 @@
+//! Transitions at the cuts between adjacent clips.
+//!
+//! A transition belongs to the clip that comes in at a cut and mixes it with
+//! the clip ending there. Over the transition both clips play: each runs on
+//! into its handles (the source media beyond its out or before its in point),
+//! so a transition can be no longer than the handles allow. A transition whose
+//! clip no longer starts at a cut (after a move) is kept but not played, and
+//! comes back if the cut does. It moves with its clip to another track and is
+//! removed with it.
+
+use std::f32::consts::FRAC_PI_2;
+use crate::core::time::Time;
+use crate::timeline::clip::{Clip, ClipId};
+use crate::timeline::timeline::Timeline;
+use crate::timeline::track::{Track, TrackError, TrackId, TrackType};
+use serde::{Deserialize, Serialize};
+
+/// Unique identifier for a transition
+pub type TransitionId = u64;
+
+/// Gain curve of an audio crossfade.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FadeCurve {
+    /// Gains sum to 1: a dip in loudness mid-fade on uncorrelated material
+    Linear,
+    /// Powers sum to 1: constant loudness on uncorrelated material
+    EqualPower,
+}
+
+impl FadeCurve {
+    /// Gains `(outgoing, incoming)` at `progress` (0.0-1.0) through the fade.
+    pub fn gains(self, progress: f32) -> (f32, f32) {
+        let progress = progress.clamp(0.0, 1.0);
+        match self {
+            FadeCurve::Linear => (1.0 - progress, progress),
+            FadeCurve::EqualPower => ((progress * FRAC_PI_2).cos(), (progress * FRAC_PI_2).sin()),
+        }
+    }
+}
+
+/// What a transition does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TransitionKind {
+    /// Video: fade the incoming clip in over the outgoing one
+    CrossDissolve,
+    /// Video: fade the outgoing clip to black, then the incoming clip up from it
+    DipToBlack,
+    /// Video: as `DipToBlack`, through white
+    DipToWhite,
+    /// Audio: fade the outgoing clip out while the incoming clip fades in
+    Crossfade(FadeCurve),
+}
+
+impl TransitionKind {
+    /// Type of track the transition works on.
+    pub fn track_type(&self) -> TrackType {
+        match self {
+            TransitionKind::Crossfade(_) => TrackType::Audio,
+            _ => TrackType::Video,
+        }
+    }
+
+    /// RGBA colour a dip passes through, if the transition is a dip.
+    pub fn dip_color(&self) -> Option<[u8; 4]> {
+        match self {
+            TransitionKind::DipToBlack => Some([0, 0, 0, 255]),
+            TransitionKind::DipToWhite => Some([255, 255, 255, 255]),
+            _ => None,
+        }
+    }
+}
+
+/// Where a transition sits relative to its cut.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TransitionAlignment {
+    /// Starts at the cut: the outgoing clip runs on into its tail handle
+    StartAtCut,
+    /// Centred on the cut: both clips use half the duration of handle
+    CenterOnCut,
+    /// Ends at the cut: the incoming clip starts early from its head handle
+    EndAtCut,
+}
+
+impl TransitionAlignment {
+    /// Split `duration` into the parts `(before, after)` the cut.
+    pub fn split(self, duration: Time) -> (Time, Time) {
+        match self {
+            TransitionAlignment::StartAtCut => (0, duration),
+            TransitionAlignment::CenterOnCut => (duration / 2, duration - duration / 2),
+            TransitionAlignment::EndAtCut => (duration, 0),
+        }
+    }
+}
+
+/// A transition into `clip_id` from the clip ending where it starts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transition {
+    pub id: TransitionId,
+    pub clip_id: ClipId, // Incoming clip; the outgoing clip is found by position
+    pub kind: TransitionKind,
+    pub alignment: TransitionAlignment,
+    pub duration: Time,
+}
+
+impl Transition {
+    /// Create a transition into `clip_id`.
+    ///
+    /// # Panics
+    /// Panics if `duration <= 0`.
+    pub fn new(
+        id: TransitionId,
+        clip_id: ClipId,
+        kind: TransitionKind,
+        alignment: TransitionAlignment,
+        duration: Time,
+    ) -> Self {
+        assert!(duration > 0, "Transition duration must be > 0");
+        Self { id, clip_id, kind, alignment, duration }
+    }
+}
+
+/// A transition resolved against its track: the clips it mixes and the
+/// timeline range `[start, end)` it covers.
+///
+/// The range is cut short if the clips' handles or lengths have shrunk
+/// since the transition was added.
+#[derive(Debug, Clone, Copy)]
+pub struct TransitionWindow<'a> {
+    pub transition: &'a Transition,
+    pub from: &'a Clip,
+    pub to: &'a Clip,
+    pub start: Time,
+    pub cut: Time,
+    pub end: Time,
+}
+
+impl TransitionWindow<'_> {
+    /// Whether `time` is inside the transition.
+    pub fn contains(&self, time: Time) -> bool {
+        self.start <= time && time < self.end
+    }
+
+    /// How far through the transition `time` is (0.0-1.0).
+    pub fn progress(&self, time: Time) -> f32 {
+        ((time - self.start) as f64 / (self.end - self.start) as f64).clamp(0.0, 1.0) as f32
+    }
+
+    /// Audio gain of `clip_id` at `time`: 1.0 outside the transition and for
+    /// clips it does not mix. Video kinds fade linearly.
+    pub fn gain(&self, clip_id: ClipId, time: Time) -> f32 {
+        if !self.contains(time) {
+            return 1.0;
+        }
+        let curve = match self.transition.kind {
+            TransitionKind::Crossfade(curve) => curve,
+            _ => FadeCurve::Linear,
+        };
+        let (outgoing, incoming) = curve.gains(self.progress(time));
+        if clip_id == self.from.id {
+            outgoing
+        } else if clip_id == self.to.id {
+            incoming
+        } else {
+            1.0
+        }
+    }
+}
+
+impl Track {
+    /// Resolve `transition` against the clips on this track, or `None` if its
+    /// clip does not start at a cut.
+    fn resolve_transition<'a>(&'a self, transition: &'a Transition) -> Option<TransitionWindow<'a>> {
+        let index = self.clips.iter().position(|c| c.id == transition.clip_id)?;
+        let to = &self.clips[index];
+        let from = index.checked_sub(1)
+            .map(|i| &self.clips[i])
+            .filter(|c| c.timeline_end == to.timeline_start)?;
+
+        let (before, after) = transition.alignment.split(transition.duration);
+        let (before_limit, after_limit) = cut_limits(from, to);
+        let (before, after) = (before.min(before_limit), after.min(after_limit));
+        if before + after == 0 {
+            return None;
+        }
+        let cut = to.timeline_start;
+        Some(TransitionWindow { transition, from, to, start: cut - before, cut, end: cut + after })
+    }
+
+    /// Every transition on this track that plays, in timeline order.
+    pub fn transition_windows(&self) -> Vec<TransitionWindow<'_>> {
+        let mut windows: Vec<_> = self.transitions
+            .iter()
+            .filter_map(|t| self.resolve_transition(t))
+            .collect();
+        windows.sort_by_key(|w| w.start);
+        windows
+    }
+
+    /// The transition playing at `time`, if any.
+    pub fn transition_at(&self, time: Time) -> Option<TransitionWindow<'_>> {
+        self.transitions
+            .iter()
+            .filter_map(|t| self.resolve_transition(t))
+            .find(|w| w.contains(time))
+    }
+
+    /// The transitions that mix `clip_id` (at most one at each end).
+    pub fn transitions_of(&self, clip_id: ClipId) -> Vec<TransitionWindow<'_>> {
+        self.transition_windows()
+            .into_iter()
+            .filter(|w| w.from.id == clip_id || w.to.id == clip_id)
+            .collect()
+    }
+
+    /// Timeline range `[start, end)` in which `clip` is heard or seen,
+    /// including the handles its transitions play.
+    pub fn clip_extent(&self, clip: &Clip) -> (Time, Time) {
+        self.transitions_of(clip.id)
+            .iter()
+            .fold((clip.timeline_start, clip.timeline_end), |(start, end), w| {
+                (start.min(w.start), end.max(w.end))
+            })
+    }
+}
+
+/// Longest parts `(before, after)` of a transition at the cut from `from`
+/// to `to`: the incoming clip's head handle and the outgoing clip's tail
+/// handle, and neither past the far end of the other clip.
+fn cut_limits(from: &Clip, to: &Clip) -> (Time, Time) {
+    let (to_head, _) = to.handles();
+    let (_, from_tail) = from.handles();
+    let before = from.duration().min(to_head.unwrap_or(Time::MAX));
+    let after = to.duration().min(from_tail.unwrap_or(Time::MAX));
+    (before, after)
+}
+
+impl Timeline {
+    /// Allocate a transition ID not used by any transition added so far.
+    pub fn allocate_transition_id(&mut self) -> TransitionId {
+        self.ids.transition.allocate()
+    }
+
+    /// Mark a transition ID as used.
+    pub(crate) fn reserve_transition_id(&mut self, id: TransitionId) {
+        self.ids.transition.reserve(id);
+    }
+
+    /// The track holding `clip_id`, with the index of the clip and of the
+    /// clip ending at its start.
+    fn edit_point(&self, clip_id: ClipId) -> Result<(usize, usize, usize), TrackError> {
+        let (track_index, clip_index) = self.tracks
+            .iter()
+            .enumerate()
+            .find_map(|(t, track)| track.clips.iter().position(|c| c.id == clip_id).map(|c| (t, c)))
+            .ok_or(TrackError::ClipNotFound { clip_id })?;
+        let clips = &self.tracks[track_index].clips;
+        let from_index = clip_index.checked_sub(1)
+            .filter(|&i| clips[i].timeline_end == clips[clip_index].timeline_start)
+            .ok_or(TrackError::NoEditPoint { clip_id })?;
+        Ok((track_index, from_index, clip_index))
+    }
+
+    /// Longest transition with `alignment` that fits the cut into `clip_id`.
+    ///
+    /// Both clips need handle media for the part of the transition on the
+    /// other side of the cut, and the transition may not run past the far
+    /// end of either clip.
+    pub fn max_transition_duration(&self, clip_id: ClipId, alignment: TransitionAlignment) -> Result<Time, TrackError> {
+        let (track_index, from_index, to_index) = self.edit_point(clip_id)?;
+        let clips = &self.tracks[track_index].clips;
+        let (before, after) = cut_limits(&clips[from_index], &clips[to_index]);
+        Ok(match alignment {
+            TransitionAlignment::StartAtCut => after,
+            TransitionAlignment::EndAtCut => before,
+            // `split` puts the odd nanosecond after the cut
+            TransitionAlignment::CenterOnCut => before.saturating_mul(2).saturating_add(1).min(after.saturating_mul(2)),
+        })
+    }
+
+    /// Add a transition at the cut into `clip_id`, replacing any transition
+    /// already there, and return its ID.
+    ///
+    /// Fails with `EmptyTransition` if `duration <= 0`,
+    /// `NoEditPoint` if no clip ends where `clip_id` starts,
+    /// `TrackLocked` if its track is locked,
+    /// `TransitionMismatch` if `kind` is for the other track type, and
+    /// `InsufficientHandles` (naming the clip that runs short) if the clips
+    /// cannot cover `duration` with `alignment`.
+    pub fn add_transition(
+        &mut self,
+        clip_id: ClipId,
+        kind: TransitionKind,
+        alignment: TransitionAlignment,
+        duration: Time,
+    ) -> Result<TransitionId, TrackError> {
+        if duration <= 0 {
+            return Err(TrackError::EmptyTransition { clip_id });
+        }
+        let (track_index, from_index, to_index) = self.edit_point(clip_id)?;
+        let track = &self.tracks[track_index];
+        track.check_unlocked()?;
+        if kind.track_type() != track.track_type {
+            return Err(TrackError::TransitionMismatch { track_id: track.id });
+        }
+
+        let (from, to) = (&track.clips[from_index], &track.clips[to_index]);
+        let (before, after) = alignment.split(duration);
+        let (before_limit, after_limit) = cut_limits(from, to);
+        if before > before_limit {
+            let short = if before > from.duration() { from.id } else { to.id };
+            return Err(TrackError::InsufficientHandles { clip_id: short });
+        }
+        if after > after_limit {
+            let short = if after > to.duration() { to.id } else { from.id };
+            return Err(TrackError::InsufficientHandles { clip_id: short });
+        }
+
+        let id = self.allocate_transition_id();
+        let track = &mut self.tracks[track_index];
+        track.transitions.retain(|t| t.clip_id != clip_id);
+        track.transitions.push(Transition::new(id, clip_id, kind, alignment, duration));
+        Ok(id)
+    }
+
+    /// Look up a transition and the track it is on.
+    pub fn transition(&self, id: TransitionId) -> Option<(&Track, &Transition)> {
+        self.tracks.iter().find_map(|track| {
+            track.transitions.iter().find(|t| t.id == id).map(|t| (track, t))
+        })
+    }
+
+    /// Remove a transition, returning it with the ID of the track it was on.
+    pub fn remove_transition(&mut self, id: TransitionId) -> Result<(TrackId, Transition), TrackError> {
+        let track = self.tracks
+            .iter_mut()
+            .find(|track| track.transitions.iter().any(|t| t.id == id))
+            .ok_or(TrackError::TransitionNotFound { transition: id })?;
+        track.check_unlocked()?;
+        let index = track.transitions.iter().position(|t| t.id == id).expect("track holds the transition");
+        Ok((track.id, track.transitions.remove(index)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use crate::core::time::from_seconds;
+    use crate::timeline::speed::Speed;
+    use crate::timeline::commands::RemoveClip;
+    use crate::timeline::history::History;
+
+    /// V1: clip 1 plays source [2s, 6s) at [0s, 4s), clip 2 plays [1s, 5s)
+    /// at [4s, 8s); both sources are 10s long. A1 holds clip 3 at [0s, 4s).
+    fn timeline() -> Timeline {
+        let mut timeline = Timeline::new();
+        let (v1, a1) = (timeline.tracks[0].id, timeline.tracks[1].id);
+        let source = |id, in_s, out_s, start_s| {
+            Clip::new(id, PathBuf::from("a.mp4"), from_seconds(in_s), from_seconds(out_s), from_seconds(start_s), 0)
+                .with_source_duration(from_seconds(10.0))
+        };
+        timeline.add_clip(v1, source(1, 2.0, 6.0, 0.0)).unwrap();
+        timeline.add_clip(v1, source(2, 1.0, 5.0, 4.0)).unwrap();
+        timeline.add_clip(a1, source(3, 0.0, 4.0, 0.0)).unwrap();
+        timeline
+    }
+
+    #[test]
+    fn test_alignment_windows() {
+        let mut timeline = timeline();
+        let cases = [
+            (TransitionAlignment::StartAtCut, 4.0, 5.0),
+            (TransitionAlignment::CenterOnCut, 3.5, 4.5),
+            (TransitionAlignment::EndAtCut, 3.0, 4.0),
+        ];
+        for (alignment, start, end) in cases {
+            timeline.add_transition(2, TransitionKind::CrossDissolve, alignment, from_seconds(1.0)).unwrap();
+            let windows = timeline.tracks[0].transition_windows();
+            assert_eq!(windows.len(), 1, "one transition per cut");
+            assert_eq!((windows[0].start, windows[0].end), (from_seconds(start), from_seconds(end)));
+            assert_eq!((windows[0].from.id, windows[0].to.id), (1, 2));
+        }
+
+        let window = timeline.tracks[0].transition_at(from_seconds(3.5)).unwrap();
+        assert_eq!(window.progress(from_seconds(3.5)), 0.5);
+        assert_eq!(timeline.tracks[0].clip_extent(window.to), (from_seconds(3.0), from_seconds(8.0)));
+        assert!(timeline.tracks[0].transition_at(from_seconds(4.0)).is_none());
+    }
+
+    #[test]
+    fn test_handles_limit_duration() {
+        let mut timeline = timeline();
+        // Clip 2 has 1s before its in point; clip 1 has 4s after its out point
+        assert_eq!(timeline.max_transition_duration(2, TransitionAlignment::EndAtCut), Ok(from_seconds(1.0)));
+        assert_eq!(timeline.max_transition_duration(2, TransitionAlignment::StartAtCut), Ok(from_seconds(4.0)));
+        assert_eq!(timeline.max_transition_duration(2, TransitionAlignment::CenterOnCut), Ok(from_seconds(2.0) + 1));
+
+        let result = timeline.add_transition(2, TransitionKind::DipToBlack, TransitionAlignment::EndAtCut, from_seconds(1.5));
+        assert_eq!(result, Err(TrackError::InsufficientHandles { clip_id: 2 }));
+
+        // At double speed the same handle lasts half as long
+        timeline.tracks[0].clips[0].set_speed(Speed::new(2, 1));
+        timeline.tracks[0].clips[0].set_timeline_start(from_seconds(2.0));
+        assert_eq!(timeline.max_transition_duration(2, TransitionAlignment::StartAtCut), Ok(from_seconds(2.0)));
+    }
+
+    #[test]
+    fn test_edit_point_and_track_type() {
+        let mut timeline = timeline();
+        let crossfade = TransitionKind::Crossfade(FadeCurve::EqualPower);
+        assert_eq!(
+            timeline.add_transition(1, TransitionKind::CrossDissolve, TransitionAlignment::StartAtCut, 1),
+            Err(TrackError::NoEditPoint { clip_id: 1 }),
+        );
+        assert_eq!(
+            timeline.add_transition(2, crossfade, TransitionAlignment::StartAtCut, 1),
+            Err(TrackError::TransitionMismatch { track_id: timeline.tracks[0].id }),
+        );
+        assert_eq!(
+            timeline.add_transition(2, TransitionKind::CrossDissolve, TransitionAlignment::StartAtCut, 0),
+            Err(TrackError::EmptyTransition { clip_id: 2 }),
+        );
+
+        // Moving the clip away parks the transition; moving it back restores it
+        let id = timeline.add_transition(2, TransitionKind::CrossDissolve, TransitionAlignment::StartAtCut, 1).unwrap();
+        timeline.tracks[0].clips[1].move_to(from_seconds(6.0));
+        assert!(timeline.tracks[0].transition_windows().is_empty());
+        timeline.tracks[0].clips[1].move_to(from_seconds(4.0));
+        assert_eq!(timeline.tracks[0].transition_windows().len(), 1);
+
+        assert_eq!(timeline.remove_transition(id).map(|(_, t)| t.clip_id), Ok(2));
+        assert!(timeline.transition(id).is_none());
+    }
+
+    #[test]
+    fn test_split_keeps_transition() {
+        let mut timeline = timeline();
+        timeline.add_transition(2, TransitionKind::CrossDissolve, TransitionAlignment::CenterOnCut, from_seconds(1.0)).unwrap();
+        timeline.split_clip(1, from_seconds(2.0)).unwrap();
+        let windows = timeline.tracks[0].transition_windows();
+        assert_eq!(windows.len(), 1);
+        assert_ne!(windows[0].from.id, 1);
+        assert_eq!(windows[0].to.id, 2);
+    }
+
+    #[test]
+    fn test_transition_follows_its_clip() {
+        let mut timeline = timeline();
+        let id = timeline.add_transition(2, TransitionKind::CrossDissolve, TransitionAlignment::StartAtCut, 1).unwrap();
+        let v2 = timeline.add_track(TrackType::Video);
+        timeline.move_linked(2, v2, from_seconds(4.0)).unwrap();
+        assert_eq!(timeline.transition(id).map(|(track, _)| track.id), Some(v2));
+
+        // Removed with the clip, and back on undo
+        let mut history = History::new();
+        history.execute(&mut timeline, RemoveClip::new(2)).unwrap();
+        assert!(timeline.transition(id).is_none());
+        history.undo(&mut timeline).unwrap();
+        assert_eq!(timeline.transition(id).map(|(track, _)| track.id), Some(v2));
+
+        // Lifted along with the clip
+        timeline.lift(from_seconds(4.0), from_seconds(8.0));
+        assert!(timeline.tracks.iter().all(|t| t.transitions.is_empty()));
+    }
+
+    #[test]
+    fn test_fade_curves() {
+        assert_eq!(FadeCurve::Linear.gains(0.25), (0.75, 0.25));
+        let (outgoing, incoming) = FadeCurve::EqualPower.gains(0.5);
+        assert!((outgoing * outgoing + incoming * incoming - 1.0).abs() < 1e-6);
+        assert_eq!(FadeCurve::EqualPower.gains(0.0), (1.0, 0.0));
+    }
+
+    #[test]
+    fn test_handle_source_at() {
+        let timeline = timeline();
+        let clip = &timeline.tracks[0].clips[0];
+        // Past the end the outgoing clip plays on from its out point
+        assert_eq!(clip.handle_source_at(from_seconds(5.0)), from_seconds(7.0));
+        assert_eq!(clip.handle_source_at(from_seconds(20.0)), from_seconds(10.0));
+        let reversed = clip.clone().with_speed(Speed::REVERSE);
+        assert_eq!(reversed.handle_source_at(from_seconds(5.0)), from_seconds(1.0));
+        assert_eq!(reversed.handles(), (Some(from_seconds(4.0)), Some(from_seconds(2.0))));
+    }
+}
@@ -0,0 +1,720 @@
+//! Ripple, roll, slip and slide trims on a track.
+//!
+//! Unlike `Clip::trim_in`/`trim_out` (overwrite-style edits of a single clip),
+//! these edits adjust neighbouring clips so the track stays gap- and
+//! overlap-consistent. Every edit is validated first; on error (including
+//! `TrackError::TrackLocked` on a locked track) the track is left unchanged.
+//!
+//! The `Track` methods edit one track. The `Timeline` methods (`*_linked`)
+//! make the same timeline change to every clip linked to the edited one, each
+//! on its own track, so linked video and audio stay in sync.
+
+use crate::timeline::clip::{Clip, ClipId};
+use crate::timeline::timeline::Timeline;
+use crate::timeline::track::{Track, TrackError, TrackId, TrackType};
+use crate::core::time::Time;
+
+/// Which end of a clip a trim moves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrimEdge {
+    /// Change `in_point`; the clip's timeline end stays fixed
+    In,
+    /// Change `out_point`; the clip's timeline start stays fixed
+    Out,
+}
+
+/// Check that `[in_point, out_point)` is a non-empty range inside the clip's source media.
+pub(crate) fn check_source_range(clip: &Clip, in_point: Time, out_point: Time) -> Result<(), TrackError> {
+    if out_point <= in_point {
+        return Err(TrackError::ClipTooShort { clip_id: clip.id });
+    }
+    let past_end = clip.source_duration.is_some_and(|d| out_point > d);
+    if in_point < 0 || past_end {
+        return Err(TrackError::SourceOutOfRange { clip_id: clip.id });
+    }
+    Ok(())
+}
+
+impl Track {
+    fn clip_index(&self, clip_id: ClipId) -> Result<usize, TrackError> {
+        self.clips
+            .iter()
+            .position(|c| c.id == clip_id)
+            .ok_or(TrackError::ClipNotFound { clip_id })
+    }
+
+    /// Shift every clip after `index` by `delta`.
+    fn shift_after(&mut self, index: usize, delta: Time) {
+        for clip in &mut self.clips[index + 1..] {
+            clip.set_timeline_start(clip.timeline_start + delta);
+        }
+    }
+
+    /// ID of the clip starting exactly where `clip_id` ends, if any.
+    fn adjacent_after(&self, clip_id: ClipId) -> Result<Option<ClipId>, TrackError> {
+        let index = self.clip_index(clip_id)?;
+        let end = self.clips[index].timeline_end;
+        Ok(self.clips.get(index + 1).filter(|c| c.timeline_start == end).map(|c| c.id))
+    }
+
+    /// Ripple one timeline edge of a clip by `delta`, with its source range
+    /// following: for `In` the clip keeps its start and gains or loses frames
+    /// at its head, for `Out` at its tail. Later clips move by `delta`.
+    ///
+    /// Used for the clips linked to a ripple-trimmed clip.
+    fn ripple_edge(&mut self, clip_id: ClipId, edge: TrimEdge, delta: Time) -> Result<(), TrackError> {
+        self.check_unlocked()?;
+        let index = self.clip_index(clip_id)?;
+        let clip = &self.clips[index];
+        if clip.time_remap.is_some() {
+            return Err(TrackError::TimeRemapped { clip_id });
+        }
+        let (start, end) = (clip.timeline_start, clip.timeline_end);
+        if end + delta <= start {
+            return Err(TrackError::ClipTooShort { clip_id });
+        }
+        let (in_point, out_point) = match edge {
+            TrimEdge::In => clip.source_range_for(start - delta, end),
+            TrimEdge::Out => clip.source_range_for(start, end + delta),
+        };
+        check_source_range(clip, in_point, out_point)?;
+
+        let clip = &mut self.clips[index];
+        (clip.in_point, clip.out_point) = (in_point, out_point);
+        clip.timeline_end = end + delta;
+        self.shift_after(index, delta);
+        Ok(())
+    }
+
+    /// Move one timeline edge of a clip by `delta`, with its source range
+    /// following. Nothing else moves: fails with `TrackError::Overlap` if the
+    /// clip would run into a neighbour.
+    ///
+    /// Used for linked clips that have no adjacent clip to roll with.
+    fn trim_edge(&mut self, clip_id: ClipId, edge: TrimEdge, delta: Time) -> Result<(), TrackError> {
+        self.check_unlocked()?;
+        let index = self.clip_index(clip_id)?;
+        let clip = &self.clips[index];
+        let (start, end) = match edge {
+            TrimEdge::In => (clip.timeline_start + delta, clip.timeline_end),
+            TrimEdge::Out => (clip.timeline_start, clip.timeline_end + delta),
+        };
+        if end <= start {
+            return Err(TrackError::ClipTooShort { clip_id });
+        }
+        let before = index.checked_sub(1).map(|i| &self.clips[i]);
+        if before.is_some_and(|c| c.timeline_end > start) || self.clips.get(index + 1).is_some_and(|c| c.timeline_start < end) {
+            return Err(TrackError::Overlap { clip_id });
+        }
+        let (in_point, out_point) = clip.source_range_for(start, end);
+        check_source_range(clip, in_point, out_point)?;
+        self.clips[index].set_timeline_range(start, end);
+        Ok(())
+    }
+
+    /// Ripple trim the start of a clip.
+    ///
+    /// Sets the clip's `in_point`; the clip keeps its timeline start, so its end
+    /// and every later clip on the track move by the change in duration.
+    /// Time-remapped clips are rejected with `Err(TrackError::TimeRemapped)`.
+    pub fn ripple_trim_in(&mut self, clip_id: ClipId, new_in_point: Time) -> Result<(), TrackError> {
+        self.check_unlocked()?;
+        let index = self.clip_index(clip_id)?;
+        let clip = &self.clips[index];
+        if clip.time_remap.is_some() {
+            return Err(TrackError::TimeRemapped { clip_id });
+        }
+        check_source_range(clip, new_in_point, clip.out_point)?;
+
+        let clip = &mut self.clips[index];
+        let old_duration = clip.duration();
+        clip.in_point = new_in_point;
+        clip.timeline_end = clip.timeline_start + clip.retimed_duration();
+        let delta = clip.duration() - old_duration;
+        self.shift_after(index, delta);
+        Ok(())
+    }
+
+    /// Ripple trim the end of a clip.
+    ///
+    /// Sets the clip's `out_point`; every later clip on the track moves by the
+    /// change in duration, so no gap opens and nothing is overwritten.
+    /// Time-remapped clips are rejected as for `ripple_trim_in`.
+    pub fn ripple_trim_out(&mut self, clip_id: ClipId, new_out_point: Time) -> Result<(), TrackError> {
+        self.check_unlocked()?;
+        let index = self.clip_index(clip_id)?;
+        let clip = &self.clips[index];
+        if clip.time_remap.is_some() {
+            return Err(TrackError::TimeRemapped { clip_id });
+        }
+        check_source_range(clip, clip.in_point, new_out_point)?;
+
+        let clip = &mut self.clips[index];
+        let old_duration = clip.duration();
+        clip.out_point = new_out_point;
+        clip.timeline_end = clip.timeline_start + clip.retimed_duration();
+        let delta = clip.duration() - old_duration;
+        self.shift_after(index, delta);
+        Ok(())
+    }
+
+    /// Roll the edit point between a clip and the clip directly after it.
+    ///
+    /// The left clip's out point and the right clip's in point move together to
+    /// `new_edit_point` (timeline time); the combined duration is unchanged.
+    /// Returns `Err(TrackError::NoAdjacentClip)` if no clip starts exactly where
+    /// `left_clip_id` ends.
+    pub fn roll_edit(&mut self, left_clip_id: ClipId, new_edit_point: Time) -> Result<(), TrackError> {
+        self.check_unlocked()?;
+        let index = self.clip_index(left_clip_id)?;
+        let left = &self.clips[index];
+        let right = match self.clips.get(index + 1) {
+            Some(right) if right.timeline_start == left.timeline_end => right,
+            _ => return Err(TrackError::NoAdjacentClip { clip_id: left_clip_id }),
+        };
+
+        let (left_in, left_out) = left.source_range_for(left.timeline_start, new_edit_point);
+        check_source_range(left, left_in, left_out)?;
+        let (right_in, right_out) = right.source_range_for(new_edit_point, right.timeline_end);
+        check_source_range(right, right_in, right_out)?;
+        if new_edit_point <= left.timeline_start {
+            return Err(TrackError::ClipTooShort { clip_id: left.id });
+        }
+        if new_edit_point >= right.timeline_end {
+            return Err(TrackError::ClipTooShort { clip_id: right.id });
+        }
+
+        let left = &mut self.clips[index];
+        left.set_timeline_range(left.timeline_start, new_edit_point);
+        let right = &mut self.clips[index + 1];
+        right.set_timeline_range(new_edit_point, right.timeline_end);
+        Ok(())
+    }
+
+    /// Slip a clip's source range by `delta`.
+    ///
+    /// In and out points both move by `delta`; the clip's position and duration
+    /// on the timeline are unchanged. A time-remap curve moves with them.
+    pub fn slip(&mut self, clip_id: ClipId, delta: Time) -> Result<(), TrackError> {
+        self.check_unlocked()?;
+        let index = self.clip_index(clip_id)?;
+        let clip = &self.clips[index];
+        check_source_range(clip, clip.in_point + delta, clip.out_point + delta)?;
+
+        let clip = &mut self.clips[index];
+        clip.in_point += delta;
+        clip.out_point += delta;
+        if let Some(remap) = &mut clip.time_remap {
+            remap.shift_source(delta);
+        }
+        Ok(())
+    }
+
+    /// Slide a clip along the timeline by `delta` between its neighbours.
+    ///
+    /// The clip's source range is unchanged. An adjacent clip before it has its
+    /// out point extended/shortened to follow, and an adjacent clip after it has
+    /// its in point adjusted, so the surrounding edit points move with the clip.
+    /// On a side without an adjacent clip the clip moves into the gap, and
+    /// `Err(TrackError::Overlap)` is returned if it would run into the next clip.
+    pub fn slide(&mut self, clip_id: ClipId, delta: Time) -> Result<(), TrackError> {
+        self.check_unlocked()?;
+        let index = self.clip_index(clip_id)?;
+        let clip = &self.clips[index];
+        let new_start = clip.timeline_start + delta;
+        let new_end = clip.timeline_end + delta;
+
+        let mut roll_left = false;
+        if let Some(left) = index.checked_sub(1).map(|i| &self.clips[i]) {
+            if left.timeline_end == clip.timeline_start {
+                let (left_in, left_out) = left.source_range_for(left.timeline_start, new_start);
+                check_source_range(left, left_in, left_out)?;
+                if new_start <= left.timeline_start {
+                    return Err(TrackError::ClipTooShort { clip_id: left.id });
+                }
+                roll_left = true;
+            } else if new_start < left.timeline_end {
+                return Err(TrackError::Overlap { clip_id });
+            }
+        }
+
+        let mut roll_right = false;
+        if let Some(right) = self.clips.get(index + 1) {
+            if right.timeline_start == clip.timeline_end {
+                let (right_in, right_out) = right.source_range_for(new_end, right.timeline_end);
+                check_source_range(right, right_in, right_out)?;
+                if new_end >= right.timeline_end {
+                    return Err(TrackError::ClipTooShort { clip_id: right.id });
+                }
+                roll_right = true;
+            } else if new_end > right.timeline_start {
+                return Err(TrackError::Overlap { clip_id });
+            }
+        }
+
+        if roll_left {
+            let left = &mut self.clips[index - 1];
+            left.set_timeline_range(left.timeline_start, new_start);
+        }
+        if roll_right {
+            let right = &mut self.clips[index + 1];
+            right.set_timeline_range(new_end, right.timeline_end);
+        }
+        self.clips[index].set_timeline_start(new_start);
+        Ok(())
+    }
+}
+
+/// Find a track among the copies an edit works on.
+fn track_in(tracks: &mut [Track], track_id: TrackId) -> Result<&mut Track, TrackError> {
+    tracks
+        .iter_mut()
+        .find(|t| t.id == track_id)
+        .ok_or(TrackError::TrackNotFound { track_id })
+}
+
+/// Source delta for a clip linked to one slipped by `delta`, so both show
+/// material from the same moment. A freeze frame shows no motion to follow.
+fn linked_slip(slipped: &Clip, linked: &Clip, delta: Time) -> Time {
+    if linked.speed == slipped.speed {
+        delta
+    } else if slipped.speed.is_freeze() {
+        0
+    } else {
+        let shift = slipped.speed.to_timeline(delta);
+        let shift = if slipped.speed.is_reverse() { -shift } else { shift };
+        linked.speed.to_source(shift)
+    }
+}
+
+impl Timeline {
+    /// Run an edit on copies of some tracks and keep them only if it succeeds,
+    /// so a failure part way through leaves the timeline unchanged.
+    fn edit_tracks(
+        &mut self,
+        track_ids: impl IntoIterator<Item = TrackId>,
+        edit: impl FnOnce(&mut [Track]) -> Result<(), TrackError>,
+    ) -> Result<(), TrackError> {
+        let mut tracks: Vec<Track> = Vec::new();
+        for track_id in track_ids {
+            if !tracks.iter().any(|t| t.id == track_id) {
+                let track = self.track(track_id).ok_or(TrackError::TrackNotFound { track_id })?;
+                tracks.push(track.clone());
+            }
+        }
+        edit(&mut tracks)?;
+        for track in tracks {
+            if let Some(original) = self.track_mut(track.id) {
+                *original = track;
+            }
+        }
+        self.update_duration();
+        Ok(())
+    }
+
+    /// Ripple trim a clip's in or out point (see `Track::ripple_trim_in` and
+    /// `Track::ripple_trim_out`), and every clip linked to it.
+    ///
+    /// Linked clips gain or lose the same timeline length at the same edge,
+    /// and later clips on each of their tracks move with them. Cues on
+    /// unlocked caption tracks and timeline markers after the clip's end move
+    /// too; cues in the span a shortened clip gives up are trimmed away, and
+    /// markers in it move to its start.
+    pub fn ripple_trim_linked(&mut self, clip_id: ClipId, edge: TrimEdge, source_point: Time) -> Result<(), TrackError> {
+        let members = self.linked_with_tracks(clip_id)?;
+        let captions: Vec<TrackId> = self.caption_tracks()
+            .filter(|t| !t.locked)
+            .map(|t| t.id)
+            .collect();
+        let track_ids = members.iter().map(|(track_id, _)| *track_id).chain(captions.iter().copied());
+        let mut ripple = None;
+        self.edit_tracks(track_ids, |tracks| {
+            let (track_id, clip) = members.iter()
+                .find(|(_, c)| c.id == clip_id)
+                .ok_or(TrackError::ClipNotFound { clip_id })?;
+            let track = track_in(tracks, *track_id)?;
+            match edge {
+                TrimEdge::In => track.ripple_trim_in(clip_id, source_point)?,
+                TrimEdge::Out => track.ripple_trim_out(clip_id, source_point)?,
+            }
+            let delta = track.clips[track.clip_index(clip_id)?].duration() - clip.duration();
+            for (track_id, member) in members.iter().filter(|(_, c)| c.id != clip_id) {
+                track_in(tracks, *track_id)?.ripple_edge(member.id, edge, delta)?;
+            }
+            let end = clip.timeline_end;
+            for track in tracks.iter_mut().filter(|t| t.track_type == TrackType::Caption && !t.locked) {
+                if delta < 0 {
+                    track.clear_cue_range(end + delta, end);
+                }
+                track.ripple_cues_from(end, delta);
+            }
+            ripple = Some((end, delta));
+            Ok(())
+        })?;
+        if let Some((end, delta)) = ripple {
+            for marker in self.markers.iter_mut().filter(|m| end + delta < m.position && m.position < end) {
+                marker.position = end + delta;
+            }
+            self.ripple_markers_from(end, delta);
+        }
+        Ok(())
+    }
+
+    /// Roll the edit point after a clip (see `Track::roll_edit`), and the same
+    /// edit point of every clip linked to either side of the cut.
+    ///
+    /// A linked clip with an adjacent clip rolls with it; one next to a gap
+    /// has just its edge moved, and fails with `TrackError::Overlap` if that
+    /// would run into another clip.
+    pub fn roll_linked(&mut self, left_clip_id: ClipId, new_edit_point: Time) -> Result<(), TrackError> {
+        let (track, left) = self.find_clip(left_clip_id)
+            .ok_or(TrackError::ClipNotFound { clip_id: left_clip_id })?;
+        let right_clip_id = track.adjacent_after(left_clip_id)?
+            .ok_or(TrackError::NoAdjacentClip { clip_id: left_clip_id })?;
+        let delta = new_edit_point - left.timeline_end;
+        let left_members = self.linked_with_tracks(left_clip_id)?;
+        let right_members = self.linked_with_tracks(right_clip_id)?;
+        let track_ids = left_members.iter().chain(&right_members).map(|(track_id, _)| *track_id);
+        let track_ids: Vec<TrackId> = track_ids.collect();
+
+        self.edit_tracks(track_ids, |tracks| {
+            // Clips after the cut that a roll on their track already moved
+            let mut rolled = Vec::new();
+            for (track_id, member) in &left_members {
+                let track = track_in(tracks, *track_id)?;
+                match track.adjacent_after(member.id)? {
+                    Some(next) => {
+                        track.roll_edit(member.id, member.timeline_end + delta)?;
+                        rolled.push(next);
+                    }
+                    None => track.trim_edge(member.id, TrimEdge::Out, delta)?,
+                }
+            }
+            for (track_id, member) in right_members.iter().filter(|(_, c)| !rolled.contains(&c.id)) {
+                track_in(tracks, *track_id)?.trim_edge(member.id, TrimEdge::In, delta)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Slip a clip's source range (see `Track::slip`), and every clip linked
+    /// to it by the matching amount of source at its own speed.
+    pub fn slip_linked(&mut self, clip_id: ClipId, delta: Time) -> Result<(), TrackError> {
+        let members = self.linked_with_tracks(clip_id)?;
+        self.edit_tracks(members.iter().map(|(track_id, _)| *track_id), |tracks| {
+            let (_, clip) = members.iter()
+                .find(|(_, c)| c.id == clip_id)
+                .ok_or(TrackError::ClipNotFound { clip_id })?;
+            for (track_id, member) in &members {
+                let member_delta = linked_slip(clip, member, delta);
+                if member_delta != 0 || member.id == clip_id {
+                    track_in(tracks, *track_id)?.slip(member.id, member_delta)?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Slide a clip between its neighbours (see `Track::slide`), and every
+    /// clip linked to it between its own neighbours.
+    pub fn slide_linked(&mut self, clip_id: ClipId, delta: Time) -> Result<(), TrackError> {
+        let members = self.linked_with_tracks(clip_id)?;
+        self.edit_tracks(members.iter().map(|(track_id, _)| *track_id), |tracks| {
+            for (track_id, member) in &members {
+                track_in(tracks, *track_id)?.slide(member.id, delta)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::time;
+    use crate::timeline::caption::Cue;
+    use crate::timeline::marker::{Marker, MarkerKind};
+    use std::path::PathBuf;
+
+    /// A 4s clip using source [2s, 6s] of a 10s file.
+    fn source_clip(id: ClipId, start: f64) -> Clip {
+        Clip::new(
+            id,
+            PathBuf::from("test.mp4"),
+            time::from_seconds(2.0),
+            time::from_seconds(6.0),
+            time::from_seconds(start),
+            0,
+        )
+        .with_source_duration(time::from_seconds(10.0))
+    }
+
+    /// Three adjacent 4s clips at 0s, 4s, 8s, each using source [2s, 6s] of a 10s file,
+    /// and a fourth clip at 20s after a gap.
+    fn track() -> Track {
+        let mut track = Track::new(1, TrackType::Video);
+        for (id, start) in [(1, 0.0), (2, 4.0), (3, 8.0), (4, 20.0)] {
+            track.add_clip(source_clip(id, start)).unwrap();
+        }
+        track
+    }
+
+    /// V1 and A1 each hold the three adjacent clips of `track()`, each video
+    /// clip (1-3) linked to the audio clip below it (11-13).
+    fn linked_timeline() -> Timeline {
+        let mut timeline = Timeline::new();
+        let (v1, a1) = (timeline.tracks[0].id, timeline.tracks[1].id);
+        for (id, start) in [(1, 0.0), (2, 4.0), (3, 8.0)] {
+            timeline.add_clip(v1, source_clip(id, start)).unwrap();
+            timeline.add_clip(a1, source_clip(id + 10, start)).unwrap();
+            timeline.link(&[id, id + 10]).unwrap();
+        }
+        timeline
+    }
+
+    /// Every linked pair covers the same timeline and source range.
+    fn assert_in_sync(timeline: &Timeline) {
+        for id in 1..=3 {
+            let video = timeline.find_clip(id).unwrap().1;
+            let audio = timeline.find_clip(id + 10).unwrap().1;
+            assert_eq!((video.timeline_start, video.timeline_end), (audio.timeline_start, audio.timeline_end), "clip {id}");
+            assert_eq!((video.in_point, video.out_point), (audio.in_point, audio.out_point), "clip {id}");
+            assert_eq!(timeline.sync_offset(id + 10), Some(0), "clip {id}");
+        }
+    }
+
+    fn starts(track: &Track) -> Vec<Time> {
+        track.clips.iter().map(|c| c.timeline_start).collect()
+    }
+
+    #[test]
+    fn test_ripple_trim_out() {
+        let mut track = track();
+        track.ripple_trim_out(1, time::from_seconds(5.0)).unwrap();
+        assert_eq!(track.clips[0].timeline_end, time::from_seconds(3.0));
+        assert_eq!(
+            starts(&track),
+            vec![0, time::from_seconds(3.0), time::from_seconds(7.0), time::from_seconds(19.0)]
+        );
+
+        // Extending ripples later clips forward instead of overlapping them
+        track.ripple_trim_out(1, time::from_seconds(8.0)).unwrap();
+        assert_eq!(track.clips[1].timeline_start, time::from_seconds(6.0));
+
+        assert_eq!(
+            track.ripple_trim_out(1, time::from_seconds(11.0)),
+            Err(TrackError::SourceOutOfRange { clip_id: 1 })
+        );
+    }
+
+    #[test]
+    fn test_ripple_trim_in() {
+        let mut track = track();
+        track.ripple_trim_in(2, time::from_seconds(3.0)).unwrap();
+        let clip = &track.clips[1];
+        assert_eq!(clip.timeline_start, time::from_seconds(4.0));
+        assert_eq!(clip.timeline_end, time::from_seconds(7.0));
+        assert_eq!(track.clips[2].timeline_start, time::from_seconds(7.0));
+
+        assert_eq!(
+            track.ripple_trim_in(2, time::from_seconds(6.0)),
+            Err(TrackError::ClipTooShort { clip_id: 2 })
+        );
+        assert_eq!(
+            track.ripple_trim_in(2, time::from_seconds(-1.0)),
+            Err(TrackError::SourceOutOfRange { clip_id: 2 })
+        );
+    }
+
+    #[test]
+    fn test_roll_edit() {
+        let mut track = track();
+        track.roll_edit(1, time::from_seconds(5.0)).unwrap();
+        assert_eq!(track.clips[0].out_point, time::from_seconds(7.0));
+        assert_eq!(track.clips[0].timeline_end, time::from_seconds(5.0));
+        assert_eq!(track.clips[1].in_point, time::from_seconds(3.0));
+        assert_eq!(track.clips[1].timeline_start, time::from_seconds(5.0));
+        assert_eq!(track.clips[1].timeline_end, time::from_seconds(8.0));
+
+        // Right clip would need source before 0s
+        assert_eq!(
+            track.roll_edit(1, time::from_seconds(1.0)),
+            Err(TrackError::SourceOutOfRange { clip_id: 2 })
+        );
+        // Right clip would be rolled away entirely
+        assert_eq!(
+            track.roll_edit(1, time::from_seconds(8.0)),
+            Err(TrackError::ClipTooShort { clip_id: 2 })
+        );
+        // Clip 3 is followed by a gap
+        assert_eq!(
+            track.roll_edit(3, time::from_seconds(13.0)),
+            Err(TrackError::NoAdjacentClip { clip_id: 3 })
+        );
+    }
+
+    #[test]
+    fn test_retimed_roll_and_slide_are_exact() {
+        use crate::timeline::speed::Speed;
+        use crate::timeline::validate::MediaDurations;
+        // Source [2s, 5s] at 1/3 speed is 9s long, at 7/3 reversed just under 1.3s
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        let mut start = 0;
+        for (id, speed) in [(1, Speed::new(1, 3)), (2, Speed::new(-7, 3)), (3, Speed::new(1, 3))] {
+            let clip = Clip::new(id, PathBuf::from("test.mp4"), time::from_seconds(2.0), time::from_seconds(5.0), start, 0)
+                .with_speed(speed);
+            start = clip.timeline_end;
+            timeline.add_clip(v1, clip).unwrap();
+        }
+
+        // Odd nanosecond edit points round at every edit; none may drift
+        for step in 1..=50 {
+            let offset = if step % 2 == 0 { 1_000_001 } else { -999_997 };
+            let cut = timeline.find_clip(1).unwrap().1.timeline_end + offset;
+            timeline.roll_linked(1, cut).unwrap();
+            timeline.slide_linked(2, -offset / 3).unwrap();
+            assert_eq!(timeline.validate(&MediaDurations::new()), Vec::new(), "step {step}");
+        }
+        let clips = &timeline.track(v1).unwrap().clips;
+        assert!(clips.windows(2).all(|pair| pair[0].timeline_end == pair[1].timeline_start));
+    }
+
+    #[test]
+    fn test_slip() {
+        let mut track = track();
+        track.slip(2, time::from_seconds(-2.0)).unwrap();
+        assert_eq!(track.clips[1].in_point, 0);
+        assert_eq!(track.clips[1].out_point, time::from_seconds(4.0));
+        assert_eq!(track.clips[1].timeline_start, time::from_seconds(4.0));
+
+        assert_eq!(
+            track.slip(2, time::from_seconds(-1.0)),
+            Err(TrackError::SourceOutOfRange { clip_id: 2 })
+        );
+    }
+
+    #[test]
+    fn test_slide() {
+        let mut track = track();
+        track.slide(2, time::from_seconds(1.0)).unwrap();
+        let (left, clip, right) = (&track.clips[0], &track.clips[1], &track.clips[2]);
+        assert_eq!(left.timeline_end, time::from_seconds(5.0));
+        assert_eq!(left.out_point, time::from_seconds(7.0));
+        assert_eq!(clip.timeline_start, time::from_seconds(5.0));
+        assert_eq!(clip.in_point, time::from_seconds(2.0));
+        assert_eq!(right.timeline_start, time::from_seconds(9.0));
+        assert_eq!(right.in_point, time::from_seconds(3.0));
+        assert_eq!(right.timeline_end, time::from_seconds(12.0));
+
+        // Left neighbour would need source past 10s
+        assert_eq!(
+            track.slide(2, time::from_seconds(4.0)),
+            Err(TrackError::SourceOutOfRange { clip_id: 1 })
+        );
+        assert_eq!(track.clips[1].timeline_start, time::from_seconds(5.0));
+    }
+
+    #[test]
+    fn test_slide_into_gap() {
+        let mut track = track();
+        // Clip 3 has a gap on its right: it moves into the gap, clip 4 untouched
+        track.slide(3, time::from_seconds(2.0)).unwrap();
+        assert_eq!(track.clips[2].timeline_start, time::from_seconds(10.0));
+        assert_eq!(track.clips[3].in_point, time::from_seconds(2.0));
+
+        // Clip 4 has a gap on its left and would run into clip 3
+        assert_eq!(
+            track.slide(4, time::from_seconds(-7.0)),
+            Err(TrackError::Overlap { clip_id: 4 })
+        );
+        assert_eq!(track.clips[3].timeline_start, time::from_seconds(20.0));
+    }
+
+    #[test]
+    fn test_linked_trims_keep_sync() {
+        let mut timeline = linked_timeline();
+        let clip = |timeline: &Timeline, id| {
+            let clip = timeline.find_clip(id).unwrap().1;
+            (clip.timeline_start, clip.timeline_end)
+        };
+
+        timeline.ripple_trim_linked(2, TrimEdge::Out, time::from_seconds(5.0)).unwrap();
+        assert_in_sync(&timeline);
+        assert_eq!(clip(&timeline, 13), (time::from_seconds(7.0), time::from_seconds(11.0)));
+
+        timeline.ripple_trim_linked(12, TrimEdge::In, time::from_seconds(3.0)).unwrap();
+        assert_in_sync(&timeline);
+        assert_eq!(clip(&timeline, 2), (time::from_seconds(4.0), time::from_seconds(6.0)));
+
+        timeline.roll_linked(1, time::from_seconds(5.0)).unwrap();
+        assert_in_sync(&timeline);
+        assert_eq!(clip(&timeline, 12), (time::from_seconds(5.0), time::from_seconds(6.0)));
+
+        timeline.slip_linked(2, time::from_seconds(-1.0)).unwrap();
+        assert_in_sync(&timeline);
+        assert_eq!(timeline.find_clip(12).unwrap().1.in_point, time::from_seconds(3.0));
+
+        timeline.slide_linked(2, time::from_seconds(0.5)).unwrap();
+        assert_in_sync(&timeline);
+        assert_eq!(clip(&timeline, 11), (0, time::from_seconds(5.5)));
+    }
+
+    #[test]
+    fn test_ripple_trim_linked_moves_cues() {
+        let mut timeline = linked_timeline();
+        let c1 = timeline.add_track(TrackType::Caption);
+        for (id, start, end) in [(1, 5.0, 6.0), (2, 7.5, 8.5), (3, 9.0, 10.0)] {
+            timeline.add_cue(c1, Cue::new(id, time::from_seconds(start), time::from_seconds(end), "Line")).unwrap();
+        }
+
+        // Clip 2 loses [7s, 8s): cue 2 loses its head, cue 3 moves left
+        timeline.ripple_trim_linked(2, TrimEdge::Out, time::from_seconds(5.0)).unwrap();
+        let cues: Vec<(Time, Time)> = timeline.track(c1).unwrap().cues.iter().map(|c| (c.start, c.end)).collect();
+        assert_eq!(cues, vec![
+            (time::from_seconds(5.0), time::from_seconds(6.0)),
+            (time::from_seconds(7.0), time::from_seconds(7.5)),
+            (time::from_seconds(8.0), time::from_seconds(9.0)),
+        ]);
+        assert_eq!(timeline.find_clip(3).unwrap().1.timeline_start, time::from_seconds(7.0));
+    }
+
+    #[test]
+    fn test_ripple_trim_linked_moves_markers() {
+        let mut timeline = linked_timeline();
+        for position in [5.0, 7.5, 9.0] {
+            let id = timeline.allocate_marker_id();
+            timeline.add_marker(Marker::new(id, time::from_seconds(position), MarkerKind::Comment));
+        }
+
+        // Clip 2 loses [7s, 8s): the marker in it moves to 7s, the later one left
+        timeline.ripple_trim_linked(2, TrimEdge::Out, time::from_seconds(5.0)).unwrap();
+        let positions: Vec<Time> = timeline.markers.iter().map(|m| m.position).collect();
+        assert_eq!(positions, vec![time::from_seconds(5.0), time::from_seconds(7.0), time::from_seconds(8.0)]);
+
+        // Lengthening moves the markers after the clip right again
+        timeline.ripple_trim_linked(2, TrimEdge::Out, time::from_seconds(6.0)).unwrap();
+        let positions: Vec<Time> = timeline.markers.iter().map(|m| m.position).collect();
+        assert_eq!(positions, vec![time::from_seconds(5.0), time::from_seconds(8.0), time::from_seconds(9.0)]);
+    }
+
+    #[test]
+    fn test_linked_trim_is_all_or_nothing() {
+        let mut timeline = linked_timeline();
+        let a1 = timeline.tracks[1].id;
+        timeline.track_mut(a1).unwrap().set_locked(true);
+        let before = timeline.tracks.clone();
+        assert_eq!(timeline.roll_linked(1, time::from_seconds(5.0)), Err(TrackError::TrackLocked { track_id: a1 }));
+        assert_eq!(timeline.ripple_trim_linked(1, TrimEdge::Out, time::from_seconds(5.0)), Err(TrackError::TrackLocked { track_id: a1 }));
+        assert_eq!(timeline.tracks, before);
+
+        // Without clip 13, clip 12 is followed by a gap: its edge moves on its own
+        timeline.track_mut(a1).unwrap().set_locked(false);
+        timeline.track_mut(a1).unwrap().remove_clip(13);
+        timeline.roll_linked(2, time::from_seconds(9.0)).unwrap();
+        let audio = timeline.find_clip(12).unwrap().1;
+        assert_eq!((audio.timeline_end, audio.out_point), (time::from_seconds(9.0), time::from_seconds(7.0)));
+        assert_eq!(timeline.find_clip(3).unwrap().1.timeline_start, time::from_seconds(9.0));
+        assert_eq!(timeline.sync_offset(12), Some(0));
+    }
+}
@@ -0,0 +1,453 @@
+//! Timeline integrity checks and automatic repair.
+//!
+//! Clip and track fields are public, so code outside the edit methods can
+//! leave a timeline in states the edits assume never happen: overlapping or
+//! unsorted clips, repeated IDs, empty or negative ranges, a timeline end that
+//! disagrees with the source range, or a stale `Timeline::duration`.
+//! `Timeline::validate` reports them as diagnostics and `Timeline::repair`
+//! fixes them. Project loading repairs what it finds and reports it; export
+//! refuses timelines with diagnostics.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::PathBuf;
+use crate::timeline::clip::{Clip, ClipId, ClipSource};
+use crate::timeline::sequence::SequenceId;
+use crate::timeline::timeline::Timeline;
+use crate::timeline::track::{Track, TrackId};
+use crate::core::time::Time;
+
+/// Durations of media files (e.g. probed from the files), used to check clip
+/// source ranges. Clips of files not listed are checked against their
+/// `source_duration`.
+pub type MediaDurations = HashMap<PathBuf, Time>;
+
+/// A broken invariant of a timeline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimelineIssue {
+    /// Two tracks have the same ID
+    DuplicateTrackId { track_id: TrackId },
+    /// A clip has the ID of an earlier clip (on `track_id` or before it)
+    DuplicateClipId { track_id: TrackId, clip_id: ClipId },
+    /// A clip starts before the timeline does
+    NegativeStart { track_id: TrackId, clip_id: ClipId },
+    /// A clip's in point is before the start of its source
+    NegativeInPoint { track_id: TrackId, clip_id: ClipId },
+    /// A clip's timeline range or source range is empty
+    ZeroLength { track_id: TrackId, clip_id: ClipId },
+    /// A clip's timeline end is not where its source range ends at its speed
+    EndMismatch { track_id: TrackId, clip_id: ClipId, expected: Time },
+    /// A clip's out point is past the end of its media or sequence
+    SourceOutOfRange { track_id: TrackId, clip_id: ClipId, source_duration: Time },
+    /// A track's clips are not sorted by timeline start
+    Unsorted { track_id: TrackId },
+    /// Two clips on a track overlap; `first` starts first
+    Overlap { track_id: TrackId, first: ClipId, second: ClipId },
+    /// `Timeline::duration` is not the end of the last clip or cue
+    StaleDuration { recorded: Time, actual: Time },
+}
+
+impl fmt::Display for TimelineIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimelineIssue::DuplicateTrackId { track_id } => write!(f, "track {} appears twice", track_id),
+            TimelineIssue::DuplicateClipId { track_id, clip_id } => {
+                write!(f, "clip {} appears twice (again on track {})", clip_id, track_id)
+            }
+            TimelineIssue::NegativeStart { track_id, clip_id } => {
+                write!(f, "clip {} on track {}: starts before the timeline", clip_id, track_id)
+            }
+            TimelineIssue::NegativeInPoint { track_id, clip_id } => {
+                write!(f, "clip {} on track {}: in point is negative", clip_id, track_id)
+            }
+            TimelineIssue::ZeroLength { track_id, clip_id } => {
+                write!(f, "clip {} on track {}: has no length", clip_id, track_id)
+            }
+            TimelineIssue::EndMismatch { track_id, clip_id, expected } => write!(
+                f,
+                "clip {} on track {}: timeline end should be {} for its source range",
+                clip_id, track_id, expected
+            ),
+            TimelineIssue::SourceOutOfRange { track_id, clip_id, source_duration } => write!(
+                f,
+                "clip {} on track {}: out point is past the end of the source ({})",
+                clip_id, track_id, source_duration
+            ),
+            TimelineIssue::Unsorted { track_id } => write!(f, "clips on track {} are out of order", track_id),
+            TimelineIssue::Overlap { track_id, first, second } => {
+                write!(f, "clips {} and {} on track {} overlap", first, second, track_id)
+            }
+            TimelineIssue::StaleDuration { recorded, actual } => {
+                write!(f, "duration is {} but the timeline ends at {}", recorded, actual)
+            }
+        }
+    }
+}
+
+/// An issue found by `Timeline::validate`, and where.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub sequence: Option<SequenceId>, // Nested sequence holding the issue; `None` for the timeline itself
+    pub issue: TimelineIssue,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.sequence {
+            Some(sequence) => write!(f, "sequence {}: {}", sequence, self.issue),
+            None => write!(f, "{}", self.issue),
+        }
+    }
+}
+
+impl Timeline {
+    /// Check the timeline and its nested sequences.
+    ///
+    /// Source ranges are checked against `media` (see `MediaDurations`) and,
+    /// for compound clips, the length of their sequence. Returns no
+    /// diagnostics for a timeline only ever changed through its edit methods.
+    pub fn validate(&self, media: &MediaDurations) -> Vec<Diagnostic> {
+        let sequences = self.sequence_durations();
+        let nested = self.sequences.values().map(|s| (Some(s.id), &s.timeline));
+        std::iter::once((None, self))
+            .chain(nested)
+            .flat_map(|(sequence, timeline)| {
+                timeline.issues(media, &sequences)
+                    .into_iter()
+                    .map(move |issue| Diagnostic { sequence, issue })
+            })
+            .collect()
+    }
+
+    /// Fix everything `validate` reports, in the timeline and its nested
+    /// sequences, and return what was found.
+    ///
+    /// - Repeated track and clip IDs: the later track or clip gets a fresh ID
+    /// - Negative in point: the clip is slipped to start at the source start
+    /// - Out point past the source end: the clip's tail is trimmed
+    /// - End mismatch: the timeline end is recomputed from the source range
+    /// - Negative start: the part before 0 is trimmed off
+    /// - Empty clips (including those left by the trims) are removed
+    /// - Unsorted clips are sorted
+    /// - Overlaps: the earlier clip is trimmed to where the later one starts,
+    ///   or removed if the later one covers it (as an overwrite edit would)
+    /// - Stale duration: recomputed
+    ///
+    /// Transitions left without their clip are removed.
+    pub fn repair(&mut self, media: &MediaDurations) -> Vec<Diagnostic> {
+        let diagnostics = self.validate(media);
+        if diagnostics.is_empty() {
+            return diagnostics;
+        }
+        // Sequences are repaired first, so compound clips are checked
+        // against the repaired lengths
+        let ids: Vec<SequenceId> = self.sequences.keys().copied().collect();
+        for id in ids {
+            let sequences = self.sequence_durations();
+            if let Some(sequence) = self.sequences.get_mut(&id) {
+                sequence.timeline.repair_timeline(media, &sequences);
+            }
+        }
+        let sequences = self.sequence_durations();
+        self.repair_timeline(media, &sequences);
+        diagnostics
+    }
+
+    /// Length of each nested sequence.
+    fn sequence_durations(&self) -> HashMap<SequenceId, Time> {
+        self.sequences.values().map(|s| (s.id, s.timeline.duration)).collect()
+    }
+
+    /// Issues of this timeline alone, without its nested sequences.
+    fn issues(&self, media: &MediaDurations, sequences: &HashMap<SequenceId, Time>) -> Vec<TimelineIssue> {
+        let mut issues = Vec::new();
+        let mut track_ids = HashSet::new();
+        let mut clip_ids = HashSet::new();
+        for track in &self.tracks {
+            let track_id = track.id;
+            if !track_ids.insert(track_id) {
+                issues.push(TimelineIssue::DuplicateTrackId { track_id });
+            }
+            for clip in &track.clips {
+                let clip_id = clip.id;
+                if !clip_ids.insert(clip_id) {
+                    issues.push(TimelineIssue::DuplicateClipId { track_id, clip_id });
+                }
+                if clip.timeline_start < 0 {
+                    issues.push(TimelineIssue::NegativeStart { track_id, clip_id });
+                }
+                if clip.in_point < 0 {
+                    issues.push(TimelineIssue::NegativeInPoint { track_id, clip_id });
+                }
+                if clip.timeline_end <= clip.timeline_start || clip.out_point <= clip.in_point {
+                    issues.push(TimelineIssue::ZeroLength { track_id, clip_id });
+                } else if let Some(expected) = expected_end(clip) {
+                    issues.push(TimelineIssue::EndMismatch { track_id, clip_id, expected });
+                }
+                if let Some(source_duration) = source_duration(clip, media, sequences) {
+                    if clip.out_point > source_duration {
+                        issues.push(TimelineIssue::SourceOutOfRange { track_id, clip_id, source_duration });
+                    }
+                }
+            }
+            if !is_sorted(track) {
+                issues.push(TimelineIssue::Unsorted { track_id });
+            }
+            let mut clips: Vec<&Clip> = track.clips.iter().collect();
+            clips.sort_by_key(|c| (c.timeline_start, c.timeline_end));
+            // Compare each clip with the clip reaching furthest right before it
+            let mut furthest: Option<&Clip> = None;
+            for clip in clips {
+                match furthest {
+                    Some(first) if first.overlaps_with(clip) => {
+                        issues.push(TimelineIssue::Overlap { track_id, first: first.id, second: clip.id });
+                    }
+                    _ => {}
+                }
+                if !matches!(furthest, Some(f) if clip.timeline_end <= f.timeline_end) {
+                    furthest = Some(clip);
+                }
+            }
+        }
+
+        let actual = self.tracks.iter().map(Track::duration).max().unwrap_or(0);
+        if self.duration != actual {
+            issues.push(TimelineIssue::StaleDuration { recorded: self.duration, actual });
+        }
+        issues
+    }
+
+    /// Repair this timeline alone (see `repair`).
+    fn repair_timeline(&mut self, media: &MediaDurations, sequences: &HashMap<SequenceId, Time>) {
+        // Fresh IDs must not collide with any ID in use
+        for track in &self.tracks {
+            self.ids.track.reserve(track.id);
+            for clip in &track.clips {
+                self.ids.clip.reserve(clip.id);
+            }
+        }
+        let mut track_ids = HashSet::new();
+        let mut clip_ids = HashSet::new();
+        for index in 0..self.tracks.len() {
+            if !track_ids.insert(self.tracks[index].id) {
+                self.tracks[index].id = self.ids.track.allocate();
+            }
+            for clip_index in 0..self.tracks[index].clips.len() {
+                if !clip_ids.insert(self.tracks[index].clips[clip_index].id) {
+                    self.tracks[index].clips[clip_index].id = self.allocate_clip_id();
+                }
+            }
+        }
+
+        for track in &mut self.tracks {
+            track.clips.retain_mut(|clip| repair_clip(clip, media, sequences));
+            track.clips.sort_by_key(|c| (c.timeline_start, c.timeline_end));
+
+            // Trim or remove each clip that the next one runs into
+            let mut kept: Vec<Clip> = Vec::with_capacity(track.clips.len());
+            for clip in std::mem::take(&mut track.clips) {
+                while let Some(last) = kept.last_mut().filter(|last| last.overlaps_with(&clip)) {
+                    if last.timeline_start < clip.timeline_start {
+                        last.set_timeline_range(last.timeline_start, clip.timeline_start);
+                        break;
+                    }
+                    kept.pop();
+                }
+                kept.push(clip);
+            }
+            track.clips = kept;
+            track.drop_stray_transitions();
+        }
+        self.update_duration();
+    }
+}
+
+/// Where `clip` should end on the timeline for its source range, if that is
+/// not where it ends. Freeze frames and time-remapped clips set their own
+/// length.
+///
+/// At speeds other than 1x, nanosecond rounding makes the two conversions
+/// disagree, so both are exact: edits that set the source range derive the
+/// timeline length from it (`Clip::retimed_duration`), and edits that move
+/// timeline edges derive the source span from the length
+/// (`Clip::source_range_for`).
+fn expected_end(clip: &Clip) -> Option<Time> {
+    if clip.speed.is_freeze() || clip.time_remap.is_some() {
+        return None;
+    }
+    let expected = clip.timeline_start + clip.retimed_duration();
+    let from_length = clip.speed.to_source(clip.duration()).abs() == clip.source_span();
+    (expected != clip.timeline_end && !from_length).then_some(expected)
+}
+
+/// Length of the media or sequence `clip` plays, if known.
+fn source_duration(clip: &Clip, media: &MediaDurations, sequences: &HashMap<SequenceId, Time>) -> Option<Time> {
+    match &clip.source {
+        ClipSource::Media(path) => media.get(path).copied().or(clip.source_duration),
+        ClipSource::Sequence(id) => sequences.get(id).copied().or(clip.source_duration),
+    }
+}
+
+/// Whether a track's clips are in the order the track keeps them in.
+fn is_sorted(track: &Track) -> bool {
+    track.clips.windows(2).all(|pair| {
+        (pair[0].timeline_start, pair[0].timeline_end) <= (pair[1].timeline_start, pair[1].timeline_end)
+    })
+}
+
+/// Repair the ranges of one clip; returns `false` if nothing is left of it.
+fn repair_clip(clip: &mut Clip, media: &MediaDurations, sequences: &HashMap<SequenceId, Time>) -> bool {
+    if clip.in_point < 0 {
+        let delta = -clip.in_point;
+        clip.in_point += delta;
+        clip.out_point += delta;
+        if let Some(remap) = &mut clip.time_remap {
+            remap.shift_source(delta);
+        }
+    }
+    if let Some(source_duration) = source_duration(clip, media, sequences) {
+        clip.out_point = clip.out_point.min(source_duration);
+    }
+    if clip.out_point <= clip.in_point {
+        return false;
+    }
+    if let Some(end) = expected_end(clip) {
+        clip.timeline_end = end;
+    }
+    if clip.timeline_start < 0 && clip.timeline_end > 0 {
+        clip.set_timeline_range(0, clip.timeline_end);
+    }
+    clip.timeline_start >= 0 && clip.timeline_end > clip.timeline_start && clip.out_point > clip.in_point
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::time::{self, constants::NANOS_PER_SECOND};
+
+    fn clip(id: ClipId, start: f64, length: f64) -> Clip {
+        Clip::new(id, PathBuf::from("a.mp4"), 0, time::from_seconds(length), time::from_seconds(start), 0)
+    }
+
+    fn issues(timeline: &Timeline, media: &MediaDurations) -> Vec<TimelineIssue> {
+        timeline.validate(media).into_iter().map(|d| d.issue).collect()
+    }
+
+    #[test]
+    fn test_valid_timeline_has_no_diagnostics() {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        timeline.add_clip(v1, clip(1, 0.0, 2.0)).unwrap();
+        timeline.add_clip(v1, clip(2, 2.0, 3.0)).unwrap();
+        timeline.tracks[0].ripple_trim_out(1, time::from_seconds(1.5)).unwrap();
+        timeline.update_duration();
+        assert!(timeline.validate(&MediaDurations::new()).is_empty());
+        assert!(timeline.repair(&MediaDurations::new()).is_empty());
+    }
+
+    #[test]
+    fn test_reports_each_issue() {
+        let mut timeline = Timeline::new();
+        let (v1, a1) = (timeline.tracks[0].id, timeline.tracks[1].id);
+        timeline.add_clip(v1, clip(1, 0.0, 2.0)).unwrap();
+        timeline.add_clip(v1, clip(2, 2.0, 2.0)).unwrap();
+        timeline.add_clip(a1, clip(3, 0.0, 2.0)).unwrap();
+        timeline.add_clip(a1, clip(4, 4.0, 1.0)).unwrap();
+
+        let track = &mut timeline.tracks[0];
+        track.clips[0].timeline_end = time::from_seconds(3.0); // Past its source range, into clip 2
+        track.clips[1].in_point = -NANOS_PER_SECOND; // Same span, shifted
+        track.clips[1].out_point = NANOS_PER_SECOND;
+        let track = &mut timeline.tracks[1];
+        track.clips[0].id = 1;
+        track.clips[0].timeline_start = -NANOS_PER_SECOND;
+        track.clips[1].out_point = track.clips[1].in_point;
+        track.clips.swap(0, 1);
+        timeline.tracks[1].id = v1;
+        timeline.duration = time::from_seconds(6.0);
+        let media = MediaDurations::from([(PathBuf::from("a.mp4"), time::from_seconds(1.5))]);
+
+        let found = issues(&timeline, &media);
+        let expected = vec![
+            TimelineIssue::EndMismatch { track_id: v1, clip_id: 1, expected: time::from_seconds(2.0) },
+            TimelineIssue::SourceOutOfRange { track_id: v1, clip_id: 1, source_duration: time::from_seconds(1.5) },
+            TimelineIssue::NegativeInPoint { track_id: v1, clip_id: 2 },
+            TimelineIssue::Overlap { track_id: v1, first: 1, second: 2 },
+            TimelineIssue::DuplicateTrackId { track_id: v1 },
+            TimelineIssue::ZeroLength { track_id: v1, clip_id: 4 },
+            TimelineIssue::DuplicateClipId { track_id: v1, clip_id: 1 },
+            TimelineIssue::NegativeStart { track_id: v1, clip_id: 1 },
+            TimelineIssue::EndMismatch { track_id: v1, clip_id: 1, expected: time::from_seconds(1.0) },
+            TimelineIssue::SourceOutOfRange { track_id: v1, clip_id: 1, source_duration: time::from_seconds(1.5) },
+            TimelineIssue::Unsorted { track_id: v1 },
+            TimelineIssue::StaleDuration { recorded: time::from_seconds(6.0), actual: time::from_seconds(5.0) },
+        ];
+        assert_eq!(found, expected);
+        assert_eq!(found[3].to_string(), format!("clips 1 and 2 on track {} overlap", v1));
+    }
+
+    #[test]
+    fn test_repair() {
+        let mut timeline = Timeline::new();
+        let (v1, a1) = (timeline.tracks[0].id, timeline.tracks[1].id);
+        timeline.add_clip(v1, clip(1, 0.0, 4.0)).unwrap();
+        timeline.add_clip(v1, clip(2, 4.0, 2.0)).unwrap();
+        timeline.add_clip(a1, clip(3, 0.0, 2.0)).unwrap();
+        timeline.add_clip(a1, clip(4, 3.0, 1.0)).unwrap();
+
+        let track = &mut timeline.tracks[0];
+        track.clips[1].set_timeline_start(time::from_seconds(3.0)); // Runs into clip 1's last second
+        track.clips[1].id = 1;
+        let track = &mut timeline.tracks[1];
+        track.clips[0].set_timeline_start(-NANOS_PER_SECOND); // Half before 0
+        track.clips[1].timeline_end = time::from_seconds(3.5); // Should be 4.0
+        timeline.duration = 0;
+
+        let repaired = timeline.repair(&MediaDurations::new());
+        assert_eq!(repaired.len(), 5);
+        assert!(timeline.validate(&MediaDurations::new()).is_empty());
+
+        // The later clip 1 got a fresh ID and the earlier one was trimmed to it
+        let spans = |track: &Track| -> Vec<(ClipId, Time, Time)> {
+            track.clips.iter().map(|c| (c.id, c.timeline_start, c.timeline_end)).collect()
+        };
+        assert_eq!(spans(&timeline.tracks[0]), vec![(1, 0, time::from_seconds(3.0)), (5, time::from_seconds(3.0), time::from_seconds(5.0))]);
+        assert_eq!(timeline.tracks[0].clips[0].out_point, time::from_seconds(3.0));
+        // Clip 3 lost its first second; clip 4 ends where its source range does
+        assert_eq!(spans(&timeline.tracks[1]), vec![(3, 0, time::from_seconds(1.0)), (4, time::from_seconds(3.0), time::from_seconds(4.0))]);
+        assert_eq!(timeline.tracks[1].clips[0].in_point, time::from_seconds(1.0));
+        assert_eq!(timeline.duration, time::from_seconds(5.0));
+        assert_eq!(timeline.allocate_clip_id(), 6);
+    }
+
+    #[test]
+    fn test_nested_sequences_and_compound_clips() {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        timeline.add_clip(v1, clip(1, 0.0, 2.0)).unwrap();
+        let sequence = timeline.compound_clips(&[1], "Nest").unwrap();
+
+        // The clip inside the sequence is shortened without updating its length
+        let nested = &mut timeline.sequences.get_mut(&sequence).unwrap().timeline;
+        nested.tracks[0].clips[0].out_point = time::from_seconds(1.0);
+        nested.tracks[0].clips[0].timeline_end = time::from_seconds(1.0);
+
+        let found = timeline.validate(&MediaDurations::new());
+        assert_eq!(found, vec![Diagnostic {
+            sequence: Some(sequence),
+            issue: TimelineIssue::StaleDuration { recorded: time::from_seconds(2.0), actual: time::from_seconds(1.0) },
+        }]);
+        assert_eq!(
+            found[0].to_string(),
+            format!("sequence {}: duration is {} but the timeline ends at {}", sequence, time::from_seconds(2.0), time::from_seconds(1.0))
+        );
+
+        // Once the sequence is repaired, the compound clip plays past its end
+        // and is trimmed to it
+        timeline.repair(&MediaDurations::new());
+        assert!(timeline.validate(&MediaDurations::new()).is_empty());
+        let compound = &timeline.tracks[0].clips[0];
+        assert_eq!((compound.out_point, compound.duration()), (time::from_seconds(1.0), time::from_seconds(1.0)));
+        assert_eq!(timeline.duration, time::from_seconds(1.0));
+    }
+}
@@ -0,0 +1,163 @@
+//! Per-clip visual properties: where and how a video clip's frame is drawn.
+//!
+//! Coordinates follow `render::Transform`: positions are normalized to the
+//! output (0.0-1.0, top-left origin), the anchor and crop to the clip's own
+//! frame. Keyframed values in the clip's `animation` override the static
+//! ones (see [`VisualProperties::animated`]).
+
+use crate::core::time::Time;
+use crate::timeline::clip::{Clip, ClipId};
+use crate::timeline::keyframe::ClipAnimation;
+use crate::timeline::timeline::Timeline;
+use crate::timeline::track::TrackError;
+use serde::{Deserialize, Serialize};
+
+/// Fraction of the frame (0.0-1.0) cut off at each edge, before flipping.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Crop {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+impl Crop {
+    pub fn new(left: f32, top: f32, right: f32, bottom: f32) -> Self {
+        Self { left, top, right, bottom }
+    }
+
+    /// This crop with every edge in 0.0-1.0 and opposite edges leaving part
+    /// of the frame visible (the right and bottom edges give way).
+    pub fn clamped(&self) -> Crop {
+        let left = self.left.clamp(0.0, 1.0);
+        let top = self.top.clamp(0.0, 1.0);
+        Crop {
+            left,
+            top,
+            right: self.right.clamp(0.0, 1.0 - left),
+            bottom: self.bottom.clamp(0.0, 1.0 - top),
+        }
+    }
+
+    /// Whether the crop leaves nothing of the frame visible.
+    pub fn is_empty(&self) -> bool {
+        self.left + self.right >= 1.0 || self.top + self.bottom >= 1.0
+    }
+}
+
+/// How a video clip's frame is placed on the output.
+///
+/// The frame is cropped, then flipped, scaled and rotated about its anchor,
+/// and the anchor is placed at `position`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct VisualProperties {
+    pub position: (f32, f32), // Anchor position in the output, (0.5, 0.5) = centred
+    pub scale: (f32, f32),    // 1.0 = native size
+    pub rotation: f32,        // Degrees clockwise about the anchor
+    pub anchor: (f32, f32),   // Pivot within the frame, (0.5, 0.5) = frame centre
+    pub crop: Crop,
+    pub opacity: f32,         // 0.0 to 1.0
+    pub flip_horizontal: bool, // Mirror left-right about the anchor
+    pub flip_vertical: bool,   // Mirror top-bottom about the anchor
+}
+
+impl Default for VisualProperties {
+    fn default() -> Self {
+        Self {
+            position: (0.5, 0.5),
+            scale: (1.0, 1.0),
+            rotation: 0.0,
+            anchor: (0.5, 0.5),
+            crop: Crop::default(),
+            opacity: 1.0,
+            flip_horizontal: false,
+            flip_vertical: false,
+        }
+    }
+}
+
+impl VisualProperties {
+    /// These properties `offset` into the clip, with every keyframed property
+    /// of `animation` taking its curve's value instead. Opacity is clamped to
+    /// 0.0-1.0 (a bezier ease may overshoot).
+    pub fn animated(&self, animation: &ClipAnimation, offset: Time) -> VisualProperties {
+        VisualProperties {
+            position: animation.position.value_at(offset).unwrap_or(self.position),
+            scale: animation.scale.value_at(offset).unwrap_or(self.scale),
+            rotation: animation.rotation.value_at(offset).unwrap_or(self.rotation),
+            opacity: animation.opacity.value_at(offset).unwrap_or(self.opacity).clamp(0.0, 1.0),
+            ..*self
+        }
+    }
+}
+
+impl Clip {
+    /// Visual properties at `timeline_position`, including keyframed values.
+    ///
+    /// Positions outside the clip (e.g. in a transition's handles) hold the
+    /// first or last keyframe.
+    pub fn visual_at(&self, timeline_position: Time) -> VisualProperties {
+        self.visual.animated(&self.animation, timeline_position - self.timeline_start)
+    }
+}
+
+impl Timeline {
+    /// Replace the static visual properties of a clip. The crop is clamped
+    /// (see `Crop::clamped`).
+    pub fn set_clip_visual(&mut self, clip_id: ClipId, mut visual: VisualProperties) -> Result<(), TrackError> {
+        let clip = self.editable_clip_mut(clip_id)?;
+        visual.crop = visual.crop.clamped();
+        clip.visual = visual;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::time;
+    use crate::timeline::keyframe::{Keyframe, KeyframeTrack};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_crop_clamped() {
+        let crop = Crop::new(-0.5, 0.25, 0.5, 0.9).clamped();
+        assert_eq!(crop, Crop::new(0.0, 0.25, 0.5, 0.75));
+        assert!(crop.is_empty());
+        assert!(!Crop::default().is_empty());
+    }
+
+    #[test]
+    fn test_keyframes_override_static_values() {
+        let mut clip = Clip::new(1, PathBuf::from("a.mp4"), 0, time::from_seconds(2.0), time::from_seconds(10.0), 0);
+        clip.visual.rotation = 90.0;
+        clip.visual.opacity = 0.5;
+        clip.visual.flip_horizontal = true;
+        clip.animation.opacity = KeyframeTrack::new(vec![
+            Keyframe::linear(0, 0.0),
+            Keyframe::linear(time::from_seconds(1.0), 2.0),
+        ]);
+
+        let visual = clip.visual_at(time::from_seconds(10.25));
+        assert_eq!(visual.opacity, 0.5);
+        assert_eq!(visual.rotation, 90.0);
+        assert!(visual.flip_horizontal);
+        // Overshooting keyframes are clamped
+        assert_eq!(clip.visual_at(time::from_seconds(11.5)).opacity, 1.0);
+    }
+
+    #[test]
+    fn test_set_clip_visual() {
+        let mut timeline = Timeline::new();
+        let v1 = timeline.tracks[0].id;
+        timeline.add_clip(v1, Clip::new(1, PathBuf::from("a.mp4"), 0, time::from_seconds(2.0), 0, 0)).unwrap();
+
+        let visual = VisualProperties { crop: Crop::new(0.1, 0.0, 1.0, 0.0), ..Default::default() };
+        timeline.set_clip_visual(1, visual).unwrap();
+        assert_eq!(timeline.find_clip(1).unwrap().1.visual.crop.right, 0.9);
+        assert_eq!(
+            timeline.set_clip_visual(7, visual),
+            Err(TrackError::ClipNotFound { clip_id: 7 })
+        );
+    }
+}
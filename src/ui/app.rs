@@ -5,10 +5,14 @@
 
 use eframe::egui::*;
 use eframe::{App, CreationContext};
-use crate::timeline::Timeline;
-use crate::timeline::Clip;
+use std::path::{Path, PathBuf};
+use crate::timeline::{Timeline, History};
+use crate::timeline::commands::TimelineEdit;
+use crate::timeline::{Clip, Diagnostic, Marker, MarkerKind, SequenceId};
 use crate::ui::{TimelineViewState, timeline_ui};
 use crate::core::time;
+use crate::export::ExportSettings;
+use crate::project::{Autosave, AutosaveConfig, Project, ProjectError, Recovery};
 
 /// Main editor application UI
 /// Defines the primary layout with placeholder panels for a video editor interface
@@ -17,6 +21,22 @@ pub struct EditorApp {
     pub timeline: Timeline,
     /// UI-specific view state for timeline visualization
     pub view_state: TimelineViewState,
+    /// Undo/redo history; all timeline edits go through it
+    pub history: History,
+    /// Compound clips opened for editing, outermost first; empty shows the root timeline
+    pub open_sequences: Vec<SequenceId>,
+    /// Name of the project, used for the default file name
+    pub project_name: String,
+    /// Settings the project is exported with
+    pub export_settings: ExportSettings,
+    /// File the project was opened from or last saved to
+    pub project_path: Option<PathBuf>,
+    /// Background autosave; `None` if it could not be started
+    autosave: Option<Autosave>,
+    /// Backups of a crashed session, offered until recovered or dismissed
+    recovery: Option<Recovery>,
+    /// Last failure or warning, shown in the status bar until dismissed
+    status: Option<String>,
 }
 
 impl EditorApp {
@@ -30,7 +50,7 @@ impl EditorApp {
     /// - One audio track with one clip
     /// - Timebase: nanoseconds (1/1,000,000,000)
     pub fn new(_cc: &CreationContext<'_>) -> Self {
-        // Create a new timeline (already has video_track and audio_track)
+        // Create a new timeline (starts with one video track and one audio track)
         let mut timeline = Timeline::new();
         
         // Create a dummy video clip
@@ -65,14 +85,205 @@ impl EditorApp {
         timeline.add_audio_clip(audio_clip)
             .expect("Failed to add dummy audio clip");
         
-        // Initialize view state with default values
-        let view_state = TimelineViewState::default();
-        
+        Self::with_project(Project::new("Untitled", timeline), None)
+    }
+
+    /// Create the editor on a project file.
+    ///
+    /// Fails if the file cannot be read or is not a valid project. What
+    /// loading repaired is shown in the status bar.
+    pub fn open(_cc: &CreationContext<'_>, path: impl AsRef<Path>) -> Result<Self, ProjectError> {
+        let path = path.as_ref();
+        let (project, repaired) = Project::load(path)?;
+        let mut app = Self::with_project(project, Some(path.to_path_buf()));
+        app.report_repairs(&repaired);
+        Ok(app)
+    }
+
+    fn with_project(project: Project, project_path: Option<PathBuf>) -> Self {
+        let config = AutosaveConfig::for_project(project_path.as_deref());
+        let (autosave, recovery, status) = match Autosave::start(config) {
+            Ok((autosave, recovery)) => (Some(autosave), recovery, None),
+            Err(e) => (None, None, Some(format!("Autosave disabled: {}", e))),
+        };
         Self {
-            timeline,
-            view_state,
+            timeline: project.timeline,
+            view_state: TimelineViewState::default(),
+            history: History::new(),
+            open_sequences: Vec::new(),
+            project_name: project.name,
+            export_settings: project.export_settings,
+            project_path,
+            autosave,
+            recovery,
+            status,
+        }
+    }
+
+    /// Show what loading a project repaired in the status bar.
+    fn report_repairs(&mut self, repaired: &[Diagnostic]) {
+        if let Some(first) = repaired.first() {
+            self.status = Some(format!(
+                "Repaired {} problem(s) in the loaded project, the first: {}",
+                repaired.len(), first
+            ));
+        }
+    }
+
+    /// Show a failure in the status bar, replacing any earlier one.
+    fn report(&mut self, action: &str, error: impl std::fmt::Display) {
+        self.status = Some(format!("{} failed: {}", action, error));
+    }
+
+    /// The project as it is now, for saving.
+    fn project(&self) -> Project {
+        Project {
+            name: self.project_name.clone(),
+            timeline: self.timeline.clone(),
+            export_settings: self.export_settings.clone(),
+        }
+    }
+
+    /// Replace the project with the newest usable backup of a crashed session,
+    /// then delete the crashed session's backups.
+    fn recover(&mut self, recovery: &Recovery) {
+        match recovery.load() {
+            Ok((project, repaired)) => {
+                self.timeline = project.timeline;
+                self.project_name = project.name;
+                self.export_settings = project.export_settings;
+                // The backup does not record where the project was saved
+                self.project_path = None;
+                self.history = History::new();
+                self.open_sequences.clear();
+                if let Some(autosave) = &mut self.autosave {
+                    autosave.mark_saved(self.history.revision());
+                }
+                self.discard_recovery(recovery);
+                self.report_repairs(&repaired);
+            }
+            Err(e) => self.report("Recovery", e),
+        }
+    }
+
+    /// Delete the backups of a crashed session.
+    fn discard_recovery(&mut self, recovery: &Recovery) {
+        if let Err(e) = recovery.discard() {
+            self.report("Removing old autosaves", e);
+        }
+    }
+
+    /// Save the project to `project_path`, or to `<name>.aves` in the
+    /// working directory if it has not been saved before.
+    fn save(&mut self) {
+        let path = self.project_path.clone().unwrap_or_else(|| {
+            PathBuf::from(format!("{}.{}", self.project_name, Project::FILE_EXTENSION))
+        });
+        match self.project().save(&path) {
+            Ok(()) => {
+                if self.project_path.as_ref() != Some(&path) {
+                    // Autosave into the directory of the file saved to
+                    self.autosave = None;
+                    match Autosave::start(AutosaveConfig::for_project(Some(&path))) {
+                        Ok((autosave, recovery)) => {
+                            self.autosave = Some(autosave);
+                            self.recovery = self.recovery.take().or(recovery);
+                        }
+                        Err(e) => self.report("Autosave", e),
+                    }
+                }
+                self.project_path = Some(path);
+                if let Some(autosave) = &mut self.autosave {
+                    autosave.mark_saved(self.history.revision());
+                }
+            }
+            Err(e) => self.report("Save", e),
+        }
+    }
+
+    /// The timeline being shown: the innermost open compound clip, or the root.
+    fn active_timeline(&self) -> &Timeline {
+        self.open_sequences.last()
+            .and_then(|&id| self.timeline.sequence(id))
+            .map_or(&self.timeline, |sequence| &sequence.timeline)
+    }
+
+    /// Mutable access to the shown timeline, for view state like the playhead.
+    fn active_timeline_mut(&mut self) -> &mut Timeline {
+        match self.open_sequences.last().copied() {
+            Some(id) if self.timeline.sequence(id).is_some() => {
+                &mut self.timeline.sequence_mut(id).unwrap().timeline
+            }
+            _ => &mut self.timeline,
+        }
+    }
+
+    /// Run an edit against the shown timeline, as one undo step on the root.
+    fn execute(&mut self, edit: TimelineEdit) -> Result<(), String> {
+        let edit = match self.open_sequences.last() {
+            Some(&id) => TimelineEdit::in_sequence(id, edit),
+            None => edit,
+        };
+        self.history.execute(&mut self.timeline, edit).map_err(|e| e.to_string())
+    }
+
+    /// Step into the compound clip under the playhead.
+    fn open_compound_at_playhead(&mut self) {
+        let timeline = self.active_timeline();
+        let sequence = timeline.clips_spanning(timeline.playhead).into_iter()
+            .filter_map(|clip_id| timeline.find_clip(clip_id))
+            .find_map(|(_, clip)| clip.sequence_id());
+        if let Some(id) = sequence {
+            self.open_sequences.push(id);
+        }
+    }
+
+    /// Drop open compound clips that an undo or redo removed.
+    fn prune_open_sequences(&mut self) {
+        if let Some(index) = self.open_sequences.iter().position(|&id| self.timeline.sequence(id).is_none()) {
+            self.open_sequences.truncate(index);
+        }
+    }
+
+    /// Undo the last timeline edit.
+    fn undo(&mut self) {
+        if let Err(e) = self.history.undo(&mut self.timeline) {
+            self.report("Undo", e);
+        }
+        self.prune_open_sequences();
+    }
+
+    /// Cut every clip under the playhead on all tracks as one undo step.
+    fn split_at_playhead(&mut self) {
+        let position = self.active_timeline().playhead;
+        if self.active_timeline().clips_spanning(position).is_empty() {
+            return;
+        }
+        let edit = TimelineEdit::new("Split at Playhead", move |tl| {
+            tl.split_all_at(position).map(|_| ())
+        });
+        if let Err(e) = self.execute(edit) {
+            self.report("Split", e);
         }
     }
+
+    /// Drop a comment marker at the playhead.
+    fn add_marker_at_playhead(&mut self) {
+        let timeline = self.active_timeline_mut();
+        let id = timeline.allocate_marker_id();
+        let marker = Marker::new(id, timeline.playhead, MarkerKind::Comment);
+        if let Err(e) = self.execute(TimelineEdit::add_marker(marker)) {
+            self.report("Add marker", e);
+        }
+    }
+
+    /// Redo the last undone timeline edit.
+    fn redo(&mut self) {
+        if let Err(e) = self.history.redo(&mut self.timeline) {
+            self.report("Redo", e);
+        }
+        self.prune_open_sequences();
+    }
 }
 
 impl App for EditorApp {
@@ -81,13 +292,119 @@ impl App for EditorApp {
     /// 
     /// Called by eframe each frame to render the UI.
     fn update(&mut self, ctx: &Context, _frame: &mut eframe::Frame) {
+        // Keyboard shortcuts: Ctrl+Z undo, Ctrl+Shift+Z / Ctrl+Y redo, Ctrl+B split,
+        // M add marker, Shift+M / Ctrl+Shift+M next / previous marker, Ctrl+S save
+        let (undo, redo, split, marker, next_marker, previous_marker, save) = ctx.input_mut(|i| {
+            let redo = i.consume_key(Modifiers::COMMAND | Modifiers::SHIFT, Key::Z)
+                || i.consume_key(Modifiers::COMMAND, Key::Y);
+            let undo = i.consume_key(Modifiers::COMMAND, Key::Z);
+            let split = i.consume_key(Modifiers::COMMAND, Key::B);
+            let previous_marker = i.consume_key(Modifiers::COMMAND | Modifiers::SHIFT, Key::M);
+            let next_marker = i.consume_key(Modifiers::SHIFT, Key::M);
+            let marker = i.consume_key(Modifiers::NONE, Key::M);
+            let save = i.consume_key(Modifiers::COMMAND, Key::S);
+            (undo, redo, split, marker, next_marker, previous_marker, save)
+        });
+        if save {
+            self.save();
+        }
+        if split {
+            self.split_at_playhead();
+        }
+        if marker {
+            self.add_marker_at_playhead();
+        }
+        let jump = if next_marker {
+            self.active_timeline().next_marker()
+        } else if previous_marker {
+            self.active_timeline().previous_marker()
+        } else {
+            None
+        };
+        if let Some(placed) = jump {
+            self.active_timeline_mut().set_playhead(placed.marker.position);
+        }
+        if undo {
+            self.undo();
+        }
+        if redo {
+            self.redo();
+        }
+
+        // Hand a snapshot to the background autosave when one is due; the
+        // repaint keeps autosave going while the editor is idle
+        if let Some(mut autosave) = self.autosave.take() {
+            if let Err(e) = autosave.update(self.history.revision(), || self.project()) {
+                self.report("Autosave", e);
+            }
+            ctx.request_repaint_after(autosave.interval());
+            self.autosave = Some(autosave);
+        }
+
+        // Offer the backups of a session that did not close cleanly
+        if let Some(recovery) = self.recovery.clone() {
+            Window::new("Recover Unsaved Work")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(Align2::CENTER_CENTER, Vec2::ZERO)
+                .show(ctx, |ui| {
+                    ui.label("The last session did not close cleanly. Recover its most recent autosave?");
+                    ui.horizontal(|ui| {
+                        if ui.button("Recover").clicked() {
+                            self.recover(&recovery);
+                            self.recovery = None;
+                        }
+                        if ui.button("Discard").clicked() {
+                            self.discard_recovery(&recovery);
+                            self.recovery = None;
+                        }
+                    });
+                });
+        }
+
         // Top panel: Menu bar
         TopBottomPanel::top("menu_bar")
             .resizable(false)
             .show(ctx, |ui| {
                 ui.horizontal(|ui| {
                     ui.label("Menu Bar");
-                    // Menu items will be added here in the future
+                    if ui.button("Save").clicked() {
+                        self.save();
+                    }
+                    ui.separator();
+
+                    let undo_text = match self.history.undo_label() {
+                        Some(label) => format!("Undo {}", label),
+                        None => "Undo".to_string(),
+                    };
+                    if ui.add_enabled(self.history.can_undo(), Button::new(undo_text)).clicked() {
+                        self.undo();
+                    }
+
+                    let redo_text = match self.history.redo_label() {
+                        Some(label) => format!("Redo {}", label),
+                        None => "Redo".to_string(),
+                    };
+                    if ui.add_enabled(self.history.can_redo(), Button::new(redo_text)).clicked() {
+                        self.redo();
+                    }
+
+                    ui.separator();
+                    if ui.button("Open Compound").clicked() {
+                        self.open_compound_at_playhead();
+                    }
+                    if ui.add_enabled(!self.open_sequences.is_empty(), Button::new("Back")).clicked() {
+                        self.open_sequences.pop();
+                    }
+                    // Breadcrumb of the opened compound clips
+                    let mut path = "Timeline".to_string();
+                    for &id in &self.open_sequences {
+                        if let Some(sequence) = self.timeline.sequence(id) {
+                            path.push_str(" › ");
+                            path.push_str(&sequence.name);
+                        }
+                    }
+                    ui.label(path);
                 });
             });
 
@@ -99,9 +416,32 @@ impl App for EditorApp {
                 ui.vertical(|ui| {
                     ui.heading("Media Pool");
                     // Media pool content will be added here in the future
+
+                    ui.separator();
+                    ui.heading("History");
+                    for label in self.history.undo_labels() {
+                        ui.label(label);
+                    }
+                    for label in self.history.redo_labels() {
+                        ui.weak(label);
+                    }
                 });
             });
 
+        // Bottom panel: Status bar with the last failure
+        if let Some(message) = self.status.clone() {
+            TopBottomPanel::bottom("status_bar")
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.colored_label(ui.visuals().error_fg_color, message);
+                        if ui.small_button("Dismiss").clicked() {
+                            self.status = None;
+                        }
+                    });
+                });
+        }
+
         // Bottom panel: Timeline
         // Per SPEC_v1.0.md.md: Timeline → Tracks → Clips hierarchy
         TopBottomPanel::bottom("timeline")
@@ -109,8 +449,12 @@ impl App for EditorApp {
             .default_height(200.0)
             .show(ctx, |ui| {
                 // Call the timeline_ui function to render the timeline
-                // Pass self.timeline and self.view_state as required
-                timeline_ui(ui, &self.timeline, &mut self.view_state);
+                // Pass the shown timeline and self.view_state as required
+                let timeline = match self.open_sequences.last().and_then(|&id| self.timeline.sequence(id)) {
+                    Some(sequence) => &sequence.timeline,
+                    None => &self.timeline,
+                };
+                timeline_ui(ui, timeline, &mut self.view_state);
             });
 
         // Central panel: Program Viewer
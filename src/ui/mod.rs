@@ -7,6 +7,8 @@ pub mod app;
 pub use timeline_view::{TimelineView, timeline_ui};
 pub use app::EditorApp;
 
+use crate::core::timecode::TimecodeFormat;
+
 /// UI state for the timeline view
 /// Manages zoom level and pan position for timeline visualization
 #[derive(Debug, Clone)]
@@ -15,6 +17,8 @@ pub struct TimelineViewState {
     pub zoom: f32,
     /// Pan position in nanoseconds (offset from timeline start)
     pub pan_nanos: i64,
+    /// Timecode settings for the ruler and playhead readout
+    pub timecode: TimecodeFormat,
 }
 
 impl Default for TimelineViewState {
@@ -22,6 +26,7 @@ impl Default for TimelineViewState {
         Self {
             zoom: 1.0,
             pan_nanos: 0,
+            timecode: TimecodeFormat::default(),
         }
     }
 }
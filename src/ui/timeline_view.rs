@@ -2,9 +2,9 @@
 //! Per SPEC_v1.0.md.md: Timeline → Tracks → Clips hierarchy.
 
 use eframe::egui::*;
-use crate::timeline::Timeline;
+use crate::timeline::{Timeline, Track, TrackType, Speed};
 use crate::ui::TimelineViewState;
-use crate::core::time::{to_seconds, from_seconds};
+use crate::core::time::{from_seconds, constants::NANOS_PER_SECOND};
 
 /// Timeline view component
 /// Per SPEC_v1.0.md.md: Timeline time ≠ source time. Clips have in/out points (source time).
@@ -148,7 +148,7 @@ pub fn timeline_ui(ui: &mut Ui, timeline: &Timeline, view_state: &mut TimelineVi
     let end_time = start_time + visible_time_range;
     
     // Draw time markers
-    let time_marker_spacing = 1.0; // 1 second intervals
+    let time_marker_spacing = NANOS_PER_SECOND as f64; // 1 second intervals
     
     // Calculate first marker time (aligned to spacing)
     let mut current_time = (start_time / time_marker_spacing).floor() * time_marker_spacing;
@@ -164,12 +164,11 @@ pub fn timeline_ui(ui: &mut Ui, timeline: &Timeline, view_state: &mut TimelineVi
                 Stroke::new(1.0, Color32::from_gray(100)),
             );
             
-            // Draw time label
-            let time_seconds = to_seconds(current_time as i64);
+            // Draw timecode label
             painter.text(
                 pos2(x + 2.0, timeline_rect.top() + 15.0),
                 Align2::LEFT_TOP,
-                format!("{:.1}s", time_seconds),
+                view_state.timecode.format(current_time as i64),
                 FontId::monospace(10.0),
                 Color32::from_gray(200),
             );
@@ -179,36 +178,188 @@ pub fn timeline_ui(ui: &mut Ui, timeline: &Timeline, view_state: &mut TimelineVi
     }
     
     // Draw clips (simplified)
-    for clip in &timeline.video_track.clips {
-        let clip_start_x = timeline_rect.left() + 
-            (((clip.timeline_start as f64 - start_time) / visible_time_range) * timeline_rect.width() as f64) as f32;
-        let clip_end_x = timeline_rect.left() + 
-            (((clip.timeline_end as f64 - start_time) / visible_time_range) * timeline_rect.width() as f64) as f32;
-        
-        if clip_end_x >= timeline_rect.left() && clip_start_x <= timeline_rect.right() {
-            let clip_rect = Rect::from_min_max(
-                pos2(clip_start_x, timeline_rect.top() + 20.0),
-                pos2(clip_end_x, timeline_rect.top() + 60.0),
+    // One lane per track: caption tracks, then video tracks top-most layer
+    // first, then audio tracks
+    let lanes: Vec<&Track> = timeline.caption_tracks().rev()
+        .chain(timeline.video_tracks().rev())
+        .chain(timeline.audio_tracks())
+        .collect();
+    // Lanes take their tracks' heights, shrunk together if they do not all fit
+    let lane_area_top = timeline_rect.top() + 20.0;
+    let total_height: f32 = lanes.iter().map(|t| t.height).sum();
+    let lane_scale = if total_height > 0.0 {
+        ((timeline_rect.bottom() - lane_area_top) / total_height).min(1.0)
+    } else {
+        1.0
+    };
+
+    let mut next_lane_top = lane_area_top;
+    for track in &lanes {
+        let lane_top = next_lane_top;
+        let lane_pitch = track.height * lane_scale;
+        let lane_height = lane_pitch * 0.8;
+        next_lane_top += lane_pitch;
+
+        // Hidden video and caption tracks and silenced audio tracks are drawn dimmed
+        let [r, g, b] = track.color;
+        let active = match track.track_type {
+            TrackType::Video | TrackType::Caption => track.enabled,
+            TrackType::Audio => timeline.audible_tracks().any(|t| t.id == track.id),
+        };
+        let clip_color = if active {
+            Color32::from_rgb(r, g, b)
+        } else {
+            Color32::from_rgb(r, g, b).gamma_multiply(0.4)
+        };
+
+        // Highlight gaps so they are easy to spot
+        for gap in track.gaps() {
+            let gap_start_x = timeline_rect.left() + 
+                (((gap.start as f64 - start_time) / visible_time_range) * timeline_rect.width() as f64) as f32;
+            let gap_end_x = timeline_rect.left() + 
+                (((gap.end as f64 - start_time) / visible_time_range) * timeline_rect.width() as f64) as f32;
+
+            if gap_end_x >= timeline_rect.left() && gap_start_x <= timeline_rect.right() {
+                let gap_rect = Rect::from_min_max(
+                    pos2(gap_start_x, lane_top),
+                    pos2(gap_end_x, lane_top + lane_height),
+                );
+                painter.rect_stroke(gap_rect, 2.0, Stroke::new(1.0, Color32::from_rgb(120, 60, 60)));
+            }
+        }
+
+        for clip in &track.clips {
+            let clip_start_x = timeline_rect.left() + 
+                (((clip.timeline_start as f64 - start_time) / visible_time_range) * timeline_rect.width() as f64) as f32;
+            let clip_end_x = timeline_rect.left() + 
+                (((clip.timeline_end as f64 - start_time) / visible_time_range) * timeline_rect.width() as f64) as f32;
+
+            if clip_end_x >= timeline_rect.left() && clip_start_x <= timeline_rect.right() {
+                let clip_rect = Rect::from_min_max(
+                    pos2(clip_start_x, lane_top),
+                    pos2(clip_end_x, lane_top + lane_height),
+                );
+                // Compound clips get their own colour so they read as nested sequences
+                let fill = if clip.sequence_id().is_some() {
+                    Color32::from_rgb(140, 200, 120)
+                } else {
+                    clip_color
+                };
+                painter.rect_filled(clip_rect, 2.0, fill);
+
+                // Label retimed clips with their speed (or as a ramp)
+                let speed_label = match (&clip.time_remap, clip.speed) {
+                    (Some(_), _) => Some("Ramp".to_string()),
+                    (None, Speed::NORMAL) => None,
+                    (None, speed) => Some(speed.to_string()),
+                };
+                if let Some(label) = speed_label {
+                    painter.text(
+                        pos2(clip_end_x - 2.0, lane_top + 2.0),
+                        Align2::RIGHT_TOP,
+                        label,
+                        FontId::monospace(10.0),
+                        Color32::from_gray(30),
+                    );
+                }
+
+                // Flag linked clips that have drifted out of sync with their group
+                if let Some(offset) = timeline.sync_offset(clip.id).filter(|&o| o != 0) {
+                    let frames = view_state.timecode.rate.time_to_frame(offset);
+                    painter.text(
+                        pos2(clip_start_x + 2.0, lane_top + 2.0),
+                        Align2::LEFT_TOP,
+                        format!("{:+}", frames),
+                        FontId::monospace(10.0),
+                        Color32::from_rgb(255, 80, 80),
+                    );
+                }
+            }
+        }
+
+        // Cues show their first line
+        for cue in &track.cues {
+            let cue_start_x = timeline_rect.left() + 
+                (((cue.start as f64 - start_time) / visible_time_range) * timeline_rect.width() as f64) as f32;
+            let cue_end_x = timeline_rect.left() + 
+                (((cue.end as f64 - start_time) / visible_time_range) * timeline_rect.width() as f64) as f32;
+            if cue_end_x < timeline_rect.left() || cue_start_x > timeline_rect.right() {
+                continue;
+            }
+            let cue_rect = Rect::from_min_max(pos2(cue_start_x, lane_top), pos2(cue_end_x, lane_top + lane_height));
+            painter.rect_filled(cue_rect, 2.0, clip_color);
+            painter.rect_stroke(cue_rect, 2.0, Stroke::new(1.0, Color32::from_gray(30)));
+            let first_line = cue.text.lines().next().unwrap_or_default();
+            painter.with_clip_rect(cue_rect.intersect(timeline_rect)).text(
+                pos2(cue_start_x + 2.0, lane_top + 2.0),
+                Align2::LEFT_TOP,
+                first_line,
+                FontId::proportional(10.0),
+                Color32::from_gray(30),
+            );
+        }
+
+        // Track name with its lock and solo state
+        let mut label = timeline.track_name(track.id).unwrap_or_default();
+        if track.locked {
+            label.push_str(" [L]");
+        }
+        if track.solo {
+            label.push_str(" [S]");
+        }
+        painter.text(
+            pos2(timeline_rect.left() + 2.0, lane_top + lane_height),
+            Align2::LEFT_BOTTOM,
+            label,
+            FontId::monospace(10.0),
+            Color32::from_gray(220),
+        );
+
+        // Draw transitions over their cuts, with a diagonal from outgoing to incoming
+        for window in track.transition_windows() {
+            let start_x = timeline_rect.left() + 
+                (((window.start as f64 - start_time) / visible_time_range) * timeline_rect.width() as f64) as f32;
+            let end_x = timeline_rect.left() + 
+                (((window.end as f64 - start_time) / visible_time_range) * timeline_rect.width() as f64) as f32;
+            if end_x < timeline_rect.left() || start_x > timeline_rect.right() {
+                continue;
+            }
+            let rect = Rect::from_min_max(pos2(start_x, lane_top), pos2(end_x, lane_top + lane_height));
+            painter.rect_filled(rect, 2.0, Color32::from_rgba_unmultiplied(255, 255, 255, 90));
+            painter.line_segment(
+                [rect.left_bottom(), rect.right_top()],
+                Stroke::new(1.0, Color32::from_gray(30)),
             );
-            painter.rect_filled(clip_rect, 2.0, Color32::from_rgb(100, 150, 255));
         }
     }
     
-    for clip in &timeline.audio_track.clips {
-        let clip_start_x = timeline_rect.left() + 
-            (((clip.timeline_start as f64 - start_time) / visible_time_range) * timeline_rect.width() as f64) as f32;
-        let clip_end_x = timeline_rect.left() + 
-            (((clip.timeline_end as f64 - start_time) / visible_time_range) * timeline_rect.width() as f64) as f32;
-        
-        if clip_end_x >= timeline_rect.left() && clip_start_x <= timeline_rect.right() {
-            let clip_rect = Rect::from_min_max(
-                pos2(clip_start_x, timeline_rect.top() + 70.0),
-                pos2(clip_end_x, timeline_rect.top() + 110.0),
+    // Draw markers along the ruler; range markers get a bar for their duration
+    for placed in timeline.all_markers() {
+        let marker = &placed.marker;
+        let x = timeline_rect.left() + 
+            (((marker.position as f64 - start_time) / visible_time_range) * timeline_rect.width() as f64) as f32;
+        if x < timeline_rect.left() || x > timeline_rect.right() {
+            continue;
+        }
+        let [r, g, b] = marker.color.rgb();
+        let color = Color32::from_rgb(r, g, b);
+        let top = timeline_rect.top() + 2.0;
+        painter.add(Shape::convex_polygon(
+            vec![pos2(x - 4.0, top), pos2(x + 4.0, top), pos2(x, top + 6.0)],
+            color,
+            Stroke::NONE,
+        ));
+        if let Some(duration) = marker.duration {
+            let end_x = timeline_rect.left() + 
+                ((((marker.position + duration) as f64 - start_time) / visible_time_range) * timeline_rect.width() as f64) as f32;
+            painter.rect_filled(
+                Rect::from_min_max(pos2(x, top), pos2(end_x.min(timeline_rect.right()), top + 3.0)),
+                0.0,
+                color,
             );
-            painter.rect_filled(clip_rect, 2.0, Color32::from_rgb(255, 150, 100));
         }
     }
-    
+
     // Draw playhead
     let playhead_x = timeline_rect.left() + 
         (((timeline.playhead as f64 - start_time) / visible_time_range) * timeline_rect.width() as f64) as f32;
@@ -219,4 +370,13 @@ pub fn timeline_ui(ui: &mut Ui, timeline: &Timeline, view_state: &mut TimelineVi
             Stroke::new(2.0, Color32::from_rgb(255, 0, 0)),
         );
     }
+
+    // Draw playhead timecode readout
+    painter.text(
+        pos2(timeline_rect.right() - 4.0, timeline_rect.top() + 2.0),
+        Align2::RIGHT_TOP,
+        view_state.timecode.format(timeline.playhead),
+        FontId::monospace(12.0),
+        Color32::from_rgb(255, 80, 80),
+    );
 }